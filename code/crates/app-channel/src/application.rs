@@ -0,0 +1,578 @@
+//! Callback-based interface for Malachite applications, as an alternative to handling [`AppMsg`]s in a loop.
+//!
+//! The application implements the [`Application`] trait, with one method per message sent by consensus,
+//! and [`run_application`] calls these methods and sends back their results to consensus.
+//! Because replies are the return values of these methods, they cannot be forgotten,
+//! and a method which takes too long to return is reported as an error, except for
+//! [`Application::get_value`] after which consensus carries on without proposing a value.
+
+use core::future::Future;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use eyre::{eyre, WrapErr};
+use tokio::sync::mpsc;
+use tracing::{error, warn};
+
+use crate::app::types::core::{
+    CommitCertificate, Context, Round, SignedExtension, Validity, ValueId,
+};
+use crate::app::types::streaming::StreamMessage;
+use crate::app::types::sync::DecidedValue;
+use crate::app::types::{
+    LocallyProposedValue, PeerId, ProposedValue, ValueValidity, VoteExtensions,
+};
+use crate::msgs::{AppMsg, ConsensusMsg, Reply};
+
+/// Default maximum time given to the application to handle a message,
+/// except for [`Application::get_value`] which is given the timeout set by consensus.
+///
+/// This matches the default deadline of the connector for most messages,
+/// see [`HostConfig`](malachitebft_config::HostConfig).
+pub const DEFAULT_REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A Malachite application, called by [`run_application`] for each message sent by consensus.
+///
+/// See [`AppMsg`] for a description of each message and of the expected reply.
+#[async_trait]
+pub trait Application<Ctx>: Send
+where
+    Ctx: Context,
+{
+    /// Consensus is ready, returns the height at which to start consensus.
+    /// See [`AppMsg::ConsensusReady`].
+    async fn consensus_ready(&mut self) -> eyre::Result<ConsensusMsg<Ctx>>;
+
+    /// A new round has started. See [`AppMsg::StartedRound`].
+    async fn started_round(
+        &mut self,
+        _height: Ctx::Height,
+        _round: Round,
+        _proposer: Ctx::Address,
+    ) -> eyre::Result<()> {
+        Ok(())
+    }
+
+    /// Build a value to propose within the given timeout, and publish its parts to the network.
+    /// See [`AppMsg::GetValue`].
+    async fn get_value(
+        &mut self,
+        height: Ctx::Height,
+        round: Round,
+        timeout: Duration,
+        vote_extensions: VoteExtensions<Ctx>,
+    ) -> eyre::Result<LocallyProposedValue<Ctx>>;
+
+    /// Re-publish the parts of a proposal to the network. See [`AppMsg::RestreamProposal`].
+    async fn restream_proposal(
+        &mut self,
+        height: Ctx::Height,
+        round: Round,
+        valid_round: Round,
+        address: Ctx::Address,
+        value_id: ValueId<Ctx>,
+    ) -> eyre::Result<()>;
+
+    /// The earliest height for which a decided value is available.
+    /// See [`AppMsg::GetHistoryMinHeight`].
+    ///
+    /// By default, no decided value is kept by the application.
+    async fn get_history_min_height(&mut self) -> eyre::Result<Ctx::Height> {
+        Ok(Ctx::Height::default())
+    }
+
+    /// Process a proposal part, returning the proposed value if the proposal is now complete.
+    /// See [`AppMsg::ReceivedProposalPart`].
+    async fn received_proposal_part(
+        &mut self,
+        from: PeerId,
+        part: StreamMessage<Ctx::ProposalPart>,
+    ) -> eyre::Result<Option<ProposedValue<Ctx>>>;
+
+    /// Validate a value received in a proposal message. See [`AppMsg::ValidateProposal`].
+    ///
+    /// By default, all values are considered valid.
+    async fn validate_proposal(
+        &mut self,
+        _height: Ctx::Height,
+        _round: Round,
+        _proposer: Ctx::Address,
+        _value: Ctx::Value,
+    ) -> eyre::Result<ValueValidity> {
+        Ok(ValueValidity::Valid)
+    }
+
+    /// The extension of this node's precommit for a value, if any. See [`AppMsg::ExtendVote`].
+    ///
+    /// By default, votes are not extended.
+    async fn extend_vote(
+        &mut self,
+        _height: Ctx::Height,
+        _round: Round,
+        _value_id: ValueId<Ctx>,
+    ) -> eyre::Result<Option<SignedExtension<Ctx>>> {
+        Ok(None)
+    }
+
+    /// Verify the extension of a precommit received from a peer.
+    /// See [`AppMsg::VerifyVoteExtension`].
+    ///
+    /// By default, all extensions are considered valid.
+    async fn verify_vote_extension(
+        &mut self,
+        _height: Ctx::Height,
+        _round: Round,
+        _value_id: ValueId<Ctx>,
+        _address: Ctx::Address,
+        _extension: Option<SignedExtension<Ctx>>,
+    ) -> eyre::Result<Validity> {
+        Ok(Validity::Valid)
+    }
+
+    /// The validator set at the given height. See [`AppMsg::GetValidatorSet`].
+    async fn get_validator_set(&mut self, height: Ctx::Height) -> eyre::Result<Ctx::ValidatorSet>;
+
+    /// Commit the decided value, returning the height at which to continue consensus.
+    /// See [`AppMsg::Decided`].
+    async fn decided(
+        &mut self,
+        certificate: CommitCertificate<Ctx>,
+    ) -> eyre::Result<ConsensusMsg<Ctx>>;
+
+    /// The value decided at the given height, if available. See [`AppMsg::GetDecidedValue`].
+    ///
+    /// By default, no decided value is kept by the application.
+    async fn get_decided_value(
+        &mut self,
+        _height: Ctx::Height,
+    ) -> eyre::Result<Option<DecidedValue<Ctx>>> {
+        Ok(None)
+    }
+
+    /// Decode a value synced from a peer. See [`AppMsg::ProcessSyncedValue`].
+    async fn process_synced_value(
+        &mut self,
+        height: Ctx::Height,
+        round: Round,
+        proposer: Ctx::Address,
+        value_bytes: Bytes,
+    ) -> eyre::Result<ProposedValue<Ctx>>;
+
+    /// Check a transaction submitted through the RPC server, returning its hash if accepted.
+    /// See [`AppMsg::ReceivedTransaction`].
+    ///
+    /// By default, all transactions are rejected.
+    async fn received_transaction(&mut self, _tx: Bytes) -> eyre::Result<Result<Bytes, String>> {
+        Ok(Err(
+            "Transactions are not supported by the application".to_string()
+        ))
+    }
+}
+
+/// Handle the messages sent by consensus with the given application, until consensus stops.
+///
+/// Returns an error if the application fails to handle a message or does not return within
+/// `reply_timeout`. If it does not return a value within the timeout set by consensus for
+/// [`AppMsg::GetValue`], no value is proposed and consensus prevotes nil once its propose
+/// timeout elapses, as if the value had been proposed too late. If it does not check a
+/// transaction within `reply_timeout`, the transaction is dropped.
+pub async fn run_application<Ctx, A>(
+    app: &mut A,
+    consensus: &mut mpsc::Receiver<AppMsg<Ctx>>,
+    reply_timeout: Duration,
+) -> eyre::Result<()>
+where
+    Ctx: Context,
+    A: Application<Ctx>,
+{
+    while let Some(msg) = consensus.recv().await {
+        handle(app, msg, reply_timeout).await?;
+    }
+
+    Err(eyre!("Consensus channel closed unexpectedly"))
+}
+
+async fn handle<Ctx, A>(app: &mut A, msg: AppMsg<Ctx>, reply_timeout: Duration) -> eyre::Result<()>
+where
+    Ctx: Context,
+    A: Application<Ctx>,
+{
+    match msg {
+        AppMsg::ConsensusReady { reply } => {
+            let msg = within(reply_timeout, "ConsensusReady", app.consensus_ready()).await?;
+            send(reply, msg, "ConsensusReady");
+        }
+
+        AppMsg::StartedRound {
+            height,
+            round,
+            proposer,
+        } => {
+            within(
+                reply_timeout,
+                "StartedRound",
+                app.started_round(height, round, proposer),
+            )
+            .await?;
+        }
+
+        AppMsg::GetValue {
+            height,
+            round,
+            timeout,
+            vote_extensions,
+            reply,
+        } => {
+            let value = tokio::time::timeout(
+                timeout,
+                app.get_value(height, round, timeout, vote_extensions),
+            )
+            .await;
+
+            match value {
+                Ok(value) => {
+                    let value = value.wrap_err("Application failed to handle GetValue")?;
+                    send(reply, value, "GetValue");
+                }
+                Err(_) => {
+                    warn!(
+                        %height, %round,
+                        "Application did not reply to GetValue within {timeout:?}, not proposing any value"
+                    );
+                }
+            }
+        }
+
+        AppMsg::RestreamProposal {
+            height,
+            round,
+            valid_round,
+            address,
+            value_id,
+        } => {
+            within(
+                reply_timeout,
+                "RestreamProposal",
+                app.restream_proposal(height, round, valid_round, address, value_id),
+            )
+            .await?;
+        }
+
+        AppMsg::GetHistoryMinHeight { reply } => {
+            let height = within(
+                reply_timeout,
+                "GetHistoryMinHeight",
+                app.get_history_min_height(),
+            )
+            .await?;
+
+            send(reply, height, "GetHistoryMinHeight");
+        }
+
+        AppMsg::ReceivedProposalPart { from, part, reply } => {
+            let value = within(
+                reply_timeout,
+                "ReceivedProposalPart",
+                app.received_proposal_part(from, part),
+            )
+            .await?;
+
+            send(reply, value, "ReceivedProposalPart");
+        }
+
+        AppMsg::ValidateProposal {
+            height,
+            round,
+            proposer,
+            value,
+            reply,
+        } => {
+            let validity = within(
+                reply_timeout,
+                "ValidateProposal",
+                app.validate_proposal(height, round, proposer, value),
+            )
+            .await?;
+
+            send(reply, validity, "ValidateProposal");
+        }
+
+        AppMsg::ExtendVote {
+            height,
+            round,
+            value_id,
+            reply,
+        } => {
+            let extension = within(
+                reply_timeout,
+                "ExtendVote",
+                app.extend_vote(height, round, value_id),
+            )
+            .await?;
+
+            send(reply, extension, "ExtendVote");
+        }
+
+        AppMsg::VerifyVoteExtension {
+            height,
+            round,
+            value_id,
+            address,
+            extension,
+            reply,
+        } => {
+            let validity = within(
+                reply_timeout,
+                "VerifyVoteExtension",
+                app.verify_vote_extension(height, round, value_id, address, extension),
+            )
+            .await?;
+
+            send(reply, validity, "VerifyVoteExtension");
+        }
+
+        AppMsg::GetValidatorSet { height, reply } => {
+            let validator_set = within(
+                reply_timeout,
+                "GetValidatorSet",
+                app.get_validator_set(height),
+            )
+            .await?;
+
+            send(reply, validator_set, "GetValidatorSet");
+        }
+
+        AppMsg::Decided { certificate, reply } => {
+            let msg = within(reply_timeout, "Decided", app.decided(certificate)).await?;
+            send(reply, msg, "Decided");
+        }
+
+        AppMsg::GetDecidedValue { height, reply } => {
+            let value = within(
+                reply_timeout,
+                "GetDecidedValue",
+                app.get_decided_value(height),
+            )
+            .await?;
+
+            send(reply, value, "GetDecidedValue");
+        }
+
+        AppMsg::ProcessSyncedValue {
+            height,
+            round,
+            proposer,
+            value_bytes,
+            reply,
+        } => {
+            let value = within(
+                reply_timeout,
+                "ProcessSyncedValue",
+                app.process_synced_value(height, round, proposer, value_bytes),
+            )
+            .await?;
+
+            send(reply, value, "ProcessSyncedValue");
+        }
+
+        AppMsg::ReceivedTransaction { tx, reply } => {
+            let result = tokio::time::timeout(reply_timeout, app.received_transaction(tx)).await;
+
+            // Consensus does not depend on transactions submitted through the RPC server,
+            // so the client is told that the application is unavailable instead
+            match result {
+                Ok(result) => {
+                    let result =
+                        result.wrap_err("Application failed to handle ReceivedTransaction")?;
+                    send(reply, result, "ReceivedTransaction");
+                }
+                Err(_) => {
+                    warn!(
+                        "Application did not reply to ReceivedTransaction within {reply_timeout:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Run the application's handler for a message, failing if it does not return within the timeout
+async fn within<T>(
+    timeout: Duration,
+    msg: &'static str,
+    handler: impl Future<Output = eyre::Result<T>>,
+) -> eyre::Result<T> {
+    tokio::time::timeout(timeout, handler)
+        .await
+        .map_err(|_| eyre!("Application did not reply to {msg} within {timeout:?}"))?
+        .wrap_err_with(|| format!("Application failed to handle {msg}"))
+}
+
+fn send<T>(reply: Reply<T>, value: T, msg: &'static str) {
+    // Consensus may have moved on in the meantime, eg. after a timeout
+    if reply.send(value).is_err() {
+        error!("Failed to send {msg} reply");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::oneshot;
+
+    use malachitebft_test::{Height, TestContext, ValidatorSet};
+
+    use super::*;
+
+    /// Only supports the messages with a default implementation, fails on the other ones,
+    /// and never returns a value to propose
+    struct App;
+
+    #[async_trait]
+    impl Application<TestContext> for App {
+        async fn consensus_ready(&mut self) -> eyre::Result<ConsensusMsg<TestContext>> {
+            Err(eyre!(
+                "ConsensusReady is not supported by the test application"
+            ))
+        }
+
+        async fn get_value(
+            &mut self,
+            _height: Height,
+            _round: Round,
+            _timeout: Duration,
+            _vote_extensions: VoteExtensions<TestContext>,
+        ) -> eyre::Result<LocallyProposedValue<TestContext>> {
+            core::future::pending().await
+        }
+
+        async fn restream_proposal(
+            &mut self,
+            _height: Height,
+            _round: Round,
+            _valid_round: Round,
+            _address: <TestContext as Context>::Address,
+            _value_id: ValueId<TestContext>,
+        ) -> eyre::Result<()> {
+            Err(eyre!(
+                "RestreamProposal is not supported by the test application"
+            ))
+        }
+
+        async fn received_proposal_part(
+            &mut self,
+            _from: PeerId,
+            _part: StreamMessage<<TestContext as Context>::ProposalPart>,
+        ) -> eyre::Result<Option<ProposedValue<TestContext>>> {
+            Ok(None)
+        }
+
+        async fn get_validator_set(&mut self, height: Height) -> eyre::Result<ValidatorSet> {
+            Err(eyre!("No validator set at height {height}"))
+        }
+
+        async fn decided(
+            &mut self,
+            _certificate: CommitCertificate<TestContext>,
+        ) -> eyre::Result<ConsensusMsg<TestContext>> {
+            Err(eyre!("Decided is not supported by the test application"))
+        }
+
+        async fn process_synced_value(
+            &mut self,
+            _height: Height,
+            _round: Round,
+            _proposer: <TestContext as Context>::Address,
+            _value_bytes: Bytes,
+        ) -> eyre::Result<ProposedValue<TestContext>> {
+            Err(eyre!(
+                "ProcessSyncedValue is not supported by the test application"
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_on_application_error() {
+        let (tx, mut rx) = mpsc::channel(1);
+        let (reply, reply_rx) = oneshot::channel();
+
+        tx.send(AppMsg::ConsensusReady { reply }).await.unwrap();
+
+        let error = run_application(&mut App, &mut rx, DEFAULT_REPLY_TIMEOUT)
+            .await
+            .unwrap_err();
+
+        assert!(error.to_string().contains("ConsensusReady"));
+        assert!(reply_rx.await.is_err(), "reply is dropped without a value");
+    }
+
+    #[tokio::test]
+    async fn replies_with_default_implementations() {
+        let (tx, mut rx) = mpsc::channel(3);
+        let (min_height_tx, min_height_rx) = oneshot::channel();
+        let (decided_tx, decided_rx) = oneshot::channel();
+        let (tx_result_tx, tx_result_rx) = oneshot::channel();
+
+        tx.send(AppMsg::GetHistoryMinHeight {
+            reply: min_height_tx,
+        })
+        .await
+        .unwrap();
+
+        tx.send(AppMsg::GetDecidedValue {
+            height: Height::new(1),
+            reply: decided_tx,
+        })
+        .await
+        .unwrap();
+
+        tx.send(AppMsg::ReceivedTransaction {
+            tx: Bytes::from_static(b"tx"),
+            reply: tx_result_tx,
+        })
+        .await
+        .unwrap();
+
+        drop(tx);
+
+        let result = run_application(&mut App, &mut rx, DEFAULT_REPLY_TIMEOUT).await;
+
+        assert_eq!(min_height_rx.await.unwrap(), Height::default());
+        assert_eq!(decided_rx.await.unwrap(), None);
+        assert!(
+            tx_result_rx.await.unwrap().is_err(),
+            "transaction is rejected"
+        );
+        assert!(result.is_err(), "consensus channel is closed");
+    }
+
+    #[tokio::test]
+    async fn keeps_running_on_missed_value() {
+        let (tx, mut rx) = mpsc::channel(1);
+        let (reply, reply_rx) = oneshot::channel();
+
+        tx.send(AppMsg::GetValue {
+            height: Height::new(1),
+            round: Round::new(0),
+            timeout: Duration::from_millis(10),
+            vote_extensions: VoteExtensions::default(),
+            reply,
+        })
+        .await
+        .unwrap();
+
+        drop(tx);
+
+        let error = run_application(&mut App, &mut rx, DEFAULT_REPLY_TIMEOUT)
+            .await
+            .unwrap_err();
+
+        assert!(
+            error.to_string().contains("channel closed"),
+            "application keeps handling messages until consensus stops"
+        );
+        assert!(reply_rx.await.is_err(), "reply is dropped without a value");
+    }
+}
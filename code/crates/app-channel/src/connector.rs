@@ -1,15 +1,104 @@
 //! Implementation of a host actor for bridiging consensus and the application via a set of channels.
 
-use ractor::{async_trait, Actor, ActorProcessingErr, ActorRef, SpawnErr};
+use std::time::{Duration, Instant};
+
+use ractor::{async_trait, Actor, ActorProcessingErr, ActorRef, RpcReplyPort, SpawnErr};
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
+use tracing::{error, warn};
 
+use malachitebft_config::HostConfig;
+use malachitebft_engine::consensus::ConsensusRef;
 use malachitebft_engine::host::HostMsg;
+use malachitebft_rpc::tx::Receipts;
 
 use crate::app::types::core::Context;
-use crate::app::types::metrics::Metrics;
+use crate::app::types::metrics::prometheus::metrics::counter::Counter;
+use crate::app::types::metrics::prometheus::metrics::family::Family;
+use crate::app::types::metrics::prometheus::metrics::histogram::{exponential_buckets, Histogram};
+use crate::app::types::metrics::SharedRegistry;
 use crate::msgs::AppMsg;
 
+type Labels = Vec<(String, String)>;
+
+/// Metrics about the replies of the application to the messages sent by consensus.
+#[derive(Clone, Debug)]
+pub struct ConnectorMetrics {
+    reply_latency: Family<Labels, Histogram, fn() -> Histogram>,
+    reply_timeouts: Family<Labels, Counter>,
+    replies_dropped: Family<Labels, Counter>,
+    unanswered: Family<Labels, Counter>,
+}
+
+impl ConnectorMetrics {
+    pub fn new() -> Self {
+        Self {
+            reply_latency: Family::new_with_constructor(|| {
+                Histogram::new(exponential_buckets(0.001, 2.0, 18))
+            }),
+            reply_timeouts: Family::default(),
+            replies_dropped: Family::default(),
+            unanswered: Family::default(),
+        }
+    }
+
+    pub fn register(registry: &SharedRegistry) -> Self {
+        let metrics = Self::new();
+
+        registry.with_prefix("malachitebft_app_channel", |registry| {
+            registry.register(
+                "reply_latency",
+                "Time taken by the application to reply to a message, by message type, in seconds",
+                metrics.reply_latency.clone(),
+            );
+
+            registry.register(
+                "reply_timeouts",
+                "Number of messages the application did not reply to in time, by message type",
+                metrics.reply_timeouts.clone(),
+            );
+
+            registry.register(
+                "replies_dropped",
+                "Number of messages the application dropped without replying, by message type",
+                metrics.replies_dropped.clone(),
+            );
+
+            registry.register(
+                "unanswered",
+                "Number of messages from consensus left without a reply, since the application \
+                 did not reply in time, by message type",
+                metrics.unanswered.clone(),
+            );
+        });
+
+        metrics
+    }
+
+    fn labels(msg: &'static str) -> Labels {
+        vec![("msg".to_string(), msg.to_string())]
+    }
+}
+
+impl Default for ConnectorMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The application failed to reply to a message
+#[derive(Debug, thiserror::Error)]
+pub enum ReplyError {
+    #[error("Application did not reply to {msg} within {deadline:?}")]
+    TimedOut {
+        msg: &'static str,
+        deadline: Duration,
+    },
+
+    #[error("Application dropped the reply channel for {msg}")]
+    Dropped { msg: &'static str },
+}
+
 /// Actor for bridging consensus and the application via a set of channels.
 ///
 /// This actor is responsible for forwarding messages from the
@@ -19,39 +108,140 @@ where
     Ctx: Context,
 {
     sender: mpsc::Sender<AppMsg<Ctx>>,
-
-    // TODO: add some metrics
-    #[allow(dead_code)]
-    metrics: Metrics,
+    deadlines: HostConfig,
+    metrics: ConnectorMetrics,
+    receipts: Receipts,
 }
 
 impl<Ctx> Connector<Ctx>
 where
     Ctx: Context,
 {
-    pub fn new(sender: mpsc::Sender<AppMsg<Ctx>>, metrics: Metrics) -> Self {
-        Connector { sender, metrics }
+    pub fn new(
+        sender: mpsc::Sender<AppMsg<Ctx>>,
+        deadlines: HostConfig,
+        metrics: ConnectorMetrics,
+        receipts: Receipts,
+    ) -> Self {
+        Connector {
+            sender,
+            deadlines,
+            metrics,
+            receipts,
+        }
     }
 
     pub async fn spawn(
         sender: mpsc::Sender<AppMsg<Ctx>>,
-        metrics: Metrics,
+        deadlines: HostConfig,
+        metrics: ConnectorMetrics,
+        receipts: Receipts,
     ) -> Result<ActorRef<HostMsg<Ctx>>, SpawnErr>
     where
         Ctx: Context,
     {
-        let (actor_ref, _) = Actor::spawn(None, Self::new(sender, metrics), ()).await?;
+        let connector = Self::new(sender, deadlines, metrics, receipts);
+        let (actor_ref, _) = Actor::spawn(None, connector, ()).await?;
+
         Ok(actor_ref)
     }
+
+    /// Wait for the application to reply to a message, warning periodically while the reply is pending
+    async fn wait_reply<T>(
+        &self,
+        msg: &'static str,
+        deadline: Duration,
+        mut rx: oneshot::Receiver<T>,
+    ) -> Result<T, ReplyError> {
+        let labels = ConnectorMetrics::labels(msg);
+        let start = Instant::now();
+
+        let timeout = tokio::time::sleep(deadline);
+        tokio::pin!(timeout);
+
+        let mut stuck = tokio::time::interval_at(
+            tokio::time::Instant::now() + self.deadlines.stuck_warning,
+            self.deadlines.stuck_warning,
+        );
+
+        loop {
+            tokio::select! {
+                result = &mut rx => {
+                    return match result {
+                        Ok(value) => {
+                            self.metrics
+                                .reply_latency
+                                .get_or_create(&labels)
+                                .observe(start.elapsed().as_secs_f64());
+
+                            Ok(value)
+                        }
+                        Err(_) => {
+                            self.metrics.replies_dropped.get_or_create(&labels).inc();
+                            Err(ReplyError::Dropped { msg })
+                        }
+                    };
+                }
+
+                _ = &mut timeout => {
+                    self.metrics.reply_timeouts.get_or_create(&labels).inc();
+                    return Err(ReplyError::TimedOut { msg, deadline });
+                }
+
+                _ = stuck.tick() => {
+                    warn!(
+                        elapsed = ?start.elapsed(),
+                        ?deadline,
+                        "Still waiting for the application to reply to {msg}"
+                    );
+                }
+            }
+        }
+    }
 }
 
 impl<Ctx> Connector<Ctx>
 where
     Ctx: Context,
 {
+    /// Forward the reply of the application to consensus, see [`Connector::drop_reply`]
+    fn forward_reply<T: Send + 'static>(
+        &self,
+        result: Result<T, ReplyError>,
+        reply_to: RpcReplyPort<T>,
+    ) -> Result<(), ActorProcessingErr> {
+        match result {
+            Ok(value) => Ok(reply_to.send(value)?),
+            Err(e) => self.drop_reply(e, reply_to),
+        }
+    }
+
+    /// If the application did not reply in time, consensus is left without a reply,
+    /// and carries on as if its request had failed.
+    fn drop_reply<T>(
+        &self,
+        error: ReplyError,
+        reply_to: RpcReplyPort<T>,
+    ) -> Result<(), ActorProcessingErr> {
+        let ReplyError::TimedOut { msg, .. } = error else {
+            return Err(error.into());
+        };
+
+        warn!("{error}, leaving consensus without a reply");
+
+        self.metrics
+            .unanswered
+            .get_or_create(&ConnectorMetrics::labels(msg))
+            .inc();
+
+        drop(reply_to);
+
+        Ok(())
+    }
+
     async fn handle_msg(
         &self,
-        _myself: ActorRef<HostMsg<Ctx>>,
+        myself: ActorRef<HostMsg<Ctx>>,
         msg: HostMsg<Ctx>,
         _state: &mut (),
     ) -> Result<(), ActorProcessingErr> {
@@ -61,7 +251,15 @@ where
 
                 self.sender.send(AppMsg::ConsensusReady { reply }).await?;
 
-                consensus_ref.cast(rx.await?.into())?;
+                let result = self
+                    .wait_reply("ConsensusReady", self.deadlines.consensus_ready, rx)
+                    .await;
+
+                match result {
+                    Ok(msg) => consensus_ref.cast(msg.into())?,
+                    Err(e @ ReplyError::TimedOut { .. }) => stop_node(&myself, &consensus_ref, e),
+                    Err(e) => return Err(e.into()),
+                }
             }
 
             HostMsg::StartedRound {
@@ -82,6 +280,7 @@ where
                 height,
                 round,
                 timeout,
+                vote_extensions,
                 reply_to,
             } => {
                 let (reply, rx) = oneshot::channel();
@@ -91,11 +290,19 @@ where
                         height,
                         round,
                         timeout,
+                        vote_extensions,
                         reply,
                     })
                     .await?;
 
-                reply_to.send(rx.await?)?;
+                // If the application does not provide a value in time, we do not reply,
+                // and consensus will time out and prevote nil as if no value had been proposed.
+                // The timeout given to the application is the propose timeout, so that
+                // a late value is never proposed once consensus has moved on.
+                match self.wait_reply("GetValue", timeout, rx).await {
+                    Ok(value) => reply_to.send(value)?,
+                    Err(e) => warn!(%height, %round, "{e}, not proposing any value"),
+                }
             }
 
             HostMsg::RestreamValue {
@@ -123,7 +330,15 @@ where
                     .send(AppMsg::GetHistoryMinHeight { reply })
                     .await?;
 
-                reply_to.send(rx.await?)?;
+                let result = self
+                    .wait_reply(
+                        "GetHistoryMinHeight",
+                        self.deadlines.get_history_min_height,
+                        rx,
+                    )
+                    .await;
+
+                self.forward_reply(result, reply_to)?;
             }
 
             HostMsg::ReceivedProposalPart {
@@ -137,11 +352,104 @@ where
                     .send(AppMsg::ReceivedProposalPart { from, part, reply })
                     .await?;
 
-                if let Some(value) = rx.await? {
-                    reply_to.send(value)?;
+                let result = self
+                    .wait_reply(
+                        "ReceivedProposalPart",
+                        self.deadlines.received_proposal_part,
+                        rx,
+                    )
+                    .await;
+
+                // Consensus only expects a reply once the full value has been received
+                match result {
+                    Ok(Some(value)) => reply_to.send(value)?,
+                    Ok(None) => {}
+                    Err(e) => self.drop_reply(e, reply_to)?,
                 }
             }
 
+            HostMsg::ValidateProposal {
+                height,
+                round,
+                proposer,
+                value,
+                reply_to,
+            } => {
+                let (reply, rx) = oneshot::channel();
+
+                self.sender
+                    .send(AppMsg::ValidateProposal {
+                        height,
+                        round,
+                        proposer,
+                        value,
+                        reply,
+                    })
+                    .await?;
+
+                let result = self
+                    .wait_reply("ValidateProposal", self.deadlines.validate_proposal, rx)
+                    .await;
+
+                self.forward_reply(result, reply_to)?;
+            }
+
+            HostMsg::ExtendVote {
+                height,
+                round,
+                value_id,
+                reply_to,
+            } => {
+                let (reply, rx) = oneshot::channel();
+
+                self.sender
+                    .send(AppMsg::ExtendVote {
+                        height,
+                        round,
+                        value_id,
+                        reply,
+                    })
+                    .await?;
+
+                let result = self
+                    .wait_reply("ExtendVote", self.deadlines.extend_vote, rx)
+                    .await;
+
+                self.forward_reply(result, reply_to)?;
+            }
+
+            HostMsg::VerifyVoteExtension {
+                height,
+                round,
+                value_id,
+                address,
+                extension,
+                reply_to,
+            } => {
+                let (reply, rx) = oneshot::channel();
+
+                self.sender
+                    .send(AppMsg::VerifyVoteExtension {
+                        height,
+                        round,
+                        value_id,
+                        address,
+                        extension,
+                        reply,
+                    })
+                    .await?;
+
+                let result = self
+                    .wait_reply(
+                        "VerifyVoteExtension",
+                        self.deadlines.verify_vote_extension,
+                        rx,
+                    )
+                    .await;
+
+                self.forward_reply(result, reply_to)?;
+            }
+
             HostMsg::GetValidatorSet { height, reply_to } => {
                 let (reply, rx) = oneshot::channel();
 
@@ -149,20 +457,33 @@ where
                     .send(AppMsg::GetValidatorSet { height, reply })
                     .await?;
 
-                reply_to.send(rx.await?)?;
+                let result = self
+                    .wait_reply("GetValidatorSet", self.deadlines.get_validator_set, rx)
+                    .await;
+
+                self.forward_reply(result, reply_to)?;
             }
 
             HostMsg::Decided {
                 certificate,
                 consensus: consensus_ref,
             } => {
+                // Report the transactions of the decided value as included
+                self.receipts.decided(&certificate);
+
                 let (reply, rx) = oneshot::channel();
 
                 self.sender
                     .send(AppMsg::Decided { certificate, reply })
                     .await?;
 
-                consensus_ref.cast(rx.await?.into())?;
+                let result = self.wait_reply("Decided", self.deadlines.decided, rx).await;
+
+                match result {
+                    Ok(msg) => consensus_ref.cast(msg.into())?,
+                    Err(e @ ReplyError::TimedOut { .. }) => stop_node(&myself, &consensus_ref, e),
+                    Err(e) => return Err(e.into()),
+                }
             }
 
             HostMsg::GetDecidedValue { height, reply_to } => {
@@ -172,7 +493,11 @@ where
                     .send(AppMsg::GetDecidedValue { height, reply })
                     .await?;
 
-                reply_to.send(rx.await?)?;
+                let result = self
+                    .wait_reply("GetDecidedValue", self.deadlines.get_decided_value, rx)
+                    .await;
+
+                self.forward_reply(result, reply_to)?;
             }
 
             HostMsg::ProcessSyncedValue {
@@ -194,7 +519,15 @@ where
                     })
                     .await?;
 
-                reply_to.send(rx.await?)?;
+                let result = self
+                    .wait_reply(
+                        "ProcessSyncedValue",
+                        self.deadlines.process_synced_value,
+                        rx,
+                    )
+                    .await;
+
+                self.forward_reply(result, reply_to)?;
             }
         };
 
@@ -202,6 +535,19 @@ where
     }
 }
 
+/// Consensus cannot make progress without the application telling it which height to start,
+/// so we stop it, together with this actor so that the application sees its channel closed.
+fn stop_node<Ctx: Context>(
+    myself: &ActorRef<HostMsg<Ctx>>,
+    consensus: &ConsensusRef<Ctx>,
+    error: ReplyError,
+) {
+    error!("{error}, stopping the node");
+
+    consensus.stop(Some(error.to_string()));
+    myself.stop(Some(error.to_string()));
+}
+
 #[async_trait]
 impl<Ctx> Actor for Connector<Ctx>
 where
@@ -232,3 +578,138 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use malachitebft_test::TestContext;
+
+    use super::*;
+
+    fn connector() -> Connector<TestContext> {
+        let (tx, _rx) = mpsc::channel(1);
+
+        let deadlines = HostConfig {
+            stuck_warning: Duration::from_millis(5),
+            ..HostConfig::default()
+        };
+
+        Connector::new(tx, deadlines, ConnectorMetrics::new(), Receipts::new())
+    }
+
+    #[tokio::test]
+    async fn wait_reply() {
+        let connector = connector();
+        let labels = ConnectorMetrics::labels("Test");
+
+        let (tx, rx) = oneshot::channel();
+        tx.send(42).unwrap();
+        let result = connector
+            .wait_reply("Test", Duration::from_secs(1), rx)
+            .await;
+        assert_eq!(result.unwrap(), 42);
+
+        let (tx, rx) = oneshot::channel::<u64>();
+        drop(tx);
+        let result = connector
+            .wait_reply("Test", Duration::from_secs(1), rx)
+            .await;
+        assert!(matches!(result, Err(ReplyError::Dropped { msg: "Test" })));
+        assert_eq!(
+            connector
+                .metrics
+                .replies_dropped
+                .get_or_create(&labels)
+                .get(),
+            1
+        );
+
+        let (_tx, rx) = oneshot::channel::<u64>();
+        let result = connector
+            .wait_reply("Test", Duration::from_millis(20), rx)
+            .await;
+        assert!(matches!(
+            result,
+            Err(ReplyError::TimedOut { msg: "Test", .. })
+        ));
+        assert_eq!(
+            connector
+                .metrics
+                .reply_timeouts
+                .get_or_create(&labels)
+                .get(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn drop_reply_on_timeout() {
+        let connector = connector();
+        let labels = ConnectorMetrics::labels("Test");
+
+        let (reply_to, rx) = oneshot::channel::<u64>();
+        let timed_out = ReplyError::TimedOut {
+            msg: "Test",
+            deadline: Duration::from_secs(1),
+        };
+
+        connector
+            .forward_reply(Err(timed_out), reply_to.into())
+            .unwrap();
+
+        assert!(rx.await.is_err(), "reply port is dropped");
+        assert_eq!(connector.metrics.unanswered.get_or_create(&labels).get(), 1);
+
+        // Other errors are reported to the caller
+        let (reply_to, _rx) = oneshot::channel::<u64>();
+        let dropped = ReplyError::Dropped { msg: "Test" };
+        assert!(connector
+            .forward_reply(Err(dropped), reply_to.into())
+            .is_err());
+    }
+
+    /// Stand-in for the consensus actor, which ignores all messages
+    struct Idle;
+
+    #[async_trait]
+    impl Actor for Idle {
+        type Msg = malachitebft_engine::consensus::Msg<TestContext>;
+        type State = ();
+        type Arguments = ();
+
+        async fn pre_start(
+            &self,
+            _myself: ActorRef<Self::Msg>,
+            _args: (),
+        ) -> Result<(), ActorProcessingErr> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn stops_node_when_consensus_ready_times_out() {
+        let (tx, mut rx) = mpsc::channel(1);
+
+        let deadlines = HostConfig {
+            consensus_ready: Duration::from_millis(20),
+            ..HostConfig::default()
+        };
+
+        let (consensus, consensus_handle) = Actor::spawn(None, Idle, ()).await.unwrap();
+        let connector = Connector::spawn(tx, deadlines, ConnectorMetrics::new(), Receipts::new())
+            .await
+            .unwrap();
+
+        connector
+            .cast(HostMsg::ConsensusReady(consensus.clone()))
+            .unwrap();
+
+        // Hold on to the reply without ever sending it
+        let _reply = rx.recv().await.unwrap();
+
+        consensus_handle.await.unwrap();
+        assert!(
+            rx.recv().await.is_none(),
+            "channel to the application is closed"
+        );
+    }
+}
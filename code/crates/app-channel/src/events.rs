@@ -0,0 +1,253 @@
+//! Subscription to the events emitted by consensus.
+
+use std::collections::BTreeSet;
+
+use derive_where::derive_where;
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::task::JoinHandle;
+
+use malachitebft_engine::util::events::TxEvent;
+pub use malachitebft_engine::util::events::{Event, EventKind};
+
+use crate::app::types::core::Context;
+
+/// Handle for subscribing to the events emitted by consensus
+#[derive_where(Clone)]
+pub struct Events<Ctx: Context> {
+    tx: TxEvent<Ctx>,
+    stopped: watch::Receiver<bool>,
+}
+
+impl<Ctx: Context> Events<Ctx> {
+    /// Along with the handle closing the subscriptions once the node has stopped
+    pub(crate) fn new(tx: TxEvent<Ctx>) -> (Self, CloseSubscriptions) {
+        let (stop, stopped) = watch::channel(false);
+        (Self { tx, stopped }, CloseSubscriptions(stop))
+    }
+
+    /// Subscribe to the events matching the given filter.
+    ///
+    /// Up to `capacity` notifications are buffered for this subscriber only,
+    /// on top of the events it has not been forwarded yet, so that a slow subscriber
+    /// neither blocks consensus nor other subscribers. Once it lags too far behind,
+    /// events are dropped and the subscriber is told how many with a [`Notification::Dropped`].
+    ///
+    /// Only events emitted after subscribing are received.
+    ///
+    /// # Panics
+    /// If `capacity` is zero.
+    pub fn subscribe(&self, filter: EventFilter, capacity: usize) -> Subscription<Ctx> {
+        let (tx, rx) = mpsc::channel(capacity);
+        let task = tokio::spawn(forward(
+            self.tx.subscribe(),
+            self.stopped.clone(),
+            filter,
+            tx,
+        ));
+
+        Subscription { rx, task }
+    }
+}
+
+/// Closes the subscriptions once the node has stopped, see [`NodeHandle::stop`](crate::NodeHandle::stop)
+pub(crate) struct CloseSubscriptions(watch::Sender<bool>);
+
+impl CloseSubscriptions {
+    /// Let the subscribers receive the events emitted so far, then close their subscriptions
+    pub(crate) fn close(&self) {
+        self.0.send_replace(true);
+    }
+}
+
+/// Which kinds of events a subscriber is interested in
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    /// `None` if all events are of interest
+    kinds: Option<BTreeSet<EventKind>>,
+}
+
+impl EventFilter {
+    /// Receive all events
+    pub fn all() -> Self {
+        Self { kinds: None }
+    }
+
+    /// Only receive events of the given kinds
+    pub fn only(kinds: impl IntoIterator<Item = EventKind>) -> Self {
+        Self {
+            kinds: Some(kinds.into_iter().collect()),
+        }
+    }
+
+    /// Whether the given event is of interest
+    pub fn matches<Ctx: Context>(&self, event: &Event<Ctx>) -> bool {
+        self.kinds
+            .as_ref()
+            .is_none_or(|kinds| kinds.contains(&event.kind()))
+    }
+}
+
+/// A notification received by a subscriber
+#[derive_where(Clone, Debug)]
+pub enum Notification<Ctx: Context> {
+    /// An event matching the subscription filter
+    Event(Event<Ctx>),
+
+    /// Some events were dropped because the subscriber could not keep up.
+    ///
+    /// The count includes dropped events which would not have matched the filter.
+    Dropped(u64),
+}
+
+/// A subscription to the events emitted by consensus.
+///
+/// Dropping the subscription unsubscribes from the events.
+pub struct Subscription<Ctx: Context> {
+    rx: mpsc::Receiver<Notification<Ctx>>,
+    task: JoinHandle<()>,
+}
+
+impl<Ctx: Context> Subscription<Ctx> {
+    /// Receive the next notification, or `None` once the node has been stopped with
+    /// [`NodeHandle::stop`](crate::NodeHandle::stop) and all the events emitted before
+    /// have been received
+    pub async fn recv(&mut self) -> Option<Notification<Ctx>> {
+        self.rx.recv().await
+    }
+}
+
+impl<Ctx: Context> Drop for Subscription<Ctx> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn forward<Ctx: Context>(
+    mut events: broadcast::Receiver<Event<Ctx>>,
+    mut stopped: watch::Receiver<bool>,
+    filter: EventFilter,
+    tx: mpsc::Sender<Notification<Ctx>>,
+) {
+    let mut dropped = 0;
+
+    loop {
+        // Events still pending when the node stops are forwarded first
+        let event = tokio::select! {
+            biased;
+
+            event = events.recv() => event,
+            _ = stopped.wait_for(|stopped| *stopped) => break,
+        };
+
+        let event = match event {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(count)) => {
+                dropped += count;
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        if !filter.matches(&event) {
+            continue;
+        }
+
+        // Waiting for the subscriber to make room lets events pile up in the broadcast
+        // channel instead, from which they get dropped once this subscriber lags too far behind.
+        if dropped > 0 {
+            if tx.send(Notification::Dropped(dropped)).await.is_err() {
+                return;
+            }
+
+            dropped = 0;
+        }
+
+        if tx.send(Notification::Event(event)).await.is_err() {
+            return;
+        }
+    }
+
+    if dropped > 0 {
+        let _ = tx.send(Notification::Dropped(dropped)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use malachitebft_test::{Height, TestContext};
+
+    use super::*;
+
+    fn started_height(height: u64) -> Event<TestContext> {
+        Event::StartedHeight(Height::new(height))
+    }
+
+    #[tokio::test]
+    async fn filters_events() {
+        let tx = TxEvent::new();
+        let (events, _close) = Events::new(tx.clone());
+
+        let mut sub = events.subscribe(EventFilter::only([EventKind::WalReplayDone]), 8);
+
+        tx.send(|| started_height(1));
+        tx.send(|| Event::WalReplayDone(Height::new(1)));
+
+        assert!(matches!(
+            sub.recv().await,
+            Some(Notification::Event(Event::WalReplayDone(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn notifies_dropped_events() {
+        let tx = TxEvent::with_capacity(4);
+        let (events, close) = Events::new(tx.clone());
+
+        let mut sub = events.subscribe(EventFilter::all(), 1);
+
+        for height in 1..=10 {
+            tx.send(|| started_height(height));
+        }
+
+        close.close();
+
+        let mut received = 0;
+        let mut dropped = 0;
+
+        while let Some(notification) = sub.recv().await {
+            match notification {
+                Notification::Event(_) => received += 1,
+                Notification::Dropped(count) => dropped += count,
+            }
+        }
+
+        assert!(dropped > 0);
+        assert_eq!(received + dropped, 10);
+    }
+
+    #[tokio::test]
+    async fn closes_once_node_stopped() {
+        let tx = TxEvent::new();
+        let (events, close) = Events::new(tx.clone());
+
+        let mut sub = events.subscribe(EventFilter::all(), 8);
+        tx.send(|| started_height(1));
+
+        // Events emitted before the node stopped are still received
+        close.close();
+
+        assert!(matches!(
+            sub.recv().await,
+            Some(Notification::Event(Event::StartedHeight(_)))
+        ));
+        assert!(sub.recv().await.is_none());
+
+        // Even though the event sender and the handle are still around
+        tx.send(|| started_height(2));
+        assert!(events
+            .subscribe(EventFilter::all(), 8)
+            .recv()
+            .await
+            .is_none());
+    }
+}
@@ -0,0 +1,115 @@
+//! Handle for stopping and restarting a node spawned with [`run`](crate::run).
+
+use core::future::Future;
+use core::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use ractor::{ActorRef, RactorErr};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use malachitebft_engine::consensus::ConsensusRef;
+use malachitebft_engine::host::HostRef;
+use malachitebft_engine::network::NetworkRef;
+use malachitebft_engine::sync::SyncRef;
+use malachitebft_engine::wal::{Msg as WalMsg, WalRef};
+
+use crate::app::types::core::Context;
+use crate::events::CloseSubscriptions;
+use crate::Channels;
+
+/// Maximum time given to each actor to stop, after which it is killed
+const STOP_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub(crate) type Restart<Ctx> = Arc<
+    dyn Fn(
+            <Ctx as Context>::Height,
+        )
+            -> Pin<Box<dyn Future<Output = Result<(Channels<Ctx>, NodeHandle<Ctx>)>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// Handle to the actors of a running node, returned by [`run`](crate::run).
+///
+/// Dropping the handle does not stop the node.
+pub struct NodeHandle<Ctx: Context> {
+    pub(crate) consensus: ConsensusRef<Ctx>,
+    pub(crate) close_subscriptions: CloseSubscriptions,
+    pub(crate) wal: WalRef<Ctx>,
+    pub(crate) sync: Option<SyncRef<Ctx>>,
+    pub(crate) host: HostRef<Ctx>,
+    pub(crate) network: NetworkRef<Ctx>,
+    pub(crate) rpc: Option<JoinHandle<()>>,
+    pub(crate) restart: Restart<Ctx>,
+}
+
+impl<Ctx: Context> NodeHandle<Ctx> {
+    /// Gracefully stop the node.
+    ///
+    /// Consensus is stopped once it is done processing its current input, the WAL is then
+    /// flushed to disk, and the network is closed. Returns once all actors have stopped,
+    /// after which the event subscriptions are closed.
+    ///
+    /// The application should stop handling [`AppMsg`](crate::AppMsg)s and release its own
+    /// resources, eg. its store, once the consensus channel is closed.
+    pub async fn stop(self) -> Result<()> {
+        info!("Stopping node");
+
+        stop_actor("consensus", &self.consensus).await;
+
+        // Stop the remaining actors even if the flush failed, and report the error afterwards
+        let flushed = match ractor::call!(self.wal, WalMsg::Flush) {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => Err(eyre!("Failed to flush WAL to disk: {e}")),
+            Err(e) => Err(eyre!("Failed to send Flush command to WAL: {e}")),
+        };
+
+        if let Some(sync) = &self.sync {
+            stop_actor("sync", sync).await;
+        }
+
+        stop_actor("host", &self.host).await;
+        stop_actor("network", &self.network).await;
+        stop_actor("WAL", &self.wal).await;
+
+        if let Some(rpc) = self.rpc {
+            rpc.abort();
+            let _ = rpc.await;
+        }
+
+        self.close_subscriptions.close();
+
+        info!("Node stopped");
+
+        flushed
+    }
+
+    /// Stop the node, and start it again at the given height within the same process,
+    /// with the same configuration. The WAL is replayed if it contains entries for that height.
+    ///
+    /// Returns the channels and handle of the restarted node. The application must release
+    /// any resource it holds exclusively, eg. its store, before the node is restarted.
+    pub async fn restart(self, height: Ctx::Height) -> Result<(Channels<Ctx>, NodeHandle<Ctx>)> {
+        let restart = Arc::clone(&self.restart);
+
+        self.stop().await?;
+
+        info!(%height, "Restarting node");
+        restart(height).await
+    }
+}
+
+async fn stop_actor<Msg>(name: &'static str, actor: &ActorRef<Msg>) {
+    match actor.stop_and_wait(None, Some(STOP_TIMEOUT)).await {
+        Ok(()) => (),
+        Err(RactorErr::Timeout) => {
+            warn!("Actor {name} did not stop within {STOP_TIMEOUT:?}, killing it");
+            let _ = actor.kill_and_wait(None).await;
+        }
+        // The actor has already stopped
+        Err(_) => (),
+    }
+}
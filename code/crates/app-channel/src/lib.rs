@@ -18,5 +18,15 @@ mod spawn;
 mod msgs;
 pub use msgs::{AppMsg, Channels, ConsensusMsg, NetworkMsg, Reply};
 
+pub mod events;
+
+pub use malachitebft_rpc::tx::{Receipts, TxReceipt};
+
+mod application;
+pub use application::{run_application, Application, DEFAULT_REPLY_TIMEOUT};
+
+mod handle;
+pub use handle::NodeHandle;
+
 mod run;
 pub use run::run;
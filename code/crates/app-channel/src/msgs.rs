@@ -5,13 +5,19 @@ use derive_where::derive_where;
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
 
-use malachitebft_engine::consensus::Msg as ConsensusActorMsg;
+use malachitebft_engine::consensus::{Msg as ConsensusActorMsg, ParamsUpdate};
 use malachitebft_engine::network::Msg as NetworkActorMsg;
+use malachitebft_rpc::tx::Receipts;
 
-use crate::app::types::core::{CommitCertificate, Context, Round, ValueId};
+use crate::app::types::core::{
+    CommitCertificate, Context, Round, SignedExtension, Validity, ValueId,
+};
 use crate::app::types::streaming::StreamMessage;
 use crate::app::types::sync::DecidedValue;
-use crate::app::types::{LocallyProposedValue, PeerId, ProposedValue};
+use crate::app::types::{
+    LocallyProposedValue, PeerId, ProposedValue, ValueValidity, VoteExtensions,
+};
+use crate::events::Events;
 
 pub type Reply<T> = oneshot::Sender<T>;
 
@@ -21,6 +27,13 @@ pub struct Channels<Ctx: Context> {
     pub consensus: mpsc::Receiver<AppMsg<Ctx>>,
     /// Channel for sending messages to the networking layer
     pub network: mpsc::Sender<NetworkMsg<Ctx>>,
+    /// Handle for subscribing to the events emitted by consensus
+    pub events: Events<Ctx>,
+    /// Receipts of the transactions submitted through the RPC server.
+    ///
+    /// The application records which transactions each value it proposes or receives contains,
+    /// with [`Receipts::proposed`], and these are reported as included once the value is decided.
+    pub receipts: Receipts,
 }
 
 /// Messages sent from consensus to the application.
@@ -56,6 +69,8 @@ pub enum AppMsg<Ctx: Context> {
         round: Round,
         /// Maximum time allowed for the application to respond
         timeout: Duration,
+        /// Vote extensions of the precommits which decided the previous height, if known
+        vote_extensions: VoteExtensions<Ctx>,
         /// Channel for sending back the value just built to consensus
         reply: Reply<LocallyProposedValue<Ctx>>,
     },
@@ -96,6 +111,60 @@ pub enum AppMsg<Ctx: Context> {
         reply: Reply<Option<ProposedValue<Ctx>>>,
     },
 
+    /// Requests the application to validate a value received in a proposal message.
+    ///
+    /// This only happens when values are carried by proposal messages rather than
+    /// streamed as proposal parts, ie. with the `ProposalOnly` value payload.
+    /// If the application replies with [`ValueValidity::Invalid`], this node prevotes nil.
+    ValidateProposal {
+        /// Height of the proposal
+        height: Ctx::Height,
+        /// Round of the proposal
+        round: Round,
+        /// Address of the proposer
+        proposer: Ctx::Address,
+        /// Value to validate
+        value: Ctx::Value,
+        /// Channel for sending back the validity of the value
+        reply: Reply<ValueValidity>,
+    },
+
+    /// Requests the application to provide an extension for this node's precommit for a value.
+    ///
+    /// The application MUST reply with the signed extension, or `None` if it does not extend votes.
+    /// The extensions of the precommits which decide a height are then given to the proposer
+    /// of the next height in [`AppMsg::GetValue`].
+    ExtendVote {
+        /// Height of the precommit
+        height: Ctx::Height,
+        /// Round of the precommit
+        round: Round,
+        /// ID of the value this node is precommitting for
+        value_id: ValueId<Ctx>,
+        /// Channel for sending back the extension, if any
+        reply: Reply<Option<SignedExtension<Ctx>>>,
+    },
+
+    /// Requests the application to verify the extension of a precommit received from a peer.
+    ///
+    /// If the application replies with [`Validity::Invalid`], the precommit is dropped
+    /// and does not count towards a decision. Precommits for a value without any extension
+    /// are verified as well, so that applications which require extensions can reject them.
+    VerifyVoteExtension {
+        /// Height of the precommit
+        height: Ctx::Height,
+        /// Round of the precommit
+        round: Round,
+        /// ID of the value the precommit is for
+        value_id: ValueId<Ctx>,
+        /// Address of the validator who sent the precommit
+        address: Ctx::Address,
+        /// Extension to verify, if any
+        extension: Option<SignedExtension<Ctx>>,
+        /// Channel for sending back the validity of the extension
+        reply: Reply<Validity>,
+    },
+
     /// Requests the validator set for a specific height
     GetValidatorSet {
         /// Height of the validator set to retrieve
@@ -146,20 +215,41 @@ pub enum AppMsg<Ctx: Context> {
         /// Channel for sending back the proposed value, if successfully decoded
         reply: Reply<ProposedValue<Ctx>>,
     },
+
+    /// Notifies the application that a transaction has been submitted through the RPC server.
+    ///
+    /// The application SHOULD check the transaction and add it to its mempool, and MUST reply
+    /// with the hash of the transaction if it was accepted, or with the reason why it was rejected.
+    /// When it proposes or receives a value including the transaction, the application SHOULD
+    /// record it with [`Receipts::proposed`] on the [`Channels::receipts`] handle, for the
+    /// transaction to be reported as included once that value is decided.
+    ///
+    /// Transactions are not gossiped to other nodes by consensus. Unless the application
+    /// propagates them itself, eg. with the `malachitebft-mempool` crate, a transaction is only
+    /// included once this node is the proposer, and never if this node is not a validator.
+    ReceivedTransaction {
+        /// Raw transaction bytes, as submitted by the client
+        tx: Bytes,
+        /// Channel for sending back the hash of the accepted transaction, or the rejection reason
+        reply: Reply<Result<Bytes, String>>,
+    },
 }
 
 /// Messages sent from the application to consensus.
 #[derive_where(Debug)]
 pub enum ConsensusMsg<Ctx: Context> {
     /// Instructs consensus to start a new height with the given validator set.
-    StartHeight(Ctx::Height, Ctx::ValidatorSet),
+    ///
+    /// Any consensus parameters set in the [`ParamsUpdate`] are changed from that height onwards.
+    /// Use [`ParamsUpdate::default()`] to keep the current parameters.
+    StartHeight(Ctx::Height, Ctx::ValidatorSet, ParamsUpdate),
 }
 
 impl<Ctx: Context> From<ConsensusMsg<Ctx>> for ConsensusActorMsg<Ctx> {
     fn from(msg: ConsensusMsg<Ctx>) -> ConsensusActorMsg<Ctx> {
         match msg {
-            ConsensusMsg::StartHeight(height, validator_set) => {
-                ConsensusActorMsg::StartHeight(height, validator_set)
+            ConsensusMsg::StartHeight(height, validator_set, params) => {
+                ConsensusActorMsg::StartHeight(height, validator_set, params)
             }
         }
     }
@@ -1,19 +1,27 @@
 //! Run Malachite consensus with the given configuration and context.
-//! Provides the application with a channel for receiving messages from consensus.
+//! Provides the application with a channel for receiving messages from consensus,
+//! and a handle for stopping or restarting the node.
 
 use std::path::PathBuf;
+use std::sync::Arc;
 
-use eyre::Result;
+use eyre::{Result, WrapErr};
+use tokio::sync::mpsc;
 
 use crate::app::types::codec::{ConsensusCodec, SyncCodec, WalCodec};
 use crate::app::types::config::Config as NodeConfig;
 use crate::app::types::core::Context;
 use crate::app::types::metrics::{Metrics, SharedRegistry};
-use crate::spawn::{spawn_host_actor, spawn_network_actor};
+use crate::connector::ConnectorMetrics;
+use crate::events::Events;
+use crate::handle::{NodeHandle, Restart};
+use crate::spawn::{spawn_host_actor, spawn_network_actor, spawn_tx_ingest};
 use crate::{app, Channels};
 
 use malachitebft_app::{spawn_consensus_actor, spawn_sync_actor, spawn_wal_actor};
 use malachitebft_engine::util::events::TxEvent;
+use malachitebft_rpc as rpc;
+use malachitebft_rpc::tx::Receipts;
 
 #[tracing::instrument("node", skip_all, fields(moniker = %cfg.moniker))]
 pub async fn run<Node, Ctx, Codec>(
@@ -24,16 +32,37 @@ pub async fn run<Node, Ctx, Codec>(
     private_key_file: PathBuf,
     start_height: Option<Ctx::Height>,
     initial_validator_set: Ctx::ValidatorSet,
-) -> Result<Channels<Ctx>>
+) -> Result<(Channels<Ctx>, NodeHandle<Ctx>)>
 where
     Ctx: Context,
-    Node: app::Node<Context = Ctx>,
+    Node: app::Node<Context = Ctx> + Clone + Send + Sync + 'static,
     Codec: WalCodec<Ctx> + Clone,
     Codec: ConsensusCodec<Ctx>,
     Codec: SyncCodec<Ctx>,
 {
+    let restart = restart_with(
+        ctx.clone(),
+        codec.clone(),
+        node.clone(),
+        cfg.clone(),
+        private_key_file.clone(),
+        initial_validator_set.clone(),
+    );
+
     let start_height = start_height.unwrap_or_default();
 
+    // Bind the RPC server before spawning any actor, so that we fail early
+    // if its address is already in use
+    let rpc_listener = if cfg.rpc.enabled {
+        let listener = rpc::bind(&cfg.rpc)
+            .await
+            .wrap_err_with(|| format!("Failed to bind RPC server to {}", cfg.rpc.listen_addr))?;
+
+        Some(listener)
+    } else {
+        None
+    };
+
     let registry = SharedRegistry::global().with_moniker(cfg.moniker.as_str());
     let metrics = Metrics::register(&registry);
 
@@ -44,13 +73,20 @@ where
     let keypair = node.get_keypair(private_key);
 
     // Spawn consensus gossip
+    let home_dir = node.get_home_dir();
+
     let (network, network_tx) =
-        spawn_network_actor(&cfg, keypair, &registry, codec.clone()).await?;
+        spawn_network_actor(&cfg, keypair, &home_dir, &registry, codec.clone()).await?;
 
-    let wal = spawn_wal_actor(&ctx, codec, &node.get_home_dir(), &registry).await?;
+    let wal = spawn_wal_actor(&ctx, codec, &home_dir, &registry).await?;
 
     // Spawn the host actor
-    let (connector, consensus_rx) = spawn_host_actor(metrics.clone()).await?;
+    let connector_metrics = ConnectorMetrics::register(&registry);
+    let (consensus_tx, consensus_rx) = mpsc::channel(128);
+    let tx_ingest_app = consensus_tx.downgrade();
+    let receipts = Receipts::new();
+    let connector =
+        spawn_host_actor(consensus_tx, cfg.host, connector_metrics, receipts.clone()).await?;
 
     let sync = spawn_sync_actor(
         ctx.clone(),
@@ -61,24 +97,90 @@ where
     )
     .await?;
 
+    let tx_event = TxEvent::new();
+
     // Spawn consensus
-    let _consensus = spawn_consensus_actor(
+    let consensus = spawn_consensus_actor(
         start_height,
         initial_validator_set,
         address,
         ctx,
         cfg,
-        network,
-        connector,
-        wal,
+        network.clone(),
+        connector.clone(),
+        wal.clone(),
         sync.clone(),
         metrics,
-        TxEvent::new(),
+        tx_event.clone(),
     )
     .await?;
 
-    Ok(Channels {
+    // Spawn the RPC server
+    let rpc = rpc_listener.map(|listener| {
+        let handles = rpc::Handles {
+            consensus: consensus.clone(),
+            host: connector.clone(),
+            network: network.clone(),
+            sync: sync.clone(),
+            events: tx_event.clone(),
+            transactions: Some(spawn_tx_ingest(tx_ingest_app, receipts.clone())),
+        };
+
+        tokio::spawn(async move {
+            if let Err(e) = rpc::serve(listener, handles).await {
+                tracing::error!("RPC server failed: {e}");
+            }
+        })
+    });
+
+    let (events, close_subscriptions) = Events::new(tx_event);
+
+    let channels = Channels {
         consensus: consensus_rx,
         network: network_tx,
+        events,
+        receipts,
+    };
+
+    let handle = NodeHandle {
+        consensus,
+        close_subscriptions,
+        wal,
+        sync,
+        host: connector,
+        network,
+        rpc,
+        restart,
+    };
+
+    Ok((channels, handle))
+}
+
+/// Run the node again with the same arguments, starting at the given height
+fn restart_with<Node, Ctx, Codec>(
+    ctx: Ctx,
+    codec: Codec,
+    node: Node,
+    cfg: NodeConfig,
+    private_key_file: PathBuf,
+    initial_validator_set: Ctx::ValidatorSet,
+) -> Restart<Ctx>
+where
+    Ctx: Context,
+    Node: app::Node<Context = Ctx> + Clone + Send + Sync + 'static,
+    Codec: WalCodec<Ctx> + Clone,
+    Codec: ConsensusCodec<Ctx>,
+    Codec: SyncCodec<Ctx>,
+{
+    Arc::new(move |height| {
+        Box::pin(run(
+            ctx.clone(),
+            codec.clone(),
+            node.clone(),
+            cfg.clone(),
+            private_key_file.clone(),
+            Some(height),
+            initial_validator_set.clone(),
+        ))
     })
 }
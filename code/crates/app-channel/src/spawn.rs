@@ -1,35 +1,92 @@
 //! Utility functions for spawning the actor system and connecting it to the application.
 
+use std::path::Path;
+
 use eyre::Result;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 
 use malachitebft_app::types::metrics::SharedRegistry;
 use malachitebft_app::types::Keypair;
-use malachitebft_config::Config as NodeConfig;
+use malachitebft_config::{Config as NodeConfig, HostConfig};
 use malachitebft_engine::consensus::ConsensusCodec;
 use malachitebft_engine::host::HostRef;
 use malachitebft_engine::network::NetworkRef;
 use malachitebft_engine::sync::SyncCodec;
+use malachitebft_rpc::tx::{Receipts, SubmittedTx, TxIngest, SUBMIT_TIMEOUT};
 
 use crate::app::types::core::Context;
-use crate::app::types::metrics::Metrics;
-use crate::connector::Connector;
+use crate::connector::{Connector, ConnectorMetrics};
 use crate::{AppMsg, NetworkMsg};
 
 pub async fn spawn_host_actor<Ctx>(
-    metrics: Metrics,
-) -> Result<(HostRef<Ctx>, mpsc::Receiver<AppMsg<Ctx>>)>
+    tx: mpsc::Sender<AppMsg<Ctx>>,
+    deadlines: HostConfig,
+    metrics: ConnectorMetrics,
+    receipts: Receipts,
+) -> Result<HostRef<Ctx>>
 where
     Ctx: Context,
 {
-    let (tx, rx) = mpsc::channel(128);
-    let actor_ref = Connector::spawn(tx, metrics).await?;
-    Ok((actor_ref, rx))
+    let actor_ref = Connector::spawn(tx, deadlines, metrics, receipts).await?;
+    Ok(actor_ref)
+}
+
+/// Maximum number of submitted transactions waiting to be forwarded to the application,
+/// further submissions are turned away until the application catches up
+const MAX_PENDING_TXS: usize = 128;
+
+/// Forward the transactions submitted through the RPC server to the application.
+///
+/// Transactions are forwarded one at a time, once the application handled the previous one,
+/// so that at most one transaction is queued in front of the consensus messages.
+///
+/// Only a weak sender is kept, so that the application channel
+/// still closes once the host actor has stopped.
+pub fn spawn_tx_ingest<Ctx>(app: mpsc::WeakSender<AppMsg<Ctx>>, receipts: Receipts) -> TxIngest
+where
+    Ctx: Context,
+{
+    let (tx, mut rx) = mpsc::channel::<SubmittedTx>(MAX_PENDING_TXS);
+
+    tokio::spawn(async move {
+        while let Some(SubmittedTx { tx, reply }) = rx.recv().await {
+            let Some(app) = app.upgrade() else {
+                break;
+            };
+
+            let (app_reply, app_rx) = oneshot::channel();
+
+            if let Err(e) = app
+                .send(AppMsg::ReceivedTransaction {
+                    tx,
+                    reply: app_reply,
+                })
+                .await
+            {
+                tracing::error!("Failed to forward transaction to the application: {e}");
+                break;
+            }
+
+            drop(app);
+
+            // The client is told the application is unavailable if it drops the reply,
+            // and has given up on the transaction once the deadline has passed
+            if let Ok(Ok(result)) = tokio::time::timeout(SUBMIT_TIMEOUT, app_rx).await {
+                let _ = reply.send(result);
+            }
+        }
+    });
+
+    TxIngest {
+        submit: tx,
+        receipts,
+    }
 }
 
 pub async fn spawn_network_actor<Ctx, Codec>(
     cfg: &NodeConfig,
     keypair: Keypair,
+    home_dir: &Path,
     registry: &SharedRegistry,
     codec: Codec,
 ) -> Result<(NetworkRef<Ctx>, mpsc::Sender<NetworkMsg<Ctx>>)>
@@ -40,7 +97,8 @@ where
 {
     let (tx, mut rx) = mpsc::channel::<NetworkMsg<Ctx>>(1);
 
-    let actor_ref = malachitebft_app::spawn_network_actor(cfg, keypair, registry, codec).await?;
+    let actor_ref =
+        malachitebft_app::spawn_network_actor(cfg, keypair, home_dir, registry, codec).await?;
 
     tokio::spawn({
         let actor_ref = actor_ref.clone();
@@ -55,3 +113,47 @@ where
 
     Ok((actor_ref, tx))
 }
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use malachitebft_test::TestContext;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn forward_one_transaction_at_a_time() {
+        let (app_tx, mut app_rx) = mpsc::channel::<AppMsg<TestContext>>(8);
+        let ingest = spawn_tx_ingest(app_tx.downgrade(), Receipts::new());
+
+        let mut replies = Vec::new();
+        for i in 0..2u8 {
+            let (reply, rx) = oneshot::channel();
+            ingest
+                .submit
+                .try_send(SubmittedTx {
+                    tx: Bytes::from(vec![i]),
+                    reply,
+                })
+                .unwrap();
+            replies.push(rx);
+        }
+
+        let Some(AppMsg::ReceivedTransaction { tx, reply }) = app_rx.recv().await else {
+            panic!("expected a transaction");
+        };
+        assert_eq!(tx, Bytes::from(vec![0]));
+
+        // The second transaction waits for the application to handle the first one
+        tokio::task::yield_now().await;
+        assert!(app_rx.try_recv().is_err());
+
+        reply.send(Ok(tx.clone())).unwrap();
+        assert_eq!(replies.remove(0).await.unwrap(), Ok(tx));
+
+        let Some(AppMsg::ReceivedTransaction { tx, .. }) = app_rx.recv().await else {
+            panic!("expected a transaction");
+        };
+        assert_eq!(tx, Bytes::from(vec![1]));
+    }
+}
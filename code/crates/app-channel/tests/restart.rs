@@ -0,0 +1,258 @@
+//! Restart a single-validator node within the same process, and check that
+//! consensus resumes at the given height by replaying the WAL.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rand::{CryptoRng, RngCore};
+use tokio::sync::mpsc;
+
+use informalsystems_malachitebft_app_channel::app::types::config::Config;
+use informalsystems_malachitebft_app_channel::app::types::core::{
+    CommitCertificate, Validity, VotingPower,
+};
+use informalsystems_malachitebft_app_channel::app::types::{
+    Keypair, LocallyProposedValue, ValueValidity,
+};
+use informalsystems_malachitebft_app_channel::app::Node;
+use informalsystems_malachitebft_app_channel::events::{
+    Event, EventFilter, EventKind, Notification,
+};
+use informalsystems_malachitebft_app_channel::{AppMsg, Channels, ConsensusMsg};
+use malachitebft_test::codec::proto::ProtobufCodec;
+use malachitebft_test::{
+    Address, Genesis, Height, PrivateKey, PublicKey, TestContext, Validator, ValidatorSet, Value,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone)]
+struct TestNode {
+    home_dir: PathBuf,
+}
+
+#[async_trait]
+impl Node for TestNode {
+    type Context = TestContext;
+    type Genesis = Genesis;
+    type PrivateKeyFile = PrivateKey;
+
+    fn get_home_dir(&self) -> PathBuf {
+        self.home_dir.clone()
+    }
+
+    fn generate_private_key<R>(&self, rng: R) -> PrivateKey
+    where
+        R: RngCore + CryptoRng,
+    {
+        PrivateKey::generate(rng)
+    }
+
+    fn get_address(&self, pk: &PublicKey) -> Address {
+        Address::from_public_key(pk)
+    }
+
+    fn get_public_key(&self, pk: &PrivateKey) -> PublicKey {
+        pk.public_key()
+    }
+
+    fn get_keypair(&self, pk: PrivateKey) -> Keypair {
+        Keypair::ed25519_from_bytes(pk.inner().to_bytes()).unwrap()
+    }
+
+    fn load_private_key(&self, file: PrivateKey) -> PrivateKey {
+        file
+    }
+
+    fn load_private_key_file(&self, path: impl AsRef<Path>) -> std::io::Result<PrivateKey> {
+        let private_key = std::fs::read_to_string(path)?;
+        serde_json::from_str(&private_key).map_err(|e| e.into())
+    }
+
+    fn make_private_key_file(&self, private_key: PrivateKey) -> PrivateKey {
+        private_key
+    }
+
+    fn load_genesis(&self, path: impl AsRef<Path>) -> std::io::Result<Genesis> {
+        let genesis = std::fs::read_to_string(path)?;
+        serde_json::from_str(&genesis).map_err(|e| e.into())
+    }
+
+    fn make_genesis(&self, validators: Vec<(PublicKey, VotingPower)>) -> Genesis {
+        let validators = validators
+            .into_iter()
+            .map(|(pk, vp)| Validator::new(pk, vp));
+
+        Genesis {
+            validator_set: ValidatorSet::new(validators),
+        }
+    }
+
+    async fn run(self) -> eyre::Result<()> {
+        Ok(())
+    }
+}
+
+/// Answer the messages sent by consensus, starting at `start_height`.
+///
+/// Every decision is reported on `decided`. The next height is started
+/// after every decision but the one at `stop_height`, at which the node stays.
+async fn serve(
+    mut channels: Channels<TestContext>,
+    validator_set: ValidatorSet,
+    start_height: Height,
+    stop_height: Height,
+    decided: mpsc::UnboundedSender<CommitCertificate<TestContext>>,
+) {
+    let start =
+        |height| ConsensusMsg::StartHeight(height, validator_set.clone(), Default::default());
+
+    while let Some(msg) = channels.consensus.recv().await {
+        match msg {
+            AppMsg::ConsensusReady { reply } => {
+                let _ = reply.send(start(start_height));
+            }
+            AppMsg::StartedRound { .. } | AppMsg::RestreamProposal { .. } => {}
+            AppMsg::GetValue {
+                height,
+                round,
+                reply,
+                ..
+            } => {
+                let value = Value::new(height.as_u64());
+                let _ = reply.send(LocallyProposedValue::new(height, round, value));
+            }
+            AppMsg::GetHistoryMinHeight { reply } => {
+                let _ = reply.send(Height::default());
+            }
+            AppMsg::ReceivedProposalPart { reply, .. } => {
+                let _ = reply.send(None);
+            }
+            AppMsg::ValidateProposal { reply, .. } => {
+                let _ = reply.send(ValueValidity::Valid);
+            }
+            AppMsg::ExtendVote { reply, .. } => {
+                let _ = reply.send(None);
+            }
+            AppMsg::VerifyVoteExtension { reply, .. } => {
+                let _ = reply.send(Validity::Valid);
+            }
+            AppMsg::GetValidatorSet { reply, .. } => {
+                let _ = reply.send(validator_set.clone());
+            }
+            AppMsg::Decided { certificate, reply } => {
+                let height = certificate.height;
+                let _ = decided.send(certificate);
+
+                if height < stop_height {
+                    let _ = reply.send(start(height.increment()));
+                }
+            }
+            AppMsg::GetDecidedValue { reply, .. } => {
+                let _ = reply.send(None);
+            }
+            AppMsg::ProcessSyncedValue { .. } => {}
+            AppMsg::ReceivedTransaction { reply, .. } => {
+                let _ = reply.send(Err("Transactions are not supported".to_string()));
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn restart_replays_wal() {
+    let home = tempfile::tempdir().unwrap();
+    let node = TestNode {
+        home_dir: home.path().to_owned(),
+    };
+
+    let private_key = node.generate_private_key(rand::thread_rng());
+    let private_key_file = home.path().join("priv_validator_key.json");
+    std::fs::write(
+        &private_key_file,
+        serde_json::to_string(&private_key).unwrap(),
+    )
+    .unwrap();
+
+    let validator_set = ValidatorSet::new([Validator::new(private_key.public_key(), 1)]);
+
+    let mut cfg = Config {
+        moniker: "restart-test".to_string(),
+        ..Config::default()
+    };
+    cfg.consensus.p2p.listen_addr = "/ip4/127.0.0.1/tcp/0".parse().unwrap();
+    cfg.sync.enabled = false;
+
+    let stop_height = Height::new(3);
+
+    let (channels, handle) = informalsystems_malachitebft_app_channel::run(
+        TestContext::new(private_key.clone()),
+        ProtobufCodec,
+        node,
+        cfg,
+        private_key_file,
+        Some(Height::new(1)),
+        validator_set.clone(),
+    )
+    .await
+    .unwrap();
+
+    let (decided_tx, mut decided_rx) = mpsc::unbounded_channel();
+    tokio::spawn(serve(
+        channels,
+        validator_set.clone(),
+        Height::new(1),
+        stop_height,
+        decided_tx.clone(),
+    ));
+
+    // Decide up to `stop_height`, without starting the next height,
+    // so that the WAL still holds the entries of that height
+    let decided = loop {
+        let certificate = tokio::time::timeout(TIMEOUT, decided_rx.recv())
+            .await
+            .expect("node decides before the timeout")
+            .unwrap();
+
+        if certificate.height == stop_height {
+            break certificate;
+        }
+    };
+
+    let (channels, handle) = handle.restart(stop_height).await.unwrap();
+
+    let mut replay = channels
+        .events
+        .subscribe(EventFilter::only([EventKind::WalReplayBegin]), 16);
+
+    tokio::spawn(serve(
+        channels,
+        validator_set,
+        stop_height,
+        stop_height,
+        decided_tx,
+    ));
+
+    let notification = tokio::time::timeout(TIMEOUT, replay.recv())
+        .await
+        .expect("WAL is replayed before the timeout");
+
+    match notification {
+        Some(Notification::Event(Event::WalReplayBegin(height, count))) => {
+            assert_eq!(height, stop_height);
+            assert!(count > 0, "the entries of the last height are replayed");
+        }
+        other => panic!("expected WAL replay to begin, got {other:?}"),
+    }
+
+    let redecided = tokio::time::timeout(TIMEOUT, decided_rx.recv())
+        .await
+        .expect("node decides again after the restart")
+        .unwrap();
+
+    assert_eq!(redecided.height, stop_height);
+    assert_eq!(redecided.value_id, decided.value_id);
+
+    handle.stop().await.unwrap();
+}
@@ -17,9 +17,8 @@ pub mod types;
 mod spawn;
 pub use spawn::{spawn_consensus_actor, spawn_network_actor, spawn_sync_actor, spawn_wal_actor};
 
-pub mod streaming {
-    pub use malachitebft_engine::util::streaming::*;
-}
+pub mod store;
+pub mod streaming;
 
 pub mod host {
     // TODO: Move this under `types`
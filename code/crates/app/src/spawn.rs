@@ -12,10 +12,16 @@ use malachitebft_engine::network::{Network, NetworkRef};
 use malachitebft_engine::sync::{Params as SyncParams, Sync, SyncCodec, SyncRef};
 use malachitebft_engine::util::events::TxEvent;
 use malachitebft_engine::wal::{Wal, WalCodec, WalRef};
-use malachitebft_network::{Config as NetworkConfig, DiscoveryConfig, GossipSubConfig, Keypair};
-
-use crate::types::config::{Config as NodeConfig, PubSubProtocol, SyncConfig, TransportProtocol};
-use crate::types::core::Context;
+use malachitebft_network::{
+    AddressBookConfig, Config as NetworkConfig, DiscoveryConfig, GossipSubConfig, Keypair,
+    PeerLists,
+};
+
+use crate::types::config::{
+    Compression, CompressionConfig, Config as NodeConfig, NatConfig, PubSubProtocol, SyncConfig,
+    TransportProtocol,
+};
+use crate::types::core::{Context, SynchronyParams};
 use crate::types::metrics::{Metrics, SharedRegistry};
 use crate::types::sync;
 use crate::types::ValuePayload;
@@ -23,6 +29,7 @@ use crate::types::ValuePayload;
 pub async fn spawn_network_actor<Ctx, Codec>(
     cfg: &NodeConfig,
     keypair: Keypair,
+    home_dir: &Path,
     registry: &SharedRegistry,
     codec: Codec,
 ) -> Result<NetworkRef<Ctx>>
@@ -31,11 +38,18 @@ where
     Codec: ConsensusCodec<Ctx>,
     Codec: SyncCodec<Ctx>,
 {
-    let config = make_gossip_config(cfg);
-
-    Network::spawn(keypair, config, registry.clone(), codec, Span::current())
-        .await
-        .map_err(Into::into)
+    let config = make_gossip_config(cfg, home_dir);
+
+    Network::spawn(
+        keypair,
+        config,
+        cfg.consensus.erasure_coding,
+        registry.clone(),
+        codec,
+        Span::current(),
+    )
+    .await
+    .map_err(Into::into)
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -62,12 +76,18 @@ where
         config::ValuePayload::ProposalAndParts => ValuePayload::ProposalAndParts,
     };
 
+    let pbts = cfg.consensus.pbts;
+    let synchrony_params = pbts
+        .enabled
+        .then(|| SynchronyParams::new(pbts.precision, pbts.message_delay));
+
     let consensus_params = ConsensusParams {
         initial_height,
         initial_validator_set,
         address,
         threshold_params: Default::default(),
         value_payload,
+        synchrony_params,
     };
 
     Consensus::spawn(
@@ -132,22 +152,32 @@ where
     Ok(Some(actor_ref))
 }
 
-fn make_gossip_config(cfg: &NodeConfig) -> NetworkConfig {
+fn make_gossip_config(cfg: &NodeConfig, home_dir: &Path) -> NetworkConfig {
     NetworkConfig {
-        listen_addr: cfg.consensus.p2p.listen_addr.clone(),
+        listen_addrs: cfg.consensus.p2p.listen_addrs(),
         persistent_peers: cfg.consensus.p2p.persistent_peers.clone(),
+        peer_lists: PeerLists {
+            allowed: cfg.consensus.p2p.peer_lists.allowed.clone(),
+            denied: cfg.consensus.p2p.peer_lists.denied.clone(),
+            private: cfg.consensus.p2p.peer_lists.private.clone(),
+            unconditional: cfg.consensus.p2p.peer_lists.unconditional.clone(),
+        },
         discovery: DiscoveryConfig {
             enabled: cfg.consensus.p2p.discovery.enabled,
+            hidden: cfg.consensus.p2p.discovery.hidden,
             ..Default::default()
         },
+        address_book: make_address_book_config(cfg, home_dir),
         idle_connection_timeout: Duration::from_secs(15 * 60),
         transport: match cfg.consensus.p2p.transport {
             TransportProtocol::Tcp => malachitebft_network::TransportProtocol::Tcp,
             TransportProtocol::Quic => malachitebft_network::TransportProtocol::Quic,
+            TransportProtocol::Both => malachitebft_network::TransportProtocol::Both,
         },
         pubsub_protocol: match cfg.consensus.p2p.protocol {
             PubSubProtocol::GossipSub(_) => malachitebft_network::PubSubProtocol::GossipSub,
             PubSubProtocol::Broadcast => malachitebft_network::PubSubProtocol::Broadcast,
+            PubSubProtocol::Direct => malachitebft_network::PubSubProtocol::Direct,
         },
         gossipsub: match cfg.consensus.p2p.protocol {
             PubSubProtocol::GossipSub(config) => GossipSubConfig {
@@ -156,9 +186,49 @@ fn make_gossip_config(cfg: &NodeConfig) -> NetworkConfig {
                 mesh_n_low: config.mesh_n_low(),
                 mesh_outbound_min: config.mesh_outbound_min(),
             },
-            PubSubProtocol::Broadcast => GossipSubConfig::default(),
+            PubSubProtocol::Broadcast | PubSubProtocol::Direct => GossipSubConfig::default(),
         },
         rpc_max_size: cfg.consensus.p2p.rpc_max_size.as_u64() as usize,
         pubsub_max_size: cfg.consensus.p2p.pubsub_max_size.as_u64() as usize,
+        rate_limits: cfg.consensus.p2p.rate_limits,
+        compression: make_compression_config(cfg.consensus.p2p.compression),
+        nat: make_nat_config(&cfg.consensus.p2p.nat),
+        direct_streams: cfg.consensus.erasure_coding.enabled,
+    }
+}
+
+fn make_address_book_config(cfg: &NodeConfig, home_dir: &Path) -> Option<AddressBookConfig> {
+    let address_book = cfg.consensus.p2p.discovery.address_book;
+
+    address_book.enabled.then(|| AddressBookConfig {
+        path: home_dir.join("address_book.json"),
+        max_age: address_book.max_age,
+    })
+}
+
+fn make_nat_config(config: &NatConfig) -> malachitebft_network::NatConfig {
+    malachitebft_network::NatConfig {
+        autonat: config.autonat,
+        relay_server: config.relay_server,
+        relay_allowed_peers: config.relay_allowed_peers.clone(),
+        relay_client: config.relay_client,
+        relays: config.relays.clone(),
+        hole_punching: config.hole_punching,
+    }
+}
+
+fn make_compression_config(config: CompressionConfig) -> malachitebft_network::CompressionConfig {
+    malachitebft_network::CompressionConfig {
+        consensus: make_compression(config.consensus),
+        proposal_parts: make_compression(config.proposal_parts),
+        sync: make_compression(config.sync),
+        rpc: make_compression(config.rpc),
+    }
+}
+
+fn make_compression(compression: Compression) -> malachitebft_network::Compression {
+    match compression {
+        Compression::None => malachitebft_network::Compression::None,
+        Compression::Lz4 => malachitebft_network::Compression::Lz4,
     }
 }
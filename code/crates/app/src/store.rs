@@ -0,0 +1,435 @@
+//! Persistent storage of the values decided by consensus, and of the proposals not decided yet.
+
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use redb::ReadableTable;
+use thiserror::Error;
+
+use malachitebft_codec::Codec;
+use malachitebft_core_consensus::ProposedValue;
+use malachitebft_core_types::{CommitCertificate, Context, Height, Round};
+use malachitebft_sync::DecidedValue;
+
+/// Storage of the decided values, to serve them to peers which are syncing,
+/// and of the undecided proposals for the current height, to recover them after a crash.
+#[async_trait]
+pub trait DecidedValueStore<Ctx>: Send + Sync + 'static
+where
+    Ctx: Context,
+{
+    type Error: core::error::Error + Send + Sync + 'static;
+
+    /// The value decided at the given height, if it is still in the store.
+    /// This is the answer to `AppMsg::GetDecidedValue`.
+    async fn get_decided_value(
+        &self,
+        height: Ctx::Height,
+    ) -> Result<Option<DecidedValue<Ctx>>, Self::Error>;
+
+    /// Store a decided value at the height of its certificate.
+    ///
+    /// The undecided proposals up to that height are removed, as they are not needed anymore.
+    async fn store_decided_value(
+        &self,
+        decided_value: DecidedValue<Ctx>,
+    ) -> Result<(), Self::Error>;
+
+    /// The lowest height of the decided values in the store
+    async fn min_decided_height(&self) -> Result<Option<Ctx::Height>, Self::Error>;
+
+    /// The highest height of the decided values in the store
+    async fn max_decided_height(&self) -> Result<Option<Ctx::Height>, Self::Error>;
+
+    /// The earliest height for which we can provide a decided value, or the default height if the store is empty.
+    /// This is the answer to `AppMsg::GetHistoryMinHeight`.
+    async fn history_min_height(&self) -> Result<Ctx::Height, Self::Error> {
+        Ok(self.min_decided_height().await?.unwrap_or_default())
+    }
+
+    /// Store a proposal for its height and round, replacing any proposal already stored for them
+    async fn store_undecided_proposal(&self, value: ProposedValue<Ctx>) -> Result<(), Self::Error>;
+
+    /// The proposal stored for the given height and round, if any
+    async fn get_undecided_proposal(
+        &self,
+        height: Ctx::Height,
+        round: Round,
+    ) -> Result<Option<ProposedValue<Ctx>>, Self::Error>;
+
+    /// Remove the decided values and undecided proposals below the given height
+    async fn prune(&self, retain_height: Ctx::Height) -> Result<(), Self::Error>;
+}
+
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error("Database error: {0}")]
+    Database(#[from] redb::DatabaseError),
+
+    #[error("Storage error: {0}")]
+    Storage(#[from] redb::StorageError),
+
+    #[error("Table error: {0}")]
+    Table(#[from] redb::TableError),
+
+    #[error("Commit error: {0}")]
+    Commit(#[from] redb::CommitError),
+
+    #[error("Transaction error: {0}")]
+    Transaction(Box<redb::TransactionError>),
+
+    #[error("Failed to encode/decode: {0}")]
+    Codec(String),
+
+    #[error("Failed to join on task: {0}")]
+    TaskJoin(#[from] tokio::task::JoinError),
+}
+
+impl From<redb::TransactionError> for StoreError {
+    fn from(e: redb::TransactionError) -> Self {
+        Self::Transaction(Box::new(e))
+    }
+}
+
+/// Heights are stored by their numeric value, as keys sort in the same order
+type HeightKey = u64;
+
+/// Rounds are stored by their numeric value, with `Round::Nil` as -1
+type UndecidedProposalKey = (u64, i64);
+
+const CERTIFICATES_TABLE: redb::TableDefinition<HeightKey, Vec<u8>> =
+    redb::TableDefinition::new("certificates");
+
+const DECIDED_VALUES_TABLE: redb::TableDefinition<HeightKey, Vec<u8>> =
+    redb::TableDefinition::new("decided_values");
+
+const UNDECIDED_PROPOSALS_TABLE: redb::TableDefinition<UndecidedProposalKey, Vec<u8>> =
+    redb::TableDefinition::new("undecided_proposals");
+
+fn undecided_key<Ctx: Context>(height: Ctx::Height, round: Round) -> UndecidedProposalKey {
+    (height.as_u64(), round.as_i64())
+}
+
+struct Db<Ctx, C> {
+    db: redb::Database,
+    codec: C,
+    _marker: PhantomData<fn() -> Ctx>,
+}
+
+impl<Ctx, C> Db<Ctx, C>
+where
+    Ctx: Context,
+    C: Codec<CommitCertificate<Ctx>> + Codec<ProposedValue<Ctx>>,
+{
+    fn new(path: impl AsRef<Path>, codec: C) -> Result<Self, StoreError> {
+        Ok(Self {
+            db: redb::Database::create(path)?,
+            codec,
+            _marker: PhantomData,
+        })
+    }
+
+    fn encode<T>(&self, value: &T) -> Result<Vec<u8>, StoreError>
+    where
+        C: Codec<T>,
+    {
+        <C as Codec<T>>::encode(&self.codec, value)
+            .map(|bytes| bytes.to_vec())
+            .map_err(|e| StoreError::Codec(e.to_string()))
+    }
+
+    fn decode<T>(&self, bytes: Vec<u8>) -> Result<T, StoreError>
+    where
+        C: Codec<T>,
+    {
+        <C as Codec<T>>::decode(&self.codec, Bytes::from(bytes))
+            .map_err(|e| StoreError::Codec(e.to_string()))
+    }
+
+    fn create_tables(&self) -> Result<(), StoreError> {
+        let tx = self.db.begin_write()?;
+        // Implicitly creates the tables if they do not exist yet
+        let _ = tx.open_table(DECIDED_VALUES_TABLE)?;
+        let _ = tx.open_table(CERTIFICATES_TABLE)?;
+        let _ = tx.open_table(UNDECIDED_PROPOSALS_TABLE)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn get_decided_value(
+        &self,
+        height: HeightKey,
+    ) -> Result<Option<DecidedValue<Ctx>>, StoreError> {
+        let tx = self.db.begin_read()?;
+
+        let value_bytes = {
+            let table = tx.open_table(DECIDED_VALUES_TABLE)?;
+            table.get(height)?.map(|value| value.value())
+        };
+
+        let certificate = {
+            let table = tx.open_table(CERTIFICATES_TABLE)?;
+            table.get(height)?.map(|value| value.value())
+        };
+
+        let (Some(value_bytes), Some(certificate)) = (value_bytes, certificate) else {
+            return Ok(None);
+        };
+
+        Ok(Some(DecidedValue::new(
+            Bytes::from(value_bytes),
+            self.decode(certificate)?,
+        )))
+    }
+
+    fn insert_decided_value(&self, decided_value: DecidedValue<Ctx>) -> Result<(), StoreError> {
+        let height = decided_value.certificate.height.as_u64();
+        let certificate = self.encode(&decided_value.certificate)?;
+
+        let tx = self.db.begin_write()?;
+        {
+            let mut values = tx.open_table(DECIDED_VALUES_TABLE)?;
+            values.insert(height, decided_value.value_bytes.to_vec())?;
+        }
+        {
+            let mut certificates = tx.open_table(CERTIFICATES_TABLE)?;
+            certificates.insert(height, certificate)?;
+        }
+        {
+            let mut undecided = tx.open_table(UNDECIDED_PROPOSALS_TABLE)?;
+            undecided.retain_in(..=(height, i64::MAX), |_, _| false)?;
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    fn decided_height(&self, first: bool) -> Result<Option<Ctx::Height>, StoreError> {
+        let tx = self.db.begin_read()?;
+        let table = tx.open_table(CERTIFICATES_TABLE)?;
+
+        let entry = if first { table.first()? } else { table.last()? };
+
+        entry
+            .map(|(_, certificate)| {
+                let certificate: CommitCertificate<Ctx> = self.decode(certificate.value())?;
+                Ok(certificate.height)
+            })
+            .transpose()
+    }
+
+    fn get_undecided_proposal(
+        &self,
+        key: UndecidedProposalKey,
+    ) -> Result<Option<ProposedValue<Ctx>>, StoreError> {
+        let tx = self.db.begin_read()?;
+        let table = tx.open_table(UNDECIDED_PROPOSALS_TABLE)?;
+
+        table
+            .get(key)?
+            .map(|value| self.decode(value.value()))
+            .transpose()
+    }
+
+    fn insert_undecided_proposal(&self, value: ProposedValue<Ctx>) -> Result<(), StoreError> {
+        let key = undecided_key::<Ctx>(value.height, value.round);
+        let value = self.encode(&value)?;
+
+        let tx = self.db.begin_write()?;
+        {
+            let mut table = tx.open_table(UNDECIDED_PROPOSALS_TABLE)?;
+            table.insert(key, value)?;
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    fn prune(&self, retain_height: HeightKey) -> Result<(), StoreError> {
+        let tx = self.db.begin_write()?;
+        {
+            let mut undecided = tx.open_table(UNDECIDED_PROPOSALS_TABLE)?;
+            undecided.retain_in(..(retain_height, i64::MIN), |_, _| false)?;
+
+            let mut decided = tx.open_table(DECIDED_VALUES_TABLE)?;
+            decided.retain_in(..retain_height, |_, _| false)?;
+
+            let mut certificates = tx.open_table(CERTIFICATES_TABLE)?;
+            certificates.retain_in(..retain_height, |_, _| false)?;
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+}
+
+/// A [`DecidedValueStore`] backed by a [redb](https://docs.rs/redb) database,
+/// encoding the certificates and proposals with the given codec.
+pub struct RedbStore<Ctx, C> {
+    db: Arc<Db<Ctx, C>>,
+}
+
+impl<Ctx, C> Clone for RedbStore<Ctx, C> {
+    fn clone(&self) -> Self {
+        Self {
+            db: Arc::clone(&self.db),
+        }
+    }
+}
+
+impl<Ctx, C> RedbStore<Ctx, C>
+where
+    Ctx: Context,
+    C: Codec<CommitCertificate<Ctx>> + Codec<ProposedValue<Ctx>>,
+{
+    /// Open the database at the given path, creating it if it does not exist
+    pub fn open(path: impl AsRef<Path>, codec: C) -> Result<Self, StoreError> {
+        let db = Db::new(path, codec)?;
+        db.create_tables()?;
+
+        Ok(Self { db: Arc::new(db) })
+    }
+
+    async fn blocking<F, T>(&self, f: F) -> Result<T, StoreError>
+    where
+        F: FnOnce(&Db<Ctx, C>) -> Result<T, StoreError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let db = Arc::clone(&self.db);
+        tokio::task::spawn_blocking(move || f(&db)).await?
+    }
+}
+
+#[async_trait]
+impl<Ctx, C> DecidedValueStore<Ctx> for RedbStore<Ctx, C>
+where
+    Ctx: Context,
+    C: Codec<CommitCertificate<Ctx>> + Codec<ProposedValue<Ctx>>,
+{
+    type Error = StoreError;
+
+    async fn get_decided_value(
+        &self,
+        height: Ctx::Height,
+    ) -> Result<Option<DecidedValue<Ctx>>, StoreError> {
+        self.blocking(move |db| db.get_decided_value(height.as_u64()))
+            .await
+    }
+
+    async fn store_decided_value(
+        &self,
+        decided_value: DecidedValue<Ctx>,
+    ) -> Result<(), StoreError> {
+        self.blocking(move |db| db.insert_decided_value(decided_value))
+            .await
+    }
+
+    async fn min_decided_height(&self) -> Result<Option<Ctx::Height>, StoreError> {
+        self.blocking(|db| db.decided_height(true)).await
+    }
+
+    async fn max_decided_height(&self) -> Result<Option<Ctx::Height>, StoreError> {
+        self.blocking(|db| db.decided_height(false)).await
+    }
+
+    async fn store_undecided_proposal(&self, value: ProposedValue<Ctx>) -> Result<(), StoreError> {
+        self.blocking(move |db| db.insert_undecided_proposal(value))
+            .await
+    }
+
+    async fn get_undecided_proposal(
+        &self,
+        height: Ctx::Height,
+        round: Round,
+    ) -> Result<Option<ProposedValue<Ctx>>, StoreError> {
+        let key = undecided_key::<Ctx>(height, round);
+        self.blocking(move |db| db.get_undecided_proposal(key))
+            .await
+    }
+
+    async fn prune(&self, retain_height: Ctx::Height) -> Result<(), StoreError> {
+        self.blocking(move |db| db.prune(retain_height.as_u64()))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use malachitebft_core_types::{AggregatedSignature, Validity};
+    use malachitebft_test::codec::proto::ProtobufCodec;
+    use malachitebft_test::{Address, Height, TestContext, Value};
+
+    use super::*;
+
+    fn proposal(height: u64, round: u32, value: u64) -> ProposedValue<TestContext> {
+        ProposedValue {
+            height: Height::new(height),
+            round: Round::new(round),
+            valid_round: Round::Nil,
+            proposer: Address::new([1; 20]),
+            value: Value::new(value),
+            validity: Validity::Valid,
+        }
+    }
+
+    fn decided(proposal: &ProposedValue<TestContext>) -> DecidedValue<TestContext> {
+        let certificate = CommitCertificate {
+            height: proposal.height,
+            round: proposal.round,
+            value_id: proposal.value.id(),
+            aggregated_signature: AggregatedSignature::new(vec![]),
+        };
+
+        DecidedValue::new(
+            Bytes::from(proposal.value.as_u64().to_be_bytes().to_vec()),
+            certificate,
+        )
+    }
+
+    #[tokio::test]
+    async fn store_decide_and_prune() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = RedbStore::open(dir.path().join("store.db"), ProtobufCodec).unwrap();
+
+        assert_eq!(store.history_min_height().await.unwrap(), Height::default());
+
+        let (p1, p2) = (proposal(1, 0, 10), proposal(2, 1, 20));
+        store.store_undecided_proposal(p1.clone()).await.unwrap();
+        store.store_undecided_proposal(p2.clone()).await.unwrap();
+
+        let undecided = store.get_undecided_proposal(p1.height, p1.round).await;
+        assert_eq!(undecided.unwrap(), Some(p1.clone()));
+
+        // Deciding a value drops the undecided proposals up to its height
+        store.store_decided_value(decided(&p1)).await.unwrap();
+        assert_eq!(
+            store
+                .get_undecided_proposal(p1.height, p1.round)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            store
+                .get_undecided_proposal(p2.height, p2.round)
+                .await
+                .unwrap(),
+            Some(p2.clone())
+        );
+
+        store.store_decided_value(decided(&p2)).await.unwrap();
+        assert_eq!(
+            store.get_decided_value(p1.height).await.unwrap(),
+            Some(decided(&p1))
+        );
+        assert_eq!(store.history_min_height().await.unwrap(), p1.height);
+        assert_eq!(store.max_decided_height().await.unwrap(), Some(p2.height));
+
+        store.prune(p2.height).await.unwrap();
+        assert_eq!(store.get_decided_value(p1.height).await.unwrap(), None);
+        assert_eq!(store.min_decided_height().await.unwrap(), Some(p2.height));
+    }
+}
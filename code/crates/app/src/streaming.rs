@@ -0,0 +1,567 @@
+//! Streams of messages, eg. the parts of a proposal, split on the sending side and reassembled on the receiving side.
+
+use core::fmt;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use sha3::Digest;
+use tracing::{debug, warn};
+
+use malachitebft_core_types::{Context, Height, Round, Signature};
+use malachitebft_metrics::prometheus::metrics::counter::Counter;
+use malachitebft_metrics::prometheus::metrics::family::Family;
+use malachitebft_metrics::prometheus::metrics::gauge::Gauge;
+use malachitebft_metrics::SharedRegistry;
+use malachitebft_peer::PeerId;
+
+pub use malachitebft_engine::util::streaming::*;
+
+/// Bounds on the incomplete streams buffered by a [`StreamReassembler`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ReassemblerConfig {
+    /// Maximum number of incomplete streams buffered for each peer
+    pub max_streams_per_peer: usize,
+
+    /// Maximum number of bytes buffered for each stream
+    pub max_stream_size: usize,
+
+    /// Incomplete streams are dropped after not receiving any message for this long
+    pub stream_timeout: Duration,
+}
+
+impl Default for ReassemblerConfig {
+    fn default() -> Self {
+        Self {
+            max_streams_per_peer: 16,
+            max_stream_size: 64 * 1024 * 1024, // 64 MiB
+            stream_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Why a stream message was rejected
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StreamError {
+    /// A message with the same sequence number was already received on the stream
+    Duplicate(Sequence),
+
+    /// The message comes after the end of the stream, or ends the stream before an already received message
+    AfterFin(Sequence),
+
+    /// The peer already has the maximum number of incomplete streams
+    TooManyStreams,
+
+    /// The stream exceeds the maximum number of bytes, it is dropped
+    TooLarge,
+}
+
+impl StreamError {
+    fn reason(&self) -> &'static str {
+        match self {
+            Self::Duplicate(_) => "duplicate",
+            Self::AfterFin(_) => "after_fin",
+            Self::TooManyStreams => "too_many_streams",
+            Self::TooLarge => "too_large",
+        }
+    }
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Duplicate(sequence) => write!(f, "duplicate message with sequence {sequence}"),
+            Self::AfterFin(sequence) => {
+                write!(
+                    f,
+                    "message with sequence {sequence} is past the end of the stream"
+                )
+            }
+            Self::TooManyStreams => write!(f, "too many incomplete streams from peer"),
+            Self::TooLarge => write!(f, "stream exceeds the maximum size"),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+/// Metrics of a [`StreamReassembler`]
+#[derive(Clone, Debug, Default)]
+pub struct StreamingMetrics {
+    streams_completed: Counter,
+    streams_expired: Counter,
+    messages_rejected: Family<Vec<(String, String)>, Counter>,
+    active_streams: Gauge,
+    buffered_bytes: Gauge,
+}
+
+impl StreamingMetrics {
+    pub fn register(registry: &SharedRegistry) -> Self {
+        let metrics = Self::default();
+
+        registry.with_prefix("malachitebft_streaming", |registry| {
+            registry.register(
+                "streams_completed",
+                "Number of streams received in full",
+                metrics.streams_completed.clone(),
+            );
+
+            registry.register(
+                "streams_expired",
+                "Number of incomplete streams dropped after timing out",
+                metrics.streams_expired.clone(),
+            );
+
+            registry.register(
+                "messages_rejected",
+                "Number of stream messages rejected, by reason",
+                metrics.messages_rejected.clone(),
+            );
+
+            registry.register(
+                "active_streams",
+                "Number of incomplete streams being buffered",
+                metrics.active_streams.clone(),
+            );
+
+            registry.register(
+                "buffered_bytes",
+                "Number of bytes buffered for the incomplete streams",
+                metrics.buffered_bytes.clone(),
+            );
+        });
+
+        metrics
+    }
+
+    fn reject(&self, error: &StreamError) {
+        self.messages_rejected
+            .get_or_create(&vec![("reason".to_string(), error.reason().to_string())])
+            .inc();
+    }
+}
+
+struct Stream<T> {
+    /// Content of the data messages, by sequence number
+    parts: BTreeMap<Sequence, T>,
+
+    /// Sequence number of the message ending the stream, once received
+    fin: Option<Sequence>,
+
+    /// Number of bytes buffered
+    size: usize,
+
+    /// Time at which the last message was received
+    last_update: Instant,
+}
+
+impl<T> Stream<T> {
+    fn new(now: Instant) -> Self {
+        Self {
+            parts: BTreeMap::new(),
+            fin: None,
+            size: 0,
+            last_update: now,
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.fin.is_some_and(|fin| self.parts.len() as u64 == fin)
+    }
+
+    fn insert(&mut self, msg: StreamMessage<T>, size: usize) -> Result<(), StreamError> {
+        let sequence = msg.sequence;
+
+        if self.parts.contains_key(&sequence) || self.fin == Some(sequence) {
+            return Err(StreamError::Duplicate(sequence));
+        }
+
+        match msg.content {
+            StreamContent::Data(data) => {
+                if self.fin.is_some_and(|fin| sequence > fin) {
+                    return Err(StreamError::AfterFin(sequence));
+                }
+
+                self.size = self.size.saturating_add(size);
+                self.parts.insert(sequence, data);
+            }
+            StreamContent::Fin(true) => {
+                let last = self.parts.last_key_value().map(|(last, _)| *last);
+
+                if self.fin.is_some() || last.is_some_and(|last| last > sequence) {
+                    return Err(StreamError::AfterFin(sequence));
+                }
+
+                self.fin = Some(sequence);
+            }
+            // Not actually the end of the stream, nothing to buffer
+            StreamContent::Fin(false) => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Reassembles the streams of messages received from peers.
+///
+/// Messages are buffered per `(peer, stream id)` until the message ending the stream
+/// and every message before it have been received, at which point the content of the stream
+/// is returned in order of sequence numbers.
+///
+/// To bound the memory used by misbehaving or faulty peers, the number of incomplete streams
+/// per peer and the size of each stream are limited, and incomplete streams expire.
+pub struct StreamReassembler<T> {
+    config: ReassemblerConfig,
+    size_of: fn(&T) -> usize,
+    streams: BTreeMap<(PeerId, StreamId), Stream<T>>,
+    metrics: StreamingMetrics,
+}
+
+impl<T> StreamReassembler<T> {
+    /// Create a reassembler measuring the size of the stream content with `size_of`
+    pub fn new(
+        config: ReassemblerConfig,
+        size_of: fn(&T) -> usize,
+        metrics: StreamingMetrics,
+    ) -> Self {
+        Self {
+            config,
+            size_of,
+            streams: BTreeMap::new(),
+            metrics,
+        }
+    }
+
+    /// Number of incomplete streams being buffered
+    pub fn len(&self) -> usize {
+        self.streams.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.streams.is_empty()
+    }
+
+    /// Buffer a message received from a peer, returning the content of its stream if it is now complete
+    pub fn insert(
+        &mut self,
+        peer_id: PeerId,
+        msg: StreamMessage<T>,
+    ) -> Result<Option<Vec<T>>, StreamError> {
+        self.insert_at(peer_id, msg, Instant::now())
+    }
+
+    fn insert_at(
+        &mut self,
+        peer_id: PeerId,
+        msg: StreamMessage<T>,
+        now: Instant,
+    ) -> Result<Option<Vec<T>>, StreamError> {
+        self.expire(now);
+
+        let result = self.insert_message(peer_id, msg, now);
+
+        if let Err(e) = &result {
+            debug!(%peer_id, "Rejected stream message: {e}");
+            self.metrics.reject(e);
+        }
+
+        self.update_gauges();
+
+        result
+    }
+
+    fn insert_message(
+        &mut self,
+        peer_id: PeerId,
+        msg: StreamMessage<T>,
+        now: Instant,
+    ) -> Result<Option<Vec<T>>, StreamError> {
+        let key = (peer_id, msg.stream_id);
+
+        if !self.streams.contains_key(&key) {
+            let peer_streams = self
+                .streams
+                .range((peer_id, StreamId::MIN)..=(peer_id, StreamId::MAX))
+                .count();
+
+            if peer_streams >= self.config.max_streams_per_peer {
+                return Err(StreamError::TooManyStreams);
+            }
+        }
+
+        let size = msg.content.as_data().map_or(0, self.size_of);
+        let stream = self.streams.entry(key).or_insert_with(|| Stream::new(now));
+
+        stream.insert(msg, size)?;
+        stream.last_update = now;
+
+        if stream.size > self.config.max_stream_size {
+            warn!(%peer_id, stream_id = %key.1, "Dropping stream exceeding the maximum size");
+            self.streams.remove(&key);
+            return Err(StreamError::TooLarge);
+        }
+
+        if !stream.is_complete() {
+            return Ok(None);
+        }
+
+        let stream = self.streams.remove(&key).expect("stream was just inserted");
+        self.metrics.streams_completed.inc();
+
+        Ok(Some(stream.parts.into_values().collect()))
+    }
+
+    /// Drop the streams which have not received any message for too long
+    fn expire(&mut self, now: Instant) {
+        let timeout = self.config.stream_timeout;
+        let before = self.streams.len();
+
+        self.streams
+            .retain(|_, stream| now.saturating_duration_since(stream.last_update) < timeout);
+
+        let expired = before - self.streams.len();
+        if expired > 0 {
+            debug!("Dropped {expired} incomplete streams after timing out");
+            self.metrics.streams_expired.inc_by(expired as u64);
+        }
+    }
+
+    fn update_gauges(&self) {
+        let buffered_bytes: usize = self.streams.values().map(|stream| stream.size).sum();
+
+        self.metrics.active_streams.set(self.streams.len() as i64);
+        self.metrics.buffered_bytes.set(buffered_bytes as i64);
+    }
+}
+
+/// The parts of a proposal built by a [`ProposalStreamer`]
+pub trait StreamedProposalPart<Ctx>: Sized
+where
+    Ctx: Context,
+{
+    /// The first part, with the metadata of the proposal
+    fn init(height: Ctx::Height, round: Round, proposer: Ctx::Address) -> Self;
+
+    /// A chunk of the proposed value
+    fn data(chunk: Bytes) -> Self;
+
+    /// The last part, with the hash of the proposal computed by a [`ProposalHasher`]
+    /// and the signature of the proposer over it
+    fn fin(hash: Bytes, signature: Signature<Ctx>) -> Self;
+}
+
+/// Computes the hash of a proposal signed in its last part.
+///
+/// This is the Keccak-256 hash of the metadata of the proposal carried by its first part,
+/// ie. its height, round and proposer, followed by the chunks of the proposed value.
+#[derive(Clone, Debug)]
+pub struct ProposalHasher {
+    hasher: sha3::Keccak256,
+}
+
+impl ProposalHasher {
+    /// Start hashing a proposal with the given metadata.
+    ///
+    /// The proposer is hashed through its textual representation, which every address has.
+    pub fn new<Ctx: Context>(height: Ctx::Height, round: Round, proposer: &Ctx::Address) -> Self {
+        let mut hasher = sha3::Keccak256::new();
+
+        hasher.update(height.as_u64().to_be_bytes());
+        hasher.update(round.as_i64().to_be_bytes());
+
+        let proposer = proposer.to_string();
+        hasher.update((proposer.len() as u64).to_be_bytes());
+        hasher.update(proposer.as_bytes());
+
+        Self { hasher }
+    }
+
+    /// Hash the next chunk of the proposed value
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.hasher.update(chunk);
+    }
+
+    /// The hash of the proposal, to be signed by its proposer
+    pub fn finalize(self) -> Bytes {
+        Bytes::from(self.hasher.finalize().to_vec())
+    }
+}
+
+/// Splits the values we propose into parts, ready to be published with `NetworkMsg::PublishProposalPart`.
+///
+/// The value is sent as an `Init` part, followed by one `Data` part per chunk of at most
+/// `part_size` bytes, and a `Fin` part with the hash of the proposal signed by the proposer.
+#[derive(Clone, Debug)]
+pub struct ProposalStreamer {
+    part_size: usize,
+    next_stream_id: StreamId,
+}
+
+impl ProposalStreamer {
+    /// Create a streamer splitting values into chunks of at most `part_size` bytes
+    ///
+    /// # Panics
+    /// If `part_size` is zero.
+    pub fn new(part_size: usize) -> Self {
+        assert!(part_size > 0, "part size must be positive");
+
+        Self {
+            part_size,
+            next_stream_id: 0,
+        }
+    }
+
+    /// Split a value into parts on a new stream, signing the hash of the proposal with `sign`
+    pub fn stream<Ctx>(
+        &mut self,
+        height: Ctx::Height,
+        round: Round,
+        proposer: Ctx::Address,
+        value: Bytes,
+        sign: impl FnOnce(&[u8]) -> Signature<Ctx>,
+    ) -> Vec<StreamMessage<Ctx::ProposalPart>>
+    where
+        Ctx: Context,
+        Ctx::ProposalPart: StreamedProposalPart<Ctx>,
+    {
+        let stream_id = self.next_stream_id;
+        self.next_stream_id += 1;
+
+        let mut hasher = ProposalHasher::new::<Ctx>(height, round, &proposer);
+        let mut parts = Vec::with_capacity(value.len().div_ceil(self.part_size) + 2);
+
+        parts.push(Ctx::ProposalPart::init(height, round, proposer));
+
+        for start in (0..value.len()).step_by(self.part_size) {
+            let chunk = value.slice(start..value.len().min(start + self.part_size));
+
+            hasher.update(&chunk);
+            parts.push(Ctx::ProposalPart::data(chunk));
+        }
+
+        let hash = hasher.finalize();
+        let signature = sign(&hash);
+        parts.push(Ctx::ProposalPart::fin(hash, signature));
+
+        let fin_sequence = parts.len() as Sequence;
+
+        parts
+            .into_iter()
+            .enumerate()
+            .map(|(sequence, part)| {
+                StreamMessage::new(stream_id, sequence as Sequence, StreamContent::Data(part))
+            })
+            .chain([StreamMessage::new(
+                stream_id,
+                fin_sequence,
+                StreamContent::Fin(true),
+            )])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reassembler(config: ReassemblerConfig) -> StreamReassembler<Vec<u8>> {
+        StreamReassembler::new(config, Vec::len, StreamingMetrics::default())
+    }
+
+    fn data(stream_id: StreamId, sequence: Sequence, len: usize) -> StreamMessage<Vec<u8>> {
+        StreamMessage::new(
+            stream_id,
+            sequence,
+            StreamContent::Data(vec![sequence as u8; len]),
+        )
+    }
+
+    fn fin(stream_id: StreamId, sequence: Sequence) -> StreamMessage<Vec<u8>> {
+        StreamMessage::new(stream_id, sequence, StreamContent::Fin(true))
+    }
+
+    #[test]
+    fn reassembles_out_of_order_streams() {
+        let mut reassembler = reassembler(ReassemblerConfig::default());
+        let peer = PeerId::random();
+
+        assert_eq!(reassembler.insert(peer, fin(1, 3)), Ok(None));
+        assert_eq!(reassembler.insert(peer, data(1, 2, 1)), Ok(None));
+        assert_eq!(reassembler.insert(peer, data(1, 0, 1)), Ok(None));
+        assert_eq!(
+            reassembler.insert(peer, data(1, 0, 1)),
+            Err(StreamError::Duplicate(0))
+        );
+        assert_eq!(
+            reassembler.insert(peer, data(1, 4, 1)),
+            Err(StreamError::AfterFin(4))
+        );
+
+        // Streams of different peers with the same id are distinct
+        assert_eq!(
+            reassembler.insert(PeerId::random(), data(1, 1, 1)),
+            Ok(None)
+        );
+        assert_eq!(reassembler.len(), 2);
+
+        assert_eq!(
+            reassembler.insert(peer, data(1, 1, 1)),
+            Ok(Some(vec![vec![0], vec![1], vec![2]]))
+        );
+        assert_eq!(reassembler.len(), 1);
+    }
+
+    #[test]
+    fn bounds_streams_per_peer_and_stream_size() {
+        let mut reassembler = reassembler(ReassemblerConfig {
+            max_streams_per_peer: 2,
+            max_stream_size: 10,
+            ..Default::default()
+        });
+        let peer = PeerId::random();
+
+        assert_eq!(reassembler.insert(peer, data(1, 0, 6)), Ok(None));
+        assert_eq!(reassembler.insert(peer, data(2, 0, 1)), Ok(None));
+        assert_eq!(
+            reassembler.insert(peer, data(3, 0, 1)),
+            Err(StreamError::TooManyStreams)
+        );
+        assert_eq!(reassembler.insert(peer, data(2, 1, 1)), Ok(None));
+
+        assert_eq!(
+            reassembler.insert(peer, data(1, 1, 6)),
+            Err(StreamError::TooLarge)
+        );
+        assert_eq!(reassembler.len(), 1);
+        assert_eq!(reassembler.insert(peer, data(3, 0, 1)), Ok(None));
+    }
+
+    #[test]
+    fn expires_stale_streams() {
+        let timeout = Duration::from_secs(10);
+        let mut reassembler = reassembler(ReassemblerConfig {
+            stream_timeout: timeout,
+            ..Default::default()
+        });
+        let (peer, start) = (PeerId::random(), Instant::now());
+
+        assert_eq!(reassembler.insert_at(peer, data(1, 0, 1), start), Ok(None));
+        assert_eq!(
+            reassembler.insert_at(peer, data(2, 0, 1), start + timeout / 2),
+            Ok(None)
+        );
+
+        assert_eq!(
+            reassembler.insert_at(peer, fin(1, 1), start + timeout),
+            Ok(None)
+        );
+        assert_eq!(reassembler.len(), 2);
+        assert_eq!(reassembler.metrics.streams_expired.get(), 1);
+
+        // Stream 2 is complete without the first message of stream 1, which has expired
+        assert_eq!(
+            reassembler.insert_at(peer, fin(2, 1), start + timeout),
+            Ok(Some(vec![vec![0]]))
+        );
+    }
+}
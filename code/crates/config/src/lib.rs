@@ -6,6 +6,7 @@ use std::time::Duration;
 
 use bytesize::ByteSize;
 use config as config_rs;
+use libp2p_identity::PeerId;
 use malachitebft_core_types::TimeoutKind;
 use multiaddr::Multiaddr;
 use serde::{Deserialize, Serialize};
@@ -31,6 +32,14 @@ pub struct Config {
     /// Metrics configuration options
     pub metrics: MetricsConfig,
 
+    /// RPC server configuration options
+    #[serde(default)]
+    pub rpc: RpcConfig,
+
+    /// Host configuration options
+    #[serde(default)]
+    pub host: HostConfig,
+
     /// Runtime configuration options
     pub runtime: RuntimeConfig,
 
@@ -57,9 +66,18 @@ pub struct P2pConfig {
     /// Address to listen for incoming connections
     pub listen_addr: Multiaddr,
 
+    /// Additional addresses to listen for incoming connections,
+    /// eg. an IPv6 address, or a TCP address alongside a QUIC one when using both transports
+    #[serde(default)]
+    pub additional_listen_addrs: Vec<Multiaddr>,
+
     /// List of nodes to keep persistent connections to
     pub persistent_peers: Vec<Multiaddr>,
 
+    /// Peers given a special treatment, eg. to run a validator behind sentry nodes
+    #[serde(default)]
+    pub peer_lists: PeerListsConfig,
+
     /// Peer discovery
     #[serde(default)]
     pub discovery: DiscoveryConfig,
@@ -75,21 +93,169 @@ pub struct P2pConfig {
 
     /// The maximum size of messages to send over RPC
     pub rpc_max_size: ByteSize,
+
+    /// Per-peer rate limits of the messages received on each channel
+    #[serde(default)]
+    pub rate_limits: RateLimitsConfig,
+
+    /// Compression of the messages sent on each channel and of the sync responses
+    #[serde(default)]
+    pub compression: CompressionConfig,
+
+    /// Traversal of the NATs and firewalls preventing peers from connecting to each other
+    #[serde(default)]
+    pub nat: NatConfig,
 }
 
 impl Default for P2pConfig {
     fn default() -> Self {
         P2pConfig {
             listen_addr: Multiaddr::empty(),
+            additional_listen_addrs: vec![],
             persistent_peers: vec![],
+            peer_lists: Default::default(),
             discovery: Default::default(),
             transport: Default::default(),
             protocol: Default::default(),
             rpc_max_size: ByteSize::mib(10),
             pubsub_max_size: ByteSize::mib(4),
+            rate_limits: Default::default(),
+            compression: Default::default(),
+            nat: Default::default(),
         }
     }
 }
+
+impl P2pConfig {
+    /// All the addresses to listen for incoming connections on
+    pub fn listen_addrs(&self) -> Vec<Multiaddr> {
+        std::iter::once(self.listen_addr.clone())
+            .chain(self.additional_listen_addrs.iter().cloned())
+            .collect()
+    }
+}
+
+/// Peers given a special treatment.
+///
+/// A peer matches an address if the address is a prefix of the peer's address, eg. `/ip4/10.0.0.1`,
+/// and if the address ends with a peer id, eg. `/p2p/12D3KooW...`, if that is the peer's id.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Default)]
+pub struct PeerListsConfig {
+    /// If not empty, only connect to the peers matching one of these addresses
+    #[serde(default)]
+    pub allowed: Vec<Multiaddr>,
+
+    /// Never connect to the peers matching one of these addresses
+    #[serde(default)]
+    pub denied: Vec<Multiaddr>,
+
+    /// Never share the addresses of the peers matching one of these addresses with other peers,
+    /// eg. the validators behind a sentry node
+    #[serde(default)]
+    pub private: Vec<Multiaddr>,
+
+    /// Always stay connected to these peers, redialing them whenever the connection drops,
+    /// eg. the sentry nodes of a validator and the validator behind a sentry node
+    #[serde(default)]
+    pub unconditional: Vec<Multiaddr>,
+}
+
+/// Per-peer rate limits of the messages received on each channel.
+/// Messages on a channel without a rate limit are not rate limited.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct RateLimitsConfig {
+    /// Rate limit of votes and proposals
+    #[serde(default)]
+    pub consensus: Option<RateLimitConfig>,
+
+    /// Rate limit of proposal parts
+    #[serde(default)]
+    pub proposal_parts: Option<RateLimitConfig>,
+
+    /// Rate limit of status messages
+    #[serde(default)]
+    pub sync: Option<RateLimitConfig>,
+}
+
+/// Maximum rate at which a peer may send messages on a channel
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    /// Number of messages per second a peer may send on average
+    pub messages_per_sec: u32,
+
+    /// Number of messages a peer may send at once
+    pub burst: u32,
+}
+
+/// Traversal of the NATs and firewalls preventing peers from connecting to each other
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct NatConfig {
+    /// Probe whether this node is publicly reachable with AutoNAT,
+    /// and help other nodes find out whether they are
+    #[serde(default)]
+    pub autonat: bool,
+
+    /// Relay the connections to the nodes behind a NAT with circuit relay v2
+    #[serde(default)]
+    pub relay_server: bool,
+
+    /// Peers allowed to reserve a slot on this relay and to connect through it when `relay_server`
+    /// is enabled, eg. `12D3KooW...`. No peer may use the relay if empty.
+    #[serde(default)]
+    pub relay_allowed_peers: Vec<PeerId>,
+
+    /// Connect to the peers behind a NAT through the relays they listen on
+    #[serde(default)]
+    pub relay_client: bool,
+
+    /// Relays to listen on, to be reachable from behind a NAT, eg. `/ip4/1.2.3.4/tcp/27000/p2p/12D3KooW...`.
+    /// Implies `relay_client`.
+    #[serde(default)]
+    pub relays: Vec<Multiaddr>,
+
+    /// Upgrade relayed connections to direct connections with DCUtR hole punching.
+    /// Implies `relay_client`.
+    #[serde(default)]
+    pub hole_punching: bool,
+}
+
+/// Compression of the messages sent on each channel and of the sync responses.
+///
+/// Channels with compression are published on their own topics, so only peers with the same
+/// setting receive their messages, while channels without compression use the plain topics and
+/// send messages as is. Sync responses are only compressed for peers which have compression of
+/// the sync responses enabled too.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct CompressionConfig {
+    /// Compression of votes and proposals
+    #[serde(default)]
+    pub consensus: Compression,
+
+    /// Compression of proposal parts
+    #[serde(default)]
+    pub proposal_parts: Compression,
+
+    /// Compression of status messages
+    #[serde(default)]
+    pub sync: Compression,
+
+    /// Compression of the responses to sync requests
+    #[serde(default)]
+    pub rpc: Compression,
+}
+
+/// Compression algorithm
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    /// No compression
+    #[default]
+    None,
+
+    /// LZ4 block compression
+    Lz4,
+}
+
 /// Peer Discovery configuration options
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Default)]
 pub struct DiscoveryConfig {
@@ -97,6 +263,11 @@ pub struct DiscoveryConfig {
     #[serde(default)]
     pub enabled: bool,
 
+    /// Keep the address of this node out of the Kademlia routing tables and the peer exchanges
+    /// of its peers, eg. for a validator behind sentry nodes
+    #[serde(default)]
+    pub hidden: bool,
+
     /// Bootstrap protocol
     #[serde(default)]
     pub bootstrap_protocol: BootstrapProtocol,
@@ -117,6 +288,38 @@ pub struct DiscoveryConfig {
     #[serde(default)]
     #[serde(with = "humantime_serde")]
     pub ephemeral_connection_timeout: Duration,
+
+    /// Address book of the peers this node connects to, persisted to seed discovery after a restart
+    #[serde(default)]
+    pub address_book: AddressBookConfig,
+}
+
+/// Address book of the peers this node connects to, stored in the node home directory
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddressBookConfig {
+    /// Persist the address book and seed discovery from it on startup
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Peers which have not been seen for this long are forgotten
+    #[serde(default = "AddressBookConfig::default_max_age")]
+    #[serde(with = "humantime_serde")]
+    pub max_age: Duration,
+}
+
+impl AddressBookConfig {
+    fn default_max_age() -> Duration {
+        Duration::from_secs(7 * 24 * 60 * 60)
+    }
+}
+
+impl Default for AddressBookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_age: Self::default_max_age(),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
@@ -187,13 +390,33 @@ pub enum TransportProtocol {
     #[default]
     Tcp,
     Quic,
+    /// Both TCP and QUIC, dialing peers over QUIC first and falling back to TCP
+    Both,
 }
 
 impl TransportProtocol {
+    /// Address of the given host and port with this transport, the QUIC one when using both transports.
+    ///
+    /// The host is either an IPv4 address, an IPv6 address or a DNS name.
     pub fn multiaddr(&self, host: &str, port: usize) -> Multiaddr {
+        let host = match host.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => format!("/ip4/{ip}"),
+            Ok(IpAddr::V6(ip)) => format!("/ip6/{ip}"),
+            Err(_) => format!("/dns/{host}"),
+        };
+
+        match self {
+            Self::Tcp => format!("{host}/tcp/{port}").parse().unwrap(),
+            Self::Quic | Self::Both => format!("{host}/udp/{port}/quic-v1").parse().unwrap(),
+        }
+    }
+
+    /// Addresses of the given host and port to fall back to if [`Self::multiaddr`] cannot be reached,
+    /// ie. the TCP one when using both transports
+    pub fn fallback_multiaddrs(&self, host: &str, port: usize) -> Vec<Multiaddr> {
         match self {
-            Self::Tcp => format!("/ip4/{host}/tcp/{port}").parse().unwrap(),
-            Self::Quic => format!("/ip4/{host}/udp/{port}/quic-v1").parse().unwrap(),
+            Self::Tcp | Self::Quic => vec![],
+            Self::Both => vec![Self::Tcp.multiaddr(host, port)],
         }
     }
 }
@@ -205,8 +428,9 @@ impl FromStr for TransportProtocol {
         match s {
             "tcp" => Ok(Self::Tcp),
             "quic" => Ok(Self::Quic),
+            "both" => Ok(Self::Both),
             e => Err(format!(
-                "unknown transport protocol: {e}, available: tcp, quic"
+                "unknown transport protocol: {e}, available: tcp, quic, both"
             )),
         }
     }
@@ -220,6 +444,9 @@ impl FromStr for TransportProtocol {
 pub enum PubSubProtocol {
     GossipSub(GossipSubConfig),
     Broadcast,
+    /// A dedicated stream to every connected peer, for small validator sets
+    /// where every node is directly connected to every validator
+    Direct,
 }
 
 impl Default for PubSubProtocol {
@@ -389,10 +616,68 @@ pub struct ConsensusConfig {
     /// Message types that can carry values
     pub value_payload: ValuePayload,
 
+    /// Proposer-Based Timestamps (PBTS)
+    #[serde(default)]
+    pub pbts: PbtsConfig,
+
+    /// Erasure coding of the proposal parts
+    #[serde(default)]
+    pub erasure_coding: ErasureCodingConfig,
+
     /// P2P configuration options
     pub p2p: P2pConfig,
 }
 
+/// Proposer-Based Timestamps (PBTS) configuration options
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PbtsConfig {
+    /// Enable Proposer-Based Timestamps
+    pub enabled: bool,
+
+    /// Bound on the difference between the clocks of any two correct validators
+    #[serde(with = "humantime_serde")]
+    pub precision: Duration,
+
+    /// Bound on the end-to-end delay for a proposal to reach all correct validators
+    #[serde(with = "humantime_serde")]
+    pub message_delay: Duration,
+}
+
+impl Default for PbtsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            precision: Duration::from_millis(500),
+            message_delay: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Erasure coding of the proposal parts configuration options.
+///
+/// Must be enabled on either all or none of the nodes of the network.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErasureCodingConfig {
+    /// Enable erasure coding of the proposal parts
+    pub enabled: bool,
+
+    /// Number of shards needed to rebuild a value (k)
+    pub data_shards: usize,
+
+    /// Number of additional shards, so that a value can be rebuilt even if as many shards are lost
+    pub parity_shards: usize,
+}
+
+impl Default for ErasureCodingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            data_shards: 4,
+            parity_shards: 2,
+        }
+    }
+}
+
 /// Message types required by consensus to deliver the value being proposed
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -499,6 +784,94 @@ impl Default for MetricsConfig {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RpcConfig {
+    /// Enable the RPC server
+    pub enabled: bool,
+
+    /// Address at which to serve the RPC endpoints
+    pub listen_addr: SocketAddr,
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        RpcConfig {
+            enabled: false,
+            listen_addr: SocketAddr::new(IpAddr::from([127, 0, 0, 1]), 26657),
+        }
+    }
+}
+
+/// Host configuration options, ie. how long the application is given to reply to consensus.
+///
+/// There is no deadline for building a value to propose, as the application is given
+/// the propose timeout instead, after which consensus prevotes nil.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HostConfig {
+    /// Deadline for replying to `ConsensusReady`, after which the node is stopped
+    #[serde(with = "humantime_serde")]
+    pub consensus_ready: Duration,
+
+    /// Deadline for replying to `GetHistoryMinHeight`
+    #[serde(with = "humantime_serde")]
+    pub get_history_min_height: Duration,
+
+    /// Deadline for replying to `ReceivedProposalPart`
+    #[serde(with = "humantime_serde")]
+    pub received_proposal_part: Duration,
+
+    /// Deadline for replying to `ValidateProposal`
+    #[serde(with = "humantime_serde")]
+    pub validate_proposal: Duration,
+
+    /// Deadline for replying to `ExtendVote`
+    #[serde(with = "humantime_serde")]
+    pub extend_vote: Duration,
+
+    /// Deadline for replying to `VerifyVoteExtension`
+    #[serde(with = "humantime_serde")]
+    pub verify_vote_extension: Duration,
+
+    /// Deadline for replying to `GetValidatorSet`
+    #[serde(with = "humantime_serde")]
+    pub get_validator_set: Duration,
+
+    /// Deadline for replying to `Decided`, after which the node is stopped
+    #[serde(with = "humantime_serde")]
+    pub decided: Duration,
+
+    /// Deadline for replying to `GetDecidedValue`
+    #[serde(with = "humantime_serde")]
+    pub get_decided_value: Duration,
+
+    /// Deadline for replying to `ProcessSyncedValue`
+    #[serde(with = "humantime_serde")]
+    pub process_synced_value: Duration,
+
+    /// Interval at which to warn about a reply which is still pending
+    #[serde(with = "humantime_serde")]
+    pub stuck_warning: Duration,
+}
+
+impl Default for HostConfig {
+    fn default() -> Self {
+        Self {
+            consensus_ready: Duration::from_secs(60),
+            get_history_min_height: Duration::from_secs(10),
+            received_proposal_part: Duration::from_secs(10),
+            validate_proposal: Duration::from_secs(10),
+            extend_vote: Duration::from_secs(10),
+            verify_vote_extension: Duration::from_secs(10),
+            get_validator_set: Duration::from_secs(10),
+            decided: Duration::from_secs(60),
+            get_decided_value: Duration::from_secs(10),
+            process_synced_value: Duration::from_secs(10),
+            stuck_warning: Duration::from_secs(5),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "flavor", rename_all = "snake_case")]
 pub enum RuntimeConfig {
@@ -539,6 +912,8 @@ pub struct TestConfig {
     pub max_retain_blocks: usize,
     #[serde(default)]
     pub vote_extensions: VoteExtensionsConfig,
+    #[serde(default)]
+    pub byzantine: ByzantineConfig,
 }
 
 impl Default for TestConfig {
@@ -550,10 +925,47 @@ impl Default for TestConfig {
             exec_time_per_tx: Duration::from_millis(1),
             max_retain_blocks: 1000,
             vote_extensions: VoteExtensionsConfig::default(),
+            byzantine: ByzantineConfig::default(),
         }
     }
 }
 
+/// Ways in which a test node deviates from the protocol.
+///
+/// All behaviors are disabled by default, ie. the node is correct.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ByzantineConfig {
+    /// Also publish a conflicting vote for every vote the node casts
+    pub equivocate_votes: bool,
+
+    /// Also publish a conflicting proposal for every proposal the node makes.
+    /// Only has an effect if proposals are published, ie. not in parts-only mode.
+    pub equivocate_proposals: bool,
+
+    /// Replace the value of every vote the node casts with a value that was never proposed
+    pub vote_invalid_values: bool,
+
+    /// Never publish the parts of the values the node proposes
+    pub withhold_proposal_parts: bool,
+
+    /// Publish the parts of the values the node proposes after this delay
+    #[serde(default, with = "humantime_serde")]
+    pub delay_proposal_parts: Option<Duration>,
+
+    /// Propose a value in every round, even when the node is not the proposer
+    pub propose_out_of_turn: bool,
+
+    /// Publish again every message of the previous rounds when moving to a new round
+    pub replay_old_rounds: bool,
+}
+
+impl ByzantineConfig {
+    /// Whether the node deviates from the protocol in any way
+    pub fn is_enabled(&self) -> bool {
+        *self != Self::default()
+    }
+}
+
 #[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct LoggingConfig {
     pub log_level: LogLevel,
@@ -648,6 +1060,33 @@ mod tests {
         std::fs::remove_file(tmp_file).unwrap();
     }
 
+    #[test]
+    fn transport_multiaddrs() {
+        assert_eq!(
+            TransportProtocol::Tcp.multiaddr("127.0.0.1", 27000),
+            "/ip4/127.0.0.1/tcp/27000".parse().unwrap()
+        );
+        assert_eq!(
+            TransportProtocol::Quic.multiaddr("::1", 27000),
+            "/ip6/::1/udp/27000/quic-v1".parse().unwrap()
+        );
+        assert_eq!(
+            TransportProtocol::Both.multiaddr("node-1", 27000),
+            "/dns/node-1/udp/27000/quic-v1".parse().unwrap()
+        );
+        assert_eq!(
+            TransportProtocol::Both.fallback_multiaddrs("node-1", 27000),
+            vec!["/dns/node-1/tcp/27000".parse().unwrap()]
+        );
+        assert!(TransportProtocol::Quic
+            .fallback_multiaddrs("node-1", 27000)
+            .is_empty());
+        assert_eq!(
+            TransportProtocol::from_str("both"),
+            Ok(TransportProtocol::Both)
+        );
+    }
+
     #[test]
     fn log_format() {
         assert_eq!(
@@ -3,7 +3,7 @@ use derive_where::derive_where;
 use malachitebft_core_types::*;
 
 use crate::input::RequestId;
-use crate::types::SignedConsensusMsg;
+use crate::types::{SignedConsensusMsg, ValueValidity, VoteExtensions};
 use crate::ConsensusMsg;
 
 /// Provides a way to construct the appropriate [`Resume`] value to
@@ -87,8 +87,17 @@ where
     /// The application MUST eventually feed a [`ProposeValue`][crate::input::Input::ProposeValue]
     /// input to consensus within the specified timeout duration.
     ///
+    /// The vote extensions of the precommits which decided the previous height are
+    /// given to the application, if this node was part of that decision.
+    ///
     /// Resume with: [`resume::Continue`]
-    GetValue(Ctx::Height, Round, Timeout, resume::Continue),
+    GetValue(
+        Ctx::Height,
+        Round,
+        Timeout,
+        VoteExtensions<Ctx>,
+        resume::Continue,
+    ),
 
     /// Requests the application to re-stream a proposal that it has already seen.
     ///
@@ -111,6 +120,61 @@ where
         resume::Continue,
     ),
 
+    /// Requests the application to validate a value received in a proposal,
+    /// when values are carried by proposal messages only and not by proposal parts.
+    ///
+    /// An invalid value leads this node to prevote nil for the proposal.
+    ///
+    /// Resume with: [`resume::ValueValidity`]
+    ValidateValue(
+        /// Height of the proposal
+        Ctx::Height,
+        /// Round of the proposal
+        Round,
+        /// Address of the proposer
+        Ctx::Address,
+        /// Value to validate
+        Ctx::Value,
+        /// For resumption
+        resume::ValueValidity,
+    ),
+
+    /// Requests the application to provide an extension for our precommit for the given value.
+    ///
+    /// Resume with: [`resume::VoteExtension`]
+    ExtendVote(
+        /// Height of the precommit
+        Ctx::Height,
+        /// Round of the precommit
+        Round,
+        /// ID of the value we are precommitting for
+        ValueId<Ctx>,
+        /// For resumption
+        resume::VoteExtension,
+    ),
+
+    /// Requests the application to verify the extension of a precommit received from a peer.
+    ///
+    /// A precommit with an invalid extension is dropped, and therefore not counted.
+    /// Precommits for a value without any extension are verified as well,
+    /// so that applications which require extensions can reject them.
+    ///
+    /// Resume with: [`resume::VoteExtensionValidity`]
+    VerifyVoteExtension(
+        /// Height of the precommit
+        Ctx::Height,
+        /// Round of the precommit
+        Round,
+        /// ID of the value the precommit is for
+        ValueId<Ctx>,
+        /// Address of the validator who sent the precommit
+        Ctx::Address,
+        /// Extension to verify, if any
+        Option<SignedExtension<Ctx>>,
+        /// For resumption
+        resume::VoteExtensionValidity,
+    ),
+
     /// Get the validator set at the given height
     ///
     /// Resume with: [`resume::ValidatorSet`]
@@ -151,6 +215,12 @@ where
     /// Resume with: [`resume::Continue`]`
     PersistTimeout(Timeout, resume::Continue),
 
+    /// Persist the local time at which a proposal for the given round of the current height
+    /// was received in the Write-Ahead Log, for its timeliness to be checked against it after a crash
+    ///
+    /// Resume with: [`resume::Continue`]`
+    PersistProposalReceiveTime(Round, Timestamp, resume::Continue),
+
     /// Sign a vote with this node's private key
     ///
     /// Resume with: [`resume::SignedVote`]
@@ -179,6 +249,14 @@ where
         ThresholdParams,
         resume::CertificateValidity,
     ),
+
+    /// Get the current time according to this node's local clock.
+    ///
+    /// Only performed when Proposer-Based Timestamps (PBTS) are enabled,
+    /// to timestamp our own proposals and check the timeliness of received proposals.
+    ///
+    /// Resume with: [`resume::LocalTime`]
+    GetLocalTime(resume::LocalTime),
 }
 
 /// A value with which the consensus process can be resumed after yielding an [`Effect`].
@@ -203,6 +281,15 @@ where
     /// Resume execution with the validity of the signature
     SignatureValidity(bool),
 
+    /// Resume execution with the validity of a proposed value, according to the application
+    ValueValidity(ValueValidity),
+
+    /// Resume execution with the extension to attach to our precommit, if any
+    VoteExtension(Option<SignedExtension<Ctx>>),
+
+    /// Resume execution with the validity of the extension of a precommit
+    VoteExtensionValidity(Validity),
+
     /// Resume execution with the signed vote
     SignedVote(SignedMessage<Ctx, Ctx::Vote>),
 
@@ -211,6 +298,9 @@ where
 
     /// Resume execution with the result of the verification of the [`CommitCertificate`]
     CertificateValidity(Result<(), CertificateError<Ctx>>),
+
+    /// Resume execution with the current time according to the local clock
+    LocalTime(Timestamp),
 }
 
 pub mod resume {
@@ -249,6 +339,39 @@ pub mod resume {
         }
     }
 
+    #[derive(Debug, Default)]
+    pub struct ValueValidity;
+
+    impl<Ctx: Context> Resumable<Ctx> for ValueValidity {
+        type Value = crate::types::ValueValidity;
+
+        fn resume_with(self, value: Self::Value) -> Resume<Ctx> {
+            Resume::ValueValidity(value)
+        }
+    }
+
+    #[derive(Debug, Default)]
+    pub struct VoteExtension;
+
+    impl<Ctx: Context> Resumable<Ctx> for VoteExtension {
+        type Value = Option<SignedExtension<Ctx>>;
+
+        fn resume_with(self, value: Self::Value) -> Resume<Ctx> {
+            Resume::VoteExtension(value)
+        }
+    }
+
+    #[derive(Debug, Default)]
+    pub struct VoteExtensionValidity;
+
+    impl<Ctx: Context> Resumable<Ctx> for VoteExtensionValidity {
+        type Value = Validity;
+
+        fn resume_with(self, value: Self::Value) -> Resume<Ctx> {
+            Resume::VoteExtensionValidity(value)
+        }
+    }
+
     #[derive(Debug, Default)]
     pub struct SignedVote;
 
@@ -281,4 +404,15 @@ pub mod resume {
             Resume::CertificateValidity(value)
         }
     }
+
+    #[derive(Debug, Default)]
+    pub struct LocalTime;
+
+    impl<Ctx: Context> Resumable<Ctx> for LocalTime {
+        type Value = Timestamp;
+
+        fn resume_with(self, value: Self::Value) -> Resume<Ctx> {
+            Resume::LocalTime(value)
+        }
+    }
 }
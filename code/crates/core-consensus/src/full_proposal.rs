@@ -1,12 +1,12 @@
-use std::collections::BTreeMap;
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
 
 use derive_where::derive_where;
-use tracing::debug;
 
-use malachitebft_core_types::{
-    Context, Height, Proposal, Round, SignedExtension, SignedProposal, Validity, Value,
-};
+use malachitebft_core_types::{Context, Height, Proposal, Round, SignedProposal, Validity, Value};
 
+use crate::util::log::debug;
 use crate::ProposedValue;
 
 /// A full proposal, ie. a proposal together with its value and validity.
@@ -18,8 +18,6 @@ pub struct FullProposal<Ctx: Context> {
     pub validity: Validity,
     /// Proposal consensus message
     pub proposal: SignedProposal<Ctx>,
-    /// Extension
-    pub extension: Option<SignedExtension<Ctx>>,
 }
 
 impl<Ctx: Context> FullProposal<Ctx> {
@@ -27,13 +25,11 @@ impl<Ctx: Context> FullProposal<Ctx> {
         builder_value: Ctx::Value,
         validity: Validity,
         proposal: SignedProposal<Ctx>,
-        extension: Option<SignedExtension<Ctx>>,
     ) -> Self {
         Self {
             builder_value,
             validity,
             proposal,
-            extension,
         }
     }
 }
@@ -48,7 +44,7 @@ enum Entry<Ctx: Context> {
     ProposalOnly(SignedProposal<Ctx>),
 
     /// Only the value has been received.
-    ValueOnly(Ctx::Value, Validity, Option<SignedExtension<Ctx>>),
+    ValueOnly(Ctx::Value, Validity),
 
     // This is a placeholder for converting a partial
     // entry (`ProposalOnly` or `ValueOnly`) to a full entry (`Full`).
@@ -58,13 +54,8 @@ enum Entry<Ctx: Context> {
 }
 
 impl<Ctx: Context> Entry<Ctx> {
-    fn full(
-        value: Ctx::Value,
-        validity: Validity,
-        proposal: SignedProposal<Ctx>,
-        extension: Option<SignedExtension<Ctx>>,
-    ) -> Self {
-        Entry::Full(FullProposal::new(value, validity, proposal, extension))
+    fn full(value: Ctx::Value, validity: Validity, proposal: SignedProposal<Ctx>) -> Self {
+        Entry::Full(FullProposal::new(value, validity, proposal))
     }
 }
 
@@ -114,7 +105,7 @@ pub struct FullProposalKeeper<Ctx: Context> {
 /// and then replaces it with the new entry if the pattern matches.
 macro_rules! replace_with {
     ($e:expr, $p:pat => $r:expr) => {
-        *$e = match ::std::mem::take($e) {
+        *$e = match ::core::mem::take($e) {
             $p => $r,
             e => e,
         };
@@ -170,13 +161,12 @@ impl<Ctx: Context> FullProposalKeeper<Ctx> {
         None
     }
 
-    #[allow(clippy::type_complexity)]
     pub fn get_value<'a>(
         &self,
         height: &Ctx::Height,
         round: Round,
         value: &'a Ctx::Value,
-    ) -> Option<(&'a Ctx::Value, Validity, Option<SignedExtension<Ctx>>)> {
+    ) -> Option<(&'a Ctx::Value, Validity)> {
         let entries = self
             .keeper
             .get(&(*height, round))
@@ -185,10 +175,10 @@ impl<Ctx: Context> FullProposalKeeper<Ctx> {
         for entry in entries {
             match entry {
                 Entry::Full(p) if p.proposal.value().id() == value.id() => {
-                    return Some((value, p.validity, p.extension.clone()));
+                    return Some((value, p.validity));
                 }
-                Entry::ValueOnly(v, validity, extension) if v.id() == value.id() => {
-                    return Some((value, *validity, extension.clone()));
+                Entry::ValueOnly(v, validity) if v.id() == value.id() => {
+                    return Some((value, *validity));
                 }
                 _ => continue,
             }
@@ -216,12 +206,9 @@ impl<Ctx: Context> FullProposalKeeper<Ctx> {
             None => Entry::ProposalOnly(new_proposal),
 
             // There is a value, create a full entry
-            Some((v, validity, extension)) => Entry::Full(FullProposal::new(
-                v.clone(),
-                validity,
-                new_proposal,
-                extension,
-            )),
+            Some((v, validity)) => {
+                Entry::Full(FullProposal::new(v.clone(), validity, new_proposal))
+            }
         }
     }
 
@@ -249,11 +236,11 @@ impl<Ctx: Context> FullProposalKeeper<Ctx> {
                                 return;
                             }
                         }
-                        Entry::ValueOnly(value, _validity, _extension) => {
+                        Entry::ValueOnly(value, _validity) => {
                             if value == new_proposal.value() {
                                 // Found a matching value. Add the proposal
-                                replace_with!(entry, Entry::ValueOnly(value, validity, extension) => {
-                                    Entry::full(value, validity, new_proposal, extension.clone())
+                                replace_with!(entry, Entry::ValueOnly(value, validity) => {
+                                    Entry::full(value, validity, new_proposal)
                                 });
 
                                 return;
@@ -292,11 +279,7 @@ impl<Ctx: Context> FullProposalKeeper<Ctx> {
             None => {
                 // First time we see something (a proposed value) for this height and round
                 // Create a full proposal with just the proposal
-                let entry = Entry::ValueOnly(
-                    new_value.value.clone(),
-                    new_value.validity,
-                    new_value.extension.clone(),
-                );
+                let entry = Entry::ValueOnly(new_value.value.clone(), new_value.validity);
                 self.keeper.insert(key, vec![entry]);
             }
             Some(entries) => {
@@ -309,7 +292,7 @@ impl<Ctx: Context> FullProposalKeeper<Ctx> {
                             if proposal.value().id() == new_value.value.id() {
                                 // Found a matching proposal. Change the entry at index i
                                 replace_with!(entry, Entry::ProposalOnly(proposal) => {
-                                    Entry::full(new_value.value.clone(), new_value.validity, proposal, new_value.extension.clone())
+                                    Entry::full(new_value.value.clone(), new_value.validity, proposal)
                                 });
 
                                 return;
@@ -338,7 +321,6 @@ impl<Ctx: Context> FullProposalKeeper<Ctx> {
                 entries.push(Entry::ValueOnly(
                     new_value.value.clone(),
                     new_value.validity,
-                    new_value.extension.clone(),
                 ));
             }
         }
@@ -363,7 +345,7 @@ impl<Ctx: Context> FullProposalKeeper<Ctx> {
                     {
                         // Found a matching proposal. Change the entry at index i
                         replace_with!(entry, Entry::ProposalOnly(proposal) => {
-                            Entry::full(new_value.value.clone(), new_value.validity, proposal, new_value.extension.clone())
+                            Entry::full(new_value.value.clone(), new_value.validity, proposal)
                         });
                     }
                 }
@@ -1,12 +1,127 @@
-use genawaiter::sync as gen;
-use genawaiter::GeneratorState;
+//! A minimal generator built on top of `async`/`await`, which allows
+//! the consensus process to yield [`Effect`]s and be resumed with a [`Resume`] value.
+//!
+//! Only depends on `core` and `alloc`, so that consensus can run in `no_std` environments.
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::task::Wake;
+use core::future::Future;
+use core::mem;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use spin::Mutex;
 
 use crate::effect::{Effect, Resume};
 use crate::error::Error;
 
-pub use gen::Gen;
-
 #[allow(private_interfaces)]
-pub type Co<Ctx> = gen::Co<Effect<Ctx>, Resume<Ctx>>;
+pub type Co<Ctx> = Yielder<Effect<Ctx>, Resume<Ctx>>;
 
 pub type CoResult<Ctx> = GeneratorState<Effect<Ctx>, Result<(), Error<Ctx>>>;
+
+/// The result of resuming a generator.
+#[derive(Debug)]
+pub enum GeneratorState<Y, R> {
+    /// The generator yielded a value and is waiting to be resumed.
+    Yielded(Y),
+
+    /// The generator completed with a value.
+    Complete(R),
+}
+
+/// The value exchanged between the generator and the code driving it.
+enum Next<Y, R> {
+    Empty,
+    Yield(Y),
+    Resume(R),
+}
+
+type Airlock<Y, R> = Arc<Mutex<Next<Y, R>>>;
+
+/// A generator which yields values of type `Y`, is resumed with values of type `R`,
+/// and whose body is the future `F`.
+pub struct Gen<Y, R, F: Future> {
+    airlock: Airlock<Y, R>,
+    future: Pin<Box<F>>,
+}
+
+impl<Y, R, F: Future> Gen<Y, R, F> {
+    /// Creates a new generator from a function which, given a [`Yielder`],
+    /// returns the future that makes up the body of the generator.
+    pub fn new(producer: impl FnOnce(Yielder<Y, R>) -> F) -> Self {
+        let airlock = Arc::new(Mutex::new(Next::Empty));
+        let future = Box::pin(producer(Yielder {
+            airlock: Arc::clone(&airlock),
+        }));
+
+        Self { airlock, future }
+    }
+
+    /// Resumes execution of the generator with the given value,
+    /// until it either yields a value or completes.
+    pub fn resume_with(&mut self, arg: R) -> GeneratorState<Y, F::Output> {
+        *self.airlock.lock() = Next::Resume(arg);
+
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+
+        match self.future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => GeneratorState::Complete(output),
+            Poll::Pending => match mem::replace(&mut *self.airlock.lock(), Next::Empty) {
+                Next::Yield(value) => GeneratorState::Yielded(value),
+                Next::Empty | Next::Resume(_) => {
+                    unreachable!("generator was suspended without yielding a value")
+                }
+            },
+        }
+    }
+}
+
+/// Handle given to the body of a generator, which allows it to yield values.
+pub struct Yielder<Y, R> {
+    airlock: Airlock<Y, R>,
+}
+
+impl<Y, R> Yielder<Y, R> {
+    /// Yields a value from the generator.
+    ///
+    /// The returned future completes with the value the generator is resumed with,
+    /// and must be awaited immediately.
+    pub fn yield_(&self, value: Y) -> impl Future<Output = R> + '_ {
+        *self.airlock.lock() = Next::Yield(value);
+
+        Barrier {
+            airlock: &self.airlock,
+        }
+    }
+}
+
+/// Future which stays pending until the generator is resumed.
+struct Barrier<'a, Y, R> {
+    airlock: &'a Airlock<Y, R>,
+}
+
+impl<Y, R> Future for Barrier<'_, Y, R> {
+    type Output = R;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut next = self.airlock.lock();
+
+        match mem::replace(&mut *next, Next::Empty) {
+            Next::Resume(arg) => Poll::Ready(arg),
+            pending => {
+                *next = pending;
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// The generator is polled synchronously, so its waker never needs to do anything.
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
@@ -14,7 +14,7 @@ mod validator_set;
 mod vote;
 mod vote_set;
 
-use proposal::on_proposal;
+use proposal::{on_proposal, on_proposal_receive_time};
 use propose::on_propose;
 use proposed_value::on_proposed_value;
 use start_height::reset_and_start_height;
@@ -47,8 +47,8 @@ where
     Ctx: Context,
 {
     match input {
-        Input::StartHeight(height, validator_set) => {
-            reset_and_start_height(co, state, metrics, height, validator_set).await
+        Input::StartHeight(height, validator_set, params) => {
+            reset_and_start_height(co, state, metrics, height, validator_set, params).await
         }
         Input::Vote(vote) => on_vote(co, state, metrics, vote).await,
         Input::Proposal(proposal) => on_proposal(co, state, metrics, proposal).await,
@@ -66,5 +66,9 @@ where
         Input::VoteSetResponse(vote_set) => {
             on_vote_set_response(co, state, metrics, vote_set).await
         }
+        Input::ProposalReceiveTime(height, round, received_at) => {
+            on_proposal_receive_time(state, height, round, received_at);
+            Ok(())
+        }
     }
 }
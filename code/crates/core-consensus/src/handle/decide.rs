@@ -1,4 +1,5 @@
 use crate::prelude::*;
+use crate::VoteExtensions;
 
 pub async fn decide<Ctx>(
     co: &Co<Ctx>,
@@ -58,6 +59,8 @@ where
             CommitCertificate::new(height, proposal_round, value.id(), commits)
         });
 
+    state.last_vote_extensions = Some((height, VoteExtensions::from_certificate(&certificate)));
+
     perform!(co, Effect::Decide(certificate, Default::default()));
 
     // Reinitialize to remove any previous round or equivocating precommits.
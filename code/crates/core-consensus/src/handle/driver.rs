@@ -4,9 +4,11 @@ use crate::handle::signature::sign_vote;
 use crate::handle::vote::on_vote;
 use crate::prelude::*;
 use crate::types::SignedConsensusMsg;
+#[cfg(feature = "tracing")]
 use crate::util::pretty::PrettyVal;
 use malachitebft_core_driver::Input as DriverInput;
 use malachitebft_core_driver::Output as DriverOutput;
+use malachitebft_core_state_machine::state::Step;
 
 #[async_recursion]
 pub async fn apply_driver_input<Ctx>(
@@ -37,7 +39,8 @@ where
             );
         }
 
-        DriverInput::Proposal(proposal, _validity) => {
+        DriverInput::Proposal(proposal, _validity)
+        | DriverInput::UntimelyProposal(proposal, _validity) => {
             if proposal.height() != state.driver.height() {
                 warn!(
                     "Ignoring proposal for height {}, current height: {}",
@@ -47,11 +50,6 @@ where
 
                 return Ok(());
             }
-
-            perform!(
-                co,
-                Effect::CancelTimeout(Timeout::propose(proposal.round()), Default::default())
-            );
         }
 
         DriverInput::Vote(vote) => {
@@ -81,7 +79,8 @@ where
         DriverInput::TimeoutElapsed(_) => (),
     }
 
-    // Record the step we were in
+    // Record the round and step we were in
+    let prev_round = state.driver.round();
     let prev_step = state.driver.step();
 
     let outputs = state
@@ -95,6 +94,7 @@ where
     // If the step has changed, update the metrics
     if prev_step != new_step {
         debug!(step.previous = ?prev_step, step.new = ?new_step, "Transitioned to new step");
+        #[cfg(feature = "tracing")]
         if let Some(valid) = &state.driver.valid_value() {
             if state.driver.step_is_propose() {
                 info!(
@@ -108,6 +108,15 @@ where
     }
 
     if prev_step != new_step {
+        // Only cancel the propose timeout once we leave the Propose step, and not as soon
+        // as we receive a proposal, as that proposal might not let us move to Prevote,
+        // eg. if it carries a POL round for which we have not seen a polka.
+        if prev_step == Step::Propose {
+            perform!(
+                co,
+                Effect::CancelTimeout(Timeout::propose(prev_round), Default::default())
+            );
+        }
         if state.driver.step_is_prevote() {
             perform!(
                 co,
@@ -194,6 +203,7 @@ where
                 "Proposing value"
             );
 
+            let proposal = timestamp_proposal(co, state, proposal).await?;
             let signed_proposal = sign_proposal(co, proposal).await?;
 
             if signed_proposal.pol_round().is_defined() {
@@ -235,7 +245,7 @@ where
                 "Voting",
             );
 
-            let extended_vote = extend_vote(vote, state);
+            let extended_vote = extend_vote(co, vote).await?;
             let signed_vote = sign_vote(co, extended_vote).await?;
 
             on_vote(co, state, metrics, signed_vote.clone()).await?;
@@ -278,9 +288,11 @@ where
         DriverOutput::GetValue(height, round, timeout) => {
             info!(%height, %round, "Requesting value");
 
+            let vote_extensions = state.vote_extensions_for(height);
+
             perform!(
                 co,
-                Effect::GetValue(height, round, timeout, Default::default())
+                Effect::GetValue(height, round, timeout, vote_extensions, Default::default())
             );
 
             Ok(())
@@ -288,26 +300,73 @@ where
     }
 }
 
-fn extend_vote<Ctx: Context>(vote: Ctx::Vote, state: &mut State<Ctx>) -> Ctx::Vote {
-    let VoteType::Precommit = vote.vote_type() else {
-        return vote;
+/// When Proposer-Based Timestamps (PBTS) are enabled, attach a timestamp to our proposal.
+///
+/// A fresh proposal is timestamped with the current local time, while a re-proposal
+/// of a value from a previous round keeps the timestamp of the original proposal.
+async fn timestamp_proposal<Ctx>(
+    co: &Co<Ctx>,
+    state: &State<Ctx>,
+    proposal: Ctx::Proposal,
+) -> Result<Ctx::Proposal, Error<Ctx>>
+where
+    Ctx: Context,
+{
+    if state.params.synchrony_params.is_none() {
+        return Ok(proposal);
+    }
+
+    let original_timestamp = if proposal.pol_round().is_defined() {
+        state
+            .full_proposal_keeper
+            .full_proposal_at_round_and_value(
+                &proposal.height(),
+                proposal.pol_round(),
+                &proposal.value().id(),
+            )
+            .and_then(|full_proposal| full_proposal.proposal.timestamp())
+    } else {
+        None
     };
 
-    let NilOrVal::Val(val_id) = vote.value() else {
-        return vote;
+    let timestamp = match original_timestamp {
+        Some(timestamp) => timestamp,
+        None => perform!(co, Effect::GetLocalTime(Default::default()),
+            Resume::LocalTime(time) => time
+        ),
+    };
+
+    Ok(Ctx::new_timed_proposal(
+        proposal.height(),
+        proposal.round(),
+        proposal.value().clone(),
+        proposal.pol_round(),
+        proposal.validator_address().clone(),
+        timestamp,
+    ))
+}
+
+/// Ask the application for an extension to attach to our precommit for a value, if any.
+async fn extend_vote<Ctx>(co: &Co<Ctx>, vote: Ctx::Vote) -> Result<Ctx::Vote, Error<Ctx>>
+where
+    Ctx: Context,
+{
+    let VoteType::Precommit = vote.vote_type() else {
+        return Ok(vote);
     };
 
-    let Some(full_proposal) = state.full_proposal_keeper.full_proposal_at_round_and_value(
-        &vote.height(),
-        vote.round(),
-        val_id,
-    ) else {
-        return vote;
+    let NilOrVal::Val(value_id) = vote.value() else {
+        return Ok(vote);
     };
 
-    if let Some(extension) = &full_proposal.extension {
-        vote.extend(extension.clone())
-    } else {
-        vote
+    let extension = perform!(
+        co,
+        Effect::ExtendVote(vote.height(), vote.round(), value_id.clone(), Default::default()),
+        Resume::VoteExtension(extension) => extension
+    );
+
+    match extension {
+        Some(extension) => Ok(vote.extend(extension)),
+        None => Ok(vote),
     }
 }
@@ -3,9 +3,10 @@ use crate::handle::signature::verify_signature;
 use crate::handle::validator_set::get_validator_set;
 use crate::input::Input;
 use crate::types::ConsensusMsg;
+#[cfg(feature = "tracing")]
 use crate::util::pretty::PrettyProposal;
-use crate::ProposedValue;
 use crate::{prelude::*, SignedConsensusMsg};
+use crate::{ProposedValue, ValueValidity};
 
 pub async fn on_proposal<Ctx>(
     co: &Co<Ctx>,
@@ -64,6 +65,24 @@ where
 
     debug_assert_eq!(proposal_height, consensus_height);
 
+    // Record when we first received a proposal for this round, so that we can later check
+    // whether it is timely. Proposals buffered for a higher height count as received
+    // when that height starts.
+    if state.params.synchrony_params.is_some() {
+        let received_at = perform!(co, Effect::GetLocalTime(Default::default()),
+            Resume::LocalTime(time) => time
+        );
+
+        // Persist it before the proposal itself, for it to be restored
+        // before the proposal is replayed from the WAL after a crash.
+        if state.record_proposal_receive_time(proposal_round, received_at) {
+            perform!(
+                co,
+                Effect::PersistProposalReceiveTime(proposal_round, received_at, Default::default())
+            );
+        }
+    }
+
     // Store the proposal in the full proposal keeper
     state.store_proposal(signed_proposal.clone());
 
@@ -80,16 +99,20 @@ where
     }
 
     if state.params.value_payload.proposal_only() {
-        // TODO - pass the received value up to the host that will verify and give back validity and extension.
-        // Currently starknet Context defines value as BlockHash, we need a PoC app for this.
+        // Our own proposals carry a value built by the application, which is valid by construction
+        let validity = if proposer_address == state.address() {
+            Validity::Valid
+        } else {
+            validate_value(co, metrics, &signed_proposal).await?
+        };
+
         let new_value = ProposedValue {
             height: signed_proposal.height(),
             round: signed_proposal.round(),
             valid_round: signed_proposal.pol_round(),
             proposer: signed_proposal.validator_address().clone(),
             value: signed_proposal.value().clone(),
-            validity: Validity::Valid,
-            extension: Default::default(),
+            validity,
         };
 
         state.store_value(&new_value);
@@ -100,13 +123,12 @@ where
         proposal_round,
         signed_proposal.value(),
     ) {
-        apply_driver_input(
-            co,
+        let input = proposal_input(
             state,
-            metrics,
-            DriverInput::Proposal(full_proposal.proposal.clone(), full_proposal.validity),
-        )
-        .await?;
+            full_proposal.proposal.clone(),
+            full_proposal.validity,
+        );
+        apply_driver_input(co, state, metrics, input).await?;
     } else {
         debug!(
             proposal.height = %proposal_height,
@@ -118,6 +140,85 @@ where
     Ok(())
 }
 
+/// Restore the local time at which a proposal for the current height was received,
+/// when replaying the Write-Ahead Log.
+pub fn on_proposal_receive_time<Ctx>(
+    state: &mut State<Ctx>,
+    height: Ctx::Height,
+    round: Round,
+    received_at: Timestamp,
+) where
+    Ctx: Context,
+{
+    if height != state.driver.height() {
+        debug!(%height, %round, "Received proposal receive time for another height, ignoring");
+        return;
+    }
+
+    state.record_proposal_receive_time(round, received_at);
+}
+
+/// Ask the application to validate the value carried by a proposal.
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+async fn validate_value<Ctx>(
+    co: &Co<Ctx>,
+    metrics: &Metrics,
+    signed_proposal: &SignedProposal<Ctx>,
+) -> Result<Validity, Error<Ctx>>
+where
+    Ctx: Context,
+{
+    let value_validity = perform!(
+        co,
+        Effect::ValidateValue(
+            signed_proposal.height(),
+            signed_proposal.round(),
+            signed_proposal.validator_address().clone(),
+            signed_proposal.value().clone(),
+            Default::default()
+        ),
+        Resume::ValueValidity(value_validity) => value_validity
+    );
+
+    if let ValueValidity::Invalid { reason } = &value_validity {
+        metrics.invalid_values.inc();
+
+        warn!(
+            height = %signed_proposal.height(),
+            round = %signed_proposal.round(),
+            proposer = %signed_proposal.validator_address(),
+            %reason,
+            "Received proposal with invalid value, will prevote nil"
+        );
+    }
+
+    Ok(value_validity.validity())
+}
+
+/// Build the driver input for a full proposal, taking its timeliness into account
+/// when Proposer-Based Timestamps (PBTS) are enabled.
+pub fn proposal_input<Ctx>(
+    state: &State<Ctx>,
+    proposal: SignedProposal<Ctx>,
+    validity: Validity,
+) -> DriverInput<Ctx>
+where
+    Ctx: Context,
+{
+    if state.is_proposal_timely(&proposal) {
+        DriverInput::Proposal(proposal, validity)
+    } else {
+        warn!(
+            height = %proposal.height(),
+            round = %proposal.round(),
+            timestamp = ?proposal.timestamp(),
+            "Received untimely proposal"
+        );
+
+        DriverInput::UntimelyProposal(proposal, validity)
+    }
+}
+
 pub async fn verify_signed_proposal<Ctx>(
     co: &Co<Ctx>,
     state: &State<Ctx>,
@@ -126,6 +227,7 @@ pub async fn verify_signed_proposal<Ctx>(
 where
     Ctx: Context,
 {
+    #[cfg(feature = "tracing")]
     let consensus_height = state.driver.height();
     let proposal_height = signed_proposal.height();
     let proposal_round = signed_proposal.round();
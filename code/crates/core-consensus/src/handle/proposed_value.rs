@@ -1,17 +1,21 @@
 use crate::prelude::*;
 
 use crate::handle::driver::apply_driver_input;
+use crate::handle::proposal::proposal_input;
 use crate::types::ProposedValue;
 
 use super::signature::sign_proposal;
 
-#[tracing::instrument(
-    skip_all,
-    fields(
-        height = %proposed_value.height,
-        round = %proposed_value.round,
-        validity = ?proposed_value.validity,
-        id = %proposed_value.value.id()
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        skip_all,
+        fields(
+            height = %proposed_value.height,
+            round = %proposed_value.round,
+            validity = ?proposed_value.validity,
+            id = %proposed_value.value.id()
+        )
     )
 )]
 pub async fn on_proposed_value<Ctx>(
@@ -40,6 +44,22 @@ where
         return Ok(());
     }
 
+    // Values assembled from proposal parts must come from the proposer of their round,
+    // while synced values are instead checked against their commit certificate.
+    if origin == ValueOrigin::Consensus {
+        let expected_proposer = state.get_proposer(proposed_value.height, proposed_value.round);
+
+        if expected_proposer != &proposed_value.proposer {
+            warn!(
+                proposer = %proposed_value.proposer,
+                expected = %expected_proposer,
+                "Received value from a non-proposer, dropping"
+            );
+
+            return Ok(());
+        }
+    }
+
     state.store_value(&proposed_value);
 
     // There are two cases where we need to generate an internal Proposal message for consensus to process the full proposal:
@@ -69,13 +89,8 @@ where
             "We have a full proposal for this round, checking..."
         );
 
-        apply_driver_input(
-            co,
-            state,
-            metrics,
-            DriverInput::Proposal(signed_proposal, proposed_value.validity),
-        )
-        .await?;
+        let input = proposal_input(state, signed_proposal, proposed_value.validity);
+        apply_driver_input(co, state, metrics, input).await?;
     }
 
     Ok(())
@@ -2,6 +2,7 @@ use crate::prelude::*;
 
 use crate::handle::driver::apply_driver_input;
 use crate::handle::handle_input;
+use crate::ParamsUpdate;
 
 pub async fn reset_and_start_height<Ctx>(
     co: &Co<Ctx>,
@@ -9,6 +10,7 @@ pub async fn reset_and_start_height<Ctx>(
     metrics: &Metrics,
     height: Ctx::Height,
     validator_set: Ctx::ValidatorSet,
+    params: ParamsUpdate,
 ) -> Result<(), Error<Ctx>>
 where
     Ctx: Context,
@@ -18,7 +20,13 @@ where
 
     metrics.step_end(state.driver.step());
 
+    if !params.is_empty() {
+        info!(%height, ?params, "Updating consensus parameters");
+        state.update_params(params);
+    }
+
     state.driver.move_to_height(height, validator_set);
+    state.clear_proposal_receive_times();
 
     debug_assert_eq!(state.driver.height(), height);
     debug_assert_eq!(state.driver.round(), Round::Nil);
@@ -65,7 +73,7 @@ async fn replay_pending_msgs<Ctx>(
 where
     Ctx: Context,
 {
-    let pending_inputs = std::mem::take(&mut state.input_queue);
+    let pending_inputs = core::mem::take(&mut state.input_queue);
     debug!(count = pending_inputs.len(), "Replaying inputs");
 
     for pending_input in pending_inputs {
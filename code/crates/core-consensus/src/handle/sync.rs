@@ -33,6 +33,10 @@ where
         return Err(Error::InvalidCertificate(certificate, e));
     }
 
+    // The extensions of the commits are given to the proposer of the next height,
+    // so only keep the ones the application considers valid, as for precommits received from peers
+    let certificate = verify_extensions(co, state, certificate).await?;
+
     // Go to Commit step via L49
     apply_driver_input(
         co,
@@ -44,3 +48,48 @@ where
 
     Ok(())
 }
+
+/// Ask the application to verify the extensions of the commits in a certificate received from Sync,
+/// removing the invalid ones.
+///
+/// The extensions are not covered by the signatures of the commits,
+/// which makes the certificate valid regardless.
+async fn verify_extensions<Ctx>(
+    co: &Co<Ctx>,
+    state: &State<Ctx>,
+    mut certificate: CommitCertificate<Ctx>,
+) -> Result<CommitCertificate<Ctx>, Error<Ctx>>
+where
+    Ctx: Context,
+{
+    for commit in &mut certificate.aggregated_signature.signatures {
+        if commit.extension.is_none() || &commit.address == state.address() {
+            continue;
+        }
+
+        let validity = perform!(
+            co,
+            Effect::VerifyVoteExtension(
+                certificate.height,
+                certificate.round,
+                certificate.value_id.clone(),
+                commit.address.clone(),
+                commit.extension.clone(),
+                Default::default()
+            ),
+            Resume::VoteExtensionValidity(validity) => validity
+        );
+
+        if !validity.is_valid() {
+            warn!(
+                certificate.height = %certificate.height,
+                validator = %commit.address,
+                "Received commit with invalid vote extension, dropping the extension"
+            );
+
+            commit.extension = None;
+        }
+    }
+
+    Ok(certificate)
+}
@@ -5,6 +5,7 @@ use crate::handle::signature::verify_signature;
 use crate::handle::validator_set::get_validator_set;
 use crate::input::Input;
 use crate::types::ConsensusMsg;
+#[cfg(feature = "tracing")]
 use crate::util::pretty::PrettyVote;
 
 pub async fn on_vote<Ctx>(
@@ -19,6 +20,7 @@ where
     let consensus_height = state.driver.height();
     let consensus_round = state.driver.round();
     let vote_height = signed_vote.height();
+    #[cfg(feature = "tracing")]
     let validator_address = signed_vote.validator_address();
 
     if consensus_height > vote_height {
@@ -75,6 +77,10 @@ where
 
     debug_assert_eq!(consensus_height, vote_height);
 
+    if !verify_vote_extension(co, state, &signed_vote).await? {
+        return Ok(());
+    }
+
     // Append the vote to the Write-ahead Log
     perform!(
         co,
@@ -94,6 +100,55 @@ where
     Ok(())
 }
 
+/// Ask the application to verify the extension of a precommit for a value received from a peer,
+/// or the lack thereof.
+///
+/// Returns `false` if the extension is invalid, in which case the precommit must not be counted.
+async fn verify_vote_extension<Ctx>(
+    co: &Co<Ctx>,
+    state: &State<Ctx>,
+    signed_vote: &SignedVote<Ctx>,
+) -> Result<bool, Error<Ctx>>
+where
+    Ctx: Context,
+{
+    let validator_address = signed_vote.validator_address();
+
+    // Only precommits are extended, and our own extensions were provided by the application
+    if signed_vote.vote_type() != VoteType::Precommit || validator_address == state.address() {
+        return Ok(true);
+    }
+
+    // Precommits for nil are not extended
+    let NilOrVal::Val(value_id) = signed_vote.value() else {
+        return Ok(true);
+    };
+
+    let validity = perform!(
+        co,
+        Effect::VerifyVoteExtension(
+            signed_vote.height(),
+            signed_vote.round(),
+            value_id.clone(),
+            validator_address.clone(),
+            signed_vote.extension().cloned(),
+            Default::default()
+        ),
+        Resume::VoteExtensionValidity(validity) => validity
+    );
+
+    if !validity.is_valid() {
+        warn!(
+            vote.height = %signed_vote.height(),
+            vote.round = %signed_vote.round(),
+            validator = %validator_address,
+            "Received precommit with invalid vote extension, dropping"
+        );
+    }
+
+    Ok(validity.is_valid())
+}
+
 pub async fn verify_signed_vote<Ctx>(
     co: &Co<Ctx>,
     state: &State<Ctx>,
@@ -102,7 +157,9 @@ pub async fn verify_signed_vote<Ctx>(
 where
     Ctx: Context,
 {
+    #[cfg(feature = "tracing")]
     let consensus_height = state.driver.height();
+    #[cfg(feature = "tracing")]
     let vote_height = signed_vote.height();
     let validator_address = signed_vote.validator_address();
 
@@ -1,10 +1,13 @@
+use alloc::string::String;
+
 use derive_where::derive_where;
 use malachitebft_core_types::{
-    CommitCertificate, Context, Round, SignedProposal, SignedVote, Timeout, ValueOrigin, VoteSet,
+    CommitCertificate, Context, Round, SignedProposal, SignedVote, Timeout, Timestamp, ValueOrigin,
+    VoteSet,
 };
 
 use crate::types::ProposedValue;
-use crate::ValueToPropose;
+use crate::{ParamsUpdate, ValueToPropose};
 
 pub type RequestId = String;
 
@@ -14,8 +17,9 @@ pub enum Input<Ctx>
 where
     Ctx: Context,
 {
-    /// Start a new height with the given validator set
-    StartHeight(Ctx::Height, Ctx::ValidatorSet),
+    /// Start a new height with the given validator set,
+    /// applying the given parameter updates from that height onwards
+    StartHeight(Ctx::Height, Ctx::ValidatorSet, ParamsUpdate),
 
     /// Process a vote
     Vote(SignedVote<Ctx>),
@@ -41,4 +45,8 @@ where
 
     /// Vote set to be sent to peer
     VoteSetResponse(VoteSet<Ctx>),
+
+    /// Local time at which a proposal for the given height and round was received,
+    /// replayed from the Write-Ahead Log after a crash
+    ProposalReceiveTime(Ctx::Height, Round, Timestamp),
 }
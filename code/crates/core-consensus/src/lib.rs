@@ -1,3 +1,20 @@
+//! Core consensus algorithm for the Malachite BFT consensus engine
+//!
+//! # Features
+//!
+//! - `std` (default): Enables support for types which require the standard library,
+//!   such as [`PeerId`] and [`Multiaddr`].
+//! - `metrics` (default): Record consensus metrics with `malachitebft-metrics`.
+//! - `tracing` (default): Emit logs with `tracing`.
+//!
+//! With default features disabled, this crate only depends on `core` and `alloc`,
+//! and can therefore run in `no_std` environments such as WebAssembly or zkVM guests.
+
+// no_std compatibility
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 mod prelude;
 
 mod input;
@@ -10,7 +27,7 @@ mod error;
 pub use error::Error;
 
 mod params;
-pub use params::{Params, ThresholdParams};
+pub use params::{Params, ParamsUpdate, ThresholdParams};
 
 mod effect;
 pub use effect::{Effect, Resumable, Resume};
@@ -35,6 +52,11 @@ pub use handle::handle;
 #[doc(hidden)]
 pub use full_proposal::{FullProposal, FullProposalKeeper};
 
+pub use util::metrics::Metrics;
+
 // Used in macros
 #[doc(hidden)]
-pub use tracing;
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub fn log_effect_error(error: &dyn core::fmt::Debug) {
+    util::log::error!("Error when processing effect: {error:?}");
+}
@@ -30,7 +30,7 @@ macro_rules! process {
                     let resume = match $handle {
                         Ok(resume) => resume,
                         Err(error) => {
-                            $crate::tracing::error!("Error when processing effect: {error:?}");
+                            $crate::log_effect_error(&error);
                             $crate::Resume::Continue
                         }
                     };
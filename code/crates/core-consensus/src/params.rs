@@ -1,5 +1,5 @@
 use derive_where::derive_where;
-use malachitebft_core_types::Context;
+use malachitebft_core_types::{Context, SynchronyParams};
 
 pub use malachitebft_core_driver::ThresholdParams;
 
@@ -22,4 +22,40 @@ pub struct Params<Ctx: Context> {
 
     /// The messages required to deliver proposals
     pub value_payload: ValuePayload,
+
+    /// The synchrony bounds used to check the timeliness of proposals,
+    /// or `None` if Proposer-Based Timestamps (PBTS) are disabled
+    pub synchrony_params: Option<SynchronyParams>,
+}
+
+impl<Ctx: Context> Params<Ctx> {
+    /// Apply the given update to these parameters.
+    pub fn apply(&mut self, update: ParamsUpdate) {
+        if let Some(threshold_params) = update.threshold_params {
+            self.threshold_params = threshold_params;
+        }
+
+        if let Some(value_payload) = update.value_payload {
+            self.value_payload = value_payload;
+        }
+    }
+}
+
+/// Consensus parameters to change when starting a new height.
+///
+/// Parameters left as `None` keep their current value.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParamsUpdate {
+    /// The new quorum and honest thresholds
+    pub threshold_params: Option<ThresholdParams>,
+
+    /// The new messages required to deliver proposals
+    pub value_payload: Option<ValuePayload>,
+}
+
+impl ParamsUpdate {
+    /// Whether this update leaves all parameters unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.threshold_params.is_none() && self.value_payload.is_none()
+    }
 }
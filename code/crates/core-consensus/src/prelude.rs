@@ -1,9 +1,10 @@
+pub use alloc::boxed::Box;
+pub use alloc::vec::Vec;
+
 pub use async_recursion::async_recursion;
-pub use tracing::{debug, info, warn};
 
 pub use malachitebft_core_driver::Input as DriverInput;
 pub use malachitebft_core_types::*;
-pub use malachitebft_metrics::Metrics;
 
 pub use crate::effect::{Effect, Resume};
 pub use crate::error::Error;
@@ -11,3 +12,5 @@ pub use crate::gen::Co;
 pub use crate::input::Input;
 pub use crate::perform;
 pub use crate::state::State;
+pub(crate) use crate::util::log::{debug, info, warn};
+pub use crate::util::metrics::Metrics;
@@ -1,12 +1,23 @@
-use std::collections::{BTreeMap, BTreeSet};
-use tracing::{debug, warn};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec;
+use alloc::vec::Vec;
 
 use malachitebft_core_driver::Driver;
 use malachitebft_core_types::*;
 
 use crate::input::Input;
+use crate::util::log::debug;
+#[cfg(feature = "tracing")]
+use crate::util::log::warn;
 use crate::util::max_queue::MaxQueue;
-use crate::{FullProposal, FullProposalKeeper, Params, ProposedValue};
+use crate::{
+    FullProposal, FullProposalKeeper, Params, ParamsUpdate, ProposedValue, VoteExtensions,
+    VoteTally,
+};
+
+/// Maximum number of rounds of the current height for which we keep
+/// the local time at which their proposal was received.
+const MAX_PROPOSAL_RECEIVE_TIMES: usize = 128;
 
 /// The state maintained by consensus for processing a [`Input`][crate::Input].
 pub struct State<Ctx>
@@ -33,6 +44,14 @@ where
 
     /// Decision per height
     pub decision: BTreeMap<(Ctx::Height, Round), SignedProposal<Ctx>>,
+
+    /// Local time at which the first proposal for a given round of the current height was received,
+    /// used to check the timeliness of proposals when PBTS is enabled.
+    pub proposal_receive_times: BTreeMap<Round, Timestamp>,
+
+    /// Height of the last decision reached by this node, with the vote extensions
+    /// of its commit certificate, to be given to the proposer of the next height.
+    pub last_vote_extensions: Option<(Ctx::Height, VoteExtensions<Ctx>)>,
 }
 
 impl<Ctx> State<Ctx>
@@ -56,6 +75,8 @@ where
             full_proposal_keeper: Default::default(),
             signed_precommits: Default::default(),
             decision: Default::default(),
+            proposal_receive_times: Default::default(),
+            last_vote_extensions: None,
         }
     }
 
@@ -75,6 +96,25 @@ where
         self.driver.validator_set()
     }
 
+    /// Update the consensus parameters.
+    ///
+    /// Must only be called at a height boundary, before moving the driver to the new height,
+    /// so that all parameters change atomically from that height onwards.
+    pub fn update_params(&mut self, update: ParamsUpdate) {
+        self.params.apply(update);
+        self.driver
+            .set_threshold_params(self.params.threshold_params);
+    }
+
+    /// The vote extensions to give to the proposer at the given height,
+    /// ie. those of the decision at the previous height, if any.
+    pub fn vote_extensions_for(&self, height: Ctx::Height) -> VoteExtensions<Ctx> {
+        match &self.last_vote_extensions {
+            Some((decided, extensions)) if decided.increment() == height => extensions.clone(),
+            _ => VoteExtensions::default(),
+        }
+    }
+
     pub fn get_proposer(&self, height: Ctx::Height, round: Round) -> &Ctx::Address {
         self.ctx
             .select_proposer(self.validator_set(), height, round)
@@ -161,6 +201,66 @@ where
         self.full_proposal_keeper.store_proposal(new_proposal)
     }
 
+    /// Record the local time at which a proposal for the given round of the current height was received.
+    ///
+    /// Only the first proposal received for a given round is taken into account.
+    /// At most [`MAX_PROPOSAL_RECEIVE_TIMES`] rounds are tracked, in which case
+    /// the highest round is forgotten in favor of a lower one.
+    ///
+    /// Returns whether the receive time was recorded.
+    pub fn record_proposal_receive_time(&mut self, round: Round, received_at: Timestamp) -> bool {
+        if self.proposal_receive_times.contains_key(&round) {
+            return false;
+        }
+
+        if self.proposal_receive_times.len() >= MAX_PROPOSAL_RECEIVE_TIMES {
+            match self.proposal_receive_times.last_key_value() {
+                Some((&highest, _)) if highest > round => {
+                    self.proposal_receive_times.remove(&highest);
+                }
+                _ => {
+                    debug!(%round, "Too many proposal receive times recorded, ignoring");
+                    return false;
+                }
+            }
+        }
+
+        self.proposal_receive_times.insert(round, received_at);
+        true
+    }
+
+    /// Check whether the given proposal is timely, as per Proposer-Based Timestamps (PBTS).
+    ///
+    /// Proposals are always considered timely if PBTS is disabled, or in parts-only mode
+    /// where proposals are implicitly derived from their parts rather than received over the network.
+    ///
+    /// Otherwise, a proposal for which we did not record a receive time is considered untimely.
+    /// This is the case of proposals provided by sync, which are decided through their
+    /// commit certificate rather than by prevoting for them, and of proposals for rounds
+    /// beyond the ones we keep track of.
+    pub fn is_proposal_timely(&self, proposal: &SignedProposal<Ctx>) -> bool {
+        let Some(synchrony_params) = &self.params.synchrony_params else {
+            return true;
+        };
+
+        if self.params.value_payload.parts_only() {
+            return true;
+        }
+
+        let Some(received_at) = self.proposal_receive_times.get(&proposal.round()) else {
+            return false;
+        };
+
+        match proposal.timestamp() {
+            Some(timestamp) => synchrony_params.is_timely(timestamp, *received_at),
+            None => false,
+        }
+    }
+
+    pub fn clear_proposal_receive_times(&mut self) {
+        self.proposal_receive_times.clear();
+    }
+
     pub fn store_value(&mut self, new_value: &ProposedValue<Ctx>) {
         // Values for higher height should have been cached for future processing
         assert_eq!(new_value.height, self.driver.height());
@@ -179,45 +279,57 @@ where
         self.input_queue.push(height, input);
     }
 
+    /// Tally the votes received so far in the given round at the current height,
+    /// or `None` if no vote was received for that round.
+    pub fn vote_tally(&self, round: Round) -> Option<VoteTally> {
+        let per_round = self.driver.votes().per_round(round)?;
+        let validator_set = self.driver.validator_set();
+
+        Some(VoteTally {
+            validators: validator_set.count(),
+            validators_voted: per_round.addresses_weights().get_inner().len(),
+            total_voting_power: validator_set.total_voting_power(),
+            voted_power: per_round.addresses_weights().sum(),
+            prevotes: per_round.votes().weight_sum(VoteType::Prevote),
+            prevotes_nil: per_round
+                .votes()
+                .get_weight(VoteType::Prevote, &NilOrVal::Nil),
+            precommits: per_round.votes().weight_sum(VoteType::Precommit),
+            precommits_nil: per_round
+                .votes()
+                .get_weight(VoteType::Precommit, &NilOrVal::Nil),
+        })
+    }
+
     pub fn print_state(&self) {
-        if let Some(per_round) = self.driver.votes().per_round(self.driver.round()) {
+        #[cfg(feature = "tracing")]
+        if let Some(tally) = self.vote_tally(self.driver.round()) {
             warn!(
                 "Number of validators having voted: {} / {}",
-                per_round.addresses_weights().get_inner().len(),
-                self.driver.validator_set().count()
+                tally.validators_voted, tally.validators
             );
             warn!(
                 "Total voting power of validators: {}",
-                self.driver.validator_set().total_voting_power()
+                tally.total_voting_power
             );
             warn!(
                 "Voting power required: {}",
-                self.driver.validator_set().total_voting_power() * 2 / 3
+                tally.total_voting_power * 2 / 3
             );
             warn!(
                 "Total voting power of validators having voted: {}",
-                per_round.addresses_weights().sum()
+                tally.voted_power
             );
             warn!(
                 "Total voting power of validators having prevoted nil: {}",
-                per_round
-                    .votes()
-                    .get_weight(VoteType::Prevote, &NilOrVal::Nil)
+                tally.prevotes_nil
             );
             warn!(
                 "Total voting power of validators having precommited nil: {}",
-                per_round
-                    .votes()
-                    .get_weight(VoteType::Precommit, &NilOrVal::Nil)
-            );
-            warn!(
-                "Total weight of prevotes: {}",
-                per_round.votes().weight_sum(VoteType::Prevote)
-            );
-            warn!(
-                "Total weight of precommits: {}",
-                per_round.votes().weight_sum(VoteType::Precommit)
+                tally.precommits_nil
             );
+            warn!("Total weight of prevotes: {}", tally.prevotes);
+            warn!("Total weight of precommits: {}", tally.precommits);
         }
     }
 }
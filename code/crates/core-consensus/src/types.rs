@@ -1,11 +1,16 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
 use derive_where::derive_where;
 
 use malachitebft_core_types::{
-    Context, Proposal, Round, Signature, SignedExtension, SignedProposal, SignedVote, Validity,
-    Vote,
+    CommitCertificate, Context, Proposal, Round, Signature, SignedExtension, SignedProposal,
+    SignedVote, Validity, Vote, VotingPower,
 };
 
+#[cfg(feature = "std")]
 pub use malachitebft_peer::PeerId;
+#[cfg(feature = "std")]
 pub use multiaddr::Multiaddr;
 
 /// A signed consensus message, ie. a signed vote or a signed proposal.
@@ -46,7 +51,6 @@ pub struct ValueToPropose<Ctx: Context> {
     pub round: Round,
     pub valid_round: Round,
     pub value: Ctx::Value,
-    pub extension: Option<SignedExtension<Ctx>>,
 }
 
 /// A value proposed by a validator
@@ -58,7 +62,68 @@ pub struct ProposedValue<Ctx: Context> {
     pub proposer: Ctx::Address,
     pub value: Ctx::Value,
     pub validity: Validity,
-    pub extension: Option<SignedExtension<Ctx>>,
+}
+
+/// The vote extensions carried by the precommits which decided the previous height,
+/// given to the proposer of the next height when asking it to build a value.
+#[derive_where(Clone, Debug, Default, PartialEq)]
+pub struct VoteExtensions<Ctx: Context> {
+    /// The extension of each validator whose precommit is part of the commit certificate
+    pub extensions: Vec<(Ctx::Address, SignedExtension<Ctx>)>,
+}
+
+impl<Ctx: Context> VoteExtensions<Ctx> {
+    /// Collect the vote extensions found in the given commit certificate
+    pub fn from_certificate(certificate: &CommitCertificate<Ctx>) -> Self {
+        let extensions = certificate
+            .aggregated_signature
+            .signatures
+            .iter()
+            .filter_map(|commit| {
+                let extension = commit.extension.clone()?;
+                Some((commit.address.clone(), extension))
+            })
+            .collect();
+
+        Self { extensions }
+    }
+
+    /// Whether there are no vote extensions
+    pub fn is_empty(&self) -> bool {
+        self.extensions.is_empty()
+    }
+
+    /// Number of vote extensions
+    pub fn len(&self) -> usize {
+        self.extensions.len()
+    }
+}
+
+/// The outcome of the validation by the application of a value received in a proposal
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValueValidity {
+    /// The value is valid
+    Valid,
+
+    /// The value is invalid, for the given reason
+    Invalid { reason: String },
+}
+
+impl ValueValidity {
+    /// Returns `Invalid` with the given reason
+    pub fn invalid(reason: impl Into<String>) -> Self {
+        Self::Invalid {
+            reason: reason.into(),
+        }
+    }
+
+    /// The validity of the value, without the reason for it being invalid
+    pub fn validity(&self) -> Validity {
+        match self {
+            Self::Valid => Validity::Valid,
+            Self::Invalid { .. } => Validity::Invalid,
+        }
+    }
 }
 
 /// The possible messages used to deliver proposals
@@ -92,3 +157,31 @@ impl ValuePayload {
         matches!(self, ValuePayload::ProposalOnly)
     }
 }
+
+/// Summary of the votes received in a round, as reported by [`State::vote_tally`][crate::State::vote_tally].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VoteTally {
+    /// Number of validators in the validator set
+    pub validators: usize,
+
+    /// Number of validators having voted
+    pub validators_voted: usize,
+
+    /// Total voting power of the validator set
+    pub total_voting_power: VotingPower,
+
+    /// Total voting power of the validators having voted
+    pub voted_power: VotingPower,
+
+    /// Total weight of prevotes
+    pub prevotes: VotingPower,
+
+    /// Total weight of prevotes for nil
+    pub prevotes_nil: VotingPower,
+
+    /// Total weight of precommits
+    pub precommits: VotingPower,
+
+    /// Total weight of precommits for nil
+    pub precommits_nil: VotingPower,
+}
@@ -0,0 +1,20 @@
+//! Logging macros, which forward to [`tracing`](https://docs.rs/tracing) when the `tracing`
+//! feature is enabled, and expand to nothing otherwise.
+
+#[cfg(feature = "tracing")]
+pub use tracing::{debug, error, info, warn};
+
+#[cfg(not(feature = "tracing"))]
+mod noop {
+    macro_rules! noop {
+        ($($arg:tt)*) => {{}};
+    }
+
+    pub(crate) use noop as debug;
+    pub(crate) use noop as error;
+    pub(crate) use noop as info;
+    pub(crate) use noop as warn;
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) use noop::{debug, error, info, warn};
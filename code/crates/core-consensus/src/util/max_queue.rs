@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 /// A data structure that maintains a queue of values associated with monotonically increasing indices,
 /// retaining only those values associated with the maximum index seen so far.
 ///
@@ -117,7 +119,7 @@ impl<I, T> MaxQueue<I, T> {
 /// - An iterator over values in the queue.
 impl<I, T> IntoIterator for MaxQueue<I, T> {
     type Item = T;
-    type IntoIter = std::vec::IntoIter<T>;
+    type IntoIter = alloc::vec::IntoIter<T>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.queue.into_iter()
@@ -126,6 +128,8 @@ impl<I, T> IntoIterator for MaxQueue<I, T> {
 
 #[cfg(test)]
 mod tests {
+    use alloc::vec;
+
     use super::*;
 
     #[test]
@@ -0,0 +1,58 @@
+//! Consensus metrics, which are only recorded when the `metrics` feature is enabled.
+
+#[cfg(feature = "metrics")]
+pub use malachitebft_metrics::Metrics;
+
+#[cfg(not(feature = "metrics"))]
+pub use noop::Metrics;
+
+#[cfg(not(feature = "metrics"))]
+mod noop {
+    use malachitebft_core_state_machine::state::Step;
+
+    /// Stand-in for the metrics recorded by consensus, which discards all measurements.
+    #[derive(Clone, Debug, Default)]
+    pub struct Metrics {
+        pub finalized_blocks: Counter,
+        pub consensus_round: Histogram,
+        pub proposal_round: Histogram,
+        pub step_timeouts: Counter,
+        pub invalid_values: Counter,
+        pub height: Gauge,
+        pub round: Gauge,
+    }
+
+    impl Metrics {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn consensus_start(&self) {}
+        pub fn consensus_end(&self) {}
+        pub fn block_start(&self) {}
+        pub fn block_end(&self) {}
+        pub fn step_start(&self, _step: Step) {}
+        pub fn step_end(&self, _step: Step) {}
+    }
+
+    #[derive(Clone, Debug, Default)]
+    pub struct Counter;
+
+    impl Counter {
+        pub fn inc(&self) {}
+    }
+
+    #[derive(Clone, Debug, Default)]
+    pub struct Gauge;
+
+    impl Gauge {
+        pub fn set(&self, _value: i64) {}
+    }
+
+    #[derive(Clone, Debug, Default)]
+    pub struct Histogram;
+
+    impl Histogram {
+        pub fn observe(&self, _value: f64) {}
+    }
+}
@@ -1,2 +1,5 @@
+pub mod log;
 pub mod max_queue;
+pub mod metrics;
+#[cfg(feature = "tracing")]
 pub mod pretty;
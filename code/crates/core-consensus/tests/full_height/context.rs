@@ -0,0 +1,367 @@
+//! A minimal context, which only depends on `core` and `alloc`
+//! so that it can be used on targets that the full test context does not support.
+
+use alloc::vec::Vec;
+use core::convert::Infallible;
+use core::fmt;
+
+use malachitebft_core_types::{
+    self as types, CertificateError, CommitCertificate, CommitSignature, NilOrVal, Round,
+    SignedExtension, SignedMessage, Timestamp, VoteType, VotingPower,
+};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Height(pub u64);
+
+impl fmt::Display for Height {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl types::Height for Height {
+    fn increment_by(&self, n: u64) -> Self {
+        Self(self.0 + n)
+    }
+
+    fn decrement_by(&self, n: u64) -> Option<Self> {
+        self.0.checked_sub(n).map(Self)
+    }
+
+    fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Address(pub u64);
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "validator-{}", self.0)
+    }
+}
+
+impl types::Address for Address {}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ValueId(pub u64);
+
+impl fmt::Display for ValueId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Value(pub u64);
+
+impl types::Value for Value {
+    type Id = ValueId;
+
+    fn id(&self) -> ValueId {
+        ValueId(self.0)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Proposal {
+    pub height: Height,
+    pub round: Round,
+    pub value: Value,
+    pub pol_round: Round,
+    pub proposer: Address,
+    pub timestamp: Option<Timestamp>,
+}
+
+impl types::Proposal<MinimalContext> for Proposal {
+    fn height(&self) -> Height {
+        self.height
+    }
+
+    fn round(&self) -> Round {
+        self.round
+    }
+
+    fn value(&self) -> &Value {
+        &self.value
+    }
+
+    fn take_value(self) -> Value {
+        self.value
+    }
+
+    fn pol_round(&self) -> Round {
+        self.pol_round
+    }
+
+    fn validator_address(&self) -> &Address {
+        &self.proposer
+    }
+
+    fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProposalPart;
+
+impl types::ProposalPart<MinimalContext> for ProposalPart {
+    fn is_first(&self) -> bool {
+        true
+    }
+
+    fn is_last(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Vote {
+    pub vote_type: VoteType,
+    pub height: Height,
+    pub round: Round,
+    pub value: NilOrVal<ValueId>,
+    pub validator: Address,
+    pub extension: Option<SignedExtension<MinimalContext>>,
+}
+
+impl types::Vote<MinimalContext> for Vote {
+    fn height(&self) -> Height {
+        self.height
+    }
+
+    fn round(&self) -> Round {
+        self.round
+    }
+
+    fn value(&self) -> &NilOrVal<ValueId> {
+        &self.value
+    }
+
+    fn take_value(self) -> NilOrVal<ValueId> {
+        self.value
+    }
+
+    fn vote_type(&self) -> VoteType {
+        self.vote_type
+    }
+
+    fn validator_address(&self) -> &Address {
+        &self.validator
+    }
+
+    fn extension(&self) -> Option<&SignedExtension<MinimalContext>> {
+        self.extension.as_ref()
+    }
+
+    fn extend(self, extension: SignedExtension<MinimalContext>) -> Self {
+        Self {
+            extension: Some(extension),
+            ..self
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Validator {
+    pub address: Address,
+    pub voting_power: VotingPower,
+}
+
+impl types::Validator<MinimalContext> for Validator {
+    fn address(&self) -> &Address {
+        &self.address
+    }
+
+    fn public_key(&self) -> &() {
+        &()
+    }
+
+    fn voting_power(&self) -> VotingPower {
+        self.voting_power
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidatorSet {
+    pub validators: Vec<Validator>,
+}
+
+impl types::ValidatorSet<MinimalContext> for ValidatorSet {
+    fn count(&self) -> usize {
+        self.validators.len()
+    }
+
+    fn total_voting_power(&self) -> VotingPower {
+        self.validators.iter().map(|v| v.voting_power).sum()
+    }
+
+    fn get_by_address(&self, address: &Address) -> Option<&Validator> {
+        self.validators.iter().find(|v| &v.address == address)
+    }
+
+    fn get_by_index(&self, index: usize) -> Option<&Validator> {
+        self.validators.get(index)
+    }
+}
+
+/// A signing scheme where signatures carry no information, and are therefore always valid.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NoSignature;
+
+impl types::SigningScheme for NoSignature {
+    type DecodingError = Infallible;
+    type Signature = ();
+    type PublicKey = ();
+    type PrivateKey = ();
+
+    fn decode_signature(_bytes: &[u8]) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn encode_signature(_signature: &()) -> Vec<u8> {
+        Vec::new()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct NoSigningProvider;
+
+impl types::SigningProvider<MinimalContext> for NoSigningProvider {
+    fn sign_vote(&self, vote: Vote) -> SignedMessage<MinimalContext, Vote> {
+        SignedMessage::new(vote, ())
+    }
+
+    fn verify_signed_vote(&self, _vote: &Vote, _signature: &(), _public_key: &()) -> bool {
+        true
+    }
+
+    fn sign_proposal(&self, proposal: Proposal) -> SignedMessage<MinimalContext, Proposal> {
+        SignedMessage::new(proposal, ())
+    }
+
+    fn verify_signed_proposal(
+        &self,
+        _proposal: &Proposal,
+        _signature: &(),
+        _public_key: &(),
+    ) -> bool {
+        true
+    }
+
+    fn sign_proposal_part(
+        &self,
+        proposal_part: ProposalPart,
+    ) -> SignedMessage<MinimalContext, ProposalPart> {
+        SignedMessage::new(proposal_part, ())
+    }
+
+    fn verify_signed_proposal_part(
+        &self,
+        _proposal_part: &ProposalPart,
+        _signature: &(),
+        _public_key: &(),
+    ) -> bool {
+        true
+    }
+
+    fn verify_commit_signature(
+        &self,
+        _certificate: &CommitCertificate<MinimalContext>,
+        _commit_sig: &CommitSignature<MinimalContext>,
+        validator: &Validator,
+    ) -> Result<VotingPower, CertificateError<MinimalContext>> {
+        Ok(validator.voting_power)
+    }
+}
+
+/// A context where the first validator of the set is always the proposer.
+#[derive(Clone, Debug)]
+pub struct MinimalContext {
+    signing_provider: NoSigningProvider,
+}
+
+impl MinimalContext {
+    pub fn new() -> Self {
+        Self {
+            signing_provider: NoSigningProvider,
+        }
+    }
+}
+
+impl types::Context for MinimalContext {
+    type Address = Address;
+    type Height = Height;
+    type ProposalPart = ProposalPart;
+    type Proposal = Proposal;
+    type Validator = Validator;
+    type ValidatorSet = ValidatorSet;
+    type Value = Value;
+    type Vote = Vote;
+    type SigningScheme = NoSignature;
+    type SigningProvider = NoSigningProvider;
+
+    fn select_proposer<'a>(
+        &self,
+        validator_set: &'a ValidatorSet,
+        _height: Height,
+        _round: Round,
+    ) -> &'a Validator {
+        &validator_set.validators[0]
+    }
+
+    fn signing_provider(&self) -> &NoSigningProvider {
+        &self.signing_provider
+    }
+
+    fn new_proposal(
+        height: Height,
+        round: Round,
+        value: Value,
+        pol_round: Round,
+        address: Address,
+    ) -> Proposal {
+        Proposal {
+            height,
+            round,
+            value,
+            pol_round,
+            proposer: address,
+            timestamp: None,
+        }
+    }
+
+    fn new_prevote(
+        height: Height,
+        round: Round,
+        value_id: NilOrVal<ValueId>,
+        address: Address,
+    ) -> Vote {
+        Vote {
+            vote_type: VoteType::Prevote,
+            height,
+            round,
+            value: value_id,
+            validator: address,
+            extension: None,
+        }
+    }
+
+    fn new_precommit(
+        height: Height,
+        round: Round,
+        value_id: NilOrVal<ValueId>,
+        address: Address,
+    ) -> Vote {
+        Vote {
+            vote_type: VoteType::Precommit,
+            height,
+            round,
+            value: value_id,
+            validator: address,
+            extension: None,
+        }
+    }
+}
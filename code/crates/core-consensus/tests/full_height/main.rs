@@ -0,0 +1,582 @@
+//! Drive consensus through a full height with the [`process!`] macro,
+//! using a minimal context and without any async runtime.
+//!
+//! This test does not depend on the engine, and is meant to also run on WebAssembly, eg.
+//!
+//! ```text
+//! cargo test -p informalsystems-malachitebft-core-consensus \
+//!   --no-default-features --target wasm32-wasip1 --test full_height
+//! ```
+
+extern crate alloc;
+
+mod context;
+
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::Infallible;
+use core::time::Duration;
+
+use informalsystems_malachitebft_core_consensus::{
+    process, Effect, Error, Input, Metrics, Params, ParamsUpdate, ProposedValue, Resumable, Resume,
+    SignedConsensusMsg, State, ValuePayload, ValueToPropose, ValueValidity, VoteExtensions,
+};
+use malachitebft_core_types::{
+    CommitCertificate, Context, Extension, NilOrVal, Round, SignedExtension, SignedMessage,
+    SigningProvider, SynchronyParams, Timeout, TimeoutKind, Timestamp, Validity, ValueOrigin,
+    Vote as _, VoteType,
+};
+
+use context::*;
+
+#[derive(Default)]
+struct Env {
+    metrics: Metrics,
+    inputs: VecDeque<Input<MinimalContext>>,
+    decisions: Vec<CommitCertificate<MinimalContext>>,
+    published: Vec<SignedConsensusMsg<MinimalContext>>,
+    cancelled_timeouts: Vec<Timeout>,
+    reject_values: bool,
+    reject_extensions: bool,
+    verified_extensions: Vec<Option<SignedExtension<MinimalContext>>>,
+    vote_extensions: Vec<VoteExtensions<MinimalContext>>,
+}
+
+/// Consensus state of the validator with the given address,
+/// along with the set of the given validators and their voting power.
+fn setup(
+    address: Address,
+    validators: &[(Address, u64)],
+    value_payload: ValuePayload,
+) -> (State<MinimalContext>, ValidatorSet) {
+    let validator_set = ValidatorSet {
+        validators: validators
+            .iter()
+            .map(|&(address, voting_power)| Validator {
+                address,
+                voting_power,
+            })
+            .collect(),
+    };
+
+    let params = Params {
+        initial_height: Height(1),
+        initial_validator_set: validator_set.clone(),
+        address,
+        threshold_params: Default::default(),
+        value_payload,
+        synchrony_params: None,
+    };
+
+    (State::new(MinimalContext::new(), params), validator_set)
+}
+
+/// Process the pending inputs, and the inputs they lead to, until there are none left
+fn run_until_idle(env: &mut Env, state: &mut State<MinimalContext>) {
+    let metrics = env.metrics.clone();
+
+    while let Some(input) = env.inputs.pop_front() {
+        process_input(env, state, &metrics, input).unwrap();
+    }
+}
+
+fn process_input(
+    env: &mut Env,
+    state: &mut State<MinimalContext>,
+    metrics: &Metrics,
+    input: Input<MinimalContext>,
+) -> Result<(), Error<MinimalContext>> {
+    let ctx = state.ctx.clone();
+
+    process!(
+        input: input,
+        state: state,
+        metrics: metrics,
+        with: effect => handle_effect(env, &ctx, effect)
+    )
+}
+
+fn handle_effect(
+    env: &mut Env,
+    ctx: &MinimalContext,
+    effect: Effect<MinimalContext>,
+) -> Result<Resume<MinimalContext>, Infallible> {
+    match effect {
+        // Timeouts never elapse in this test, except for the commit timeout
+        // which is needed for consensus to decide.
+        Effect::ScheduleTimeout(timeout, r) => {
+            if timeout.kind == TimeoutKind::Commit {
+                env.inputs.push_back(Input::TimeoutElapsed(timeout));
+            }
+
+            Ok(r.resume_with(()))
+        }
+
+        Effect::GetValue(height, round, _timeout, vote_extensions, r) => {
+            env.vote_extensions.push(vote_extensions);
+
+            env.inputs.push_back(Input::Propose(ValueToPropose {
+                height,
+                round,
+                valid_round: Round::Nil,
+                value: Value(height.0 * 100),
+            }));
+
+            Ok(r.resume_with(()))
+        }
+
+        Effect::Decide(certificate, r) => {
+            env.decisions.push(certificate);
+            Ok(r.resume_with(()))
+        }
+
+        Effect::SignVote(vote, r) => Ok(r.resume_with(ctx.signing_provider().sign_vote(vote))),
+
+        Effect::SignProposal(proposal, r) => {
+            Ok(r.resume_with(ctx.signing_provider().sign_proposal(proposal)))
+        }
+
+        Effect::ValidateValue(_, _, _, _, r) => {
+            if env.reject_values {
+                Ok(r.resume_with(ValueValidity::invalid("rejected by the test")))
+            } else {
+                Ok(r.resume_with(ValueValidity::Valid))
+            }
+        }
+
+        Effect::ExtendVote(height, _, _, r) => {
+            let extension = Extension::from(height.0.to_be_bytes().to_vec());
+            Ok(r.resume_with(Some(SignedExtension::new(extension, ()))))
+        }
+
+        Effect::VerifyVoteExtension(_, _, _, _, extension, r) => {
+            env.verified_extensions.push(extension);
+            Ok(r.resume_with(Validity::from_bool(!env.reject_extensions)))
+        }
+
+        Effect::Publish(msg, r) => {
+            env.published.push(msg);
+            Ok(r.resume_with(()))
+        }
+
+        Effect::VerifySignature(_, _, r) => Ok(r.resume_with(true)),
+        Effect::VerifyCertificate(_, _, _, r) => Ok(r.resume_with(Ok(()))),
+        Effect::GetValidatorSet(_, r) => Ok(r.resume_with(None)),
+        Effect::GetLocalTime(r) => Ok(r.resume_with(Timestamp::UNIX_EPOCH)),
+
+        Effect::CancelTimeout(timeout, r) => {
+            env.cancelled_timeouts.push(timeout);
+            Ok(r.resume_with(()))
+        }
+
+        // Nothing to persist, and nodes never ask for nor serve vote sets in this test
+        Effect::ResetTimeouts(r)
+        | Effect::CancelAllTimeouts(r)
+        | Effect::StartRound(_, _, _, r)
+        | Effect::RestreamValue(_, _, _, _, _, r)
+        | Effect::GetVoteSet(_, _, r)
+        | Effect::SendVoteSetResponse(_, _, _, _, r)
+        | Effect::PersistMessage(_, r)
+        | Effect::PersistTimeout(_, r)
+        | Effect::PersistProposalReceiveTime(_, _, r) => Ok(r.resume_with(())),
+    }
+}
+
+#[test]
+fn decide_full_height() {
+    let address = Address(1);
+    let (mut state, validator_set) = setup(address, &[(address, 1)], ValuePayload::PartsOnly);
+    let mut env = Env::default();
+
+    for height in [Height(1), Height(2)] {
+        env.inputs.push_back(Input::StartHeight(
+            height,
+            validator_set.clone(),
+            ParamsUpdate::default(),
+        ));
+
+        run_until_idle(&mut env, &mut state);
+
+        let certificate = env
+            .decisions
+            .last()
+            .expect("a value should have been decided");
+        assert_eq!(certificate.height, height);
+        assert_eq!(certificate.round, Round::new(0));
+        assert_eq!(certificate.value_id, ValueId(height.0 * 100));
+    }
+
+    assert_eq!(env.decisions.len(), 2);
+}
+
+#[test]
+fn prevote_nil_for_invalid_value() {
+    let (proposer, address) = (Address(2), Address(1));
+    let (mut state, validator_set) = setup(
+        address,
+        &[(proposer, 1), (address, 1)],
+        ValuePayload::ProposalOnly,
+    );
+    let mut env = Env {
+        reject_values: true,
+        ..Env::default()
+    };
+
+    let proposal = Proposal {
+        height: Height(1),
+        round: Round::new(0),
+        value: Value(42),
+        pol_round: Round::Nil,
+        proposer,
+        timestamp: None,
+    };
+
+    env.inputs.push_back(Input::StartHeight(
+        Height(1),
+        validator_set,
+        ParamsUpdate::default(),
+    ));
+    env.inputs
+        .push_back(Input::Proposal(SignedMessage::new(proposal, ())));
+
+    run_until_idle(&mut env, &mut state);
+
+    let prevote = env
+        .published
+        .iter()
+        .find_map(|msg| match msg {
+            SignedConsensusMsg::Vote(vote) if vote.vote_type == VoteType::Prevote => Some(vote),
+            _ => None,
+        })
+        .expect("a prevote should have been published");
+
+    assert_eq!(prevote.value, NilOrVal::Nil);
+    assert!(env.decisions.is_empty());
+
+    #[cfg(feature = "metrics")]
+    assert_eq!(env.metrics.invalid_values.get(), 1);
+}
+
+#[test]
+fn drop_values_from_non_proposers() {
+    let (proposer, address, other) = (Address(2), Address(1), Address(3));
+
+    for value_proposer in [other, proposer] {
+        let (mut state, validator_set) = setup(
+            address,
+            &[(proposer, 1), (address, 1), (other, 1)],
+            ValuePayload::PartsOnly,
+        );
+        let mut env = Env::default();
+
+        let value = ProposedValue {
+            height: Height(1),
+            round: Round::new(0),
+            valid_round: Round::Nil,
+            proposer: value_proposer,
+            value: Value(42),
+            validity: Validity::Valid,
+        };
+
+        env.inputs.push_back(Input::StartHeight(
+            Height(1),
+            validator_set,
+            ParamsUpdate::default(),
+        ));
+        env.inputs
+            .push_back(Input::ProposedValue(value, ValueOrigin::Consensus));
+
+        run_until_idle(&mut env, &mut state);
+
+        let prevoted = env.published.iter().any(|msg| {
+            matches!(msg, SignedConsensusMsg::Vote(vote) if vote.vote_type == VoteType::Prevote)
+        });
+
+        // We only prevote for the value of the proposer of the round
+        assert_eq!(prevoted, value_proposer == proposer);
+    }
+}
+
+#[test]
+fn cancel_propose_timeout_when_leaving_propose_step() {
+    let (proposer, address) = (Address(2), Address(1));
+    let (mut state, validator_set) = setup(
+        address,
+        &[(proposer, 1), (address, 1)],
+        ValuePayload::ProposalOnly,
+    );
+    let mut env = Env::default();
+
+    // A vote of our peer for round 1 makes us skip to that round
+    let prevote = MinimalContext::new_prevote(Height(1), Round::new(1), NilOrVal::Nil, proposer);
+
+    // The proposal for round 1 re-proposes a value for which we have not seen a polka in round 0
+    let proposal = Proposal {
+        height: Height(1),
+        round: Round::new(1),
+        value: Value(42),
+        pol_round: Round::new(0),
+        proposer,
+        timestamp: None,
+    };
+
+    env.inputs.push_back(Input::StartHeight(
+        Height(1),
+        validator_set,
+        ParamsUpdate::default(),
+    ));
+    env.inputs
+        .push_back(Input::Vote(SignedMessage::new(prevote, ())));
+    env.inputs
+        .push_back(Input::Proposal(SignedMessage::new(proposal, ())));
+
+    run_until_idle(&mut env, &mut state);
+
+    // Only the propose timeout of the round we skipped is cancelled, and we are still waiting
+    // for a proposal we can prevote for in round 1, until its propose timeout elapses
+    assert_eq!(state.driver.round(), Round::new(1));
+    assert!(state.driver.step_is_propose());
+    assert_eq!(
+        env.cancelled_timeouts,
+        vec![Timeout::propose(Round::new(0))]
+    );
+
+    env.inputs
+        .push_back(Input::TimeoutElapsed(Timeout::propose(Round::new(1))));
+
+    run_until_idle(&mut env, &mut state);
+
+    // The propose timeout of round 1 is cancelled once we leave the Propose step
+    assert!(!state.driver.step_is_propose());
+    assert_eq!(
+        env.cancelled_timeouts[..2],
+        [
+            Timeout::propose(Round::new(0)),
+            Timeout::propose(Round::new(1))
+        ]
+    );
+}
+
+#[test]
+fn check_timeliness_against_replayed_receive_time() {
+    let (proposer, address) = (Address(2), Address(1));
+
+    // Proposals are received at the Unix epoch, long after they were created
+    let timestamp = Timestamp::UNIX_EPOCH.saturating_add(Duration::from_secs(100));
+
+    // Without the receive time persisted before the crash, the replayed proposal is untimely
+    for (restored, expected) in [
+        (None, NilOrVal::Nil),
+        (Some(timestamp), NilOrVal::Val(ValueId(42))),
+    ] {
+        let (mut state, validator_set) = setup(
+            address,
+            &[(proposer, 1), (address, 1)],
+            ValuePayload::ProposalOnly,
+        );
+
+        state.params.synchrony_params = Some(SynchronyParams::new(
+            Duration::from_millis(500),
+            Duration::from_secs(2),
+        ));
+        let mut env = Env::default();
+
+        let proposal = Proposal {
+            height: Height(1),
+            round: Round::new(0),
+            value: Value(42),
+            pol_round: Round::Nil,
+            proposer,
+            timestamp: Some(timestamp),
+        };
+
+        env.inputs.push_back(Input::StartHeight(
+            Height(1),
+            validator_set,
+            ParamsUpdate::default(),
+        ));
+
+        if let Some(received_at) = restored {
+            env.inputs.push_back(Input::ProposalReceiveTime(
+                Height(1),
+                Round::new(0),
+                received_at,
+            ));
+        }
+
+        env.inputs
+            .push_back(Input::Proposal(SignedMessage::new(proposal, ())));
+
+        run_until_idle(&mut env, &mut state);
+
+        let prevote = env
+            .published
+            .iter()
+            .find_map(|msg| match msg {
+                SignedConsensusMsg::Vote(vote) if vote.vote_type == VoteType::Prevote => Some(vote),
+                _ => None,
+            })
+            .expect("a prevote should have been published");
+
+        assert_eq!(prevote.value, expected);
+    }
+}
+
+#[test]
+fn vote_extensions() {
+    let (address, peer) = (Address(1), Address(2));
+    // We are the proposer, but cannot decide without the votes of our peer
+    let (mut state, validator_set) =
+        setup(address, &[(address, 1), (peer, 3)], ValuePayload::PartsOnly);
+    let mut env = Env {
+        reject_extensions: true,
+        ..Env::default()
+    };
+
+    let value_id = NilOrVal::Val(ValueId(100));
+    let prevote = MinimalContext::new_prevote(Height(1), Round::new(0), value_id, peer);
+    let precommit = MinimalContext::new_precommit(Height(1), Round::new(0), value_id, peer)
+        .extend(SignedExtension::new(Extension::from(vec![42]), ()));
+
+    env.inputs.push_back(Input::StartHeight(
+        Height(1),
+        validator_set.clone(),
+        ParamsUpdate::default(),
+    ));
+    env.inputs
+        .push_back(Input::Vote(SignedMessage::new(prevote, ())));
+    env.inputs
+        .push_back(Input::Vote(SignedMessage::new(precommit.clone(), ())));
+
+    run_until_idle(&mut env, &mut state);
+
+    // Our own precommit is extended
+    let own_precommit = env
+        .published
+        .iter()
+        .find_map(|msg| match msg {
+            SignedConsensusMsg::Vote(vote) if vote.vote_type == VoteType::Precommit => Some(vote),
+            _ => None,
+        })
+        .expect("a precommit should have been published");
+
+    assert!(own_precommit.extension().is_some());
+
+    // The precommit of our peer is not counted, since its extension is invalid
+    assert!(env.decisions.is_empty());
+
+    env.reject_extensions = false;
+    env.inputs
+        .push_back(Input::Vote(SignedMessage::new(precommit, ())));
+
+    run_until_idle(&mut env, &mut state);
+
+    assert_eq!(env.decisions.len(), 1);
+
+    // The extensions of the decision are given to the proposer at the next height
+    env.inputs.push_back(Input::StartHeight(
+        Height(2),
+        validator_set,
+        ParamsUpdate::default(),
+    ));
+
+    run_until_idle(&mut env, &mut state);
+
+    assert_eq!(env.vote_extensions.len(), 2);
+    assert!(env.vote_extensions[0].is_empty());
+    assert_eq!(env.vote_extensions[1].len(), 2);
+}
+
+#[test]
+fn precommits_without_extension_are_verified() {
+    let (address, peer) = (Address(1), Address(2));
+    let (mut state, validator_set) =
+        setup(address, &[(address, 1), (peer, 3)], ValuePayload::PartsOnly);
+    let mut env = Env {
+        reject_extensions: true,
+        ..Env::default()
+    };
+
+    let value_id = NilOrVal::Val(ValueId(100));
+    let prevote = MinimalContext::new_prevote(Height(1), Round::new(0), value_id, peer);
+    let precommit = MinimalContext::new_precommit(Height(1), Round::new(0), value_id, peer);
+
+    env.inputs.push_back(Input::StartHeight(
+        Height(1),
+        validator_set,
+        ParamsUpdate::default(),
+    ));
+    env.inputs
+        .push_back(Input::Vote(SignedMessage::new(prevote, ())));
+    env.inputs
+        .push_back(Input::Vote(SignedMessage::new(precommit, ())));
+
+    run_until_idle(&mut env, &mut state);
+
+    // The application requires extensions, so the precommit of our peer is not counted
+    assert_eq!(env.verified_extensions, vec![None]);
+    assert!(env.decisions.is_empty());
+}
+
+#[test]
+fn vote_extensions_from_sync() {
+    let (address, peer) = (Address(1), Address(2));
+    let extension = SignedExtension::new(Extension::from(vec![42]), ());
+
+    for reject_extensions in [false, true] {
+        let (mut state, validator_set) =
+            setup(address, &[(address, 1), (peer, 3)], ValuePayload::PartsOnly);
+        let mut env = Env {
+            reject_extensions,
+            ..Env::default()
+        };
+
+        let value_id = ValueId(100);
+        let precommit =
+            MinimalContext::new_precommit(Height(1), Round::new(0), NilOrVal::Val(value_id), peer)
+                .extend(extension.clone());
+
+        let certificate = CommitCertificate::new(
+            Height(1),
+            Round::new(0),
+            value_id,
+            vec![SignedMessage::new(precommit, ())],
+        );
+
+        let value = ProposedValue {
+            height: Height(1),
+            round: Round::new(0),
+            valid_round: Round::Nil,
+            proposer: address,
+            value: Value(100),
+            validity: Validity::Valid,
+        };
+
+        env.inputs.push_back(Input::StartHeight(
+            Height(1),
+            validator_set.clone(),
+            ParamsUpdate::default(),
+        ));
+        env.inputs
+            .push_back(Input::ProposedValue(value, ValueOrigin::Sync));
+        env.inputs.push_back(Input::CommitCertificate(certificate));
+
+        run_until_idle(&mut env, &mut state);
+
+        assert_eq!(env.decisions.len(), 1);
+        assert_eq!(env.verified_extensions, vec![Some(extension.clone())]);
+
+        // The verified extensions of the synced certificate are given to the proposer at the next height
+        env.inputs.push_back(Input::StartHeight(
+            Height(2),
+            validator_set,
+            ParamsUpdate::default(),
+        ));
+
+        run_until_idle(&mut env, &mut state);
+
+        let expected = if reject_extensions { 0 } else { 1 };
+        assert_eq!(env.vote_extensions.last().unwrap().len(), expected);
+    }
+}
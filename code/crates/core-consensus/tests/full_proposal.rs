@@ -1,3 +1,5 @@
+#![cfg(not(target_arch = "wasm32"))]
+
 use malachitebft_core_types::{
     Context, Round, SignedProposal, SigningProvider, Validity, ValueOrigin,
 };
@@ -61,7 +63,6 @@ fn value(
         proposer,
         value: Value::new(value),
         validity,
-        extension: Default::default(),
     }
 }
 
@@ -74,7 +75,6 @@ fn val_msg(proposer: Address, round: u32, value: u64, validity: Validity) -> Inp
             value: Value::new(value),
             validity,
             proposer,
-            extension: Default::default(),
         },
         ValueOrigin::Consensus,
     )
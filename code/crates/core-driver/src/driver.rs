@@ -112,6 +112,18 @@ where
         self.certificates = vec![];
     }
 
+    /// Set the quorum thresholds to use from the next height onwards.
+    ///
+    /// The new thresholds only take effect after the next call to `move_to_height()`.
+    pub fn set_threshold_params(&mut self, threshold_params: ThresholdParams) {
+        self.threshold_params = threshold_params;
+    }
+
+    /// Return the configured quorum thresholds.
+    pub fn threshold_params(&self) -> ThresholdParams {
+        self.threshold_params
+    }
+
     /// Return the height of the consensus.
     pub fn height(&self) -> Ctx::Height {
         self.round_state.height
@@ -255,7 +267,10 @@ where
                 self.apply_new_round(height, round, proposer)
             }
             Input::ProposeValue(round, value) => self.apply_propose_value(round, value),
-            Input::Proposal(proposal, validity) => self.apply_proposal(proposal, validity),
+            Input::Proposal(proposal, validity) => self.apply_proposal(proposal, validity, true),
+            Input::UntimelyProposal(proposal, validity) => {
+                self.apply_proposal(proposal, validity, false)
+            }
             Input::Vote(vote) => self.apply_vote(vote),
             Input::TimeoutElapsed(timeout) => self.apply_timeout(timeout),
         }
@@ -311,6 +326,7 @@ where
         &mut self,
         proposal: SignedProposal<Ctx>,
         validity: Validity,
+        timely: bool,
     ) -> Result<Option<RoundOutput<Ctx>>, Error<Ctx>> {
         if self.height() != proposal.height() {
             return Err(Error::InvalidProposalHeight {
@@ -321,7 +337,7 @@ where
 
         let round = proposal.round();
 
-        match self.store_and_multiplex_proposal(proposal, validity) {
+        match self.store_and_multiplex_proposal(proposal, validity, timely) {
             Some(round_input) => self.apply_input(round, round_input),
             None => Ok(None),
         }
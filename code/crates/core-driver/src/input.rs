@@ -19,6 +19,10 @@ where
     /// Receive a proposal, of the given validity
     Proposal(SignedProposal<Ctx>, Validity),
 
+    /// Receive a proposal, of the given validity, which was not received in a timely manner
+    /// with respect to its timestamp, as per Proposer-Based Timestamps (PBTS)
+    UntimelyProposal(SignedProposal<Ctx>, Validity),
+
     /// Receive a vote
     Vote(SignedVote<Ctx>),
 
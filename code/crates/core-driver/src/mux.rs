@@ -20,6 +20,7 @@
 //! | any             | PrecommitAny          | \*              | PrecommitAny                    | any (unchanged) | L47            | sch\_precommit\_timer              |
 //! | propose         | none                  | InvalidProposal | InvalidProposal                 | prevote         | L22, L26       | prevote\_nil                       |
 //! | propose         | none                  | Proposal        | Proposal                        | prevote         | L22, L24       | prevote(v)                         |
+//! | propose         | none                  | UntimelyProposal| InvalidProposal                 | prevote         | L22, L26       | prevote\_nil                       |
 //! | propose         | PolkaPrevious(v, vr)  | InvalidProposal | InvalidProposalAndPolkaPrevious | prevote         | L28, L33       | prevote\_nil                       |
 //! | propose         | PolkaPrevious(v, vr)  | Proposal(v,vr)  | ProposalAndPolkaPrevious        | prevote         | L28, L30       | prevote(v)                         |
 //! | prevote         | PolkaNil              | \*              | PolkaNil                        | precommit       | L44            | precommit\_nil                     |
@@ -76,12 +77,16 @@ where
     /// 6. If we are at the propose step, and a polka exists for a the propopsal's POL round,
     ///    return `RoundInput::ProposalAndPolkaPrevious`, including the proposal.
     ///
-    /// 7. If none of the above conditions are met, simply wrap the proposal in
+    /// 7. If the proposal has no POL round and was not received in a timely manner,
+    ///    as per Proposer-Based Timestamps (PBTS), return `RoundInput::InvalidProposal`.
+    ///
+    /// 8. If none of the above conditions are met, simply wrap the proposal in
     ///    `RoundInput::Proposal` and return it.
     pub(crate) fn multiplex_proposal(
         &mut self,
         proposal: Ctx::Proposal,
         validity: Validity,
+        timely: bool,
     ) -> Option<RoundInput<Ctx>> {
         // Should only receive proposals for our height.
         assert_eq!(self.height(), proposal.height());
@@ -161,6 +166,11 @@ where
             return Some(RoundInput::ProposalAndPolkaPrevious(proposal));
         }
 
+        // L22 with PBTS: an untimely proposal without a POL round is treated as invalid
+        if !timely && proposal.pol_round().is_nil() {
+            return Some(RoundInput::InvalidProposal);
+        }
+
         Some(RoundInput::Proposal(proposal))
     }
 
@@ -168,6 +178,7 @@ where
         &mut self,
         signed_proposal: SignedProposal<Ctx>,
         validity: Validity,
+        timely: bool,
     ) -> Option<RoundInput<Ctx>> {
         // Should only receive proposals for our height.
         assert_eq!(self.height(), signed_proposal.height());
@@ -176,9 +187,12 @@ where
 
         // Store the proposal and its validity
         self.proposal_keeper
-            .store_proposal(signed_proposal, validity);
+            .store_proposal(signed_proposal, validity, timely);
 
-        self.multiplex_proposal(proposal, validity)
+        // Only the timeliness of the first copy of the proposal we received matters
+        let timely = self.proposal_keeper.is_proposal_timely(proposal.round());
+
+        self.multiplex_proposal(proposal, validity, timely)
     }
 
     pub(crate) fn store_and_multiplex_certificate(
@@ -268,7 +282,11 @@ where
 
             match self.round_state().step {
                 Step::Propose => {
-                    if let Some(input) = self.multiplex_proposal(proposal.clone(), *validity) {
+                    let timely = self.proposal_keeper.is_proposal_timely(round);
+
+                    if let Some(input) =
+                        self.multiplex_proposal(proposal.clone(), *validity, timely)
+                    {
                         result.push(input)
                     }
                 }
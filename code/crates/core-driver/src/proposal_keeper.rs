@@ -42,6 +42,9 @@ where
 {
     /// The proposal received in a given round (proposal.round) if any.
     proposal: Option<(SignedProposal<Ctx>, Validity)>,
+
+    /// Whether the proposal was received in a timely manner (PBTS).
+    timely: bool,
 }
 
 impl<Ctx> PerRound<Ctx>
@@ -53,6 +56,7 @@ where
         &mut self,
         proposal: SignedProposal<Ctx>,
         validity: Validity,
+        timely: bool,
     ) -> Result<(), RecordProposalError<Ctx>> {
         if let Some((existing, _)) = self.get_proposal() {
             if existing.value() != proposal.value() {
@@ -73,6 +77,11 @@ where
             }
         }
 
+        // Only record the timeliness of the first copy of the proposal we receive
+        if self.proposal.is_none() {
+            self.timely = timely;
+        }
+
         // Add the proposal
         self.proposal = Some((proposal, validity));
 
@@ -117,6 +126,13 @@ where
             .and_then(|round_info| round_info.proposal.as_ref())
     }
 
+    /// Return whether the proposal for the round, if any, was received in a timely manner.
+    pub fn is_proposal_timely(&self, round: Round) -> bool {
+        self.per_round
+            .get(&round)
+            .is_some_and(|round_info| round_info.proposal.is_some() && round_info.timely)
+    }
+
     /// Return the evidence of equivocation.
     pub fn evidence(&self) -> &EvidenceMap<Ctx> {
         &self.evidence
@@ -126,10 +142,15 @@ where
     ///
     /// # Precondition
     /// - The given proposal must have been proposed by the expected proposer at the proposal's height and round.
-    pub fn store_proposal(&mut self, proposal: SignedProposal<Ctx>, validity: Validity) {
+    pub fn store_proposal(
+        &mut self,
+        proposal: SignedProposal<Ctx>,
+        validity: Validity,
+        timely: bool,
+    ) {
         let per_round = self.per_round.entry(proposal.round()).or_default();
 
-        match per_round.add(proposal, validity) {
+        match per_round.add(proposal, validity, timely) {
             Ok(()) => (),
 
             Err(RecordProposalError::ConflictingProposal {
@@ -187,6 +208,19 @@ where
         self.map.get(address)
     }
 
+    /// Iterate over the evidence of equivocation, grouped by the address of the equivocating validator.
+    #[allow(clippy::type_complexity)]
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<
+        Item = (
+            &Ctx::Address,
+            &Vec<(SignedProposal<Ctx>, SignedProposal<Ctx>)>,
+        ),
+    > {
+        self.map.iter()
+    }
+
     /// Add evidence of equivocating proposals, ie. two proposals submitted by the same validator,
     /// but with different values but for the same height and round.
     ///
@@ -4,7 +4,8 @@ use std::sync::Arc;
 
 use malachitebft_core_state_machine::state::{RoundValue, State, Step};
 use malachitebft_core_types::{
-    NilOrVal, Round, SignedProposal, SignedVote, Timeout, TimeoutKind, Validity,
+    NilOrVal, Round, SignedProposal, SignedVote, ThresholdParam, ThresholdParams, Timeout,
+    TimeoutKind, Validity,
 };
 use malachitebft_test::proposer_selector::{FixedProposer, ProposerSelector, RotateProposer};
 use malachitebft_test::utils::validators::make_validators;
@@ -645,6 +646,123 @@ fn driver_steps_not_proposer_invalid() {
     run_steps(&mut driver, steps, sel.as_ref(), &vs);
 }
 
+#[test]
+fn driver_steps_not_proposer_untimely() {
+    let value = Value::new(9999);
+
+    let [(v1, _sk1), (v2, sk2), (v3, _sk3)] = make_validators([2, 1, 3]);
+
+    // Proposer is v1, so we are not the proposer
+    let (my_sk, my_addr) = (sk2, v2.address);
+
+    let height = Height::new(1);
+    let ctx = TestContext::new(my_sk.clone());
+    let sel = Arc::new(FixedProposer::new(v1.address));
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
+
+    let mut driver = Driver::new(ctx, height, vs.clone(), my_addr, Default::default());
+
+    let proposal =
+        new_signed_proposal(Height::new(1), Round::new(0), value, Round::Nil, v1.address);
+
+    let steps = vec![
+        TestStep {
+            desc: "Start round 0, we are not the proposer",
+            input: Some(Input::NewRound(
+                Height::new(1),
+                Round::new(0),
+                proposal.validator_address,
+            )),
+            expected_outputs: vec![Output::ScheduleTimeout(Timeout::propose(Round::new(0)))],
+            expected_round: Round::new(0),
+            new_state: State {
+                height: Height::new(1),
+                round: Round::new(0),
+                step: Step::Propose,
+                ..Default::default()
+            },
+        },
+        TestStep {
+            desc: "Receive a valid but untimely proposal, prevote nil (v2)",
+            input: Some(Input::UntimelyProposal(proposal.clone(), Validity::Valid)),
+            expected_outputs: vec![Output::Vote(Vote::new_prevote(
+                Height::new(1),
+                Round::new(0),
+                NilOrVal::Nil,
+                my_addr,
+            ))],
+            expected_round: Round::new(0),
+            new_state: State {
+                height: Height::new(1),
+                round: Round::new(0),
+                step: Step::Prevote,
+                ..Default::default()
+            },
+        },
+        TestStep {
+            desc: "Receive our own prevote (v2)",
+            input: None,
+            expected_outputs: vec![],
+            expected_round: Round::new(0),
+            new_state: State {
+                height: Height::new(1),
+                round: Round::new(0),
+                step: Step::Prevote,
+                ..Default::default()
+            },
+        },
+        TestStep {
+            desc: "v1 prevotes its own proposal",
+            input: Some(Input::Vote(new_signed_prevote(
+                Height::new(1),
+                Round::new(0),
+                NilOrVal::Val(value.id()),
+                v1.address,
+            ))),
+            expected_outputs: vec![],
+            expected_round: Round::new(0),
+            new_state: State {
+                height: Height::new(1),
+                round: Round::new(0),
+                step: Step::Prevote,
+                ..Default::default()
+            },
+        },
+        TestStep {
+            desc: "v3 prevotes v1's proposal, we have a polka for the untimely proposal, precommit it (v2)",
+            input: Some(Input::Vote(new_signed_prevote(
+                Height::new(1),
+                Round::new(0),
+                NilOrVal::Val(value.id()),
+                v3.address,
+            ))),
+            expected_outputs: vec![Output::Vote(Vote::new_precommit(
+                Height::new(1),
+                Round::new(0),
+                NilOrVal::Val(value.id()),
+                my_addr,
+            ))],
+            expected_round: Round::new(0),
+            new_state: State {
+                height: Height::new(1),
+                round: Round::new(0),
+                step: Step::Precommit,
+                locked: Some(RoundValue {
+                    value,
+                    round: Round::new(0),
+                }),
+                valid: Some(RoundValue {
+                    value,
+                    round: Round::new(0),
+                }),
+                ..Default::default()
+            },
+        },
+    ];
+
+    run_steps(&mut driver, steps, sel.as_ref(), &vs);
+}
+
 #[test]
 fn driver_steps_not_proposer_other_height() {
     let value = Value::new(9999);
@@ -1222,6 +1340,72 @@ fn driver_steps_skip_round_quorum_threshold() {
     run_steps(&mut driver, steps, sel.as_ref(), &vs);
 }
 
+#[test]
+fn driver_threshold_params_change_at_next_height() {
+    let value = Value::new(9999);
+
+    let [(v1, _sk1), (v2, sk2), (v3, _sk3)] = make_validators([1, 2, 3]);
+
+    // Proposer is v1, so we are not the proposer
+    let (my_sk, my_addr) = (sk2, v2.address);
+
+    let ctx = TestContext::new(my_sk.clone());
+    let vs = ValidatorSet::new(vec![v1.clone(), v2.clone(), v3.clone()]);
+
+    let mut driver = Driver::new(ctx, Height::new(1), vs.clone(), my_addr, Default::default());
+
+    // Lower the quorum threshold to more than a third of the total voting power
+    let threshold_params = ThresholdParams {
+        quorum: ThresholdParam::F_PLUS_ONE,
+        ..Default::default()
+    };
+
+    driver.set_threshold_params(threshold_params);
+    assert_eq!(driver.threshold_params(), threshold_params);
+
+    // Start round 0, receive the proposal and a prevote for it from v3, with a weight of 3 out of 6
+    let prevote_for_value = |driver: &mut Driver<TestContext>, height: Height| {
+        let proposal = new_signed_proposal(height, Round::new(0), value, Round::Nil, v1.address);
+
+        driver
+            .process(Input::NewRound(height, Round::new(0), v1.address))
+            .expect("execute succeeded");
+
+        driver
+            .process(Input::Proposal(proposal, Validity::Valid))
+            .expect("execute succeeded");
+
+        driver
+            .process(Input::Vote(new_signed_prevote(
+                height,
+                Round::new(0),
+                NilOrVal::Val(value.id()),
+                v3.address,
+            )))
+            .expect("execute succeeded")
+    };
+
+    // The current height still uses the 2f+1 quorum threshold, so a weight of 3 is not enough
+    let outputs = prevote_for_value(&mut driver, Height::new(1));
+    assert_eq!(outputs, vec![]);
+    assert_eq!(driver.step(), Step::Prevote);
+
+    // The new threshold applies from the next height onwards, where a weight of 3 is a quorum
+    driver.move_to_height(Height::new(2), vs);
+
+    let outputs = prevote_for_value(&mut driver, Height::new(2));
+    assert_eq!(
+        outputs,
+        vec![Output::Vote(Vote::new_precommit(
+            Height::new(2),
+            Round::new(0),
+            NilOrVal::Val(value.id()),
+            my_addr,
+        ))]
+    );
+    assert_eq!(driver.step(), Step::Precommit);
+}
+
 fn run_steps(
     driver: &mut Driver<TestContext>,
     steps: Vec<TestStep>,
@@ -1,6 +1,6 @@
 use crate::signing::SigningProvider;
 use crate::{
-    Address, Height, NilOrVal, Proposal, ProposalPart, Round, SigningScheme, Validator,
+    Address, Height, NilOrVal, Proposal, ProposalPart, Round, SigningScheme, Timestamp, Validator,
     ValidatorSet, Value, ValueId, Vote,
 };
 
@@ -60,6 +60,24 @@ where
         address: Self::Address,
     ) -> Self::Proposal;
 
+    /// Build a new proposal for the given value at the given height, round and POL round,
+    /// carrying the time at which it was created by the proposer.
+    ///
+    /// Used instead of [`Context::new_proposal`] when Proposer-Based Timestamps (PBTS) are enabled.
+    /// Contexts which support PBTS must override this method, the default implementation
+    /// ignores the timestamp.
+    fn new_timed_proposal(
+        height: Self::Height,
+        round: Round,
+        value: Self::Value,
+        pol_round: Round,
+        address: Self::Address,
+        timestamp: Timestamp,
+    ) -> Self::Proposal {
+        let _ = timestamp;
+        Self::new_proposal(height, round, value, pol_round, address)
+    }
+
     /// Build a new prevote vote by the validator with the given address,
     /// for the value identified by the given value id, at the given round.
     fn new_prevote(
@@ -23,6 +23,7 @@ mod signed_message;
 mod signing;
 mod threshold;
 mod timeout;
+mod timestamp;
 mod validator_set;
 mod value;
 mod vote;
@@ -62,6 +63,7 @@ pub use signed_message::SignedMessage;
 pub use signing::{SigningProvider, SigningProviderExt, SigningScheme};
 pub use threshold::{Threshold, ThresholdParam, ThresholdParams};
 pub use timeout::{Timeout, TimeoutKind};
+pub use timestamp::{SynchronyParams, Timestamp};
 pub use validator_set::{Address, Validator, ValidatorSet, VotingPower};
 pub use value::{NilOrVal, Value, ValueOrigin};
 pub use vote::{Extension, Vote, VoteType};
@@ -1,6 +1,6 @@
 use core::fmt::Debug;
 
-use crate::{Context, Round};
+use crate::{Context, Round, Timestamp};
 
 /// Defines the requirements for a proposal type.
 pub trait Proposal<Ctx>
@@ -25,6 +25,13 @@ where
 
     /// Address of the validator who issued this proposal
     fn validator_address(&self) -> &Ctx::Address;
+
+    /// The time at which the proposer created this proposal, if any.
+    ///
+    /// Only required when Proposer-Based Timestamps (PBTS) are enabled.
+    fn timestamp(&self) -> Option<Timestamp> {
+        None
+    }
 }
 
 /// Whether or not a proposal is valid.
@@ -0,0 +1,104 @@
+use core::fmt;
+use core::time::Duration;
+
+/// A point in time, represented as the duration elapsed since the Unix epoch.
+///
+/// Used by Proposer-Based Timestamps (PBTS), where a proposal carries the time
+/// at which it was created by its proposer.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(Duration);
+
+impl Timestamp {
+    /// The Unix epoch.
+    pub const UNIX_EPOCH: Self = Self(Duration::ZERO);
+
+    /// Create a timestamp from the duration elapsed since the Unix epoch.
+    pub const fn from_unix_duration(duration: Duration) -> Self {
+        Self(duration)
+    }
+
+    /// Create a timestamp from the number of nanoseconds elapsed since the Unix epoch.
+    pub const fn from_unix_nanos(nanos: u64) -> Self {
+        Self(Duration::from_nanos(nanos))
+    }
+
+    /// The duration elapsed since the Unix epoch.
+    pub const fn as_unix_duration(&self) -> Duration {
+        self.0
+    }
+
+    /// The number of nanoseconds elapsed since the Unix epoch,
+    /// saturating at `u64::MAX` (around year 2554).
+    pub fn as_unix_nanos(&self) -> u64 {
+        u64::try_from(self.0.as_nanos()).unwrap_or(u64::MAX)
+    }
+
+    /// Add the given duration to this timestamp, saturating on overflow.
+    pub fn saturating_add(self, duration: Duration) -> Self {
+        Self(self.0.saturating_add(duration))
+    }
+
+    /// Subtract the given duration from this timestamp, saturating at the Unix epoch.
+    pub fn saturating_sub(self, duration: Duration) -> Self {
+        Self(self.0.saturating_sub(duration))
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:09}", self.0.as_secs(), self.0.subsec_nanos())
+    }
+}
+
+/// Synchrony bounds used to check whether a proposal is timely,
+/// as per Proposer-Based Timestamps (PBTS).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SynchronyParams {
+    /// Bound on the difference between the clocks of any two correct validators.
+    pub precision: Duration,
+
+    /// Bound on the end-to-end delay for a proposal to reach all correct validators.
+    pub message_delay: Duration,
+}
+
+impl SynchronyParams {
+    /// Create new synchrony parameters.
+    pub const fn new(precision: Duration, message_delay: Duration) -> Self {
+        Self {
+            precision,
+            message_delay,
+        }
+    }
+
+    /// Whether a proposal with the given timestamp, received at the given local time, is timely.
+    ///
+    /// A proposal is timely if it was received no earlier than `timestamp - precision`,
+    /// and no later than `timestamp + message_delay + precision`.
+    pub fn is_timely(&self, timestamp: Timestamp, received_at: Timestamp) -> bool {
+        let lower_bound = timestamp.saturating_sub(self.precision);
+        let upper_bound = timestamp
+            .saturating_add(self.message_delay)
+            .saturating_add(self.precision);
+
+        lower_bound <= received_at && received_at <= upper_bound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_timely() {
+        let params = SynchronyParams::new(Duration::from_millis(500), Duration::from_secs(2));
+        let timestamp = Timestamp::from_unix_duration(Duration::from_secs(100));
+
+        let at = |millis| Timestamp::from_unix_duration(Duration::from_millis(millis));
+
+        assert!(!params.is_timely(timestamp, at(99_499)));
+        assert!(params.is_timely(timestamp, at(99_500)));
+        assert!(params.is_timely(timestamp, at(100_000)));
+        assert!(params.is_timely(timestamp, at(102_500)));
+        assert!(!params.is_timely(timestamp, at(102_501)));
+    }
+}
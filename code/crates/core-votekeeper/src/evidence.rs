@@ -36,6 +36,14 @@ where
         self.map.get(address)
     }
 
+    /// Iterate over the evidence of equivocation, grouped by the address of the equivocating validator.
+    #[allow(clippy::type_complexity)]
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<Item = (&Ctx::Address, &Vec<(SignedVote<Ctx>, SignedVote<Ctx>)>)> {
+        self.map.iter()
+    }
+
     /// Add evidence of equivocation.
     pub fn add(&mut self, existing: SignedVote<Ctx>, vote: SignedVote<Ctx>) {
         debug_assert_eq!(existing.validator_address(), vote.validator_address());
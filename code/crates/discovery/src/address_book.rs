@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{fs, io};
+
+use libp2p::{Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info};
+
+use crate::util;
+
+/// Maximum number of peers kept in the address book, the least recently seen ones are dropped first
+const MAX_ENTRIES: usize = 1000;
+
+/// Where to persist the address book and how long to remember peers for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AddressBookConfig {
+    /// File in which the address book is stored
+    pub path: PathBuf,
+
+    /// Peers which have not been seen for this long are forgotten
+    pub max_age: Duration,
+}
+
+/// What is known about a peer this node has been connected to
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Addresses the peer listens on
+    pub addrs: Vec<Multiaddr>,
+
+    /// Last time a connection to the peer was established, in seconds since the Unix epoch
+    pub last_seen: u64,
+
+    /// Number of connections successfully established with the peer
+    pub successes: u32,
+
+    /// Number of failed attempts to connect to the peer
+    pub failures: u32,
+}
+
+impl Entry {
+    /// Fraction of the connection attempts to this peer which succeeded
+    pub fn success_rate(&self) -> f64 {
+        let attempts = self.successes.saturating_add(self.failures);
+
+        if attempts == 0 {
+            0.0
+        } else {
+            f64::from(self.successes) / f64::from(attempts)
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Stored {
+    peers: HashMap<PeerId, Entry>,
+}
+
+/// Peers this node has been connected to, persisted across restarts to seed discovery.
+///
+/// Without a [`AddressBookConfig`], the address book is kept in memory only.
+#[derive(Debug, Default)]
+pub struct AddressBook {
+    config: Option<AddressBookConfig>,
+    entries: HashMap<PeerId, Entry>,
+    /// Whether the entries changed since they were last persisted
+    dirty: bool,
+}
+
+impl AddressBook {
+    /// How often to persist the changes to the address book, see [`AddressBook::flush`],
+    /// rather than writing it to disk on every connection
+    pub const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+    /// Load the address book from disk, starting with an empty one if it cannot be read
+    pub fn load(config: Option<AddressBookConfig>) -> Self {
+        let entries = match &config {
+            Some(config) => match read(config) {
+                Ok(stored) => stored.peers,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+                Err(e) => {
+                    error!(path = %config.path.display(), "Error loading address book: {e}");
+                    HashMap::new()
+                }
+            },
+            None => HashMap::new(),
+        };
+
+        let mut address_book = Self {
+            config,
+            entries,
+            dirty: false,
+        };
+        address_book.prune(now());
+
+        if address_book.config.is_some() {
+            info!("Loaded {} peers from the address book", address_book.len());
+        }
+
+        address_book
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, peer_id: &PeerId) -> Option<&Entry> {
+        self.entries.get(peer_id)
+    }
+
+    /// Up to `count` peers and the address to dial them at, the most reliable and most recently seen first
+    pub fn best_peers(&self, count: usize) -> Vec<(PeerId, Multiaddr)> {
+        let mut entries: Vec<_> = self
+            .entries
+            .iter()
+            .filter_map(|(peer_id, entry)| {
+                util::dialable_addr(&entry.addrs).map(|addr| (peer_id, entry, addr))
+            })
+            .collect();
+
+        entries.sort_by(|(_, a, _), (_, b, _)| {
+            b.success_rate()
+                .total_cmp(&a.success_rate())
+                .then(b.last_seen.cmp(&a.last_seen))
+        });
+
+        entries
+            .into_iter()
+            .take(count)
+            .map(|(peer_id, _, addr)| (*peer_id, addr.clone()))
+            .collect()
+    }
+
+    /// Record that a connection to the peer listening on the given addresses was established
+    pub fn record_success(&mut self, peer_id: PeerId, addrs: Vec<Multiaddr>) {
+        let entry = self.entries.entry(peer_id).or_insert_with(|| Entry {
+            addrs: Vec::new(),
+            last_seen: 0,
+            successes: 0,
+            failures: 0,
+        });
+
+        if !addrs.is_empty() {
+            entry.addrs = addrs;
+        }
+
+        entry.last_seen = now();
+        entry.successes = entry.successes.saturating_add(1);
+
+        self.dirty = true;
+    }
+
+    /// Record that all attempts to connect to a known peer failed
+    pub fn record_failure(&mut self, peer_id: &PeerId) {
+        if let Some(entry) = self.entries.get_mut(peer_id) {
+            entry.failures = entry.failures.saturating_add(1);
+
+            self.dirty = true;
+        }
+    }
+
+    /// Forget the peers for which the predicate returns `false`, eg. the peers which are now private
+    pub fn retain(&mut self, mut keep: impl FnMut(&PeerId, &Entry) -> bool) {
+        let len = self.entries.len();
+        self.entries.retain(|peer_id, entry| keep(peer_id, entry));
+
+        if self.entries.len() != len {
+            self.dirty = true;
+        }
+    }
+
+    /// Persist the changes made since the address book was last persisted, if any
+    pub fn flush(&mut self) {
+        if self.dirty {
+            self.save();
+            self.dirty = false;
+        }
+    }
+
+    /// Forget the peers which have not been seen for too long,
+    /// and the least recently seen ones if there are too many
+    fn prune(&mut self, now: u64) {
+        if let Some(config) = &self.config {
+            let max_age = config.max_age.as_secs();
+            self.entries
+                .retain(|_, entry| now.saturating_sub(entry.last_seen) <= max_age);
+        }
+
+        if self.entries.len() > MAX_ENTRIES {
+            let mut last_seen: Vec<_> = self.entries.values().map(|e| e.last_seen).collect();
+            last_seen.sort_unstable_by(|a, b| b.cmp(a));
+            let oldest_kept = last_seen[MAX_ENTRIES - 1];
+
+            self.entries
+                .retain(|_, entry| entry.last_seen >= oldest_kept);
+        }
+    }
+
+    /// Persist the address book, if it has a path
+    fn save(&mut self) {
+        self.prune(now());
+
+        let Some(config) = &self.config else {
+            return;
+        };
+
+        let stored = Stored {
+            peers: self.entries.clone(),
+        };
+
+        match write(config, &stored) {
+            Ok(()) => debug!("Saved {} peers to the address book", self.entries.len()),
+            Err(e) => error!(path = %config.path.display(), "Error saving address book: {e}"),
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn read(config: &AddressBookConfig) -> io::Result<Stored> {
+    let bytes = fs::read(&config.path)?;
+    serde_json::from_slice(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Write the address book to a temporary file first, so that it is never left half-written
+fn write(config: &AddressBookConfig, stored: &Stored) -> io::Result<()> {
+    if let Some(dir) = config.path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let bytes = serde_json::to_vec_pretty(stored).map_err(io::Error::other)?;
+
+    let tmp_path = config.path.with_extension("tmp");
+    fs::write(&tmp_path, bytes)?;
+    fs::rename(&tmp_path, &config.path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(port: u16) -> Multiaddr {
+        format!("/ip4/127.0.0.1/tcp/{port}").parse().unwrap()
+    }
+
+    fn config(name: &str) -> AddressBookConfig {
+        let dir = std::env::temp_dir().join(format!(
+            "malachitebft-address-book-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+
+        AddressBookConfig {
+            path: dir.join("address_book.json"),
+            max_age: Duration::from_secs(3600),
+        }
+    }
+
+    #[test]
+    fn persists_across_restarts() {
+        let config = config("persist");
+        let (reliable, unreliable) = (PeerId::random(), PeerId::random());
+
+        let mut address_book = AddressBook::load(Some(config.clone()));
+        assert!(address_book.is_empty());
+
+        address_book.record_success(unreliable, vec![addr(1000)]);
+        address_book.record_failure(&unreliable);
+        address_book.record_success(reliable, vec![addr(2000)]);
+
+        // Unknown peers are not recorded on failure
+        address_book.record_failure(&PeerId::random());
+
+        address_book.flush();
+
+        let address_book = AddressBook::load(Some(config.clone()));
+        assert_eq!(address_book.len(), 2);
+        assert_eq!(address_book.get(&unreliable).unwrap().success_rate(), 0.5);
+        assert_eq!(
+            address_book.best_peers(2),
+            vec![(reliable, addr(2000)), (unreliable, addr(1000))]
+        );
+        assert_eq!(address_book.best_peers(1), vec![(reliable, addr(2000))]);
+
+        let _ = fs::remove_dir_all(config.path.parent().unwrap());
+    }
+
+    #[test]
+    fn entries_age_out() {
+        let config = config("age");
+        let (old, recent) = (PeerId::random(), PeerId::random());
+
+        let mut address_book = AddressBook::load(Some(config.clone()));
+        address_book.record_success(recent, vec![addr(1000)]);
+        address_book.record_success(old, vec![addr(2000)]);
+        address_book.entries.get_mut(&old).unwrap().last_seen -= 2 * 3600;
+        address_book.save();
+
+        let address_book = AddressBook::load(Some(config.clone()));
+        assert_eq!(address_book.best_peers(10), vec![(recent, addr(1000))]);
+
+        let _ = fs::remove_dir_all(config.path.parent().unwrap());
+    }
+
+    #[test]
+    fn saves_changes_on_flush_only() {
+        let config = config("flush");
+        let (kept, forgotten) = (PeerId::random(), PeerId::random());
+
+        let mut address_book = AddressBook::load(Some(config.clone()));
+        address_book.record_success(kept, vec![addr(1000)]);
+        address_book.record_success(forgotten, vec![addr(2000)]);
+        assert!(AddressBook::load(Some(config.clone())).is_empty());
+
+        address_book.flush();
+        assert_eq!(AddressBook::load(Some(config.clone())).len(), 2);
+
+        address_book.retain(|peer_id, _| *peer_id == kept);
+        address_book.flush();
+        assert_eq!(
+            AddressBook::load(Some(config.clone())).best_peers(10),
+            vec![(kept, addr(1000))]
+        );
+
+        let _ = fs::remove_dir_all(config.path.parent().unwrap());
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::convert::Infallible;
 use std::iter;
 use std::time::Duration;
 
@@ -12,12 +13,17 @@ use libp2p::swarm::NetworkBehaviour;
 use libp2p::{kad, Multiaddr, PeerId, StreamProtocol};
 use serde::{Deserialize, Serialize};
 
-use crate::config::BootstrapProtocol;
+use crate::config::{BootstrapProtocol, PeerLists};
+use crate::filter::PeerFilter;
 use crate::Config;
 
 const DISCOVERY_KAD_PROTOCOL: &str = "/malachitebft-discovery/kad/v1beta1";
 const DISCOVERY_REQRES_PROTOCOL: &str = "/malachitebft-discovery/reqres/v1beta1";
 
+/// Agent version with which hidden nodes identify themselves, so that their peers
+/// neither add them to their routing tables nor share their addresses
+pub const HIDDEN_AGENT_VERSION: &str = "malachitebft-hidden";
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Request {
     Peers(HashSet<(Option<PeerId>, Multiaddr)>),
@@ -48,6 +54,12 @@ impl From<request_response::Event<Request, Response>> for NetworkEvent {
     }
 }
 
+impl From<Infallible> for NetworkEvent {
+    fn from(event: Infallible) -> Self {
+        match event {}
+    }
+}
+
 impl<A, B> From<Either<A, B>> for NetworkEvent
 where
     A: Into<NetworkEvent>,
@@ -64,6 +76,7 @@ where
 #[derive(NetworkBehaviour)]
 #[behaviour(to_swarm = "NetworkEvent")]
 pub struct Behaviour {
+    pub filter: PeerFilter,
     pub kademlia: Toggle<kad::Behaviour<MemoryStore>>,
     pub request_response: request_response::cbor::Behaviour<Request, Response>,
 }
@@ -89,7 +102,7 @@ fn request_response_config() -> request_response::Config {
 }
 
 impl Behaviour {
-    pub fn new(keypair: &Keypair, config: Config) -> Self {
+    pub fn new(keypair: &Keypair, config: Config, peer_lists: &PeerLists) -> Self {
         let kademlia = Toggle::from(
             (config.enabled && config.bootstrap_protocol == BootstrapProtocol::Kademlia).then(
                 || {
@@ -99,7 +112,12 @@ impl Behaviour {
                         kademlia_config(),
                     );
 
-                    kademlia.set_mode(Some(Mode::Server));
+                    // Peers only add to their routing table the nodes running in server mode
+                    kademlia.set_mode(Some(if config.hidden {
+                        Mode::Client
+                    } else {
+                        Mode::Server
+                    }));
 
                     kademlia
                 },
@@ -112,6 +130,7 @@ impl Behaviour {
         );
 
         Self {
+            filter: PeerFilter::new(peer_lists),
             kademlia,
             request_response,
         }
@@ -1,5 +1,7 @@
 use std::time::Duration;
 
+use libp2p::Multiaddr;
+
 const DEFAULT_NUM_OUTBOUND_PEERS: usize = 20;
 const DEFAULT_NUM_INBOUND_PEERS: usize = 20;
 
@@ -27,6 +29,10 @@ pub enum Selector {
 pub struct Config {
     pub enabled: bool,
 
+    /// Keep the address of this node out of the Kademlia routing tables and the peer exchanges
+    /// of its peers, eg. for a validator behind sentry nodes, see [`HIDDEN_AGENT_VERSION`]
+    pub hidden: bool,
+
     pub bootstrap_protocol: BootstrapProtocol,
     pub selector: Selector,
 
@@ -38,6 +44,10 @@ pub struct Config {
     pub dial_max_retries: usize,
     pub request_max_retries: usize,
     pub connect_request_max_retries: usize,
+
+    /// Dial the peers known by a QUIC address at the TCP address on the same port
+    /// if they cannot be reached over QUIC, eg. when listening on both transports
+    pub tcp_fallback: bool,
 }
 
 impl Default for Config {
@@ -48,6 +58,7 @@ impl Default for Config {
 
         Self {
             enabled: true,
+            hidden: false,
 
             bootstrap_protocol: BootstrapProtocol::default(),
             selector: Selector::default(),
@@ -60,6 +71,8 @@ impl Default for Config {
             dial_max_retries: DEFAULT_DIAL_MAX_RETRIES,
             request_max_retries: DEFAULT_PEERS_REQUEST_MAX_RETRIES,
             connect_request_max_retries: DEFAULT_CONNECT_REQUEST_MAX_RETRIES,
+
+            tcp_fallback: false,
         }
     }
 }
@@ -92,4 +105,30 @@ impl Config {
     pub fn set_ephemeral_connection_timeout(&mut self, timeout: Duration) {
         self.ephemeral_connection_timeout = timeout;
     }
+
+    pub fn set_hidden(&mut self, hidden: bool) {
+        self.hidden = hidden;
+    }
+}
+
+/// Peers given a special treatment, eg. to run a validator behind sentry nodes.
+///
+/// A peer matches an address if the address is a prefix of the peer's address, eg. `/ip4/10.0.0.1`,
+/// and if the address ends with a peer id, eg. `/ip4/10.0.0.1/tcp/27000/p2p/12D3KooW...`
+/// or simply `/p2p/12D3KooW...`, if that is the peer's id.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PeerLists {
+    /// If not empty, only connect to the peers matching one of these addresses
+    pub allowed: Vec<Multiaddr>,
+
+    /// Never connect to the peers matching one of these addresses
+    pub denied: Vec<Multiaddr>,
+
+    /// Never share the addresses of the peers matching one of these addresses,
+    /// neither through Kademlia nor in responses to peers requests
+    pub private: Vec<Multiaddr>,
+
+    /// Dial these addresses on startup and redial them whenever the connection drops,
+    /// without counting the connections towards the inbound and outbound limits
+    pub unconditional: Vec<Multiaddr>,
 }
@@ -1,3 +1,5 @@
+use std::num::NonZeroU8;
+
 use libp2p::{swarm::dial_opts::DialOpts, Multiaddr, PeerId};
 
 use crate::util::Retry;
@@ -6,6 +8,10 @@ use crate::util::Retry;
 pub struct ConnectionData {
     peer_id: Option<PeerId>,
     multiaddr: Multiaddr,
+    /// Addresses to try in order if the peer cannot be reached at `multiaddr`
+    fallback_addrs: Vec<Multiaddr>,
+    /// Fallback address being dialed, when the peer id is unknown, see [`Self::fall_back`]
+    fallback_index: Option<usize>,
     pub retry: Retry,
 }
 
@@ -14,10 +20,18 @@ impl ConnectionData {
         Self {
             peer_id,
             multiaddr,
+            fallback_addrs: Vec::new(),
+            fallback_index: None,
             retry: Retry::new(),
         }
     }
 
+    /// The addresses must belong to the same peer as `multiaddr`
+    pub fn with_fallback_addrs(mut self, fallback_addrs: Vec<Multiaddr>) -> Self {
+        self.fallback_addrs = fallback_addrs;
+        self
+    }
+
     pub fn set_peer_id(&mut self, peer_id: PeerId) {
         self.peer_id = Some(peer_id);
     }
@@ -30,15 +44,58 @@ impl ConnectionData {
         self.multiaddr.clone()
     }
 
+    /// Address the peer is being dialed at, ie. `multiaddr` unless falling back to another address
+    pub fn dial_addr(&self) -> Multiaddr {
+        self.fallback_index
+            .map_or(&self.multiaddr, |index| &self.fallback_addrs[index])
+            .clone()
+    }
+
+    pub fn is_fallback(&self) -> bool {
+        self.fallback_index.is_some()
+    }
+
+    /// Moves on to the next fallback address after failing to dial the peer.
+    ///
+    /// Only needed when the peer id is unknown, as a peer can then only be dialed at one address
+    /// at a time. Returns `false` once all the addresses were tried, starting over from `multiaddr`.
+    pub fn fall_back(&mut self) -> bool {
+        if self.peer_id.is_some() {
+            return false;
+        }
+
+        let next = self.fallback_index.map_or(0, |index| index + 1);
+
+        if next < self.fallback_addrs.len() {
+            self.fallback_index = Some(next);
+            true
+        } else {
+            self.fallback_index = None;
+            false
+        }
+    }
+
     pub fn build_dial_opts(&self) -> DialOpts {
         if let Some(peer_id) = self.peer_id {
-            DialOpts::peer_id(peer_id)
-                .addresses(vec![self.multiaddr.clone()])
-                .allocate_new_port()
-                .build()
+            let opts = DialOpts::peer_id(peer_id)
+                .addresses(
+                    std::iter::once(self.multiaddr.clone())
+                        .chain(self.fallback_addrs.iter().cloned())
+                        .collect(),
+                )
+                .allocate_new_port();
+
+            if self.fallback_addrs.is_empty() {
+                opts.build()
+            } else {
+                // Dial the addresses one at a time, so that the fallback addresses
+                // are only used if the peer cannot be reached at the preferred one
+                opts.override_dial_concurrency_factor(NonZeroU8::MIN)
+                    .build()
+            }
         } else {
             DialOpts::unknown_peer_id()
-                .address(self.multiaddr.clone())
+                .address(self.dial_addr())
                 .allocate_new_port()
                 .build()
         }
@@ -0,0 +1,273 @@
+use std::convert::Infallible;
+use std::fmt;
+use std::task::{Context, Poll};
+
+use libp2p::core::transport::PortUse;
+use libp2p::core::Endpoint;
+use libp2p::multiaddr::Protocol;
+use libp2p::swarm::{
+    dummy, ConnectionDenied, ConnectionId, FromSwarm, NetworkBehaviour, THandler, THandlerInEvent,
+    THandlerOutEvent, ToSwarm,
+};
+use libp2p::{Multiaddr, PeerId};
+use tracing::debug;
+
+use crate::config::PeerLists;
+
+/// Whether the given peer matches the given address, as described in [`PeerLists`].
+///
+/// Unknown peer ids and addresses never match.
+pub fn matches(pattern: &Multiaddr, peer_id: Option<&PeerId>, addr: Option<&Multiaddr>) -> bool {
+    let mut expected_peer_id = None;
+    let mut prefix = Vec::new();
+
+    for protocol in pattern.iter() {
+        match protocol {
+            Protocol::P2p(id) => expected_peer_id = Some(id),
+            protocol => prefix.push(protocol),
+        }
+    }
+
+    if let Some(expected_peer_id) = expected_peer_id {
+        if peer_id != Some(&expected_peer_id) {
+            return false;
+        }
+    }
+
+    if prefix.is_empty() {
+        return true;
+    }
+
+    addr.is_some_and(|addr| {
+        let protocols: Vec<_> = addr
+            .iter()
+            .filter(|protocol| !matches!(protocol, Protocol::P2p(_)))
+            .collect();
+
+        protocols.starts_with(&prefix)
+    })
+}
+
+/// Returns the peer id the address ends with, if any
+///
+/// For relayed addresses, this is the id of the peer reached through the relay, not of the relay.
+pub(crate) fn peer_id_of(addr: &Multiaddr) -> Option<PeerId> {
+    match addr.iter().last() {
+        Some(Protocol::P2p(peer_id)) => Some(peer_id),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+pub struct DeniedPeer {
+    peer_id: Option<PeerId>,
+    addr: Multiaddr,
+}
+
+impl fmt::Display for DeniedPeer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.peer_id {
+            Some(peer_id) => write!(f, "Peer {peer_id} at {} is not allowed", self.addr),
+            None => write!(f, "Peer at {} is not allowed", self.addr),
+        }
+    }
+}
+
+impl std::error::Error for DeniedPeer {}
+
+/// Denies connections to and from the peers which are not allowed by the [`PeerLists`].
+#[derive(Clone, Debug, Default)]
+pub struct PeerFilter {
+    allowed: Vec<Multiaddr>,
+    denied: Vec<Multiaddr>,
+}
+
+impl PeerFilter {
+    pub fn new(peer_lists: &PeerLists) -> Self {
+        Self {
+            allowed: peer_lists.allowed.clone(),
+            denied: peer_lists.denied.clone(),
+        }
+    }
+
+    /// Whether the peer is denied, based on what is known about it
+    pub fn is_denied(&self, peer_id: Option<&PeerId>, addr: Option<&Multiaddr>) -> bool {
+        self.denied
+            .iter()
+            .any(|pattern| matches(pattern, peer_id, addr))
+    }
+
+    /// Whether a connection to the given peer at the given address is allowed
+    pub fn is_allowed(&self, peer_id: &PeerId, addr: &Multiaddr) -> bool {
+        !self.is_denied(Some(peer_id), Some(addr))
+            && (self.allowed.is_empty()
+                || self
+                    .allowed
+                    .iter()
+                    .any(|pattern| matches(pattern, Some(peer_id), Some(addr))))
+    }
+
+    /// Whether dialing the given address might lead to an allowed connection.
+    ///
+    /// If the peer id is not known yet, only the denied addresses are checked,
+    /// the connection is then checked again once established.
+    pub fn may_dial(&self, peer_id: Option<&PeerId>, addr: &Multiaddr) -> bool {
+        match peer_id {
+            Some(peer_id) => self.is_allowed(peer_id, addr),
+            None => !self.is_denied(None, Some(addr)),
+        }
+    }
+
+    fn check(&self, peer_id: PeerId, addr: &Multiaddr) -> Result<(), ConnectionDenied> {
+        if self.is_allowed(&peer_id, addr) {
+            Ok(())
+        } else {
+            debug!(%peer_id, %addr, "Denying connection");
+
+            Err(ConnectionDenied::new(DeniedPeer {
+                peer_id: Some(peer_id),
+                addr: addr.clone(),
+            }))
+        }
+    }
+}
+
+impl NetworkBehaviour for PeerFilter {
+    type ConnectionHandler = dummy::ConnectionHandler;
+    type ToSwarm = Infallible;
+
+    fn handle_pending_inbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        _local_addr: &Multiaddr,
+        remote_addr: &Multiaddr,
+    ) -> Result<(), ConnectionDenied> {
+        // The peer id is not known yet, deny early based on the address only
+        if self.is_denied(None, Some(remote_addr)) {
+            debug!(addr = %remote_addr, "Denying connection");
+
+            return Err(ConnectionDenied::new(DeniedPeer {
+                peer_id: None,
+                addr: remote_addr.clone(),
+            }));
+        }
+
+        Ok(())
+    }
+
+    fn handle_established_inbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        peer: PeerId,
+        _local_addr: &Multiaddr,
+        remote_addr: &Multiaddr,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.check(peer, remote_addr)?;
+        Ok(dummy::ConnectionHandler)
+    }
+
+    fn handle_established_outbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        peer: PeerId,
+        addr: &Multiaddr,
+        _role_override: Endpoint,
+        _port_use: PortUse,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.check(peer, addr)?;
+        Ok(dummy::ConnectionHandler)
+    }
+
+    fn on_swarm_event(&mut self, _event: FromSwarm) {}
+
+    fn on_connection_handler_event(
+        &mut self,
+        _peer_id: PeerId,
+        _connection_id: ConnectionId,
+        event: THandlerOutEvent<Self>,
+    ) {
+        match event {}
+    }
+
+    fn poll(
+        &mut self,
+        _cx: &mut Context<'_>,
+    ) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(s: &str) -> Multiaddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn matches_address_prefix_and_peer_id() {
+        let peer_id = PeerId::random();
+        let other_peer_id = PeerId::random();
+        let peer_addr = addr("/ip4/10.0.0.1/tcp/27000");
+
+        assert!(matches(
+            &addr("/ip4/10.0.0.1"),
+            Some(&peer_id),
+            Some(&peer_addr)
+        ));
+        assert!(!matches(
+            &addr("/ip4/10.0.0.2"),
+            Some(&peer_id),
+            Some(&peer_addr)
+        ));
+        assert!(!matches(&addr("/ip4/10.0.0.1"), Some(&peer_id), None));
+
+        let by_id = addr(&format!("/p2p/{peer_id}"));
+        assert!(matches(&by_id, Some(&peer_id), Some(&peer_addr)));
+        assert!(matches(&by_id, Some(&peer_id), None));
+        assert!(!matches(&by_id, Some(&other_peer_id), Some(&peer_addr)));
+        assert!(!matches(&by_id, None, Some(&peer_addr)));
+
+        let by_both = addr(&format!("/ip4/10.0.0.1/tcp/27000/p2p/{peer_id}"));
+        assert!(matches(&by_both, Some(&peer_id), Some(&peer_addr)));
+        assert!(!matches(&by_both, Some(&other_peer_id), Some(&peer_addr)));
+    }
+
+    #[test]
+    fn denied_peers_take_precedence() {
+        let sentry = PeerId::random();
+        let other = PeerId::random();
+        let sentry_addr = addr("/ip4/10.0.0.1/tcp/27000");
+
+        let filter = PeerFilter::new(&PeerLists {
+            allowed: vec![addr("/ip4/10.0.0.3"), addr(&format!("/p2p/{sentry}"))],
+            denied: vec![addr(&format!("/p2p/{other}"))],
+            ..Default::default()
+        });
+
+        assert!(filter.is_allowed(&sentry, &sentry_addr));
+        assert!(filter.is_allowed(&sentry, &addr("/ip4/10.0.0.2/tcp/27000")));
+        assert!(!filter.is_allowed(&other, &addr("/ip4/10.0.0.3/tcp/27000")));
+        assert!(!filter.is_allowed(&PeerId::random(), &sentry_addr));
+        assert!(filter.is_allowed(&PeerId::random(), &addr("/ip4/10.0.0.3/tcp/27000")));
+
+        assert!(PeerFilter::default().is_allowed(&other, &sentry_addr));
+    }
+
+    #[test]
+    fn peer_id_of_relayed_address() {
+        let (relay, peer_id) = (PeerId::random(), PeerId::random());
+        let relay_addr = addr(&format!("/ip4/10.0.0.1/tcp/27000/p2p/{relay}"));
+
+        assert_eq!(peer_id_of(&relay_addr), Some(relay));
+        assert_eq!(
+            peer_id_of(&relay_addr.clone().with(Protocol::P2pCircuit)),
+            None
+        );
+        assert_eq!(
+            peer_id_of(&addr(&format!("{relay_addr}/p2p-circuit/p2p/{peer_id}"))),
+            Some(peer_id)
+        );
+    }
+}
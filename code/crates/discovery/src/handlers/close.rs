@@ -12,8 +12,10 @@ where
     }
 
     fn should_close(&self, peer_id: PeerId, connection_id: ConnectionId) -> bool {
+        // Never close connections to unconditional peers
+        !self.is_unconditional_peer(&peer_id)
         // Only close ephemeral connections (i.e not inbound/outbound connections)
-        self.outbound_connections
+        && self.outbound_connections
             .get(&peer_id)
             .map_or(true, |out_conn| {
                 out_conn.connection_id != Some(connection_id)
@@ -87,6 +89,18 @@ where
             self.inbound_connections.remove(&peer_id);
         }
 
+        if !self.active_connections.contains_key(&peer_id) {
+            if let Some((_, addr)) = self
+                .unconditional_peers
+                .iter()
+                .find(|(id, _)| id.as_ref() == Some(&peer_id))
+            {
+                warn!("Lost connection to unconditional peer {peer_id}");
+
+                self.redial_unconditional_peer(Some(peer_id), addr.clone());
+            }
+        }
+
         self.update_connections_metrics();
     }
 }
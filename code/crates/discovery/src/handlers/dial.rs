@@ -1,7 +1,13 @@
-use libp2p::{core::ConnectedPoint, swarm::ConnectionId, PeerId, Swarm};
-use tracing::{debug, error, info};
+use std::time::Duration;
 
-use crate::{connection::ConnectionData, controller::PeerData, Discovery, DiscoveryClient};
+use libp2p::{core::ConnectedPoint, swarm::ConnectionId, Multiaddr, PeerId, Swarm};
+use tracing::{debug, error, info, warn};
+
+use crate::{connection::ConnectionData, controller::PeerData, util, Discovery, DiscoveryClient};
+
+/// Delay before redialing an unconditional peer, after the connection dropped
+/// or all retries failed
+const UNCONDITIONAL_PEER_REDIAL_DELAY: Duration = Duration::from_secs(5);
 
 impl<C> Discovery<C>
 where
@@ -27,6 +33,8 @@ where
             && (!check_already_dialed || !self.controller.dial_is_done_on(connection_data) || connection_data.retry.count() != 0)
             // Is not itself (multiaddr)
             && !swarm.listeners().any(|addr| *addr == connection_data.multiaddr())
+            // Is not filtered out
+            && self.filter.may_dial(connection_data.peer_id().as_ref(), &connection_data.multiaddr())
     }
 
     pub fn dial_peer(&mut self, swarm: &mut Swarm<C>, connection_data: ConnectionData) {
@@ -45,14 +53,14 @@ where
             .dial
             .register_in_progress(connection_id, connection_data.clone());
 
-        // Do not count retries as new interactions
-        if connection_data.retry.count() == 0 {
+        // Do not count retries and fallbacks as new interactions
+        if connection_data.retry.count() == 0 && !connection_data.is_fallback() {
             self.metrics.increment_total_dials();
         }
 
         info!(
             "Dialing peer at {}, retry #{}",
-            connection_data.multiaddr(),
+            connection_data.dial_addr(),
             connection_data.retry.count()
         );
 
@@ -61,13 +69,13 @@ where
                 error!(
                     "Error dialing peer {} at {}: {}",
                     peer_id,
-                    connection_data.multiaddr(),
+                    connection_data.dial_addr(),
                     e
                 );
             } else {
                 error!(
                     "Error dialing peer at {}: {}",
-                    connection_data.multiaddr(),
+                    connection_data.dial_addr(),
                     e
                 );
             }
@@ -119,7 +127,15 @@ where
 
     pub fn handle_failed_connection(&mut self, swarm: &mut Swarm<C>, connection_id: ConnectionId) {
         if let Some(mut connection_data) = self.controller.dial.remove_in_progress(&connection_id) {
-            if connection_data.retry.count() < self.config.dial_max_retries {
+            if connection_data.fall_back() {
+                // Dial the next address of the peer right away, it does not count as a retry
+                debug!(
+                    "Falling back to dialing peer at {}",
+                    connection_data.dial_addr()
+                );
+
+                self.controller.dial.add_to_queue(connection_data, None);
+            } else if connection_data.retry.count() < self.config.dial_max_retries {
                 // Retry dialing after a delay
                 connection_data.retry.inc_count();
 
@@ -137,11 +153,55 @@ where
 
                 self.metrics.increment_total_failed_dials();
 
+                if let Some(peer_id) = connection_data.peer_id() {
+                    self.address_book.record_failure(&peer_id);
+                }
+
+                if self.is_unconditional_addr(&connection_data.multiaddr()) {
+                    self.redial_unconditional_peer(
+                        connection_data.peer_id(),
+                        connection_data.multiaddr(),
+                    );
+                }
+
                 self.make_extension_step(swarm);
             }
         }
     }
 
+    fn is_unconditional_addr(&self, addr: &Multiaddr) -> bool {
+        self.unconditional_peers.iter().any(|(_, a)| a == addr)
+    }
+
+    pub(crate) fn redial_unconditional_peer(&mut self, peer_id: Option<PeerId>, addr: Multiaddr) {
+        warn!(
+            "Redialing unconditional peer at {addr} in {}s",
+            UNCONDITIONAL_PEER_REDIAL_DELAY.as_secs()
+        );
+
+        // Start over with a fresh retry count
+        self.controller.dial.add_to_queue(
+            self.configured_peer_connection_data(peer_id, addr),
+            Some(UNCONDITIONAL_PEER_REDIAL_DELAY),
+        );
+    }
+
+    /// Connection data of a bootstrap node or unconditional peer, which falling back to TCP
+    /// applies to as their addresses are configured with a single transport
+    fn configured_peer_connection_data(
+        &self,
+        peer_id: Option<PeerId>,
+        addr: Multiaddr,
+    ) -> ConnectionData {
+        let fallback_addr = self
+            .config
+            .tcp_fallback
+            .then(|| util::tcp_fallback_addr(&addr))
+            .flatten();
+
+        ConnectionData::new(peer_id, addr).with_fallback_addrs(fallback_addr.into_iter().collect())
+    }
+
     pub(crate) fn add_to_dial_queue(&mut self, swarm: &Swarm<C>, connection_data: ConnectionData) {
         if self.should_dial(swarm, &connection_data, true) {
             // Already register as dialed address to avoid flooding the dial queue
@@ -154,7 +214,15 @@ where
 
     pub fn dial_bootstrap_nodes(&mut self, swarm: &Swarm<C>) {
         for (peer_id, addr) in &self.bootstrap_nodes.clone() {
-            self.add_to_dial_queue(swarm, ConnectionData::new(*peer_id, addr.clone()));
+            let connection_data = self.configured_peer_connection_data(*peer_id, addr.clone());
+            self.add_to_dial_queue(swarm, connection_data);
+        }
+    }
+
+    pub fn dial_unconditional_peers(&mut self, swarm: &Swarm<C>) {
+        for (peer_id, addr) in &self.unconditional_peers.clone() {
+            let connection_data = self.configured_peer_connection_data(*peer_id, addr.clone());
+            self.add_to_dial_queue(swarm, connection_data);
         }
     }
 }
@@ -2,7 +2,10 @@ use libp2p::{identify, swarm::ConnectionId, PeerId, Swarm};
 use tracing::{info, warn};
 
 use crate::config::BootstrapProtocol;
-use crate::{request::RequestData, Discovery, DiscoveryClient, OutboundConnection, State};
+use crate::{
+    is_hidden, is_private, request::RequestData, util, Discovery, DiscoveryClient,
+    OutboundConnection, State,
+};
 
 impl<C> Discovery<C>
 where
@@ -21,24 +24,39 @@ where
         peer_id: PeerId,
         info: identify::Info,
     ) {
-        // Ignore identify intervals
+        // Ignore identify intervals, but keep track of the addresses the peer listens on,
+        // eg. of the relays it got a reservation on since it was first identified
         if self
             .active_connections
             .get(&peer_id)
             .map_or(false, |connections| connections.contains(&connection_id))
         {
+            self.discovered_peers.insert(peer_id, info);
             return;
         }
 
-        if self
-            .controller
-            .dial
-            .remove_in_progress(&connection_id)
-            .is_none()
-        {
-            // Remove any matching in progress connections to avoid dangling data
-            self.controller
-                .dial_remove_matching_in_progress_connections(&peer_id);
+        let dialed_addr = match self.controller.dial.remove_in_progress(&connection_id) {
+            Some(connection_data) => Some(connection_data.multiaddr()),
+            None => {
+                // Remove any matching in progress connections to avoid dangling data
+                self.controller
+                    .dial_remove_matching_in_progress_connections(&peer_id);
+
+                None
+            }
+        };
+
+        // If the peer was dialed or listens at the address of an unconditional peer, save the peer id
+        if let Some(unconditional_peer) = self.unconditional_peers.iter_mut().find(|(id, addr)| {
+            id.is_none() && (dialed_addr.as_ref() == Some(addr) || info.listen_addrs.contains(addr))
+        }) {
+            unconditional_peer.0 = Some(peer_id);
+        }
+
+        // Never persist private and hidden peers
+        if !is_private(&self.private_peers, &peer_id, &info.listen_addrs) && !is_hidden(&info) {
+            self.address_book
+                .record_success(peer_id, info.listen_addrs.clone());
         }
 
         match self.discovered_peers.insert(peer_id, info.clone()) {
@@ -54,9 +72,9 @@ where
                 if let Some(bootstrap_node) = self
                     .bootstrap_nodes
                     .iter_mut()
-                    .find(|(_, addr)| addr == info.listen_addrs.first().unwrap())
+                    .find(|(_, addr)| info.listen_addrs.contains(addr))
                 {
-                    *bootstrap_node = (Some(peer_id), info.listen_addrs.first().unwrap().clone());
+                    bootstrap_node.0 = Some(peer_id);
                 }
             }
         }
@@ -72,7 +90,11 @@ where
             self.active_connections.insert(peer_id, vec![connection_id]);
         }
 
-        if self.is_enabled() {
+        if self.is_unconditional_peer(&peer_id) {
+            // Connections to unconditional peers are kept open, without counting
+            // towards the number of outbound and inbound connections
+            info!("Connection {connection_id} from peer {peer_id} is unconditional");
+        } else if self.is_enabled() {
             if self
                 .outbound_connections
                 .get(&peer_id)
@@ -124,12 +146,6 @@ where
                     self.make_extension_step(swarm);
                 }
             }
-            // Add the address to the Kademlia routing table
-            if self.config.bootstrap_protocol == BootstrapProtocol::Kademlia {
-                swarm
-                    .behaviour_mut()
-                    .add_address(&peer_id, info.listen_addrs.first().unwrap().clone());
-            }
         } else {
             // If discovery is disabled, connections to bootstrap nodes are outbound,
             // and all other connections are ephemeral, except if later the connections
@@ -158,6 +174,20 @@ where
             }
         }
 
+        // Add the addresses to the Kademlia routing table, unless the peer must be kept private
+        if self.is_enabled()
+            && self.config.bootstrap_protocol == BootstrapProtocol::Kademlia
+            && !is_hidden(&info)
+        {
+            for listen_addr in util::dialable_addrs(&info.listen_addrs) {
+                if !self.is_private_peer(Some(&peer_id), Some(listen_addr)) {
+                    swarm
+                        .behaviour_mut()
+                        .add_address(&peer_id, listen_addr.clone());
+                }
+            }
+        }
+
         self.update_connections_metrics();
     }
 }
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use libp2p::{
     request_response::{OutboundRequestId, ResponseChannel},
@@ -9,8 +9,9 @@ use tracing::{error, info, trace};
 use crate::{
     behaviour::{self, Response},
     connection::ConnectionData,
+    is_hidden,
     request::RequestData,
-    Discovery, DiscoveryClient,
+    util, Discovery, DiscoveryClient,
 };
 
 impl<C> Discovery<C>
@@ -138,12 +139,35 @@ where
         swarm: &mut Swarm<C>,
         peers: HashSet<(Option<PeerId>, Multiaddr)>,
     ) {
+        let mut addrs_by_peer: HashMap<PeerId, Vec<Multiaddr>> = HashMap::new();
+
         for (peer_id, listen_addr) in peers {
-            self.add_to_dial_queue(swarm, ConnectionData::new(peer_id, listen_addr));
+            match peer_id {
+                Some(peer_id) => addrs_by_peer.entry(peer_id).or_default().push(listen_addr),
+                None => self.add_to_dial_queue(swarm, ConnectionData::new(None, listen_addr)),
+            }
+        }
+
+        // Dial each peer at its preferred address, falling back to its other addresses.
+        // Addresses we listen on ourselves, eg. on the loopback interface, are not the peer's.
+        for (peer_id, listen_addrs) in addrs_by_peer {
+            let mut addrs = util::dialable_addrs(&listen_addrs)
+                .into_iter()
+                .filter(|addr| !swarm.listeners().any(|listen_addr| listen_addr == *addr))
+                .filter(|addr| self.filter.may_dial(Some(&peer_id), addr))
+                .cloned();
+
+            if let Some(addr) = addrs.next() {
+                let connection_data =
+                    ConnectionData::new(Some(peer_id), addr).with_fallback_addrs(addrs.collect());
+
+                self.add_to_dial_queue(swarm, connection_data);
+            }
         }
     }
 
-    /// Returns all discovered peers, including bootstrap nodes, except the given peer.
+    /// Returns all discovered peers, including bootstrap nodes, except the given peer and private peers.
+    /// A peer listening on several addresses is returned once per address.
     fn get_all_peers_except(&self, peer: PeerId) -> HashSet<(Option<PeerId>, Multiaddr)> {
         let mut remaining_bootstrap_nodes: Vec<_> = self.bootstrap_nodes.clone();
 
@@ -151,23 +175,30 @@ where
             .discovered_peers
             .iter()
             .filter_map(|(peer_id, info)| {
-                if peer_id == &peer {
-                    // Remove the peer also from the bootstrap nodes (if it is there)
-                    if let Some(addr) = info.listen_addrs.first() {
-                        remaining_bootstrap_nodes.retain(|(_, x)| x != addr);
-                    }
+                // Remove the peer also from the bootstrap nodes (if it is there)
+                remaining_bootstrap_nodes.retain(|(_, x)| !info.listen_addrs.contains(x));
 
+                // Never share the addresses of hidden peers
+                if peer_id == &peer || is_hidden(info) {
                     return None;
                 }
 
-                info.listen_addrs.first().map(|addr| {
-                    remaining_bootstrap_nodes.retain(|(_, x)| x != addr);
-                    (Some(*peer_id), addr.clone())
-                })
+                Some(
+                    util::dialable_addrs(&info.listen_addrs)
+                        .into_iter()
+                        // Never share the address of private peers
+                        .filter(|addr| !self.is_private_peer(Some(peer_id), Some(addr)))
+                        .map(|addr| (Some(*peer_id), addr.clone())),
+                )
             })
+            .flatten()
             .collect();
 
         for (peer_id, addr) in remaining_bootstrap_nodes {
+            if self.is_private_peer(peer_id.as_ref(), Some(&addr)) {
+                continue;
+            }
+
             peers.insert((peer_id, addr));
         }
 
@@ -43,6 +43,7 @@ where
             .keys()
             .filter(|peer_id| {
                 self.outbound_connections.contains_key(peer_id)
+                    || self.is_unconditional_peer(peer_id)
                     || self.controller.connect_request.is_done_on(peer_id)
             })
             .cloned()
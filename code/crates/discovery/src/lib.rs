@@ -8,6 +8,9 @@ use libp2p::{identify, kad, request_response, swarm::ConnectionId, Multiaddr, Pe
 
 mod util;
 
+mod address_book;
+pub use address_book::{AddressBook, AddressBookConfig};
+
 mod behaviour;
 pub use behaviour::*;
 
@@ -15,11 +18,14 @@ mod connection;
 use connection::ConnectionData;
 
 pub mod config;
-pub use config::Config;
+pub use config::{Config, PeerLists};
 
 mod controller;
 use controller::Controller;
 
+mod filter;
+pub use filter::{matches, PeerFilter};
+
 mod handlers;
 use handlers::selection::selector::Selector;
 
@@ -60,6 +66,10 @@ where
     selector: Box<dyn Selector<C>>,
 
     bootstrap_nodes: Vec<(Option<PeerId>, Multiaddr)>,
+    unconditional_peers: Vec<(Option<PeerId>, Multiaddr)>,
+    private_peers: Vec<Multiaddr>,
+    filter: PeerFilter,
+    address_book: AddressBook,
     discovered_peers: HashMap<PeerId, identify::Info>,
     active_connections: HashMap<PeerId, Vec<ConnectionId>>,
     outbound_connections: HashMap<PeerId, OutboundConnection>,
@@ -69,11 +79,32 @@ where
     metrics: Metrics,
 }
 
+/// Whether the peer identified itself as hidden, see [`Config::hidden`]
+fn is_hidden(info: &identify::Info) -> bool {
+    info.agent_version == HIDDEN_AGENT_VERSION
+}
+
+/// Whether the peer listening on the given addresses is private, by its id or any of its addresses
+fn is_private(private_peers: &[Multiaddr], peer_id: &PeerId, addrs: &[Multiaddr]) -> bool {
+    private_peers.iter().any(|pattern| {
+        filter::matches(pattern, Some(peer_id), None)
+            || addrs
+                .iter()
+                .any(|addr| filter::matches(pattern, Some(peer_id), Some(addr)))
+    })
+}
+
 impl<C> Discovery<C>
 where
     C: DiscoveryClient,
 {
-    pub fn new(config: Config, bootstrap_nodes: Vec<Multiaddr>, registry: &mut Registry) -> Self {
+    pub fn new(
+        config: Config,
+        bootstrap_nodes: Vec<Multiaddr>,
+        peer_lists: PeerLists,
+        mut address_book: AddressBook,
+        registry: &mut Registry,
+    ) -> Self {
         info!(
             "Discovery is {}",
             if config.enabled {
@@ -83,6 +114,37 @@ where
             }
         );
 
+        let mut bootstrap_nodes: Vec<_> = bootstrap_nodes
+            .into_iter()
+            .map(|addr| (None, addr))
+            .collect();
+
+        // Forget the peers which became private since they were persisted
+        address_book
+            .retain(|peer_id, entry| !is_private(&peer_lists.private, peer_id, &entry.addrs));
+
+        // Seed discovery with the most reliable peers this node was connected to before a restart
+        if config.enabled {
+            let known_peers: Vec<_> = address_book
+                .best_peers(config.num_outbound_peers)
+                .into_iter()
+                .filter(|(_, addr)| !bootstrap_nodes.iter().any(|(_, a)| a == addr))
+                .collect();
+
+            if !known_peers.is_empty() {
+                info!(
+                    "Seeding discovery with {} peers from the address book",
+                    known_peers.len()
+                );
+            }
+
+            bootstrap_nodes.extend(
+                known_peers
+                    .into_iter()
+                    .map(|(peer_id, addr)| (Some(peer_id), addr)),
+            );
+        }
+
         let state = if config.enabled && bootstrap_nodes.is_empty() {
             warn!("No bootstrap nodes provided");
             info!("Discovery found 0 peers in 0ms");
@@ -105,24 +167,31 @@ where
             State::Idle
         };
 
+        let filter = PeerFilter::new(&peer_lists);
+        let metrics = Metrics::new(registry, !config.enabled || bootstrap_nodes.is_empty());
+
         Self {
             config,
             state,
 
             selector: Discovery::get_selector(config.bootstrap_protocol, config.selector),
 
-            bootstrap_nodes: bootstrap_nodes
-                .clone()
+            bootstrap_nodes,
+            unconditional_peers: peer_lists
+                .unconditional
                 .into_iter()
-                .map(|addr| (None, addr))
+                .map(|addr| (filter::peer_id_of(&addr), addr))
                 .collect(),
+            filter,
+            private_peers: peer_lists.private,
+            address_book,
             discovered_peers: HashMap::new(),
             active_connections: HashMap::new(),
             outbound_connections: HashMap::new(),
             inbound_connections: HashMap::new(),
 
             controller: Controller::new(),
-            metrics: Metrics::new(registry, !config.enabled || bootstrap_nodes.is_empty()),
+            metrics,
         }
     }
 
@@ -130,6 +199,23 @@ where
         self.config.enabled
     }
 
+    fn is_unconditional_peer(&self, peer_id: &PeerId) -> bool {
+        self.unconditional_peers
+            .iter()
+            .any(|(id, _)| id.as_ref() == Some(peer_id))
+    }
+
+    fn is_private_peer(&self, peer_id: Option<&PeerId>, addr: Option<&Multiaddr>) -> bool {
+        self.private_peers
+            .iter()
+            .any(|pattern| filter::matches(pattern, peer_id, addr))
+    }
+
+    /// Persist the changes to the address book, see [`AddressBook::SAVE_INTERVAL`]
+    pub fn flush_address_book(&mut self) {
+        self.address_book.flush();
+    }
+
     fn active_connections_len(&self) -> usize {
         self.active_connections.values().map(Vec::len).sum()
     }
@@ -1,5 +1,52 @@
 use std::time::Duration;
 
+use libp2p::multiaddr::Protocol;
+use libp2p::Multiaddr;
+
+/// Address to reach a peer at among the addresses it listens on, see [`dialable_addrs`].
+pub(crate) fn dialable_addr(listen_addrs: &[Multiaddr]) -> Option<&Multiaddr> {
+    dialable_addrs(listen_addrs).into_iter().next()
+}
+
+/// Addresses a peer listens on, in the order in which to try to reach the peer at.
+///
+/// Direct addresses come first, so as not to go through a relay when the peer is reachable
+/// without it, eg. when it is on the same network as us. Relayed addresses come last,
+/// for peers behind a NAT to be reached through the relay once dialing them directly failed.
+/// QUIC addresses then come before the addresses of other transports, eg. TCP.
+pub(crate) fn dialable_addrs(listen_addrs: &[Multiaddr]) -> Vec<&Multiaddr> {
+    let mut addrs: Vec<_> = listen_addrs.iter().collect();
+    addrs.sort_by_key(|addr| (is_relayed(addr), !is_quic(addr)));
+    addrs
+}
+
+pub(crate) fn is_relayed(addr: &Multiaddr) -> bool {
+    addr.iter().any(|protocol| protocol == Protocol::P2pCircuit)
+}
+
+fn is_quic(addr: &Multiaddr) -> bool {
+    addr.iter()
+        .any(|protocol| matches!(protocol, Protocol::QuicV1 | Protocol::Quic))
+}
+
+/// TCP address on the same port as the given direct QUIC address, to fall back to
+/// if the peer cannot be reached over QUIC
+pub(crate) fn tcp_fallback_addr(addr: &Multiaddr) -> Option<Multiaddr> {
+    if !is_quic(addr) || is_relayed(addr) {
+        return None;
+    }
+
+    Some(
+        addr.iter()
+            .filter_map(|protocol| match protocol {
+                Protocol::Udp(port) => Some(Protocol::Tcp(port)),
+                Protocol::QuicV1 | Protocol::Quic => None,
+                protocol => Some(protocol),
+            })
+            .collect(),
+    )
+}
+
 #[derive(Debug, Clone)]
 struct FibonacciBackoff {
     current: u64,
@@ -57,3 +104,43 @@ impl Retry {
             .expect("FibonacciBackoff is an infinite iterator")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dial_direct_addresses_first() {
+        let relayed: Multiaddr =
+            "/ip4/1.2.3.4/udp/27000/quic-v1/p2p/12D3KooWHRyfTBKcjkqjNk5UZarJhzT7rXZYfr4DmaCWJgen62Xk/p2p-circuit"
+                .parse()
+                .unwrap();
+        let tcp: Multiaddr = "/ip4/5.6.7.8/tcp/27000".parse().unwrap();
+        let quic: Multiaddr = "/ip4/5.6.7.8/udp/27000/quic-v1".parse().unwrap();
+
+        let listen_addrs = vec![relayed.clone(), tcp.clone(), quic.clone()];
+
+        assert_eq!(dialable_addrs(&listen_addrs), vec![&quic, &tcp, &relayed]);
+        assert_eq!(dialable_addr(&listen_addrs), Some(&quic));
+    }
+
+    #[test]
+    fn fall_back_to_tcp_on_the_same_port() {
+        let quic: Multiaddr =
+            "/ip6/::1/udp/27000/quic-v1/p2p/12D3KooWHRyfTBKcjkqjNk5UZarJhzT7rXZYfr4DmaCWJgen62Xk"
+                .parse()
+                .unwrap();
+        let tcp: Multiaddr =
+            "/ip6/::1/tcp/27000/p2p/12D3KooWHRyfTBKcjkqjNk5UZarJhzT7rXZYfr4DmaCWJgen62Xk"
+                .parse()
+                .unwrap();
+        let relayed: Multiaddr =
+            "/ip4/1.2.3.4/udp/27000/quic-v1/p2p/12D3KooWHRyfTBKcjkqjNk5UZarJhzT7rXZYfr4DmaCWJgen62Xk/p2p-circuit"
+                .parse()
+                .unwrap();
+
+        assert_eq!(tcp_fallback_addr(&quic), Some(tcp.clone()));
+        assert_eq!(tcp_fallback_addr(&tcp), None);
+        assert_eq!(tcp_fallback_addr(&relayed), None);
+    }
+}
@@ -1,7 +1,8 @@
-use std::collections::BTreeSet;
-use std::time::Duration;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
+use derive_where::derive_where;
 use eyre::eyre;
 use ractor::{Actor, ActorProcessingErr, ActorRef, RpcReplyPort};
 use tokio::time::Instant;
@@ -10,13 +11,16 @@ use tracing::{debug, error, info, warn};
 use malachitebft_codec as codec;
 use malachitebft_config::TimeoutConfig;
 use malachitebft_core_consensus::{
-    Effect, PeerId, Resumable, Resume, SignedConsensusMsg, ValueToPropose,
+    Effect, PeerId, Resumable, Resume, SignedConsensusMsg, ThresholdParams, ValuePayload,
+    ValueToPropose, ValueValidity, VoteExtensions, VoteTally,
 };
+use malachitebft_core_state_machine::state::Step;
 use malachitebft_core_types::{
     Context, Round, SignedExtension, SigningProvider, SigningProviderExt, Timeout, TimeoutKind,
-    ValidatorSet, ValueOrigin,
+    Timestamp, ValidatorSet, Validity, ValueId, ValueOrigin,
 };
 use malachitebft_metrics::Metrics;
+use malachitebft_network::{MessageAcceptance, MessageId};
 use malachitebft_sync::{
     self as sync, InboundRequestId, Response, ValueResponse, VoteSetRequest, VoteSetResponse,
 };
@@ -26,7 +30,7 @@ use crate::network::{NetworkEvent, NetworkMsg, NetworkRef, Status};
 use crate::sync::Msg as SyncMsg;
 use crate::sync::SyncRef;
 use crate::util::events::{Event, TxEvent};
-use crate::util::streaming::StreamMessage;
+use crate::util::streaming::{StreamId, StreamMessage};
 use crate::util::timers::{TimeoutElapsed, TimerScheduler};
 use crate::wal::{Msg as WalMsg, WalEntry, WalRef};
 
@@ -60,6 +64,32 @@ where
 
 pub type ConsensusRef<Ctx> = ActorRef<Msg<Ctx>>;
 
+/// Consensus parameters to change when starting a new height.
+///
+/// All validators applying the same update at the same height switch to the
+/// new parameters together, without having to restart.
+/// Parameters left as `None` keep their current value.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ParamsUpdate {
+    /// The new timeouts
+    pub timeouts: Option<TimeoutConfig>,
+
+    /// The new quorum and honest thresholds
+    pub threshold_params: Option<ThresholdParams>,
+
+    /// The new messages required to deliver proposals
+    pub value_payload: Option<ValuePayload>,
+}
+
+impl ParamsUpdate {
+    fn consensus(&self) -> malachitebft_core_consensus::ParamsUpdate {
+        malachitebft_core_consensus::ParamsUpdate {
+            threshold_params: self.threshold_params,
+            value_payload: self.value_payload,
+        }
+    }
+}
+
 pub struct Consensus<Ctx>
 where
     Ctx: Context,
@@ -79,8 +109,9 @@ where
 pub type ConsensusMsg<Ctx> = Msg<Ctx>;
 
 pub enum Msg<Ctx: Context> {
-    /// Start consensus for the given height with the given validator set
-    StartHeight(Ctx::Height, Ctx::ValidatorSet),
+    /// Start consensus for the given height with the given validator set,
+    /// applying the given parameter updates from that height onwards
+    StartHeight(Ctx::Height, Ctx::ValidatorSet, ParamsUpdate),
 
     /// Received an event from the gossip layer
     NetworkEvent(NetworkEvent<Ctx>),
@@ -89,13 +120,39 @@ pub enum Msg<Ctx: Context> {
     TimeoutElapsed(TimeoutElapsed<Timeout>),
 
     /// The proposal builder has built a value and can be used in a new proposal consensus message
-    ProposeValue(Ctx::Height, Round, Ctx::Value, Option<SignedExtension<Ctx>>),
+    ProposeValue(Ctx::Height, Round, Ctx::Value),
 
     /// Received and assembled the full value proposed by a validator
     ReceivedProposedValue(ProposedValue<Ctx>, ValueOrigin),
 
+    /// Received and assembled the full value proposed by a validator,
+    /// from the proposal parts of a stream received from the given peer
+    ReceivedStreamedValue(PeerId, StreamId, ProposedValue<Ctx>),
+
     /// Get the status of the consensus state machine
     GetStatus(RpcReplyPort<Status<Ctx>>),
+
+    /// Get a snapshot of the state of consensus, for inspection purposes
+    GetInfo(RpcReplyPort<ConsensusInfo<Ctx>>),
+}
+
+/// Snapshot of the state of consensus at the current height and round
+#[derive_where(Clone, Debug)]
+pub struct ConsensusInfo<Ctx: Context> {
+    /// The current height
+    pub height: Ctx::Height,
+
+    /// The current round
+    pub round: Round,
+
+    /// The current step within the round
+    pub step: Step,
+
+    /// The proposer for the current round, if the round has started
+    pub proposer: Option<Ctx::Address>,
+
+    /// The votes received so far in the current round
+    pub votes: Option<VoteTally>,
 }
 
 impl<Ctx: Context> From<NetworkEvent<Ctx>> for Msg<Ctx> {
@@ -115,15 +172,27 @@ impl<Ctx: Context> From<TimeoutElapsed<Timeout>> for Msg<Ctx> {
 type Timers = TimerScheduler<Timeout>;
 
 struct Timeouts {
+    /// The timeouts to use at the start of each height
+    initial: TimeoutConfig,
+
+    /// The timeouts in effect at the current round
     config: TimeoutConfig,
 }
 
 impl Timeouts {
     pub fn new(config: TimeoutConfig) -> Self {
-        Self { config }
+        Self {
+            initial: config,
+            config,
+        }
     }
 
-    fn reset(&mut self, config: TimeoutConfig) {
+    fn reset(&mut self) {
+        self.config = self.initial;
+    }
+
+    fn update(&mut self, config: TimeoutConfig) {
+        self.initial = config;
         self.config = config;
     }
 
@@ -173,6 +242,62 @@ pub struct State<Ctx: Context> {
 
     /// The current phase
     phase: Phase,
+
+    /// Evidence of equivocation already reported at the current height
+    reported_evidence: ReportedEvidence<Ctx>,
+
+    /// The GossipSub messages carrying the proposal parts of each stream, to be validated
+    /// once a value is rebuilt from the stream
+    pending_streams: PendingStreams,
+}
+
+/// Maximum number of streams whose proposal parts are awaiting validation.
+/// The messages of the streams evicted beyond that are eventually ignored by the network layer.
+const MAX_PENDING_STREAMS: usize = 64;
+
+/// The GossipSub messages carrying the proposal parts of the streams received from peers
+#[derive(Default)]
+struct PendingStreams {
+    messages: HashMap<(PeerId, StreamId), Vec<MessageId>>,
+    order: VecDeque<(PeerId, StreamId)>,
+}
+
+impl PendingStreams {
+    fn insert(&mut self, from: PeerId, stream_id: StreamId, msg_id: MessageId) {
+        let key = (from, stream_id);
+
+        if !self.messages.contains_key(&key) {
+            if self.order.len() >= MAX_PENDING_STREAMS {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.messages.remove(&oldest);
+                }
+            }
+
+            self.order.push_back(key);
+        }
+
+        let messages = self.messages.entry(key).or_default();
+
+        // The proposal parts of an erasure-coded stream share the message announcing the stream
+        if !messages.contains(&msg_id) {
+            messages.push(msg_id);
+        }
+    }
+
+    fn remove(&mut self, from: PeerId, stream_id: StreamId) -> Vec<MessageId> {
+        let key = (from, stream_id);
+        self.order.retain(|k| k != &key);
+        self.messages.remove(&key).unwrap_or_default()
+    }
+}
+
+/// Number of equivocations already reported for each validator at a given height,
+/// so that each piece of evidence recorded by the driver is only reported once.
+#[derive_where(Default)]
+struct ReportedEvidence<Ctx: Context> {
+    height: Option<Ctx::Height>,
+    proposals: BTreeMap<Ctx::Address, usize>,
+    votes: BTreeMap<Ctx::Address, usize>,
 }
 
 impl<Ctx> State<Ctx>
@@ -223,6 +348,83 @@ where
         myself: &ActorRef<Msg<Ctx>>,
         state: &mut State<Ctx>,
         input: ConsensusInput<Ctx>,
+    ) -> Result<(), ConsensusError<Ctx>> {
+        let result = self
+            .process_consensus_input(myself, state, input, &mut None)
+            .await;
+
+        self.report_evidence(state);
+
+        result
+    }
+
+    /// Process a vote or proposal received from a peer in the given GossipSub message,
+    /// and report whether the message is valid, depending on its signature.
+    ///
+    /// Messages whose signature was not checked, eg. because they are for a past height,
+    /// are ignored rather than rejected.
+    async fn process_peer_input(
+        &self,
+        myself: &ActorRef<Msg<Ctx>>,
+        state: &mut State<Ctx>,
+        msg_id: MessageId,
+        input: ConsensusInput<Ctx>,
+    ) -> Result<(), ConsensusError<Ctx>> {
+        let mut valid_signature = None;
+
+        let result = self
+            .process_consensus_input(myself, state, input, &mut valid_signature)
+            .await;
+
+        self.report_evidence(state);
+
+        let acceptance = match valid_signature {
+            Some(true) => MessageAcceptance::Accept,
+            Some(false) => MessageAcceptance::Reject,
+            None => MessageAcceptance::Ignore,
+        };
+
+        self.report_validation(msg_id, acceptance);
+
+        result
+    }
+
+    async fn on_proposed_value(
+        &self,
+        myself: &ActorRef<Msg<Ctx>>,
+        state: &mut State<Ctx>,
+        value: ProposedValue<Ctx>,
+        origin: ValueOrigin,
+    ) {
+        self.tx_event
+            .send(|| Event::ReceivedProposedValue(value.clone(), origin));
+
+        let result = self
+            .process_input(myself, state, ConsensusInput::ProposedValue(value, origin))
+            .await;
+
+        if let Err(e) = result {
+            error!("Error when processing ReceivedProposedValue message: {e}");
+        }
+    }
+
+    fn report_validation(&self, msg_id: MessageId, acceptance: MessageAcceptance) {
+        if let Err(e) = self
+            .network
+            .cast(NetworkMsg::ReportValidation(msg_id, acceptance))
+        {
+            error!("Error when reporting message validation: {e}");
+        }
+    }
+
+    /// Process an input, recording in `valid_signature` whether the signatures checked
+    /// while doing so were all valid, if any.
+    async fn process_consensus_input(
+        &self,
+        myself: &ActorRef<Msg<Ctx>>,
+        state: &mut State<Ctx>,
+        input: ConsensusInput<Ctx>,
+        valid_signature: &mut Option<bool>,
     ) -> Result<(), ConsensusError<Ctx>> {
         let height = state.height();
 
@@ -231,18 +433,66 @@ where
             state: &mut state.consensus,
             metrics: &self.metrics,
             with: effect => {
-                self.handle_effect(
+                let resume = self.handle_effect(
                     myself,
                     height,
                     &mut state.timers,
                     &mut state.timeouts,
                     state.phase,
                     effect
-                ).await
+                ).await;
+
+                if let Ok(Resume::SignatureValidity(valid)) = &resume {
+                    *valid_signature = Some(valid_signature.unwrap_or(true) && *valid);
+                }
+
+                resume
             }
         )
     }
 
+    /// Emit an event for each piece of evidence of equivocation
+    /// recorded by the driver since the last time this was called.
+    fn report_evidence(&self, state: &mut State<Ctx>) {
+        let height = state.height();
+        let reported = &mut state.reported_evidence;
+
+        if reported.height != Some(height) {
+            *reported = ReportedEvidence {
+                height: Some(height),
+                ..Default::default()
+            };
+        }
+
+        let driver = &state.consensus.driver;
+
+        for (address, evidence) in driver.evidence().iter() {
+            let count = reported.proposals.entry(address.clone()).or_default();
+
+            for (existing, conflicting) in &evidence[*count..] {
+                warn!(%height, %address, "Detected equivocating proposals");
+
+                self.tx_event
+                    .send(|| Event::ProposalEquivocation(existing.clone(), conflicting.clone()));
+            }
+
+            *count = evidence.len();
+        }
+
+        for (address, evidence) in driver.votes().evidence().iter() {
+            let count = reported.votes.entry(address.clone()).or_default();
+
+            for (existing, conflicting) in &evidence[*count..] {
+                warn!(%height, %address, "Detected equivocating votes");
+
+                self.tx_event
+                    .send(|| Event::VoteEquivocation(existing.clone(), conflicting.clone()));
+            }
+
+            *count = evidence.len();
+        }
+    }
+
     async fn handle_msg(
         &self,
         myself: ActorRef<Msg<Ctx>>,
@@ -250,14 +500,19 @@ where
         msg: Msg<Ctx>,
     ) -> Result<(), ActorProcessingErr> {
         match msg {
-            Msg::StartHeight(height, validator_set) => {
+            Msg::StartHeight(height, validator_set, params) => {
                 state.phase = Phase::Running;
 
+                if let Some(timeouts) = params.timeouts {
+                    info!(%height, ?timeouts, "Updating timeouts");
+                    state.timeouts.update(timeouts);
+                }
+
                 let result = self
                     .process_input(
                         &myself,
                         state,
-                        ConsensusInput::StartHeight(height, validator_set),
+                        ConsensusInput::StartHeight(height, validator_set, params.consensus()),
                     )
                     .await;
 
@@ -281,13 +536,12 @@ where
                 Ok(())
             }
 
-            Msg::ProposeValue(height, round, value, extension) => {
+            Msg::ProposeValue(height, round, value) => {
                 let value_to_propose = ValueToPropose {
                     height,
                     round,
                     valid_round: Round::Nil,
                     value: value.clone(),
-                    extension,
                 };
 
                 let result = self
@@ -444,35 +698,45 @@ where
                         }
                     }
 
-                    NetworkEvent::Vote(from, vote) => {
+                    NetworkEvent::Vote(from, msg_id, vote) => {
                         if let Err(e) = self
-                            .process_input(&myself, state, ConsensusInput::Vote(vote))
+                            .process_peer_input(&myself, state, msg_id, ConsensusInput::Vote(vote))
                             .await
                         {
                             error!(%from, "Error when processing vote: {e}");
                         }
                     }
 
-                    NetworkEvent::Proposal(from, proposal) => {
+                    NetworkEvent::Proposal(from, msg_id, proposal) => {
                         if state.consensus.params.value_payload.parts_only() {
                             error!(%from, "Properly configured peer should never send proposal messages in BlockPart mode");
+                            self.report_validation(msg_id, MessageAcceptance::Reject);
                             return Ok(());
                         }
 
                         if let Err(e) = self
-                            .process_input(&myself, state, ConsensusInput::Proposal(proposal))
+                            .process_peer_input(
+                                &myself,
+                                state,
+                                msg_id,
+                                ConsensusInput::Proposal(proposal),
+                            )
                             .await
                         {
                             error!(%from, "Error when processing proposal: {e}");
                         }
                     }
 
-                    NetworkEvent::ProposalPart(from, part) => {
+                    NetworkEvent::ProposalPart(from, msg_id, part) => {
                         if state.consensus.params.value_payload.proposal_only() {
                             error!(%from, "Properly configured peer should never send block part messages in Proposal mode");
+                            self.report_validation(msg_id, MessageAcceptance::Reject);
                             return Ok(());
                         }
 
+                        let stream_id = part.stream_id;
+                        state.pending_streams.insert(from, stream_id, msg_id);
+
                         self.host
                             .call_and_forward(
                                 |reply_to| HostMsg::ReceivedProposalPart {
@@ -481,7 +745,7 @@ where
                                     reply_to,
                                 },
                                 &myself,
-                                |value| Msg::ReceivedProposedValue(value, ValueOrigin::Consensus),
+                                move |value| Msg::ReceivedStreamedValue(from, stream_id, value),
                                 None,
                             )
                             .map_err(|e| {
@@ -503,6 +767,8 @@ where
 
                 state.timeouts.increase_timeout(timeout.kind);
 
+                self.tx_event.send(|| Event::TimeoutElapsed(timeout));
+
                 if matches!(
                     timeout.kind,
                     TimeoutKind::Prevote
@@ -527,17 +793,25 @@ where
             }
 
             Msg::ReceivedProposedValue(value, origin) => {
-                self.tx_event
-                    .send(|| Event::ReceivedProposedValue(value.clone(), origin));
+                self.on_proposed_value(&myself, state, value, origin).await;
+                Ok(())
+            }
 
-                let result = self
-                    .process_input(&myself, state, ConsensusInput::ProposedValue(value, origin))
-                    .await;
+            Msg::ReceivedStreamedValue(from, stream_id, value) => {
+                // The proposal parts of the stream are valid if the value rebuilt from them is
+                for msg_id in state.pending_streams.remove(from, stream_id) {
+                    let acceptance = if value.validity.is_valid() {
+                        MessageAcceptance::Accept
+                    } else {
+                        MessageAcceptance::Reject
+                    };
 
-                if let Err(e) = result {
-                    error!("Error when processing ReceivedProposedValue message: {e}");
+                    self.report_validation(msg_id, acceptance);
                 }
 
+                self.on_proposed_value(&myself, state, value, ValueOrigin::Consensus)
+                    .await;
+
                 Ok(())
             }
 
@@ -551,6 +825,27 @@ where
 
                 Ok(())
             }
+
+            Msg::GetInfo(reply_to) => {
+                let height = state.consensus.height();
+                let round = state.consensus.round();
+
+                let info = ConsensusInfo {
+                    height,
+                    round,
+                    step: state.consensus.driver.step(),
+                    proposer: round
+                        .is_defined()
+                        .then(|| state.consensus.get_proposer(height, round).clone()),
+                    votes: state.consensus.vote_tally(round),
+                };
+
+                if let Err(e) = reply_to.send(info) {
+                    error!("Error when replying to GetInfo message: {e}");
+                }
+
+                Ok(())
+            }
         }
     }
 
@@ -657,6 +952,21 @@ where
                         error!("Error when replaying TimeoutElapsed: {e}");
                     }
                 }
+
+                WalEntry::ProposalReceiveTime(round, received_at) => {
+                    let height = state.height();
+
+                    if let Err(e) = self
+                        .process_input(
+                            myself,
+                            state,
+                            ConsensusInput::ProposalReceiveTime(height, round, received_at),
+                        )
+                        .await
+                    {
+                        error!("Error when replaying ProposalReceiveTime: {e}");
+                    }
+                }
             }
         }
 
@@ -671,6 +981,7 @@ where
         height: Ctx::Height,
         round: Round,
         timeout: Duration,
+        vote_extensions: VoteExtensions<Ctx>,
     ) -> Result<(), ActorProcessingErr> {
         // Call `GetValue` on the Host actor, and forward the reply
         // to the current actor, wrapping it in `Msg::ProposeValue`.
@@ -679,16 +990,12 @@ where
                 height,
                 round,
                 timeout,
+                vote_extensions,
                 reply_to,
             },
             myself,
             |proposed: LocallyProposedValue<Ctx>| {
-                Msg::<Ctx>::ProposeValue(
-                    proposed.height,
-                    proposed.round,
-                    proposed.value,
-                    proposed.extension,
-                )
+                Msg::<Ctx>::ProposeValue(proposed.height, proposed.round, proposed.value)
             },
             None,
         )?;
@@ -709,6 +1016,68 @@ where
         Ok(validator_set)
     }
 
+    async fn validate_value(
+        &self,
+        height: Ctx::Height,
+        round: Round,
+        proposer: Ctx::Address,
+        value: Ctx::Value,
+        timeout: Duration,
+    ) -> Result<ValueValidity, ActorProcessingErr> {
+        ractor::call_t!(
+            self.host,
+            |reply_to| HostMsg::ValidateProposal {
+                height,
+                round,
+                proposer,
+                value,
+                reply_to
+            },
+            timeout.as_millis() as u64
+        )
+        .map_err(|e| {
+            eyre!("Failed to validate value at height {height}, round {round}: {e:?}").into()
+        })
+    }
+
+    async fn extend_vote(
+        &self,
+        height: Ctx::Height,
+        round: Round,
+        value_id: ValueId<Ctx>,
+    ) -> Result<Option<SignedExtension<Ctx>>, ActorProcessingErr> {
+        ractor::call!(self.host, |reply_to| HostMsg::ExtendVote {
+            height,
+            round,
+            value_id,
+            reply_to
+        })
+        .map_err(|e| {
+            eyre!("Failed to get vote extension at height {height}, round {round}: {e:?}").into()
+        })
+    }
+
+    async fn verify_vote_extension(
+        &self,
+        height: Ctx::Height,
+        round: Round,
+        value_id: ValueId<Ctx>,
+        address: Ctx::Address,
+        extension: Option<SignedExtension<Ctx>>,
+    ) -> Result<Validity, ActorProcessingErr> {
+        ractor::call!(self.host, |reply_to| HostMsg::VerifyVoteExtension {
+            height,
+            round,
+            value_id,
+            address,
+            extension,
+            reply_to
+        })
+        .map_err(|e| {
+            eyre!("Failed to verify vote extension at height {height}, round {round}: {e:?}").into()
+        })
+    }
+
     async fn get_history_min_height(&self) -> Result<Ctx::Height, ActorProcessingErr> {
         ractor::call!(self.host, |reply_to| HostMsg::GetHistoryMinHeight {
             reply_to
@@ -776,7 +1145,7 @@ where
     ) -> Result<Resume<Ctx>, ActorProcessingErr> {
         match effect {
             Effect::ResetTimeouts(r) => {
-                timeouts.reset(self.timeout_config);
+                timeouts.reset();
                 Ok(r.resume_with(()))
             }
 
@@ -870,6 +1239,14 @@ where
                 Ok(r.resume_with(valid))
             }
 
+            Effect::GetLocalTime(r) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+
+                Ok(r.resume_with(Timestamp::from_unix_duration(now)))
+            }
+
             Effect::Publish(msg, r) => {
                 // Sync the WAL to disk before we broadcast the message
                 // NOTE: The message has already been append to the WAL by the `PersistMessage` effect.
@@ -885,10 +1262,10 @@ where
                 Ok(r.resume_with(()))
             }
 
-            Effect::GetValue(height, round, timeout, r) => {
+            Effect::GetValue(height, round, timeout, vote_extensions, r) => {
                 let timeout_duration = timeouts.duration_for(timeout.kind);
 
-                self.get_value(myself, height, round, timeout_duration)
+                self.get_value(myself, height, round, timeout_duration, vote_extensions)
                     .map_err(|e| eyre!("Error when asking for value to be built: {e:?}"))?;
 
                 Ok(r.resume_with(()))
@@ -904,6 +1281,51 @@ where
                 Ok(r.resume_with(validator_set))
             }
 
+            Effect::ValidateValue(height, round, proposer, value, r) => {
+                // Consider the value invalid if the host cannot validate it before
+                // the propose timeout would have elapsed, so that we prevote nil
+                // rather than for a value we know nothing about
+                let timeout = timeouts.duration_for(TimeoutKind::Propose);
+
+                let validity = self
+                    .validate_value(height, round, proposer.clone(), value, timeout)
+                    .await
+                    .unwrap_or_else(|e| ValueValidity::invalid(e.to_string()));
+
+                if let ValueValidity::Invalid { reason } = &validity {
+                    self.tx_event.send(|| {
+                        Event::RejectedValue(height, round, proposer.clone(), reason.clone())
+                    });
+                }
+
+                Ok(r.resume_with(validity))
+            }
+
+            Effect::ExtendVote(height, round, value_id, r) => {
+                // Precommit without an extension if the host cannot provide one
+                let extension = self
+                    .extend_vote(height, round, value_id)
+                    .await
+                    .unwrap_or_else(|e| {
+                        error!("{e}");
+                        None
+                    });
+
+                Ok(r.resume_with(extension))
+            }
+
+            Effect::VerifyVoteExtension(height, round, value_id, address, extension, r) => {
+                let validity = self
+                    .verify_vote_extension(height, round, value_id, address, extension)
+                    .await
+                    .unwrap_or_else(|e| {
+                        error!("{e}");
+                        Validity::Invalid
+                    });
+
+                Ok(r.resume_with(validity))
+            }
+
             Effect::RestreamValue(height, round, valid_round, address, value_id, r) => {
                 self.host
                     .cast(HostMsg::RestreamValue {
@@ -995,6 +1417,17 @@ where
 
                 Ok(r.resume_with(()))
             }
+
+            Effect::PersistProposalReceiveTime(round, received_at, r) => {
+                self.wal_append(
+                    height,
+                    WalEntry::ProposalReceiveTime(round, received_at),
+                    phase,
+                )
+                .await?;
+
+                Ok(r.resume_with(()))
+            }
         }
     }
 }
@@ -1022,6 +1455,8 @@ where
             consensus: ConsensusState::new(self.ctx.clone(), self.params.clone()),
             connected_peers: BTreeSet::new(),
             phase: Phase::Unstarted,
+            reported_evidence: ReportedEvidence::default(),
+            pending_streams: PendingStreams::default(),
         })
     }
 
@@ -4,8 +4,10 @@ use std::time::Duration;
 use derive_where::derive_where;
 use ractor::{ActorRef, RpcReplyPort};
 
-use malachitebft_core_consensus::PeerId;
-use malachitebft_core_types::{CommitCertificate, Context, Round, SignedExtension, ValueId};
+use malachitebft_core_consensus::{PeerId, ValueValidity, VoteExtensions};
+use malachitebft_core_types::{
+    CommitCertificate, Context, Round, SignedExtension, Validity, ValueId,
+};
 use malachitebft_sync::DecidedValue;
 
 use crate::consensus::ConsensusRef;
@@ -23,21 +25,14 @@ pub struct LocallyProposedValue<Ctx: Context> {
     pub height: Ctx::Height,
     pub round: Round,
     pub value: Ctx::Value,
-    pub extension: Option<SignedExtension<Ctx>>,
 }
 
 impl<Ctx: Context> LocallyProposedValue<Ctx> {
-    pub fn new(
-        height: Ctx::Height,
-        round: Round,
-        value: Ctx::Value,
-        extension: Option<SignedExtension<Ctx>>,
-    ) -> Self {
+    pub fn new(height: Ctx::Height, round: Round, value: Ctx::Value) -> Self {
         Self {
             height,
             round,
             value,
-            extension,
         }
     }
 }
@@ -63,6 +58,7 @@ pub enum HostMsg<Ctx: Context> {
         height: Ctx::Height,
         round: Round,
         timeout: Duration,
+        vote_extensions: VoteExtensions<Ctx>,
         reply_to: RpcReplyPort<LocallyProposedValue<Ctx>>,
     },
 
@@ -85,6 +81,34 @@ pub enum HostMsg<Ctx: Context> {
         reply_to: RpcReplyPort<ProposedValue<Ctx>>,
     },
 
+    /// Validate a value received in a proposal message, when values are not streamed as proposal parts.
+    /// The value is considered invalid if the host does not reply within the propose timeout.
+    ValidateProposal {
+        height: Ctx::Height,
+        round: Round,
+        proposer: Ctx::Address,
+        value: Ctx::Value,
+        reply_to: RpcReplyPort<ValueValidity>,
+    },
+
+    /// Request an extension for our precommit for the given value
+    ExtendVote {
+        height: Ctx::Height,
+        round: Round,
+        value_id: ValueId<Ctx>,
+        reply_to: RpcReplyPort<Option<SignedExtension<Ctx>>>,
+    },
+
+    /// Verify the extension of a precommit received from the given validator
+    VerifyVoteExtension {
+        height: Ctx::Height,
+        round: Round,
+        value_id: ValueId<Ctx>,
+        address: Ctx::Address,
+        extension: Option<SignedExtension<Ctx>>,
+        reply_to: RpcReplyPort<Validity>,
+    },
+
     /// Get the validator set at a given height
     GetValidatorSet {
         height: Ctx::Height,
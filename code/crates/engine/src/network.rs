@@ -1,5 +1,6 @@
 use std::collections::{BTreeSet, HashMap};
 use std::marker::PhantomData;
+use std::time::Instant;
 
 use async_trait::async_trait;
 use derive_where::derive_where;
@@ -7,7 +8,7 @@ use eyre::eyre;
 use libp2p::identity::Keypair;
 use libp2p::request_response;
 use ractor::port::OutputPortSubscriber;
-use ractor::{Actor, ActorProcessingErr, ActorRef, OutputPort, RpcReplyPort};
+use ractor::{Actor, ActorProcessingErr, ActorRef, OutputMessage, OutputPort, RpcReplyPort};
 use tokio::task::JoinHandle;
 use tracing::{error, trace};
 
@@ -16,16 +17,37 @@ use malachitebft_sync::{
 };
 
 use malachitebft_codec as codec;
+use malachitebft_config::ErasureCodingConfig;
 use malachitebft_core_consensus::SignedConsensusMsg;
 use malachitebft_core_types::{Context, SignedProposal, SignedVote};
 use malachitebft_metrics::SharedRegistry;
 use malachitebft_network::handle::CtrlHandle;
-use malachitebft_network::{Channel, Config, Event, Multiaddr, PeerId};
+use malachitebft_network::{
+    Bytes, Channel, Config, Event, MessageAcceptance, MessageId, Multiaddr, PeerId,
+};
 
 use crate::consensus::ConsensusCodec;
 use crate::sync::SyncCodec;
+use crate::util::erasure::{
+    ErasureChunk, ErasureInit, ErasureMsg, Inserted, ReedSolomon, StreamDecoder, StreamEncoder,
+};
 use crate::util::streaming::StreamMessage;
 
+/// Send events to a single subscriber, through ports of its own to not notify the other subscribers.
+///
+/// Each event gets its own port as a port only buffers a few events
+/// until its subscriber starts receiving them.
+fn notify_subscriber<T: OutputMessage>(
+    subscriber: &OutputPortSubscriber<T>,
+    events: impl IntoIterator<Item = T>,
+) {
+    for event in events {
+        let port = OutputPort::default();
+        subscriber.subscribe_to_port(&port);
+        port.send(event);
+    }
+}
+
 pub type NetworkRef<Ctx> = ActorRef<Msg<Ctx>>;
 pub type NetworkMsg<Ctx> = Msg<Ctx>;
 
@@ -54,6 +76,7 @@ where
     pub async fn spawn(
         keypair: Keypair,
         config: Config,
+        erasure_coding: ErasureCodingConfig,
         metrics: SharedRegistry,
         codec: Codec,
         span: tracing::Span,
@@ -61,6 +84,7 @@ where
         let args = Args {
             keypair,
             config,
+            erasure_coding,
             metrics,
         };
 
@@ -72,9 +96,17 @@ where
 pub struct Args {
     pub keypair: Keypair,
     pub config: Config,
+    pub erasure_coding: ErasureCodingConfig,
     pub metrics: SharedRegistry,
 }
 
+/// Erasure coding of the outgoing and incoming proposal parts streams
+#[derive(Debug)]
+pub struct ErasureCoding {
+    encoder: StreamEncoder,
+    decoder: StreamDecoder,
+}
+
 #[derive_where(Clone, Debug, PartialEq, Eq)]
 pub enum NetworkEvent<Ctx: Context> {
     Listening(Multiaddr),
@@ -82,10 +114,12 @@ pub enum NetworkEvent<Ctx: Context> {
     PeerConnected(PeerId),
     PeerDisconnected(PeerId),
 
-    Vote(PeerId, SignedVote<Ctx>),
+    /// A vote, along with the GossipSub message which carried it,
+    /// for consensus to report whether it is valid with [`Msg::ReportValidation`]
+    Vote(PeerId, MessageId, SignedVote<Ctx>),
 
-    Proposal(PeerId, SignedProposal<Ctx>),
-    ProposalPart(PeerId, StreamMessage<Ctx::ProposalPart>),
+    Proposal(PeerId, MessageId, SignedProposal<Ctx>),
+    ProposalPart(PeerId, MessageId, StreamMessage<Ctx::ProposalPart>),
 
     Status(PeerId, Status<Ctx>),
 
@@ -96,11 +130,13 @@ pub enum NetworkEvent<Ctx: Context> {
 pub enum State<Ctx: Context> {
     Stopped,
     Running {
+        listen_addrs: Vec<Multiaddr>,
         peers: BTreeSet<PeerId>,
         output_port: OutputPort<NetworkEvent<Ctx>>,
         ctrl_handle: CtrlHandle,
         recv_task: JoinHandle<()>,
         inbound_requests: HashMap<InboundRequestId, request_response::InboundRequestId>,
+        erasure_coding: Option<Box<ErasureCoding>>,
     },
 }
 
@@ -119,6 +155,13 @@ impl<Ctx: Context> Status<Ctx> {
     }
 }
 
+/// The addresses a node listens on and the peers it is connected to
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NetworkInfo {
+    pub listen_addrs: Vec<Multiaddr>,
+    pub peers: BTreeSet<PeerId>,
+}
+
 pub enum Msg<Ctx: Context> {
     /// Subscribe this actor to receive gossip events
     Subscribe(OutputPortSubscriber<NetworkEvent<Ctx>>),
@@ -141,11 +184,163 @@ pub enum Msg<Ctx: Context> {
     /// Request for number of peers from gossip
     GetState { reply: RpcReplyPort<usize> },
 
+    /// Request the addresses we listen on and the set of connected peers
+    GetInfo { reply: RpcReplyPort<NetworkInfo> },
+
+    /// Report whether a message received from a peer is valid, for it to be relayed or not
+    ReportValidation(MessageId, MessageAcceptance),
+
     // Event emitted by the gossip layer
     #[doc(hidden)]
     NewEvent(Event),
 }
 
+impl<Ctx, Codec> Network<Ctx, Codec>
+where
+    Ctx: Context,
+    Codec: codec::Codec<StreamMessage<Ctx::ProposalPart>>,
+{
+    async fn on_erasure_msg(
+        &self,
+        from: PeerId,
+        msg_id: MessageId,
+        data: Bytes,
+        erasure_coding: &mut ErasureCoding,
+        ctrl_handle: &CtrlHandle,
+        output_port: &OutputPort<NetworkEvent<Ctx>>,
+    ) -> Result<(), ActorProcessingErr> {
+        let result = ErasureMsg::decode(data).and_then(|msg| match msg {
+            ErasureMsg::Init(init) => {
+                let commitment = init.commitment();
+                let recorded = erasure_coding
+                    .decoder
+                    .insert_init(from, msg_id.clone(), init)?;
+
+                // The announcement of a stream is only validated by consensus,
+                // along with the proposal parts rebuilt from the stream
+                let acceptance = (!recorded).then_some(MessageAcceptance::Ignore);
+                Ok((commitment, acceptance, None))
+            }
+            ErasureMsg::Chunk { relay, chunk } => {
+                let commitment = chunk.init.commitment();
+
+                // A chunk is valid as soon as its Merkle proof is
+                let (acceptance, to_relay) =
+                    match erasure_coding.decoder.insert_chunk(from, &chunk)? {
+                        Inserted::New => (MessageAcceptance::Accept, relay.then_some(chunk)),
+                        Inserted::Duplicate => (MessageAcceptance::Accept, None),
+                        Inserted::TooManyStreams => (MessageAcceptance::Ignore, None),
+                    };
+
+                Ok((commitment, Some(acceptance), to_relay))
+            }
+        });
+
+        let (commitment, acceptance, to_relay) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                error!(%from, "Invalid erasure-coded proposal part: {e}");
+                ctrl_handle
+                    .report_validation(msg_id, MessageAcceptance::Reject)
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        if let Some(acceptance) = acceptance {
+            ctrl_handle.report_validation(msg_id, acceptance).await?;
+        }
+
+        // Relay the chunks sent to us by the proposer to the other peers
+        if let Some(chunk) = to_relay {
+            let msg = ErasureMsg::Chunk {
+                relay: false,
+                chunk,
+            };
+
+            ctrl_handle
+                .publish(Channel::ProposalParts, msg.encode()?)
+                .await?;
+        }
+
+        let stream = match erasure_coding.decoder.try_decode(&commitment) {
+            Ok(Some(stream)) => stream,
+            Ok(None) => return Ok(()),
+            Err(e) => {
+                error!(origin = %from, "Failed to rebuild erasure-coded stream: {e}");
+
+                if let Some(init_id) = erasure_coding.decoder.init_message_id(&commitment) {
+                    ctrl_handle
+                        .report_validation(init_id.clone(), MessageAcceptance::Reject)
+                        .await?;
+                }
+
+                return Ok(());
+            }
+        };
+
+        trace!(
+            origin = %stream.origin,
+            stream_id = %stream.stream_id,
+            "Rebuilt erasure-coded proposal parts stream"
+        );
+
+        let mut parts = Vec::with_capacity(stream.messages.len());
+        for data in stream.messages {
+            match self.codec.decode(data) {
+                Ok(msg) => parts.push(msg),
+                Err(e) => {
+                    error!(origin = %stream.origin, "Failed to decode stream message: {e:?}");
+                    ctrl_handle
+                        .report_validation(stream.message_id, MessageAcceptance::Reject)
+                        .await?;
+                    return Ok(());
+                }
+            }
+        }
+
+        // The announcement of the stream is validated along with the proposal parts
+        for part in parts {
+            output_port.send(NetworkEvent::ProposalPart(
+                stream.origin,
+                stream.message_id.clone(),
+                part,
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Publish the announcement of an erasure-coded stream, and send each chunk
+/// to one of the peers, which will relay it to the others.
+async fn publish_erasure_coded(
+    ctrl_handle: &CtrlHandle,
+    peers: &BTreeSet<PeerId>,
+    init: ErasureInit,
+    chunks: Vec<ErasureChunk>,
+) -> Result<(), ActorProcessingErr> {
+    trace!(
+        stream_id = %init.stream_id,
+        chunks = %chunks.len(),
+        "Publishing erasure-coded proposal parts stream"
+    );
+
+    ctrl_handle
+        .publish(Channel::ProposalParts, ErasureMsg::Init(init).encode()?)
+        .await?;
+
+    for (chunk, peer_id) in chunks.into_iter().zip(peers.iter().cycle()) {
+        let msg = ErasureMsg::Chunk { relay: true, chunk };
+
+        ctrl_handle
+            .send(*peer_id, Channel::ProposalParts, msg.encode()?)
+            .await?;
+    }
+
+    Ok(())
+}
+
 #[async_trait]
 impl<Ctx, Codec> Actor for Network<Ctx, Codec>
 where
@@ -167,8 +362,32 @@ where
         myself: ActorRef<Msg<Ctx>>,
         args: Args,
     ) -> Result<Self::State, ActorProcessingErr> {
+        let reed_solomon = if args.erasure_coding.enabled {
+            // The chunks of erasure-coded streams are each sent to a single peer
+            if !args.config.has_direct_streams() {
+                return Err(eyre!("Erasure coding requires direct streams to be enabled").into());
+            }
+
+            let reed_solomon = ReedSolomon::new(
+                args.erasure_coding.data_shards,
+                args.erasure_coding.parity_shards,
+            )
+            .ok_or_else(|| eyre!("Invalid erasure coding configuration"))?;
+
+            Some(reed_solomon)
+        } else {
+            None
+        };
+
         let handle = malachitebft_network::spawn(args.keypair, args.config, args.metrics).await?;
 
+        let erasure_coding = reed_solomon.map(|reed_solomon| {
+            Box::new(ErasureCoding {
+                encoder: StreamEncoder::new(handle.peer_id(), reed_solomon),
+                decoder: StreamDecoder::new(),
+            })
+        });
+
         let (mut recv_handle, ctrl_handle) = handle.split();
 
         let recv_task = tokio::spawn(async move {
@@ -181,11 +400,13 @@ where
         });
 
         Ok(State::Running {
+            listen_addrs: Vec::new(),
             peers: BTreeSet::new(),
             output_port: OutputPort::default(),
             ctrl_handle,
             recv_task,
             inbound_requests: HashMap::new(),
+            erasure_coding,
         })
     }
 
@@ -205,10 +426,12 @@ where
         state: &mut State<Ctx>,
     ) -> Result<(), ActorProcessingErr> {
         let State::Running {
+            listen_addrs,
             peers,
             output_port,
             ctrl_handle,
             inbound_requests,
+            erasure_coding,
             ..
         } = state
         else {
@@ -216,7 +439,13 @@ where
         };
 
         match msg {
-            Msg::Subscribe(subscriber) => subscriber.subscribe_to_port(output_port),
+            Msg::Subscribe(subscriber) => {
+                subscriber.subscribe_to_port(output_port);
+
+                // Actors subscribing after we started listening would otherwise never know about it
+                let events = listen_addrs.iter().cloned().map(NetworkEvent::Listening);
+                notify_subscriber(&subscriber, events);
+            }
 
             Msg::Publish(msg) => match self.codec.encode(&msg) {
                 Ok(data) => ctrl_handle.publish(Channel::Consensus, data).await?,
@@ -231,9 +460,21 @@ where
                 );
 
                 let data = self.codec.encode(&msg);
-                match data {
-                    Ok(data) => ctrl_handle.publish(Channel::ProposalParts, data).await?,
-                    Err(e) => error!("Failed to encode proposal part: {e:?}"),
+                match (data, erasure_coding) {
+                    (Ok(data), None) => ctrl_handle.publish(Channel::ProposalParts, data).await?,
+                    (Ok(data), Some(erasure_coding)) => {
+                        let stream = erasure_coding.encoder.push(
+                            msg.stream_id,
+                            data,
+                            msg.is_fin(),
+                            Instant::now(),
+                        )?;
+
+                        if let Some((init, chunks)) = stream {
+                            publish_erasure_coded(ctrl_handle, peers, init, chunks).await?;
+                        }
+                    }
+                    (Err(e), _) => error!("Failed to encode proposal part: {e:?}"),
                 }
             }
 
@@ -282,6 +523,7 @@ where
             }
 
             Msg::NewEvent(Event::Listening(addr)) => {
+                listen_addrs.push(addr.clone());
                 output_port.send(NetworkEvent::Listening(addr));
             }
 
@@ -295,30 +537,50 @@ where
                 output_port.send(NetworkEvent::PeerDisconnected(peer_id));
             }
 
-            Msg::NewEvent(Event::Message(Channel::Consensus, from, data)) => {
+            Msg::NewEvent(Event::Message(Channel::Consensus, from, msg_id, data)) => {
                 let msg = match self.codec.decode(data) {
                     Ok(msg) => msg,
                     Err(e) => {
                         error!(%from, "Failed to decode gossip message: {e:?}");
+                        ctrl_handle
+                            .report_validation(msg_id, MessageAcceptance::Reject)
+                            .await?;
                         return Ok(());
                     }
                 };
 
+                // The message is validated by consensus, once it has checked its signature
                 let event = match msg {
-                    SignedConsensusMsg::Vote(vote) => NetworkEvent::Vote(from, vote),
+                    SignedConsensusMsg::Vote(vote) => NetworkEvent::Vote(from, msg_id, vote),
                     SignedConsensusMsg::Proposal(proposal) => {
-                        NetworkEvent::Proposal(from, proposal)
+                        NetworkEvent::Proposal(from, msg_id, proposal)
                     }
                 };
 
                 output_port.send(event);
             }
 
-            Msg::NewEvent(Event::Message(Channel::ProposalParts, from, data)) => {
+            Msg::NewEvent(Event::Message(Channel::ProposalParts, from, msg_id, data)) => {
+                if let Some(erasure_coding) = erasure_coding {
+                    return self
+                        .on_erasure_msg(
+                            from,
+                            msg_id,
+                            data,
+                            erasure_coding,
+                            ctrl_handle,
+                            output_port,
+                        )
+                        .await;
+                }
+
                 let msg: StreamMessage<Ctx::ProposalPart> = match self.codec.decode(data) {
                     Ok(stream_msg) => stream_msg,
                     Err(e) => {
                         error!(%from, "Failed to decode stream message: {e:?}");
+                        ctrl_handle
+                            .report_validation(msg_id, MessageAcceptance::Reject)
+                            .await?;
                         return Ok(());
                     }
                 };
@@ -330,10 +592,13 @@ where
                     "Received proposal part"
                 );
 
-                output_port.send(NetworkEvent::ProposalPart(from, msg));
+                // The message is validated by consensus, once the application has rebuilt
+                // a value from the proposal parts of the stream
+                output_port.send(NetworkEvent::ProposalPart(from, msg_id, msg));
             }
 
-            Msg::NewEvent(Event::Message(Channel::Sync, from, data)) => {
+            // Status messages are sent over the broadcast protocol, which does not validate messages
+            Msg::NewEvent(Event::Message(Channel::Sync, from, _msg_id, data)) => {
                 let status: sync::Status<Ctx> = match self.codec.decode(data) {
                     Ok(status) => status,
                     Err(e) => {
@@ -399,6 +664,10 @@ where
                 }
             },
 
+            Msg::ReportValidation(msg_id, acceptance) => {
+                ctrl_handle.report_validation(msg_id, acceptance).await?;
+            }
+
             Msg::GetState { reply } => {
                 let number_peers = match state {
                     State::Stopped => 0,
@@ -406,6 +675,21 @@ where
                 };
                 reply.send(number_peers)?;
             }
+
+            Msg::GetInfo { reply } => {
+                let info = match state {
+                    State::Stopped => NetworkInfo::default(),
+                    State::Running {
+                        listen_addrs,
+                        peers,
+                        ..
+                    } => NetworkInfo {
+                        listen_addrs: listen_addrs.clone(),
+                        peers: peers.clone(),
+                    },
+                };
+                reply.send(info)?;
+            }
         }
 
         Ok(())
@@ -431,3 +715,88 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use tokio::sync::mpsc;
+
+    struct TestMsg(Multiaddr);
+
+    impl From<Multiaddr> for TestMsg {
+        fn from(addr: Multiaddr) -> Self {
+            TestMsg(addr)
+        }
+    }
+
+    struct TestActor;
+
+    #[async_trait]
+    impl Actor for TestActor {
+        type State = mpsc::UnboundedSender<Multiaddr>;
+        type Arguments = mpsc::UnboundedSender<Multiaddr>;
+        type Msg = TestMsg;
+
+        async fn pre_start(
+            &self,
+            _myself: ActorRef<TestMsg>,
+            tx: Self::Arguments,
+        ) -> Result<Self::State, ActorProcessingErr> {
+            Ok(tx)
+        }
+
+        async fn handle(
+            &self,
+            _myself: ActorRef<TestMsg>,
+            TestMsg(addr): TestMsg,
+            tx: &mut Self::State,
+        ) -> Result<(), ActorProcessingErr> {
+            tx.send(addr)?;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn notify_late_subscribers() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let (actor, _) = TestActor::spawn(None, TestActor, tx).await.unwrap();
+
+        let output_port = OutputPort::default();
+        output_port.send("/ip4/127.0.0.1/tcp/1000".parse::<Multiaddr>().unwrap());
+
+        // More events than a port buffers
+        let addrs: Vec<Multiaddr> = (0..20)
+            .map(|port| format!("/ip4/127.0.0.1/tcp/{port}").parse().unwrap())
+            .collect();
+
+        let subscriber: OutputPortSubscriber<Multiaddr> = Box::new(actor.clone());
+        subscriber.subscribe_to_port(&output_port);
+        notify_subscriber(&subscriber, addrs.clone());
+
+        let mut received = Vec::new();
+        while received.len() < addrs.len() {
+            let addr = tokio::time::timeout(Duration::from_secs(1), rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            received.push(addr);
+        }
+
+        received.sort_by_key(|addr| addr.to_string());
+        let mut expected = addrs;
+        expected.sort_by_key(|addr| addr.to_string());
+        assert_eq!(received, expected);
+
+        // Only the events sent on the shared port after subscribing to it are received
+        output_port.send("/ip4/127.0.0.1/tcp/100".parse::<Multiaddr>().unwrap());
+        let addr = tokio::time::timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .unwrap();
+        assert_eq!(addr, Some("/ip4/127.0.0.1/tcp/100".parse().unwrap()));
+
+        actor.stop(None);
+    }
+}
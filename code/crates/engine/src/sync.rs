@@ -6,7 +6,7 @@ use bytes::Bytes;
 use derive_where::derive_where;
 use eyre::eyre;
 
-use ractor::{Actor, ActorProcessingErr, ActorRef};
+use ractor::{Actor, ActorProcessingErr, ActorRef, RpcReplyPort};
 use rand::SeedableRng;
 use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
@@ -99,6 +99,28 @@ pub enum Msg<Ctx: Context> {
 
     /// Consensus has sent a vote set response to a peer
     SentVoteSetResponse(InboundRequestId, Ctx::Height, Round),
+
+    /// Get a snapshot of the state of sync, for inspection purposes
+    GetInfo(RpcReplyPort<SyncInfo<Ctx>>),
+}
+
+/// Snapshot of the state of sync
+#[derive_where(Clone, Debug)]
+pub struct SyncInfo<Ctx: Context> {
+    /// Height of last decided value
+    pub tip_height: Ctx::Height,
+
+    /// Height currently syncing
+    pub sync_height: Ctx::Height,
+
+    /// Latest status received from each peer
+    pub peers: Vec<sync::Status<Ctx>>,
+
+    /// Heights for which a decided value was requested, along with the peer it was requested from
+    pub pending_value_requests: Vec<(Ctx::Height, PeerId)>,
+
+    /// Heights and rounds for which a vote set was requested, along with the peer it was requested from
+    pub pending_vote_set_requests: Vec<(Ctx::Height, Round, PeerId)>,
 }
 
 impl<Ctx: Context> From<NetworkEvent<Ctx>> for Msg<Ctx> {
@@ -340,6 +362,30 @@ where
                     .await?;
             }
 
+            Msg::GetInfo(reply_to) => {
+                let info = SyncInfo {
+                    tip_height: state.sync.tip_height,
+                    sync_height: state.sync.sync_height,
+                    peers: state.sync.peers.values().cloned().collect(),
+                    pending_value_requests: state
+                        .sync
+                        .pending_decided_value_requests
+                        .iter()
+                        .map(|(height, peer)| (*height, *peer))
+                        .collect(),
+                    pending_vote_set_requests: state
+                        .sync
+                        .pending_vote_set_requests
+                        .iter()
+                        .map(|((height, round), peer)| (*height, *round, *peer))
+                        .collect(),
+                };
+
+                if let Err(e) = reply_to.send(info) {
+                    error!("Error when replying to GetInfo message: {e}");
+                }
+            }
+
             Msg::NetworkEvent(NetworkEvent::PeerDisconnected(peer_id)) => {
                 info!(%peer_id, "Disconnected from peer");
 
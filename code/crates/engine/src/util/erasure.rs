@@ -0,0 +1,1019 @@
+//! Erasure coding of proposal parts streams.
+//!
+//! Once the proposer has streamed all the proposal parts of a value, the encoded stream messages
+//! are concatenated and split into `k` data shards, to which `n - k` Reed-Solomon parity shards
+//! are added, so that the value can be rebuilt from any `k` of the `n` shards.
+//!
+//! The proposer publishes an [`ErasureInit`] message carrying the parameters of the stream
+//! and the Merkle root of the shards, and sends each shard, along with its Merkle proof,
+//! to a single peer which relays it to the others.
+//! This way the proposer only uploads `n / k` times the size of the value.
+//!
+//! Every leaf of the Merkle tree commits to the parameters of the stream, which every chunk carries,
+//! so that they cannot be forged. As the network layer authenticates the peer which published
+//! the [`ErasureInit`] message, a stream is only rebuilt once announced by the proposer itself.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant};
+
+use byteorder::{ReadBytesExt, WriteBytesExt, BE};
+use bytes::Bytes;
+use sha3::{Digest, Sha3_256};
+
+use malachitebft_network::{MessageId, PeerId};
+
+use crate::util::streaming::StreamId;
+
+/// Maximum number of shards, as imposed by the size of the Galois field
+pub const MAX_SHARDS: usize = 256;
+
+/// Maximum number of incoming streams kept around while waiting for their shards
+const MAX_INCOMING_STREAMS: usize = 64;
+
+/// Maximum number of incoming streams not rebuilt yet which a single peer can make us collect
+const MAX_PENDING_STREAMS_PER_PEER: usize = 8;
+
+/// Time after which an outgoing stream which never got its last message is dropped
+const OUTGOING_STREAM_TTL: Duration = Duration::from_secs(60);
+
+pub type Hash = [u8; 32];
+
+/// Arithmetic in GF(2^8), with the polynomial x^8 + x^4 + x^3 + x^2 + 1
+mod gf {
+    const POLYNOMIAL: u16 = 0x11d;
+
+    const fn tables() -> ([u8; 512], [u8; 256]) {
+        let mut exp = [0; 512];
+        let mut log = [0; 256];
+
+        let mut x: u16 = 1;
+        let mut i = 0;
+        while i < 255 {
+            exp[i] = x as u8;
+            exp[i + 255] = x as u8;
+            log[x as usize] = i as u8;
+
+            x <<= 1;
+            if x & 0x100 != 0 {
+                x ^= POLYNOMIAL;
+            }
+
+            i += 1;
+        }
+
+        (exp, log)
+    }
+
+    const TABLES: ([u8; 512], [u8; 256]) = tables();
+    const EXP: [u8; 512] = TABLES.0;
+    const LOG: [u8; 256] = TABLES.1;
+
+    pub fn mul(a: u8, b: u8) -> u8 {
+        if a == 0 || b == 0 {
+            0
+        } else {
+            EXP[LOG[a as usize] as usize + LOG[b as usize] as usize]
+        }
+    }
+
+    pub fn inv(a: u8) -> u8 {
+        debug_assert_ne!(a, 0, "zero has no inverse");
+        EXP[255 - LOG[a as usize] as usize]
+    }
+
+    /// `acc += coef * src`
+    pub fn mul_add(acc: &mut [u8], coef: u8, src: &[u8]) {
+        if coef == 0 {
+            return;
+        }
+
+        for (a, s) in acc.iter_mut().zip(src) {
+            *a ^= mul(coef, *s);
+        }
+    }
+}
+
+/// Systematic Reed-Solomon code, whose parity shards are computed with a Cauchy matrix,
+/// so that any `data_shards` rows of the encoding matrix form an invertible matrix.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ReedSolomon {
+    data_shards: usize,
+    parity_shards: usize,
+}
+
+impl ReedSolomon {
+    pub fn new(data_shards: usize, parity_shards: usize) -> Option<Self> {
+        if data_shards == 0 || data_shards + parity_shards > MAX_SHARDS {
+            return None;
+        }
+
+        Some(Self {
+            data_shards,
+            parity_shards,
+        })
+    }
+
+    pub fn data_shards(&self) -> usize {
+        self.data_shards
+    }
+
+    pub fn total_shards(&self) -> usize {
+        self.data_shards + self.parity_shards
+    }
+
+    /// Size of each shard for data of the given length
+    pub fn shard_len(&self, len: usize) -> usize {
+        len.div_ceil(self.data_shards).max(1)
+    }
+
+    /// Row of the encoding matrix for the shard at the given index
+    fn row(&self, index: usize) -> Vec<u8> {
+        if index < self.data_shards {
+            (0..self.data_shards)
+                .map(|j| u8::from(j == index))
+                .collect()
+        } else {
+            // Indices are below 256 and the data shard indices are below the parity shard indices,
+            // so that `index ^ j` is never zero.
+            (0..self.data_shards)
+                .map(|j| gf::inv(index as u8 ^ j as u8))
+                .collect()
+        }
+    }
+
+    /// Split the data into data shards, padded with zeros, and compute the parity shards
+    pub fn encode(&self, data: &[u8]) -> Vec<Vec<u8>> {
+        let shard_len = self.shard_len(data.len());
+
+        let mut shards: Vec<Vec<u8>> = (0..self.data_shards)
+            .map(|i| {
+                let start = (i * shard_len).min(data.len());
+                let end = ((i + 1) * shard_len).min(data.len());
+
+                let mut shard = data[start..end].to_vec();
+                shard.resize(shard_len, 0);
+                shard
+            })
+            .collect();
+
+        for index in self.data_shards..self.total_shards() {
+            let mut parity = vec![0; shard_len];
+
+            for (coef, shard) in self.row(index).into_iter().zip(&shards) {
+                gf::mul_add(&mut parity, coef, shard);
+            }
+
+            shards.push(parity);
+        }
+
+        shards
+    }
+
+    /// Rebuild data of the given length from any `data_shards` shards, by their index
+    pub fn reconstruct(&self, shards: &BTreeMap<usize, Bytes>, len: usize) -> Option<Vec<u8>> {
+        let shard_len = self.shard_len(len);
+
+        let shards: Vec<(usize, &Bytes)> = shards
+            .iter()
+            .filter(|(index, shard)| **index < self.total_shards() && shard.len() == shard_len)
+            .take(self.data_shards)
+            .map(|(index, shard)| (*index, shard))
+            .collect();
+
+        if shards.len() < self.data_shards {
+            return None;
+        }
+
+        let matrix: Vec<Vec<u8>> = shards.iter().map(|(index, _)| self.row(*index)).collect();
+        let inverse = invert(matrix)?;
+
+        let mut data = Vec::with_capacity(self.data_shards * shard_len);
+
+        for row in inverse {
+            let mut shard = vec![0; shard_len];
+
+            for (coef, (_, src)) in row.into_iter().zip(&shards) {
+                gf::mul_add(&mut shard, coef, src);
+            }
+
+            data.extend_from_slice(&shard);
+        }
+
+        data.truncate(len);
+        Some(data)
+    }
+}
+
+/// Invert a square matrix over GF(2^8) with Gauss-Jordan elimination
+fn invert(mut matrix: Vec<Vec<u8>>) -> Option<Vec<Vec<u8>>> {
+    let size = matrix.len();
+
+    let mut inverse: Vec<Vec<u8>> = (0..size)
+        .map(|i| (0..size).map(|j| u8::from(i == j)).collect())
+        .collect();
+
+    for col in 0..size {
+        let pivot = (col..size).find(|row| matrix[*row][col] != 0)?;
+        matrix.swap(col, pivot);
+        inverse.swap(col, pivot);
+
+        let scale = gf::inv(matrix[col][col]);
+        for j in 0..size {
+            matrix[col][j] = gf::mul(matrix[col][j], scale);
+            inverse[col][j] = gf::mul(inverse[col][j], scale);
+        }
+
+        for row in 0..size {
+            let factor = matrix[row][col];
+            if row == col || factor == 0 {
+                continue;
+            }
+
+            for j in 0..size {
+                matrix[row][j] ^= gf::mul(factor, matrix[col][j]);
+                inverse[row][j] ^= gf::mul(factor, inverse[col][j]);
+            }
+        }
+    }
+
+    Some(inverse)
+}
+
+fn leaf_hash(params: &Hash, data: &[u8]) -> Hash {
+    let mut hasher = Sha3_256::new();
+    hasher.update([0]);
+    hasher.update(params);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha3_256::new();
+    hasher.update([1]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Compute the Merkle root of the given leaves, each committing to the given parameters
+/// and padded with zero hashes to a power of two, along with the proof of inclusion of each leaf.
+pub fn merkle_tree(params: &Hash, leaves: &[Vec<u8>]) -> (Hash, Vec<Vec<Hash>>) {
+    let mut level: Vec<Hash> = leaves.iter().map(|leaf| leaf_hash(params, leaf)).collect();
+    level.resize(leaves.len().next_power_of_two(), [0; 32]);
+
+    let mut proofs = vec![Vec::new(); leaves.len()];
+
+    while level.len() > 1 {
+        for (index, proof) in proofs.iter_mut().enumerate() {
+            proof.push(level[(index >> proof.len()) ^ 1]);
+        }
+
+        level = level
+            .chunks(2)
+            .map(|pair| node_hash(&pair[0], &pair[1]))
+            .collect();
+    }
+
+    (level[0], proofs)
+}
+
+/// Verify that the given data is the leaf at the given index of the Merkle tree with the given root,
+/// committing to the given parameters
+pub fn verify_merkle_proof(
+    root: &Hash,
+    params: &Hash,
+    index: usize,
+    data: &[u8],
+    proof: &[Hash],
+) -> bool {
+    if proof.len() >= usize::BITS as usize || index >> proof.len() != 0 {
+        return false;
+    }
+
+    let hash = proof
+        .iter()
+        .enumerate()
+        .fold(leaf_hash(params, data), |hash, (level, sibling)| {
+            if (index >> level) & 1 == 0 {
+                node_hash(&hash, sibling)
+            } else {
+                node_hash(sibling, &hash)
+            }
+        });
+
+    &hash == root
+}
+
+fn invalid_data(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// Sent by the proposer to announce an erasure-coded stream, and carried by each of its chunks
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ErasureInit {
+    /// The peer which proposes the stream
+    pub origin: PeerId,
+
+    pub stream_id: StreamId,
+
+    /// Merkle root of the shards
+    pub root: Hash,
+
+    pub data_shards: u16,
+    pub parity_shards: u16,
+
+    /// Length of the erasure-coded data
+    pub len: u32,
+}
+
+impl ErasureInit {
+    fn reed_solomon(&self) -> Option<ReedSolomon> {
+        ReedSolomon::new(self.data_shards as usize, self.parity_shards as usize)
+    }
+
+    fn check(&self) -> io::Result<ReedSolomon> {
+        self.reed_solomon().ok_or_else(|| {
+            invalid_data(format!(
+                "invalid number of shards: {} data shards, {} parity shards",
+                self.data_shards, self.parity_shards
+            ))
+        })
+    }
+
+    /// Hash of the parameters of the stream, which every leaf of the Merkle tree commits to
+    fn params_hash(&self) -> Hash {
+        let mut hasher = Sha3_256::new();
+        hasher.update(self.origin.to_bytes());
+        hasher.update(self.stream_id.to_be_bytes());
+        hasher.update(self.data_shards.to_be_bytes());
+        hasher.update(self.parity_shards.to_be_bytes());
+        hasher.update(self.len.to_be_bytes());
+        hasher.finalize().into()
+    }
+
+    /// Identifies the stream, along with all of its parameters
+    pub fn commitment(&self) -> Hash {
+        node_hash(&self.params_hash(), &self.root)
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) -> io::Result<()> {
+        let origin = self.origin.to_bytes();
+        let origin_len = u8::try_from(origin.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "peer id too long"))?;
+
+        buf.write_u8(origin_len)?;
+        buf.write_all(&origin)?;
+        buf.write_u64::<BE>(self.stream_id)?;
+        buf.write_all(&self.root)?;
+        buf.write_u16::<BE>(self.data_shards)?;
+        buf.write_u16::<BE>(self.parity_shards)?;
+        buf.write_u32::<BE>(self.len)?;
+
+        Ok(())
+    }
+
+    fn decode(buf: &mut &[u8]) -> io::Result<Self> {
+        let mut origin = vec![0; buf.read_u8()? as usize];
+        buf.read_exact(&mut origin)?;
+
+        Ok(Self {
+            origin: PeerId::from_bytes(&origin)
+                .map_err(|e| invalid_data(format!("invalid origin: {e}")))?,
+            stream_id: buf.read_u64::<BE>()?,
+            root: read_hash(buf)?,
+            data_shards: buf.read_u16::<BE>()?,
+            parity_shards: buf.read_u16::<BE>()?,
+            len: buf.read_u32::<BE>()?,
+        })
+    }
+}
+
+/// A shard of an erasure-coded stream, along with its Merkle proof
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ErasureChunk {
+    pub init: ErasureInit,
+    pub index: u16,
+    pub proof: Vec<Hash>,
+    pub data: Bytes,
+}
+
+impl ErasureChunk {
+    pub fn verify(&self) -> bool {
+        verify_merkle_proof(
+            &self.init.root,
+            &self.init.params_hash(),
+            self.index as usize,
+            &self.data,
+            &self.proof,
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ErasureMsg {
+    Init(ErasureInit),
+
+    /// A chunk to be relayed to the other peers if `relay` is set,
+    /// ie. when it is sent by the proposer
+    Chunk {
+        relay: bool,
+        chunk: ErasureChunk,
+    },
+}
+
+fn read_hash(buf: &mut &[u8]) -> io::Result<Hash> {
+    let mut hash = [0; 32];
+    buf.read_exact(&mut hash)?;
+    Ok(hash)
+}
+
+impl ErasureMsg {
+    const TAG_INIT: u8 = 0x01;
+    const TAG_CHUNK: u8 = 0x02;
+
+    pub fn encode(&self) -> io::Result<Bytes> {
+        let mut buf = Vec::new();
+
+        match self {
+            ErasureMsg::Init(init) => {
+                buf.write_u8(Self::TAG_INIT)?;
+                init.encode(&mut buf)?;
+            }
+
+            ErasureMsg::Chunk { relay, chunk } => {
+                let proof_len = u8::try_from(chunk.proof.len())
+                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "proof too long"))?;
+
+                buf.write_u8(Self::TAG_CHUNK)?;
+                buf.write_u8(u8::from(*relay))?;
+                chunk.init.encode(&mut buf)?;
+                buf.write_u16::<BE>(chunk.index)?;
+                buf.write_u8(proof_len)?;
+                for hash in &chunk.proof {
+                    buf.write_all(hash)?;
+                }
+                buf.write_all(&chunk.data)?;
+            }
+        }
+
+        Ok(Bytes::from(buf))
+    }
+
+    pub fn decode(bytes: Bytes) -> io::Result<Self> {
+        let mut buf = bytes.as_ref();
+
+        match buf.read_u8()? {
+            Self::TAG_INIT => Ok(ErasureMsg::Init(ErasureInit::decode(&mut buf)?)),
+
+            Self::TAG_CHUNK => {
+                let relay = buf.read_u8()? != 0;
+                let init = ErasureInit::decode(&mut buf)?;
+                let index = buf.read_u16::<BE>()?;
+
+                let proof_len = buf.read_u8()?;
+                let proof = (0..proof_len)
+                    .map(|_| read_hash(&mut buf))
+                    .collect::<io::Result<_>>()?;
+
+                let data = bytes.slice(bytes.len() - buf.len()..);
+
+                Ok(ErasureMsg::Chunk {
+                    relay,
+                    chunk: ErasureChunk {
+                        init,
+                        index,
+                        proof,
+                        data,
+                    },
+                })
+            }
+
+            tag => Err(invalid_data(format!("invalid erasure message tag: {tag}"))),
+        }
+    }
+}
+
+/// Buffers the messages of outgoing streams until they are complete, to erasure code them
+#[derive(Debug)]
+pub struct StreamEncoder {
+    origin: PeerId,
+    reed_solomon: ReedSolomon,
+    streams: HashMap<StreamId, (Instant, Vec<Bytes>)>,
+}
+
+impl StreamEncoder {
+    pub fn new(origin: PeerId, reed_solomon: ReedSolomon) -> Self {
+        Self {
+            origin,
+            reed_solomon,
+            streams: HashMap::new(),
+        }
+    }
+
+    /// Buffer an encoded stream message, returning the erasure-coded stream once the last one is pushed.
+    ///
+    /// Streams started more than [`OUTGOING_STREAM_TTL`] ago are dropped.
+    pub fn push(
+        &mut self,
+        stream_id: StreamId,
+        msg: Bytes,
+        is_fin: bool,
+        now: Instant,
+    ) -> io::Result<Option<(ErasureInit, Vec<ErasureChunk>)>> {
+        self.streams.retain(|_, (started, _)| {
+            now.saturating_duration_since(*started) < OUTGOING_STREAM_TTL
+        });
+
+        let (_, messages) = self
+            .streams
+            .entry(stream_id)
+            .or_insert_with(|| (now, Vec::new()));
+
+        messages.push(msg);
+
+        if !is_fin {
+            return Ok(None);
+        }
+
+        let messages = self
+            .streams
+            .remove(&stream_id)
+            .map(|(_, messages)| messages)
+            .unwrap_or_default();
+
+        self.encode(stream_id, &messages).map(Some)
+    }
+
+    fn encode(
+        &self,
+        stream_id: StreamId,
+        messages: &[Bytes],
+    ) -> io::Result<(ErasureInit, Vec<ErasureChunk>)> {
+        let mut data = Vec::new();
+        for msg in messages {
+            data.write_u32::<BE>(msg.len() as u32)?;
+            data.write_all(msg)?;
+        }
+
+        let len = u32::try_from(data.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "stream too large"))?;
+
+        let mut init = ErasureInit {
+            origin: self.origin,
+            stream_id,
+            root: [0; 32],
+            data_shards: self.reed_solomon.data_shards as u16,
+            parity_shards: self.reed_solomon.parity_shards as u16,
+            len,
+        };
+
+        let shards = self.reed_solomon.encode(&data);
+        let (root, proofs) = merkle_tree(&init.params_hash(), &shards);
+        init.root = root;
+
+        let chunks = shards
+            .into_iter()
+            .zip(proofs)
+            .enumerate()
+            .map(|(index, (data, proof))| ErasureChunk {
+                init: init.clone(),
+                index: index as u16,
+                proof,
+                data: Bytes::from(data),
+            })
+            .collect();
+
+        Ok((init, chunks))
+    }
+}
+
+/// A stream rebuilt from its chunks
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedStream {
+    /// The peer which proposed the stream
+    pub origin: PeerId,
+
+    /// The GossipSub message which carried the [`ErasureInit`] message
+    pub message_id: MessageId,
+
+    pub stream_id: StreamId,
+
+    /// The encoded stream messages, in order
+    pub messages: Vec<Bytes>,
+}
+
+/// Outcome of recording a chunk
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Inserted {
+    New,
+    Duplicate,
+
+    /// The chunk was dropped, as the peer which sent it made us collect too many streams
+    TooManyStreams,
+}
+
+#[derive(Debug)]
+struct IncomingStream {
+    /// The peer whose message made us start collecting the stream
+    peer: PeerId,
+
+    init: ErasureInit,
+
+    /// The GossipSub message which carried the announcement of the stream by its origin, if received
+    announced: Option<MessageId>,
+
+    chunks: BTreeMap<usize, Bytes>,
+    done: bool,
+}
+
+/// Collects the chunks of incoming streams, and rebuilds the streams once enough chunks are received
+#[derive(Debug, Default)]
+pub struct StreamDecoder {
+    /// Streams by commitment
+    streams: HashMap<Hash, IncomingStream>,
+    order: VecDeque<Hash>,
+
+    /// Number of streams not rebuilt yet, by the peer which made us start collecting them
+    pending: HashMap<PeerId, usize>,
+}
+
+impl StreamDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn release(&mut self, peer: PeerId) {
+        if let Some(count) = self.pending.get_mut(&peer) {
+            *count -= 1;
+            if *count == 0 {
+                self.pending.remove(&peer);
+            }
+        }
+    }
+
+    /// The stream with the given parameters, unless the given peer made us collect too many streams
+    fn stream(&mut self, peer: PeerId, init: &ErasureInit) -> Option<&mut IncomingStream> {
+        let commitment = init.commitment();
+
+        if !self.streams.contains_key(&commitment) {
+            if self.pending.get(&peer).copied().unwrap_or_default() >= MAX_PENDING_STREAMS_PER_PEER
+            {
+                return None;
+            }
+
+            if self.order.len() >= MAX_INCOMING_STREAMS {
+                if let Some(oldest) = self.order.pop_front() {
+                    if let Some(stream) = self.streams.remove(&oldest) {
+                        if !stream.done {
+                            self.release(stream.peer);
+                        }
+                    }
+                }
+            }
+
+            *self.pending.entry(peer).or_default() += 1;
+            self.order.push_back(commitment);
+            self.streams.insert(
+                commitment,
+                IncomingStream {
+                    peer,
+                    init: init.clone(),
+                    announced: None,
+                    chunks: BTreeMap::new(),
+                    done: false,
+                },
+            );
+        }
+
+        self.streams.get_mut(&commitment)
+    }
+
+    /// Record the announcement of a stream by the given peer,
+    /// returning whether it was recorded, or an error if it is invalid
+    pub fn insert_init(
+        &mut self,
+        from: PeerId,
+        message_id: MessageId,
+        init: ErasureInit,
+    ) -> io::Result<bool> {
+        init.check()?;
+
+        if init.origin != from {
+            return Err(invalid_data(format!(
+                "stream of {} announced by another peer",
+                init.origin
+            )));
+        }
+
+        let Some(stream) = self.stream(from, &init) else {
+            return Ok(false);
+        };
+
+        if stream.announced.is_none() {
+            stream.announced = Some(message_id);
+        }
+
+        Ok(true)
+    }
+
+    /// Record a chunk sent by the given peer, returning an error if it is invalid
+    pub fn insert_chunk(&mut self, from: PeerId, chunk: &ErasureChunk) -> io::Result<Inserted> {
+        let reed_solomon = chunk.init.check()?;
+
+        if chunk.index as usize >= reed_solomon.total_shards() || !chunk.verify() {
+            return Err(invalid_data("invalid Merkle proof"));
+        }
+
+        let Some(stream) = self.stream(from, &chunk.init) else {
+            return Ok(Inserted::TooManyStreams);
+        };
+
+        if stream.done || stream.chunks.contains_key(&(chunk.index as usize)) {
+            return Ok(Inserted::Duplicate);
+        }
+
+        stream
+            .chunks
+            .insert(chunk.index as usize, chunk.data.clone());
+
+        Ok(Inserted::New)
+    }
+
+    /// The GossipSub message which announced the stream with the given commitment, if received
+    pub fn init_message_id(&self, commitment: &Hash) -> Option<&MessageId> {
+        self.streams.get(commitment)?.announced.as_ref()
+    }
+
+    /// Rebuild the stream with the given commitment, if it was announced by its origin
+    /// and enough chunks were received. A stream is only ever rebuilt once.
+    pub fn try_decode(&mut self, commitment: &Hash) -> io::Result<Option<DecodedStream>> {
+        let Some(stream) = self.streams.get_mut(commitment) else {
+            return Ok(None);
+        };
+
+        let Some(message_id) = &stream.announced else {
+            return Ok(None);
+        };
+
+        let Some(reed_solomon) = stream.init.reed_solomon() else {
+            return Ok(None);
+        };
+
+        if stream.done || stream.chunks.len() < reed_solomon.data_shards() {
+            return Ok(None);
+        }
+
+        let Some(data) = reed_solomon.reconstruct(&stream.chunks, stream.init.len as usize) else {
+            return Ok(None);
+        };
+
+        let mut buf = data.as_slice();
+        let mut messages = Vec::new();
+        while !buf.is_empty() {
+            let len = buf.read_u32::<BE>()? as usize;
+            if len > buf.len() {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+
+            let (msg, rest) = buf.split_at(len);
+            messages.push(Bytes::copy_from_slice(msg));
+            buf = rest;
+        }
+
+        let decoded = DecodedStream {
+            origin: stream.init.origin,
+            message_id: message_id.clone(),
+            stream_id: stream.init.stream_id,
+            messages,
+        };
+
+        stream.done = true;
+        stream.chunks.clear();
+
+        let peer = stream.peer;
+        self.release(peer);
+
+        Ok(Some(decoded))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use malachitebft_network::PeerIdExt;
+
+    use super::*;
+
+    fn random_peer() -> PeerId {
+        PeerId::from_libp2p(&libp2p::PeerId::random())
+    }
+
+    #[test]
+    fn reconstruct_from_any_data_shards() {
+        let reed_solomon = ReedSolomon::new(4, 3).unwrap();
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7 % 251) as u8).collect();
+
+        let shards = reed_solomon.encode(&data);
+        assert_eq!(shards.len(), 7);
+
+        for missing in [[0, 1, 2], [4, 5, 6], [0, 3, 6], [1, 2, 5]] {
+            let available = shards
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !missing.contains(index))
+                .map(|(index, shard)| (index, Bytes::from(shard.clone())))
+                .collect();
+
+            assert_eq!(
+                reed_solomon.reconstruct(&available, data.len()),
+                Some(data.clone())
+            );
+        }
+
+        let too_few = shards
+            .iter()
+            .take(3)
+            .enumerate()
+            .map(|(index, shard)| (index, Bytes::from(shard.clone())))
+            .collect();
+
+        assert_eq!(reed_solomon.reconstruct(&too_few, data.len()), None);
+    }
+
+    #[test]
+    fn merkle_proofs() {
+        let params = [7; 32];
+        let leaves: Vec<Vec<u8>> = (0..5u8).map(|i| vec![i; 10]).collect();
+        let (root, proofs) = merkle_tree(&params, &leaves);
+
+        for (index, (leaf, proof)) in leaves.iter().zip(&proofs).enumerate() {
+            assert!(verify_merkle_proof(&root, &params, index, leaf, proof));
+            assert!(!verify_merkle_proof(&root, &params, index, &[42], proof));
+            assert!(!verify_merkle_proof(&root, &params, index ^ 1, leaf, proof));
+            assert!(!verify_merkle_proof(&root, &[8; 32], index, leaf, proof));
+        }
+    }
+
+    #[test]
+    fn rebuild_stream_from_relayed_chunks() {
+        let origin = random_peer();
+        let relayer = random_peer();
+        let messages: Vec<Bytes> = (0..10u8).map(|i| Bytes::from(vec![i; 100])).collect();
+
+        let mut encoder = StreamEncoder::new(origin, ReedSolomon::new(3, 2).unwrap());
+        let mut decoder = StreamDecoder::new();
+        let now = Instant::now();
+
+        for msg in &messages[..9] {
+            assert_eq!(encoder.push(1, msg.clone(), false, now).unwrap(), None);
+        }
+
+        let (init, chunks) = encoder
+            .push(1, messages[9].clone(), true, now)
+            .unwrap()
+            .unwrap();
+        let commitment = init.commitment();
+
+        // Chunks may be received before the announcement of the stream
+        for chunk in &chunks[2..] {
+            let msg = ErasureMsg::Chunk {
+                relay: true,
+                chunk: chunk.clone(),
+            };
+            let ErasureMsg::Chunk { chunk, .. } =
+                ErasureMsg::decode(msg.encode().unwrap()).unwrap()
+            else {
+                panic!("expected a chunk");
+            };
+
+            assert_eq!(
+                decoder.insert_chunk(relayer, &chunk).unwrap(),
+                Inserted::New
+            );
+            assert_eq!(
+                decoder.insert_chunk(relayer, &chunk).unwrap(),
+                Inserted::Duplicate
+            );
+        }
+
+        assert_eq!(decoder.try_decode(&commitment).unwrap(), None);
+
+        // Only the origin of the stream can announce it
+        let forged_id = MessageId::from("forged");
+        assert!(decoder
+            .insert_init(relayer, forged_id.clone(), init.clone())
+            .is_err());
+
+        // Announcing the stream with other parameters does not prevent it from being rebuilt
+        let forged = ErasureInit {
+            len: init.len + 1,
+            ..init.clone()
+        };
+        assert!(decoder.insert_init(origin, forged_id, forged).unwrap());
+        assert_eq!(decoder.init_message_id(&commitment), None);
+
+        let msg = ErasureMsg::decode(ErasureMsg::Init(init.clone()).encode().unwrap()).unwrap();
+        assert_eq!(msg, ErasureMsg::Init(init.clone()));
+
+        let message_id = MessageId::from("init");
+        assert!(decoder
+            .insert_init(origin, message_id.clone(), init)
+            .unwrap());
+        assert_eq!(decoder.init_message_id(&commitment), Some(&message_id));
+
+        let decoded = decoder.try_decode(&commitment).unwrap().unwrap();
+        assert_eq!(decoded.origin, origin);
+        assert_eq!(decoded.message_id, message_id);
+        assert_eq!(decoded.stream_id, 1);
+        assert_eq!(decoded.messages, messages);
+
+        assert_eq!(decoder.try_decode(&commitment).unwrap(), None);
+
+        let mut forged = chunks[0].clone();
+        forged.data = Bytes::from_static(b"forged");
+        assert!(decoder.insert_chunk(relayer, &forged).is_err());
+
+        let mut forged = chunks[0].clone();
+        forged.init.len += 1;
+        assert!(decoder.insert_chunk(relayer, &forged).is_err());
+    }
+
+    #[test]
+    fn limit_pending_streams_per_peer() {
+        let origin = random_peer();
+        let (spammer, peer) = (random_peer(), random_peer());
+
+        let mut encoder = StreamEncoder::new(origin, ReedSolomon::new(2, 1).unwrap());
+        let mut decoder = StreamDecoder::new();
+        let now = Instant::now();
+
+        let streams: Vec<_> = (0..MAX_PENDING_STREAMS_PER_PEER as u64 + 2)
+            .map(|stream_id| {
+                encoder
+                    .push(stream_id, Bytes::from_static(b"part"), true, now)
+                    .unwrap()
+                    .unwrap()
+            })
+            .collect();
+
+        for (_, chunks) in &streams[..MAX_PENDING_STREAMS_PER_PEER] {
+            assert_eq!(
+                decoder.insert_chunk(spammer, &chunks[0]).unwrap(),
+                Inserted::New
+            );
+        }
+
+        let (_, chunks) = &streams[MAX_PENDING_STREAMS_PER_PEER];
+        assert_eq!(
+            decoder.insert_chunk(spammer, &chunks[0]).unwrap(),
+            Inserted::TooManyStreams
+        );
+        assert_eq!(
+            decoder.insert_chunk(peer, &chunks[0]).unwrap(),
+            Inserted::New
+        );
+
+        // Rebuilt streams no longer count against the peer which sent their first chunk
+        let (init, chunks) = &streams[0];
+        assert!(decoder
+            .insert_init(origin, MessageId::from("init"), init.clone())
+            .unwrap());
+        decoder.insert_chunk(peer, &chunks[1]).unwrap();
+        assert!(decoder.try_decode(&init.commitment()).unwrap().is_some());
+
+        let (_, chunks) = &streams[MAX_PENDING_STREAMS_PER_PEER + 1];
+        assert_eq!(
+            decoder.insert_chunk(spammer, &chunks[0]).unwrap(),
+            Inserted::New
+        );
+    }
+
+    #[test]
+    fn drop_unfinished_outgoing_streams() {
+        let mut encoder = StreamEncoder::new(random_peer(), ReedSolomon::new(2, 1).unwrap());
+        let now = Instant::now();
+
+        encoder
+            .push(1, Bytes::from_static(b"init"), false, now)
+            .unwrap();
+        encoder
+            .push(
+                2,
+                Bytes::from_static(b"init"),
+                false,
+                now + OUTGOING_STREAM_TTL / 2,
+            )
+            .unwrap();
+        encoder
+            .push(
+                2,
+                Bytes::from_static(b"data"),
+                false,
+                now + OUTGOING_STREAM_TTL,
+            )
+            .unwrap();
+
+        assert_eq!(encoder.streams.len(), 1);
+        assert_eq!(encoder.streams[&2].1.len(), 2);
+    }
+}
@@ -4,17 +4,26 @@ use derive_where::derive_where;
 use tokio::sync::broadcast;
 
 use malachitebft_core_consensus::{ProposedValue, SignedConsensusMsg, ValueToPropose};
-use malachitebft_core_types::{CommitCertificate, Context, Round, Timeout, ValueOrigin};
+use malachitebft_core_types::{
+    CommitCertificate, Context, Round, SignedProposal, SignedVote, Timeout, ValueOrigin,
+};
 
 pub type RxEvent<Ctx> = broadcast::Receiver<Event<Ctx>>;
 
+#[derive_where(Clone)]
 pub struct TxEvent<Ctx: Context> {
     tx: broadcast::Sender<Event<Ctx>>,
 }
 
 impl<Ctx: Context> TxEvent<Ctx> {
     pub fn new() -> Self {
-        let (tx, _) = broadcast::channel(128);
+        Self::with_capacity(128)
+    }
+
+    /// Create a new event channel, where each subscriber
+    /// lags behind once `capacity` events are pending for it.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
         Self { tx }
     }
 
@@ -49,6 +58,54 @@ pub enum Event<Ctx: Context> {
     WalReplayConsensus(SignedConsensusMsg<Ctx>),
     WalReplayTimeout(Timeout),
     WalReplayDone(Ctx::Height),
+    TimeoutElapsed(Timeout),
+    ProposalEquivocation(SignedProposal<Ctx>, SignedProposal<Ctx>),
+    VoteEquivocation(SignedVote<Ctx>, SignedVote<Ctx>),
+    RejectedValue(Ctx::Height, Round, Ctx::Address, String),
+}
+
+/// The kind of an [`Event`], without its payload
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EventKind {
+    StartedHeight,
+    StartedRound,
+    Published,
+    ProposedValue,
+    ReceivedProposedValue,
+    Decided,
+    RequestedVoteSet,
+    SentVoteSetResponse,
+    WalReplayBegin,
+    WalReplayConsensus,
+    WalReplayTimeout,
+    WalReplayDone,
+    TimeoutElapsed,
+    ProposalEquivocation,
+    VoteEquivocation,
+    RejectedValue,
+}
+
+impl<Ctx: Context> Event<Ctx> {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::StartedHeight(_) => EventKind::StartedHeight,
+            Event::StartedRound(_, _) => EventKind::StartedRound,
+            Event::Published(_) => EventKind::Published,
+            Event::ProposedValue(_) => EventKind::ProposedValue,
+            Event::ReceivedProposedValue(_, _) => EventKind::ReceivedProposedValue,
+            Event::Decided(_) => EventKind::Decided,
+            Event::RequestedVoteSet(_, _) => EventKind::RequestedVoteSet,
+            Event::SentVoteSetResponse(_, _, _) => EventKind::SentVoteSetResponse,
+            Event::WalReplayBegin(_, _) => EventKind::WalReplayBegin,
+            Event::WalReplayConsensus(_) => EventKind::WalReplayConsensus,
+            Event::WalReplayTimeout(_) => EventKind::WalReplayTimeout,
+            Event::WalReplayDone(_) => EventKind::WalReplayDone,
+            Event::TimeoutElapsed(_) => EventKind::TimeoutElapsed,
+            Event::ProposalEquivocation(_, _) => EventKind::ProposalEquivocation,
+            Event::VoteEquivocation(_, _) => EventKind::VoteEquivocation,
+            Event::RejectedValue(_, _, _, _) => EventKind::RejectedValue,
+        }
+    }
 }
 
 impl<Ctx: Context> fmt::Display for Event<Ctx> {
@@ -82,6 +139,25 @@ impl<Ctx: Context> fmt::Display for Event<Ctx> {
             Event::WalReplayConsensus(msg) => write!(f, "WalReplayConsensus(msg: {msg:?})"),
             Event::WalReplayTimeout(timeout) => write!(f, "WalReplayTimeout(timeout: {timeout:?})"),
             Event::WalReplayDone(height) => write!(f, "WalReplayDone(height: {height})"),
+            Event::TimeoutElapsed(timeout) => write!(f, "TimeoutElapsed(timeout: {timeout:?})"),
+            Event::ProposalEquivocation(existing, conflicting) => {
+                write!(
+                    f,
+                    "ProposalEquivocation(existing: {existing:?}, conflicting: {conflicting:?})"
+                )
+            }
+            Event::VoteEquivocation(existing, conflicting) => {
+                write!(
+                    f,
+                    "VoteEquivocation(existing: {existing:?}, conflicting: {conflicting:?})"
+                )
+            }
+            Event::RejectedValue(height, round, proposer, reason) => {
+                write!(
+                    f,
+                    "RejectedValue(height: {height}, round: {round}, proposer: {proposer}, reason: {reason})"
+                )
+            }
         }
     }
 }
@@ -1,3 +1,4 @@
+pub mod erasure;
 pub mod events;
 pub mod streaming;
 pub mod ticker;
@@ -63,7 +63,7 @@ pub struct Args<Codec> {
 pub struct State<Ctx: Context> {
     height: Ctx::Height,
     wal_sender: mpsc::Sender<self::thread::WalMsg<Ctx>>,
-    _handle: std::thread::JoinHandle<()>,
+    handle: Option<std::thread::JoinHandle<()>>,
 }
 
 impl<Ctx, Codec> Wal<Ctx, Codec>
@@ -201,7 +201,7 @@ where
         Ok(State {
             height: Ctx::Height::default(),
             wal_sender: tx,
-            _handle: handle,
+            handle: Some(handle),
         })
     }
 
@@ -233,6 +233,16 @@ where
 
         let _ = state.wal_sender.send(self::thread::WalMsg::Shutdown).await;
 
+        // Wait for the WAL thread to exit, so that the log is closed once the actor has stopped
+        if let Some(handle) = state.handle.take() {
+            if tokio::task::spawn_blocking(move || handle.join())
+                .await?
+                .is_err()
+            {
+                error!("WAL thread panicked");
+            }
+        }
+
         Ok(())
     }
 }
@@ -5,7 +5,7 @@ use derive_where::derive_where;
 
 use malachitebft_codec::Codec;
 use malachitebft_core_consensus::SignedConsensusMsg;
-use malachitebft_core_types::{Context, Round, Timeout};
+use malachitebft_core_types::{Context, Round, Timeout, Timestamp};
 
 /// Codec for encoding and decoding WAL entries.
 ///
@@ -29,6 +29,7 @@ where
 pub enum WalEntry<Ctx: Context> {
     ConsensusMsg(SignedConsensusMsg<Ctx>),
     Timeout(Timeout),
+    ProposalReceiveTime(Round, Timestamp),
 }
 
 impl<Ctx> WalEntry<Ctx>
@@ -42,6 +43,7 @@ where
                 SignedConsensusMsg::Proposal(_) => "Consensus(Proposal)",
             },
             Self::Timeout(_) => "Timeout",
+            Self::ProposalReceiveTime(_, _) => "ProposalReceiveTime",
         }
     }
 }
@@ -52,6 +54,7 @@ where
 {
     const TAG_CONSENSUS: u8 = 0x01;
     const TAG_TIMEOUT: u8 = 0x02;
+    const TAG_PROPOSAL_RECEIVE_TIME: u8 = 0x03;
 
     pub fn encode<C, W>(&self, codec: &C, mut buf: W) -> io::Result<()>
     where
@@ -88,6 +91,17 @@ where
 
                 Ok(())
             }
+
+            WalEntry::ProposalReceiveTime(round, received_at) => {
+                // Write tag
+                buf.write_u8(Self::TAG_PROPOSAL_RECEIVE_TIME)?;
+
+                // Write round and receive time
+                buf.write_i64::<BE>(round.as_i64())?;
+                buf.write_u64::<BE>(received_at.as_unix_nanos())?;
+
+                Ok(())
+            }
         }
     }
 
@@ -119,6 +133,12 @@ where
                 Ok(WalEntry::Timeout(timeout))
             }
 
+            Self::TAG_PROPOSAL_RECEIVE_TIME => {
+                let round = Round::from(buf.read_i64::<BE>()?);
+                let received_at = Timestamp::from_unix_nanos(buf.read_u64::<BE>()?);
+                Ok(WalEntry::ProposalReceiveTime(round, received_at))
+            }
+
             _ => Err(io::Error::new(io::ErrorKind::InvalidData, "invalid tag")),
         }
     }
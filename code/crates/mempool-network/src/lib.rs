@@ -29,7 +29,7 @@ pub use msg::NetworkMsg;
 use behaviour::{Behaviour, NetworkEvent};
 use handle::Handle;
 
-const METRICS_PREFIX: &str = "malachitebft_test_mempool";
+const METRICS_PREFIX: &str = "malachitebft_mempool_network";
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Channel {
@@ -75,7 +75,7 @@ impl fmt::Display for Channel {
     }
 }
 
-const PROTOCOL: &str = "/malachitebft-test-mempool/v1beta1";
+const PROTOCOL: &str = "/malachitebft-mempool/v1beta1";
 
 pub type BoxError = Box<dyn Error + Send + Sync + 'static>;
 
@@ -0,0 +1,10 @@
+use std::io::Result;
+
+fn main() -> Result<()> {
+    let mut config = prost_build::Config::new();
+    config.enable_type_names();
+    config.bytes(["."]);
+    config.compile_protos(&["proto/malachite.mempool.app.proto"], &["proto"])?;
+
+    Ok(())
+}
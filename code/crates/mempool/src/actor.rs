@@ -0,0 +1,340 @@
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use ractor::{Actor, ActorProcessingErr, ActorRef, RpcReplyPort};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, trace};
+
+use malachitebft_mempool_network::handle::CtrlHandle;
+use malachitebft_mempool_network::{Channel, Event, Keypair, NetworkMsg, PeerId};
+use malachitebft_metrics::SharedRegistry;
+
+use crate::gossip::{decode_batch, encode_batch};
+use crate::pool::{AddError, AddResult, Limits, Pool};
+use crate::validator::TxValidator;
+
+pub type MempoolMsg<V> = Msg<V>;
+pub type MempoolRef<V> = ActorRef<Msg<V>>;
+
+/// Mempool configuration
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    /// Size and count limits of the mempool
+    pub limits: Limits,
+
+    /// Maximum number of transactions to gossip at once in a batch.
+    /// Gossip is disabled when set to zero.
+    pub gossip_batch_size: usize,
+
+    /// Interval at which incomplete batches of transactions are gossiped
+    pub gossip_interval: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            limits: Limits::default(),
+            gossip_batch_size: 256,
+            gossip_interval: Duration::from_millis(100),
+        }
+    }
+}
+
+pub struct Args<V> {
+    /// The application-supplied transaction validator
+    pub validator: V,
+
+    /// The keypair of the node on the mempool network
+    pub keypair: Keypair,
+
+    /// Configuration of the mempool network
+    pub network: malachitebft_mempool_network::Config,
+
+    /// Registry for the mempool network metrics
+    pub registry: SharedRegistry,
+}
+
+/// Actor holding the pool of pending transactions and gossiping them to peers
+pub struct Mempool<V> {
+    config: Config,
+    span: tracing::Span,
+    marker: PhantomData<fn() -> V>,
+}
+
+pub enum Msg<V: TxValidator> {
+    /// Submit a transaction to the mempool.
+    /// Once admitted, the transaction is gossiped to peers.
+    Add {
+        tx: V::Tx,
+        reply: Option<RpcReplyPort<AddResult<V>>>,
+    },
+
+    /// Select transactions to include in a block, see [`Pool::reap`]
+    Reap {
+        max_bytes: usize,
+        max_count: usize,
+        reply: RpcReplyPort<Vec<V::Tx>>,
+    },
+
+    /// Remove the given committed transactions from the mempool,
+    /// and re-check the remaining ones.
+    Update { tx_hashes: Vec<V::Hash> },
+
+    /// Request the number of transactions in the mempool, and their total size in bytes
+    GetSize { reply: RpcReplyPort<(usize, usize)> },
+
+    // Internal messages
+    #[doc(hidden)]
+    NetworkEvent(Event),
+
+    #[doc(hidden)]
+    FlushGossip,
+}
+
+pub struct State<V: TxValidator> {
+    pool: Pool<V>,
+    pending_gossip: Vec<V::Tx>,
+    ctrl_handle: CtrlHandle,
+    recv_task: JoinHandle<()>,
+    gossip_task: Option<JoinHandle<()>>,
+}
+
+impl<V: TxValidator> Mempool<V> {
+    pub fn new(config: Config, span: tracing::Span) -> Self {
+        Self {
+            config,
+            span,
+            marker: PhantomData,
+        }
+    }
+
+    pub async fn spawn(
+        config: Config,
+        args: Args<V>,
+        span: tracing::Span,
+    ) -> Result<MempoolRef<V>, ractor::SpawnErr> {
+        let (actor_ref, _) = Actor::spawn(None, Self::new(config, span), args).await?;
+        Ok(actor_ref)
+    }
+
+    fn gossip_enabled(&self) -> bool {
+        self.config.gossip_batch_size > 0
+    }
+
+    async fn add_local_tx(
+        &self,
+        tx: V::Tx,
+        state: &mut State<V>,
+    ) -> Result<AddResult<V>, ActorProcessingErr> {
+        let result = state.pool.add(tx.clone());
+
+        match &result {
+            Ok(hash) => {
+                trace!(?hash, "Added transaction to the mempool");
+
+                if self.gossip_enabled() {
+                    state.pending_gossip.push(tx);
+
+                    if state.pending_gossip.len() >= self.config.gossip_batch_size {
+                        self.flush_gossip(state).await?;
+                    }
+                }
+            }
+            Err(e) => {
+                debug!("Rejected transaction: {e}");
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn flush_gossip(&self, state: &mut State<V>) -> Result<(), ActorProcessingErr> {
+        if state.pending_gossip.is_empty() {
+            return Ok(());
+        }
+
+        let txs = std::mem::take(&mut state.pending_gossip);
+
+        let bytes = match encode_batch(state.pool.validator(), &txs)
+            .and_then(|batch| NetworkMsg::TransactionBatch(batch).to_network_bytes())
+        {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                error!("Failed to encode transaction batch: {e}");
+                return Ok(());
+            }
+        };
+
+        trace!(
+            count = txs.len(),
+            size = bytes.len(),
+            "Gossiping transactions"
+        );
+
+        state.ctrl_handle.broadcast(Channel::Mempool, bytes).await?;
+
+        Ok(())
+    }
+
+    fn handle_network_event(&self, event: Event, state: &mut State<V>) {
+        match event {
+            Event::Listening(address) => {
+                info!(%address, "Listening");
+            }
+            Event::PeerConnected(peer_id) => {
+                info!(%peer_id, "Connected to peer");
+            }
+            Event::PeerDisconnected(peer_id) => {
+                info!(%peer_id, "Disconnected from peer");
+            }
+            Event::Message(_channel, from, _msg_id, msg) => {
+                self.handle_network_msg(&from, msg, state);
+            }
+        }
+    }
+
+    fn handle_network_msg(&self, from: &PeerId, msg: NetworkMsg, state: &mut State<V>) {
+        match msg {
+            NetworkMsg::TransactionBatch(batch) => {
+                let txs = match decode_batch(&batch) {
+                    Ok(txs) => txs,
+                    Err(e) => {
+                        error!(%from, "Failed to decode transaction batch: {e}");
+                        return;
+                    }
+                };
+
+                trace!(%from, "Received batch with {} transactions", txs.len());
+
+                // Transactions received over gossip are not gossiped again by us,
+                // the gossip layer already relays them to the rest of the network.
+                // Duplicates are dropped before being checked by the application.
+                for bytes in txs {
+                    let tx = match state.pool.validator().decode_tx(bytes) {
+                        Ok(tx) => tx,
+                        Err(e) => {
+                            debug!(%from, "Failed to decode transaction: {e}");
+                            continue;
+                        }
+                    };
+
+                    match state.pool.add(tx) {
+                        Ok(hash) => trace!(%from, ?hash, "Added transaction to the mempool"),
+                        Err(AddError::Duplicate) => {}
+                        Err(e) => debug!(%from, "Rejected transaction: {e}"),
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<V: TxValidator> Actor for Mempool<V> {
+    type Msg = Msg<V>;
+    type State = State<V>;
+    type Arguments = Args<V>;
+
+    async fn pre_start(
+        &self,
+        myself: MempoolRef<V>,
+        args: Args<V>,
+    ) -> Result<State<V>, ActorProcessingErr> {
+        let handle =
+            malachitebft_mempool_network::spawn(args.keypair, args.network, args.registry).await?;
+        let (mut recv_handle, ctrl_handle) = handle.split();
+
+        let recv_myself = myself.clone();
+        let recv_task = tokio::spawn(async move {
+            while let Some(event) = recv_handle.recv().await {
+                if let Err(e) = recv_myself.cast(Msg::NetworkEvent(event)) {
+                    error!("Actor has died, stopping gossip mempool: {e:?}");
+                    break;
+                }
+            }
+        });
+
+        let gossip_task = self
+            .gossip_enabled()
+            .then(|| myself.send_interval(self.config.gossip_interval, || Msg::FlushGossip));
+
+        Ok(State {
+            pool: Pool::new(args.validator, self.config.limits),
+            pending_gossip: Vec::new(),
+            ctrl_handle,
+            recv_task,
+            gossip_task,
+        })
+    }
+
+    #[tracing::instrument("mempool", parent = &self.span, skip_all)]
+    async fn handle(
+        &self,
+        _myself: MempoolRef<V>,
+        msg: Msg<V>,
+        state: &mut State<V>,
+    ) -> Result<(), ActorProcessingErr> {
+        match msg {
+            Msg::Add { tx, reply } => {
+                let result = self.add_local_tx(tx, state).await?;
+
+                if let Some(reply) = reply {
+                    reply.send(result)?;
+                }
+            }
+
+            Msg::Reap {
+                max_bytes,
+                max_count,
+                reply,
+            } => {
+                let txs = state.pool.reap(max_bytes, max_count);
+                debug!(count = txs.len(), "Reaped transactions");
+
+                reply.send(txs)?;
+            }
+
+            Msg::Update { tx_hashes } => {
+                let removed = state.pool.remove(&tx_hashes);
+                let invalid = state.pool.recheck();
+
+                debug!(
+                    %removed, %invalid, remaining = state.pool.len(),
+                    "Updated mempool after commit"
+                );
+            }
+
+            Msg::GetSize { reply } => {
+                reply.send((state.pool.len(), state.pool.total_bytes()))?;
+            }
+
+            Msg::NetworkEvent(event) => {
+                self.handle_network_event(event, state);
+            }
+
+            Msg::FlushGossip => {
+                self.flush_gossip(state).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn post_stop(
+        &self,
+        _myself: MempoolRef<V>,
+        state: &mut State<V>,
+    ) -> Result<(), ActorProcessingErr> {
+        info!("Stopping...");
+
+        if let Some(gossip_task) = state.gossip_task.take() {
+            gossip_task.abort();
+        }
+
+        state.ctrl_handle.shutdown().await?;
+        state.recv_task.abort();
+
+        Ok(())
+    }
+}
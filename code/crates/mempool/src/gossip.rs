@@ -0,0 +1,34 @@
+//! Encoding of transaction batches gossiped over the mempool network
+
+use bytes::Bytes;
+use prost_types::Any;
+
+use malachitebft_mempool_network::types::MempoolTransactionBatch;
+use malachitebft_proto::Error as ProtoError;
+
+use crate::proto;
+use crate::validator::TxValidator;
+
+/// Encode a batch of transactions to be gossiped to peers.
+pub fn encode_batch<V: TxValidator>(
+    validator: &V,
+    txs: &[V::Tx],
+) -> Result<MempoolTransactionBatch, ProtoError> {
+    let batch = proto::TransactionBatch {
+        transactions: txs.iter().map(|tx| validator.encode_tx(tx)).collect(),
+    };
+
+    Ok(MempoolTransactionBatch::new(Any::from_msg(&batch)?))
+}
+
+/// Decode a batch of transactions received from a peer.
+///
+/// Each transaction is returned in its encoded form,
+/// to be decoded with [`TxValidator::decode_tx`].
+pub fn decode_batch(batch: &MempoolTransactionBatch) -> Result<Vec<Bytes>, ProtoError> {
+    let batch = batch
+        .transaction_batch
+        .to_msg::<proto::TransactionBatch>()?;
+
+    Ok(batch.transactions)
+}
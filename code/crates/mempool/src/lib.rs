@@ -0,0 +1,23 @@
+//! A generic transaction mempool for applications built on top of Malachite.
+//!
+//! The application supplies the transaction validation logic by implementing
+//! the [`TxValidator`] trait. The [`Pool`] keeps the admitted transactions ordered
+//! by fee and by sender nonce, within configurable size and count limits,
+//! and the [`Mempool`] actor gossips them to peers over the mempool network.
+//!
+//! When proposing a value, the application reaps transactions from the mempool
+//! with [`Pool::reap`] or [`MempoolMsg::Reap`].
+
+mod actor;
+pub use actor::{Args, Config, Mempool, MempoolMsg, MempoolRef, Msg, State};
+
+mod pool;
+pub use pool::{AddError, AddResult, Limits, Pool};
+
+mod validator;
+pub use validator::{TxInfo, TxValidator};
+
+pub mod gossip;
+pub mod proto;
+
+pub use malachitebft_mempool_network as network;
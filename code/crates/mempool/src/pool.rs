@@ -0,0 +1,677 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet, VecDeque};
+use std::ops::Bound;
+
+use thiserror::Error;
+
+use crate::validator::{TxInfo, TxValidator};
+
+/// Size and count limits of the mempool
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum number of transactions held in the mempool
+    pub max_tx_count: usize,
+
+    /// Maximum total size of the transactions held in the mempool, in bytes
+    pub max_total_bytes: usize,
+
+    /// Maximum size of a single transaction, in bytes
+    pub max_tx_bytes: usize,
+
+    /// Number of recently committed transaction hashes to remember,
+    /// in order to ignore committed transactions which are gossiped back to us.
+    pub seen_cache_size: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_tx_count: 10_000,
+            max_total_bytes: 64 * 1024 * 1024, // 64 MiB
+            max_tx_bytes: 1024 * 1024,         // 1 MiB
+            seen_cache_size: 100_000,
+        }
+    }
+}
+
+/// The reason why a transaction was not added to the mempool
+#[derive(Debug, Error)]
+pub enum AddError<E> {
+    /// The transaction is already in the mempool, or was committed recently
+    #[error("Transaction is already known")]
+    Duplicate,
+
+    /// The transaction was rejected by the application
+    #[error("Transaction is invalid: {0}")]
+    Invalid(E),
+
+    /// The transaction exceeds the maximum transaction size
+    #[error("Transaction is too large: {size} bytes, maximum is {max} bytes")]
+    TooLarge { size: usize, max: usize },
+
+    /// A transaction with the same sender and nonce, and a higher or equal fee, is already in the mempool
+    #[error("Transaction with the same nonce and a higher or equal fee is already in the mempool")]
+    Underpriced,
+
+    /// The mempool is full and no transaction with a lower fee could be evicted to make room
+    #[error("Mempool is full")]
+    Full,
+}
+
+/// The outcome of adding a transaction to the mempool
+pub type AddResult<V> = Result<<V as TxValidator>::Hash, AddError<<V as TxValidator>::Error>>;
+
+struct Entry<V: TxValidator> {
+    tx: V::Tx,
+    info: TxInfo<V::Sender>,
+}
+
+/// A bounded set of hashes, which forgets the oldest hashes first.
+struct SeenCache<H> {
+    capacity: usize,
+    hashes: HashSet<H>,
+    order: VecDeque<H>,
+}
+
+impl<H> SeenCache<H>
+where
+    H: Clone + Eq + core::hash::Hash,
+{
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            hashes: HashSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn contains(&self, hash: &H) -> bool {
+        self.hashes.contains(hash)
+    }
+
+    fn insert(&mut self, hash: H) {
+        if self.capacity == 0 || !self.hashes.insert(hash.clone()) {
+            return;
+        }
+
+        self.order.push_back(hash);
+
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.hashes.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// A pool of pending transactions, ordered by fee and by sender nonce.
+///
+/// Transactions are checked by the application-supplied [`TxValidator`] before
+/// being admitted. When the pool is full, the transactions paying the lowest fee
+/// are evicted to make room for transactions paying a higher fee.
+pub struct Pool<V: TxValidator> {
+    validator: V,
+    limits: Limits,
+    txs: HashMap<V::Hash, Entry<V>>,
+    senders: BTreeMap<V::Sender, BTreeMap<u64, V::Hash>>,
+    total_bytes: usize,
+    seen: SeenCache<V::Hash>,
+}
+
+impl<V: TxValidator> Pool<V> {
+    pub fn new(validator: V, limits: Limits) -> Self {
+        Self {
+            validator,
+            limits,
+            txs: HashMap::new(),
+            senders: BTreeMap::new(),
+            total_bytes: 0,
+            seen: SeenCache::new(limits.seen_cache_size),
+        }
+    }
+
+    pub fn validator(&self) -> &V {
+        &self.validator
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Number of transactions in the mempool
+    pub fn len(&self) -> usize {
+        self.txs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.txs.is_empty()
+    }
+
+    /// Total size of the transactions in the mempool, in bytes
+    pub fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
+    pub fn contains(&self, hash: &V::Hash) -> bool {
+        self.txs.contains_key(hash)
+    }
+
+    pub fn get(&self, hash: &V::Hash) -> Option<&V::Tx> {
+        self.txs.get(hash).map(|entry| &entry.tx)
+    }
+
+    /// Check the given transaction and add it to the mempool.
+    ///
+    /// A transaction with the same sender and nonce as a transaction already
+    /// in the mempool replaces it if it pays a strictly higher fee.
+    ///
+    /// Evicted and replaced transactions are forgotten, and can be added again later.
+    ///
+    /// Returns the hash of the transaction if it was added.
+    pub fn add(&mut self, tx: V::Tx) -> AddResult<V> {
+        let hash = self.validator.hash_tx(&tx);
+
+        if self.seen.contains(&hash) || self.txs.contains_key(&hash) {
+            return Err(AddError::Duplicate);
+        }
+
+        let info = self.validator.check_tx(&tx).map_err(AddError::Invalid)?;
+
+        if info.size > self.limits.max_tx_bytes {
+            return Err(AddError::TooLarge {
+                size: info.size,
+                max: self.limits.max_tx_bytes,
+            });
+        }
+
+        let replaced = self
+            .senders
+            .get(&info.sender)
+            .and_then(|nonces| nonces.get(&info.nonce))
+            .cloned();
+
+        let (freed_count, freed_bytes) = match &replaced {
+            Some(old) => {
+                let old = &self.txs[old];
+
+                if old.info.fee >= info.fee {
+                    return Err(AddError::Underpriced);
+                }
+
+                (1, old.info.size)
+            }
+            None => (0, 0),
+        };
+
+        let needed_count =
+            (self.txs.len() - freed_count + 1).saturating_sub(self.limits.max_tx_count);
+        let needed_bytes = (self.total_bytes - freed_bytes + info.size)
+            .saturating_sub(self.limits.max_total_bytes);
+
+        let victims = if needed_count > 0 || needed_bytes > 0 {
+            self.eviction_victims(&info, needed_count, needed_bytes)
+                .ok_or(AddError::Full)?
+        } else {
+            Vec::new()
+        };
+
+        if let Some(old) = replaced {
+            self.remove_tx(&old);
+        }
+
+        for victim in victims {
+            self.remove_tx(&victim);
+        }
+
+        self.senders
+            .entry(info.sender.clone())
+            .or_default()
+            .insert(info.nonce, hash.clone());
+
+        self.total_bytes += info.size;
+        self.txs.insert(hash.clone(), Entry { tx, info });
+
+        Ok(hash)
+    }
+
+    /// Select the transactions to evict in order to make room for a transaction
+    /// with the given info, starting with the lowest fees.
+    ///
+    /// Evicting a transaction also evicts the transactions of the same sender with
+    /// a higher nonce, since those cannot be executed anymore.
+    ///
+    /// Returns `None` if not enough room can be made.
+    fn eviction_victims(
+        &self,
+        incoming: &TxInfo<V::Sender>,
+        needed_count: usize,
+        needed_bytes: usize,
+    ) -> Option<Vec<V::Hash>> {
+        let mut candidates = self
+            .txs
+            .values()
+            .map(|entry| &entry.info)
+            .filter(|info| info.fee < incoming.fee)
+            // Never open a nonce gap below the incoming transaction
+            .filter(|info| info.sender != incoming.sender || info.nonce > incoming.nonce)
+            .collect::<Vec<_>>();
+
+        // Lowest fee first, and for equal fees, highest nonce first
+        candidates.sort_by(|a, b| a.fee.cmp(&b.fee).then(b.nonce.cmp(&a.nonce)));
+
+        let mut victims = Vec::new();
+        let mut evicted = HashSet::new();
+        let (mut count, mut bytes) = (0, 0);
+
+        for candidate in candidates {
+            if count >= needed_count && bytes >= needed_bytes {
+                break;
+            }
+
+            for hash in self.senders[&candidate.sender]
+                .range(candidate.nonce..)
+                .map(|(_, h)| h)
+            {
+                if evicted.insert(hash.clone()) {
+                    count += 1;
+                    bytes += self.txs[hash].info.size;
+                    victims.push(hash.clone());
+                }
+            }
+        }
+
+        (count >= needed_count && bytes >= needed_bytes).then_some(victims)
+    }
+
+    fn remove_tx(&mut self, hash: &V::Hash) -> Option<V::Tx> {
+        let entry = self.txs.remove(hash)?;
+
+        if let Some(nonces) = self.senders.get_mut(&entry.info.sender) {
+            nonces.remove(&entry.info.nonce);
+
+            if nonces.is_empty() {
+                self.senders.remove(&entry.info.sender);
+            }
+        }
+
+        self.total_bytes -= entry.info.size;
+
+        Some(entry.tx)
+    }
+
+    /// Select transactions to include in a block, highest fee first,
+    /// while preserving the nonce order of transactions from the same sender.
+    ///
+    /// The selected transactions are not removed from the mempool, this is done
+    /// with [`Pool::remove`] once they are committed.
+    pub fn reap(&self, max_bytes: usize, max_count: usize) -> Vec<V::Tx> {
+        // Next transaction of each sender, by fee
+        let mut heap = BinaryHeap::new();
+
+        for (sender, nonces) in &self.senders {
+            if let Some((&nonce, hash)) = nonces.first_key_value() {
+                heap.push((self.txs[hash].info.fee, Reverse(sender), nonce));
+            }
+        }
+
+        let mut reaped = Vec::new();
+        let mut bytes = 0;
+
+        while reaped.len() < max_count {
+            let Some((_, Reverse(sender), nonce)) = heap.pop() else {
+                break;
+            };
+
+            let nonces = &self.senders[sender];
+            let entry = &self.txs[&nonces[&nonce]];
+
+            if bytes + entry.info.size > max_bytes {
+                // The next transactions of this sender cannot be included without this one
+                continue;
+            }
+
+            bytes += entry.info.size;
+            reaped.push(entry.tx.clone());
+
+            let next = nonces
+                .range((Bound::Excluded(nonce), Bound::Unbounded))
+                .next();
+
+            if let Some((&nonce, hash)) = next {
+                heap.push((self.txs[hash].info.fee, Reverse(sender), nonce));
+            }
+        }
+
+        reaped
+    }
+
+    /// Remove the given transactions from the mempool, typically after they have been committed.
+    ///
+    /// The transactions are remembered as committed, so that they are not added back
+    /// if they are gossiped to us again.
+    ///
+    /// Returns the number of transactions removed.
+    pub fn remove(&mut self, hashes: &[V::Hash]) -> usize {
+        let mut removed = 0;
+
+        for hash in hashes {
+            if self.remove_tx(hash).is_some() {
+                removed += 1;
+            }
+
+            self.seen.insert(hash.clone());
+        }
+
+        removed
+    }
+
+    /// Check all transactions in the mempool again, and remove the ones
+    /// which are not valid anymore, eg. after a block has been committed.
+    ///
+    /// Removing a transaction also removes the transactions of the same sender with
+    /// a higher nonce, since those cannot be executed anymore.
+    ///
+    /// Returns the number of transactions removed.
+    pub fn recheck(&mut self) -> usize {
+        let invalid = self
+            .txs
+            .values()
+            .filter(|entry| self.validator.check_tx(&entry.tx).is_err())
+            .map(|entry| (entry.info.sender.clone(), entry.info.nonce))
+            .collect::<Vec<_>>();
+
+        let mut removed = 0;
+
+        for (sender, nonce) in invalid {
+            // The transactions of the sender may already have been removed, from a lower nonce
+            let Some(nonces) = self.senders.get(&sender) else {
+                continue;
+            };
+
+            let hashes = nonces
+                .range(nonce..)
+                .map(|(_, hash)| hash.clone())
+                .collect::<Vec<_>>();
+
+            for hash in hashes {
+                self.remove_tx(&hash);
+                removed += 1;
+            }
+        }
+
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Mutex;
+
+    use bytes::Bytes;
+
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Tx {
+        sender: u8,
+        nonce: u64,
+        fee: u64,
+        size: usize,
+    }
+
+    fn tx(sender: u8, nonce: u64, fee: u64) -> Tx {
+        Tx {
+            sender,
+            nonce,
+            fee,
+            size: 10,
+        }
+    }
+
+    #[derive(Default)]
+    struct TestValidator {
+        min_nonce: AtomicU64,
+        rejected: Mutex<HashSet<(u8, u64)>>,
+    }
+
+    impl TxValidator for TestValidator {
+        type Tx = Tx;
+        type Hash = (u8, u64, u64, usize);
+        type Sender = u8;
+        type Error = String;
+
+        fn hash_tx(&self, tx: &Tx) -> Self::Hash {
+            (tx.sender, tx.nonce, tx.fee, tx.size)
+        }
+
+        fn check_tx(&self, tx: &Tx) -> Result<TxInfo<u8>, String> {
+            if tx.fee == 0 {
+                return Err("zero fee".to_string());
+            }
+
+            if tx.nonce < self.min_nonce.load(Ordering::SeqCst) {
+                return Err("nonce too low".to_string());
+            }
+
+            if self
+                .rejected
+                .lock()
+                .unwrap()
+                .contains(&(tx.sender, tx.nonce))
+            {
+                return Err("rejected".to_string());
+            }
+
+            Ok(TxInfo {
+                sender: tx.sender,
+                nonce: tx.nonce,
+                fee: tx.fee,
+                size: tx.size,
+            })
+        }
+
+        fn encode_tx(&self, tx: &Tx) -> Bytes {
+            Bytes::from(vec![tx.sender])
+        }
+
+        fn decode_tx(&self, _bytes: Bytes) -> Result<Tx, String> {
+            Err("unsupported".to_string())
+        }
+    }
+
+    fn pool(max_tx_count: usize, max_total_bytes: usize) -> Pool<TestValidator> {
+        Pool::new(
+            TestValidator::default(),
+            Limits {
+                max_tx_count,
+                max_total_bytes,
+                max_tx_bytes: 100,
+                seen_cache_size: 100,
+            },
+        )
+    }
+
+    #[test]
+    fn reap_orders_by_fee_and_nonce() {
+        let mut pool = pool(100, 1000);
+
+        pool.add(tx(1, 1, 5)).unwrap();
+        pool.add(tx(1, 0, 1)).unwrap();
+        pool.add(tx(2, 0, 3)).unwrap();
+        pool.add(tx(3, 7, 4)).unwrap();
+
+        let reaped = pool.reap(usize::MAX, usize::MAX);
+        assert_eq!(
+            reaped,
+            vec![tx(3, 7, 4), tx(2, 0, 3), tx(1, 0, 1), tx(1, 1, 5)]
+        );
+    }
+
+    #[test]
+    fn reap_respects_limits() {
+        let mut pool = pool(100, 1000);
+
+        pool.add(tx(1, 0, 5)).unwrap();
+        pool.add(tx(2, 0, 4)).unwrap();
+        pool.add(Tx {
+            size: 50,
+            ..tx(3, 0, 3)
+        })
+        .unwrap();
+        pool.add(tx(3, 1, 9)).unwrap();
+        pool.add(tx(4, 0, 2)).unwrap();
+
+        assert_eq!(pool.reap(usize::MAX, 2), vec![tx(1, 0, 5), tx(2, 0, 4)]);
+
+        // The large transaction does not fit, nor does the next one from the same sender
+        assert_eq!(
+            pool.reap(40, usize::MAX),
+            vec![tx(1, 0, 5), tx(2, 0, 4), tx(4, 0, 2)]
+        );
+
+        // Reaping does not remove transactions
+        assert_eq!(pool.len(), 5);
+    }
+
+    #[test]
+    fn rejects_duplicates_and_committed() {
+        let mut pool = pool(100, 1000);
+
+        let hash = pool.add(tx(1, 0, 5)).unwrap();
+        assert!(matches!(pool.add(tx(1, 0, 5)), Err(AddError::Duplicate)));
+
+        assert_eq!(pool.remove(&[hash]), 1);
+        assert!(pool.is_empty());
+        assert!(matches!(pool.add(tx(1, 0, 5)), Err(AddError::Duplicate)));
+    }
+
+    #[test]
+    fn rejects_invalid_and_too_large() {
+        let mut pool = pool(100, 1000);
+
+        assert!(matches!(pool.add(tx(1, 0, 0)), Err(AddError::Invalid(_))));
+        assert!(matches!(
+            pool.add(Tx {
+                size: 101,
+                ..tx(1, 0, 1)
+            }),
+            Err(AddError::TooLarge {
+                size: 101,
+                max: 100
+            })
+        ));
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn replaces_by_fee() {
+        let mut pool = pool(100, 1000);
+
+        pool.add(tx(1, 0, 5)).unwrap();
+        assert!(matches!(pool.add(tx(1, 0, 4)), Err(AddError::Underpriced)));
+
+        pool.add(tx(1, 0, 6)).unwrap();
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.total_bytes(), 10);
+        assert_eq!(pool.reap(usize::MAX, usize::MAX), vec![tx(1, 0, 6)]);
+    }
+
+    #[test]
+    fn evicts_lowest_fee_when_full() {
+        let mut pool = pool(3, 1000);
+
+        pool.add(tx(1, 0, 5)).unwrap();
+        pool.add(tx(2, 0, 2)).unwrap();
+        pool.add(tx(2, 1, 8)).unwrap();
+
+        // Not paying more than any transaction in the pool
+        assert!(matches!(pool.add(tx(3, 0, 2)), Err(AddError::Full)));
+
+        // Evicting the cheapest transaction also evicts the next one from the same sender
+        pool.add(tx(3, 0, 3)).unwrap();
+        assert_eq!(pool.len(), 2);
+        assert_eq!(
+            pool.reap(usize::MAX, usize::MAX),
+            vec![tx(1, 0, 5), tx(3, 0, 3)]
+        );
+    }
+
+    #[test]
+    fn resubmits_evicted() {
+        let mut pool = pool(2, 1000);
+
+        pool.add(tx(1, 0, 2)).unwrap();
+        pool.add(tx(2, 0, 5)).unwrap();
+        pool.add(tx(3, 0, 3)).unwrap();
+        assert!(!pool.contains(&(1, 0, 2, 10)));
+
+        // Once there is room again, the evicted transaction is not a duplicate
+        pool.remove(&[(2, 0, 5, 10)]);
+        pool.add(tx(1, 0, 2)).unwrap();
+        assert_eq!(
+            pool.reap(usize::MAX, usize::MAX),
+            vec![tx(3, 0, 3), tx(1, 0, 2)]
+        );
+    }
+
+    #[test]
+    fn evicts_to_respect_total_size() {
+        let mut pool = pool(100, 30);
+
+        pool.add(tx(1, 0, 5)).unwrap();
+        pool.add(tx(2, 0, 2)).unwrap();
+        pool.add(tx(3, 0, 1)).unwrap();
+
+        pool.add(Tx {
+            size: 20,
+            ..tx(4, 0, 3)
+        })
+        .unwrap();
+        assert_eq!(pool.total_bytes(), 30);
+        assert_eq!(
+            pool.reap(usize::MAX, usize::MAX),
+            vec![
+                tx(1, 0, 5),
+                Tx {
+                    size: 20,
+                    ..tx(4, 0, 3)
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn recheck_removes_invalid() {
+        let mut pool = pool(100, 1000);
+
+        pool.add(tx(1, 0, 5)).unwrap();
+        pool.add(tx(1, 1, 5)).unwrap();
+        pool.add(tx(2, 3, 5)).unwrap();
+        assert_eq!(pool.recheck(), 0);
+
+        pool.validator().min_nonce.store(2, Ordering::SeqCst);
+        assert_eq!(pool.recheck(), 2);
+        assert_eq!(pool.reap(usize::MAX, usize::MAX), vec![tx(2, 3, 5)]);
+        assert_eq!(pool.total_bytes(), 10);
+    }
+
+    #[test]
+    fn recheck_removes_higher_nonces() {
+        let mut pool = pool(100, 1000);
+
+        pool.add(tx(1, 0, 5)).unwrap();
+        pool.add(tx(1, 1, 5)).unwrap();
+        pool.add(tx(1, 2, 5)).unwrap();
+        pool.add(tx(2, 1, 4)).unwrap();
+
+        // The later transactions of the sender cannot be executed without the invalid one
+        pool.validator().rejected.lock().unwrap().insert((1, 1));
+        assert_eq!(pool.recheck(), 2);
+        assert_eq!(
+            pool.reap(usize::MAX, usize::MAX),
+            vec![tx(1, 0, 5), tx(2, 1, 4)]
+        );
+        assert_eq!(pool.total_bytes(), 20);
+    }
+}
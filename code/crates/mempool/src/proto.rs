@@ -0,0 +1,5 @@
+//! Protobuf definitions for the mempool gossip messages
+
+#![allow(missing_docs)]
+
+include!(concat!(env!("OUT_DIR"), "/malachite.mempool.app.rs"));
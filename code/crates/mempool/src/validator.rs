@@ -0,0 +1,54 @@
+use core::fmt::{Debug, Display};
+use core::hash::Hash;
+
+use bytes::Bytes;
+
+/// Information extracted from a transaction by the application when it is checked.
+///
+/// The mempool uses it to order, deduplicate and bound the transactions it holds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxInfo<Sender> {
+    /// The account which sent the transaction
+    pub sender: Sender,
+
+    /// The sender's nonce, transactions from the same sender are reaped in nonce order
+    pub nonce: u64,
+
+    /// The fee paid by the transaction, higher fees are reaped first
+    pub fee: u64,
+
+    /// The size of the encoded transaction, in bytes
+    pub size: usize,
+}
+
+/// Transaction validation logic supplied by the application.
+///
+/// Every transaction submitted locally or received over gossip is checked
+/// with [`TxValidator::check_tx`] before it is admitted into the mempool.
+/// After a block is committed, the remaining transactions are re-checked
+/// so that transactions invalidated by the block are dropped.
+pub trait TxValidator: Send + Sync + 'static {
+    /// The transaction type
+    type Tx: Clone + Debug + Send + Sync + 'static;
+
+    /// The transaction hash, used to deduplicate and remove transactions
+    type Hash: Clone + Eq + Hash + Debug + Send + Sync + 'static;
+
+    /// The sender of a transaction
+    type Sender: Clone + Ord + Debug + Send + Sync + 'static;
+
+    /// The error returned when a transaction is rejected by the application
+    type Error: Display + Debug + Send + Sync + 'static;
+
+    /// Compute the hash of the given transaction.
+    fn hash_tx(&self, tx: &Self::Tx) -> Self::Hash;
+
+    /// Check whether the transaction is valid against the current application state.
+    fn check_tx(&self, tx: &Self::Tx) -> Result<TxInfo<Self::Sender>, Self::Error>;
+
+    /// Encode the transaction for gossiping it to other peers.
+    fn encode_tx(&self, tx: &Self::Tx) -> Bytes;
+
+    /// Decode a transaction received from another peer.
+    fn decode_tx(&self, bytes: Bytes) -> Result<Self::Tx, Self::Error>;
+}
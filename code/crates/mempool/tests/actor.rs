@@ -0,0 +1,263 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use rand::Rng;
+use tokio::time::{sleep, timeout, Instant};
+
+use informalsystems_malachitebft_mempool::network::{self, Keypair, Multiaddr};
+use informalsystems_malachitebft_mempool::{
+    AddError, Args, Config, Limits, Mempool, MempoolRef, Msg, TxInfo, TxValidator,
+};
+use malachitebft_metrics::SharedRegistry;
+
+/// Maximum time to wait for the mempools to reach the expected state
+const WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, PartialEq)]
+struct Tx {
+    sender: u8,
+    nonce: u64,
+    fee: u64,
+}
+
+fn tx(sender: u8, nonce: u64, fee: u64) -> Tx {
+    Tx { sender, nonce, fee }
+}
+
+/// Accepts all transactions except the rejected ones, by sender and nonce
+#[derive(Clone, Default)]
+struct TestValidator {
+    rejected: Arc<Mutex<HashSet<(u8, u64)>>>,
+}
+
+impl TxValidator for TestValidator {
+    type Tx = Tx;
+    type Hash = (u8, u64, u64);
+    type Sender = u8;
+    type Error = String;
+
+    fn hash_tx(&self, tx: &Tx) -> Self::Hash {
+        (tx.sender, tx.nonce, tx.fee)
+    }
+
+    fn check_tx(&self, tx: &Tx) -> Result<TxInfo<u8>, String> {
+        if self
+            .rejected
+            .lock()
+            .unwrap()
+            .contains(&(tx.sender, tx.nonce))
+        {
+            return Err("rejected".to_string());
+        }
+
+        Ok(TxInfo {
+            sender: tx.sender,
+            nonce: tx.nonce,
+            fee: tx.fee,
+            size: 17,
+        })
+    }
+
+    fn encode_tx(&self, tx: &Tx) -> Bytes {
+        let mut bytes = vec![tx.sender];
+        bytes.extend_from_slice(&tx.nonce.to_be_bytes());
+        bytes.extend_from_slice(&tx.fee.to_be_bytes());
+        Bytes::from(bytes)
+    }
+
+    fn decode_tx(&self, bytes: Bytes) -> Result<Tx, String> {
+        if bytes.len() != 17 {
+            return Err(format!("invalid length: {}", bytes.len()));
+        }
+
+        Ok(Tx {
+            sender: bytes[0],
+            nonce: u64::from_be_bytes(bytes[1..9].try_into().unwrap()),
+            fee: u64::from_be_bytes(bytes[9..17].try_into().unwrap()),
+        })
+    }
+}
+
+fn addr(port: usize) -> Multiaddr {
+    format!("/ip4/127.0.0.1/tcp/{port}").parse().unwrap()
+}
+
+async fn spawn(
+    moniker: &str,
+    config: Config,
+    validator: TestValidator,
+    port: usize,
+    persistent_peers: Vec<Multiaddr>,
+) -> MempoolRef<TestValidator> {
+    let args = Args {
+        validator,
+        keypair: Keypair::generate_ed25519(),
+        network: network::Config {
+            listen_addr: addr(port),
+            persistent_peers,
+            idle_connection_timeout: Duration::from_secs(60),
+            transport: network::TransportProtocol::Tcp,
+        },
+        registry: SharedRegistry::global().with_moniker(moniker),
+    };
+
+    Mempool::spawn(config, args, tracing::Span::none())
+        .await
+        .unwrap()
+}
+
+async fn add(mempool: &MempoolRef<TestValidator>, tx: Tx) -> Result<(u8, u64, u64), String> {
+    ractor::call!(mempool, |reply| Msg::Add {
+        tx,
+        reply: Some(reply)
+    })
+    .unwrap()
+    .map_err(|e| e.to_string())
+}
+
+async fn size(mempool: &MempoolRef<TestValidator>) -> usize {
+    ractor::call!(mempool, |reply| Msg::GetSize { reply })
+        .unwrap()
+        .0
+}
+
+async fn reap(mempool: &MempoolRef<TestValidator>) -> Vec<Tx> {
+    ractor::call!(mempool, |reply| Msg::Reap {
+        max_bytes: usize::MAX,
+        max_count: usize::MAX,
+        reply
+    })
+    .unwrap()
+}
+
+/// Wait until the mempool holds the expected number of transactions
+async fn wait_for_size(mempool: &MempoolRef<TestValidator>, expected: usize) {
+    wait_until(|| async { size(mempool).await == expected })
+        .await
+        .unwrap_or_else(|_| panic!("mempool never held {expected} transactions"));
+}
+
+async fn wait_until<F, Fut>(mut done: F) -> Result<(), tokio::time::error::Elapsed>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = bool>,
+{
+    timeout(WAIT_TIMEOUT, async {
+        while !done().await {
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+}
+
+#[tokio::test]
+async fn gossip_drops_duplicates() {
+    let port = rand::thread_rng().gen_range(21000..50000);
+
+    // A gossips pairs of transactions, B gossips each transaction on its own,
+    // so that the batches they gossip differ even when they share transactions
+    let config = |gossip_batch_size| Config {
+        gossip_batch_size,
+        gossip_interval: Duration::from_secs(3600),
+        ..Config::default()
+    };
+
+    let a = spawn(
+        "mempool-a",
+        config(2),
+        TestValidator::default(),
+        port,
+        vec![],
+    )
+    .await;
+    let b = spawn(
+        "mempool-b",
+        config(1),
+        TestValidator::default(),
+        port + 1,
+        vec![addr(port)],
+    )
+    .await;
+
+    // Gossip from B until A is subscribed to its transactions,
+    // the transactions gossiped before then are not received by A
+    let deadline = Instant::now() + WAIT_TIMEOUT;
+    let mut probes = 0;
+    while size(&a).await == 0 {
+        assert!(Instant::now() < deadline, "A never received gossip from B");
+
+        add(&b, tx(0, probes, 1)).await.unwrap();
+        probes += 1;
+
+        sleep(Duration::from_millis(200)).await;
+    }
+
+    let (a_size, b_size) = (size(&a).await, size(&b).await);
+
+    // A receives the transaction it already holds from B
+    add(&a, tx(1, 0, 5)).await.unwrap();
+    add(&b, tx(1, 0, 5)).await.unwrap();
+
+    // B receives the same transaction back from A, along with a new one
+    add(&a, tx(2, 0, 5)).await.unwrap();
+    wait_for_size(&b, b_size + 2).await;
+
+    // Transactions are held once, whichever peer they were received from
+    assert_eq!(size(&a).await, a_size + 2);
+    assert_eq!(reap(&a).await.len(), a_size + 2);
+    assert_eq!(reap(&b).await.len(), b_size + 2);
+
+    // Transactions received over gossip are not added again
+    assert_eq!(
+        add(&b, tx(2, 0, 5)).await,
+        Err(AddError::<String>::Duplicate.to_string())
+    );
+
+    a.stop(None);
+    b.stop(None);
+}
+
+#[tokio::test]
+async fn update_removes_committed_and_rechecks() {
+    let port = rand::thread_rng().gen_range(21000..50000);
+    let validator = TestValidator::default();
+
+    let config = Config {
+        gossip_batch_size: 0,
+        limits: Limits::default(),
+        ..Config::default()
+    };
+
+    let mempool = spawn("mempool-update", config, validator.clone(), port, vec![]).await;
+
+    let committed = add(&mempool, tx(1, 0, 5)).await.unwrap();
+    add(&mempool, tx(1, 1, 5)).await.unwrap();
+    add(&mempool, tx(1, 2, 5)).await.unwrap();
+    add(&mempool, tx(2, 0, 3)).await.unwrap();
+    add(&mempool, tx(2, 1, 3)).await.unwrap();
+
+    // The committed transaction is removed and never added back
+    mempool
+        .cast(Msg::Update {
+            tx_hashes: vec![committed],
+        })
+        .unwrap();
+    wait_for_size(&mempool, 4).await;
+    assert_eq!(
+        add(&mempool, tx(1, 0, 5)).await,
+        Err(AddError::<String>::Duplicate.to_string())
+    );
+
+    // The transactions which became invalid are removed on recheck,
+    // along with the later transactions of their sender
+    validator.rejected.lock().unwrap().insert((1, 1));
+
+    mempool.cast(Msg::Update { tx_hashes: vec![] }).unwrap();
+    wait_for_size(&mempool, 2).await;
+    assert_eq!(reap(&mempool).await, vec![tx(2, 0, 3), tx(2, 1, 3)]);
+
+    mempool.stop(None);
+}
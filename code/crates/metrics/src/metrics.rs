@@ -81,6 +81,9 @@ pub struct Inner {
     /// Number of times consensus was blocked in Prevote or Precommit step and required vote synchronization
     pub step_timeouts: Counter,
 
+    /// Number of proposed values which the application rejected as invalid
+    pub invalid_values: Counter,
+
     /// Number of connected peers, ie. for each consensus node, how many peers is it connected to)
     pub connected_peers: Gauge,
 
@@ -121,6 +124,7 @@ impl Metrics {
             consensus_round: Histogram::new(linear_buckets(0.0, 1.0, 20)),
             proposal_round: Histogram::new(linear_buckets(0.0, 1.0, 20)),
             step_timeouts: Counter::default(),
+            invalid_values: Counter::default(),
             connected_peers: Gauge::default(),
             height: Gauge::default(),
             round: Gauge::default(),
@@ -196,6 +200,12 @@ impl Metrics {
                 metrics.step_timeouts.clone(),
             );
 
+            registry.register(
+                "invalid_values",
+                "Number of proposed values which the application rejected as invalid",
+                metrics.invalid_values.clone(),
+            );
+
             registry.register(
                 "connected_peers",
                 "Number of connected peers, ie. for each consensus node, how many peers is it connected to",
@@ -2,8 +2,9 @@ use std::time::Duration;
 
 use libp2p::kad::{Addresses, KBucketKey, KBucketRef};
 use libp2p::request_response::{OutboundRequestId, ResponseChannel};
+use libp2p::swarm::behaviour::toggle::Toggle;
 use libp2p::swarm::NetworkBehaviour;
-use libp2p::{gossipsub, identify, ping};
+use libp2p::{autonat, dcutr, gossipsub, identify, ping, relay};
 use libp2p_broadcast as broadcast;
 
 pub use libp2p::identity::Keypair;
@@ -13,7 +14,9 @@ use malachitebft_discovery as discovery;
 use malachitebft_metrics::Registry;
 use malachitebft_sync as sync;
 
-use crate::{Config, GossipSubConfig, PROTOCOL};
+use crate::{
+    direct, nat, Channel, Compression, CompressionConfig, Config, GossipSubConfig, PROTOCOL,
+};
 
 #[derive(Debug)]
 pub enum NetworkEvent {
@@ -21,8 +24,13 @@ pub enum NetworkEvent {
     Ping(ping::Event),
     GossipSub(gossipsub::Event),
     Broadcast(broadcast::Event),
+    Direct(direct::Event),
     Sync(sync::Event),
     Discovery(discovery::NetworkEvent),
+    AutoNat(autonat::Event),
+    RelayServer(relay::Event),
+    RelayClient(relay::client::Event),
+    Dcutr(dcutr::Event),
 }
 
 impl From<identify::Event> for NetworkEvent {
@@ -49,6 +57,12 @@ impl From<broadcast::Event> for NetworkEvent {
     }
 }
 
+impl From<direct::Event> for NetworkEvent {
+    fn from(event: direct::Event) -> Self {
+        Self::Direct(event)
+    }
+}
+
 impl From<sync::Event> for NetworkEvent {
     fn from(event: sync::Event) -> Self {
         Self::Sync(event)
@@ -61,6 +75,30 @@ impl From<discovery::NetworkEvent> for NetworkEvent {
     }
 }
 
+impl From<autonat::Event> for NetworkEvent {
+    fn from(event: autonat::Event) -> Self {
+        Self::AutoNat(event)
+    }
+}
+
+impl From<relay::Event> for NetworkEvent {
+    fn from(event: relay::Event) -> Self {
+        Self::RelayServer(event)
+    }
+}
+
+impl From<relay::client::Event> for NetworkEvent {
+    fn from(event: relay::client::Event) -> Self {
+        Self::RelayClient(event)
+    }
+}
+
+impl From<dcutr::Event> for NetworkEvent {
+    fn from(event: dcutr::Event) -> Self {
+        Self::Dcutr(event)
+    }
+}
+
 #[derive(NetworkBehaviour)]
 #[behaviour(to_swarm = "NetworkEvent")]
 pub struct Behaviour {
@@ -68,8 +106,13 @@ pub struct Behaviour {
     pub ping: ping::Behaviour,
     pub gossipsub: gossipsub::Behaviour,
     pub broadcast: broadcast::Behaviour,
+    pub direct: Toggle<direct::Behaviour>,
     pub sync: sync::Behaviour,
     pub discovery: discovery::Behaviour,
+    pub autonat: Toggle<autonat::Behaviour>,
+    pub relay_server: Toggle<relay::Behaviour>,
+    pub relay_client: Toggle<relay::client::Behaviour>,
+    pub dcutr: Toggle<dcutr::Behaviour>,
 }
 
 /// Dummy implementation of Debug for Behaviour.
@@ -109,6 +152,17 @@ impl discovery::DiscoveryClient for Behaviour {
     }
 }
 
+/// Identifier of a message received over the broadcast protocol or a direct stream,
+/// which have none of their own
+pub(crate) fn broadcast_message_id(data: &[u8]) -> gossipsub::MessageId {
+    use seahash::SeaHasher;
+    use std::hash::Hasher;
+
+    let mut hasher = SeaHasher::new();
+    hasher.write(data);
+    gossipsub::MessageId::new(hasher.finish().to_be_bytes().as_slice())
+}
+
 fn message_id(message: &gossipsub::Message) -> gossipsub::MessageId {
     use seahash::SeaHasher;
     use std::hash::{Hash, Hasher};
@@ -131,20 +185,66 @@ fn gossipsub_config(config: GossipSubConfig, max_transmit_size: usize) -> gossip
         .mesh_outbound_min(config.mesh_outbound_min)
         .mesh_n(config.mesh_n)
         .message_id_fn(message_id)
+        // Only forward messages once validated by the application
+        .validate_messages()
         .build()
         .unwrap()
 }
 
+fn peer_score_params(compression: &CompressionConfig) -> gossipsub::PeerScoreParams {
+    let mut params = gossipsub::PeerScoreParams {
+        // Keep good behaviour on the topics from offsetting the penalties for exceeding rate limits
+        topic_score_cap: 10.0,
+        // Nodes of a test network or validators with their sentry nodes may share an IP address
+        ip_colocation_factor_weight: 0.0,
+        ..Default::default()
+    };
+
+    for channel in Channel::consensus() {
+        params.topics.insert(
+            channel.to_gossipsub_topic(compression.get(*channel)).hash(),
+            topic_score_params(),
+        );
+    }
+
+    params
+}
+
+fn topic_score_params() -> gossipsub::TopicScoreParams {
+    gossipsub::TopicScoreParams {
+        // Consensus messages are too sparse to expect a minimum rate of deliveries in the mesh
+        mesh_message_deliveries_weight: 0.0,
+        mesh_failure_penalty_weight: 0.0,
+        // Graylist peers after a few messages rejected by the application
+        invalid_message_deliveries_weight: -10.0,
+        ..Default::default()
+    }
+}
+
 impl Behaviour {
-    pub fn new_with_metrics(config: &Config, keypair: &Keypair, registry: &mut Registry) -> Self {
-        let identify = identify::Behaviour::new(identify::Config::new(
-            PROTOCOL.to_string(),
-            keypair.public(),
-        ));
+    pub fn new_with_metrics(
+        config: &Config,
+        keypair: &Keypair,
+        relay_client: relay::client::Behaviour,
+        registry: &mut Registry,
+    ) -> Self {
+        let peer_id = keypair.public().to_peer_id();
+
+        // Let the peers know about the addresses of the relays this node listens on
+        // as soon as their reservations are accepted
+        let mut identify_config = identify::Config::new(PROTOCOL.to_string(), keypair.public())
+            .with_push_listen_addr_updates(true);
+
+        if config.discovery.hidden {
+            identify_config =
+                identify_config.with_agent_version(discovery::HIDDEN_AGENT_VERSION.to_string());
+        }
+
+        let identify = identify::Behaviour::new(identify_config);
 
         let ping = ping::Behaviour::new(ping::Config::new().with_interval(Duration::from_secs(5)));
 
-        let gossipsub = gossipsub::Behaviour::new_with_metrics(
+        let mut gossipsub = gossipsub::Behaviour::new_with_metrics(
             gossipsub::MessageAuthenticity::Signed(keypair.clone()),
             gossipsub_config(config.gossipsub, config.pubsub_max_size),
             registry.sub_registry_with_prefix("gossipsub"),
@@ -152,6 +252,13 @@ impl Behaviour {
         )
         .unwrap();
 
+        gossipsub
+            .with_peer_score(
+                peer_score_params(&config.compression),
+                gossipsub::PeerScoreThresholds::default(),
+            )
+            .unwrap();
+
         let broadcast = broadcast::Behaviour::new_with_metrics(
             broadcast::Config {
                 max_buf_size: config.pubsub_max_size,
@@ -159,20 +266,53 @@ impl Behaviour {
             registry.sub_registry_with_prefix("broadcast"),
         );
 
+        let direct = config.has_direct_streams().then(|| {
+            direct::Behaviour::new(direct::Config {
+                max_message_size: config.pubsub_max_size,
+                validators: config.persistent_peers.clone(),
+            })
+        });
+
         let sync = sync::Behaviour::new_with_metrics(
-            sync::Config::default().with_max_response_size(config.rpc_max_size),
+            sync::Config::default()
+                .with_max_response_size(config.rpc_max_size)
+                .with_compression(config.compression.rpc != Compression::None),
             registry.sub_registry_with_prefix("sync"),
         );
 
-        let discovery = discovery::Behaviour::new(keypair, config.discovery);
+        let discovery = discovery::Behaviour::new(keypair, config.discovery, &config.peer_lists);
+
+        let autonat = config
+            .nat
+            .autonat
+            .then(|| autonat::Behaviour::new(peer_id, autonat::Config::default()));
+
+        let relay_server = config.nat.relay_server.then(|| {
+            relay::Behaviour::new(
+                peer_id,
+                nat::relay_server_config(&config.nat.relay_allowed_peers),
+            )
+        });
+
+        let relay_client = config.nat.is_relay_client_enabled().then_some(relay_client);
+
+        let dcutr = config
+            .nat
+            .hole_punching
+            .then(|| dcutr::Behaviour::new(peer_id));
 
         Self {
             identify,
             ping,
             gossipsub,
             broadcast,
+            direct: Toggle::from(direct),
             sync,
             discovery,
+            autonat: Toggle::from(autonat),
+            relay_server: Toggle::from(relay_server),
+            relay_client: Toggle::from(relay_client),
+            dcutr: Toggle::from(dcutr),
         }
     }
 }
@@ -4,6 +4,8 @@ use libp2p::gossipsub;
 use libp2p_broadcast as broadcast;
 use serde::{Deserialize, Serialize};
 
+use crate::{Compression, CompressionConfig};
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Channel {
     Consensus,
@@ -20,12 +22,18 @@ impl Channel {
         &[Channel::Consensus, Channel::ProposalParts]
     }
 
-    pub fn to_gossipsub_topic(self) -> gossipsub::IdentTopic {
-        gossipsub::IdentTopic::new(self.as_str())
+    /// Topic of the channel on which messages compressed with the given algorithm are published
+    pub fn to_gossipsub_topic(self, compression: Compression) -> gossipsub::IdentTopic {
+        gossipsub::IdentTopic::new(self.topic(compression))
+    }
+
+    /// Topic of the channel on which messages compressed with the given algorithm are broadcast
+    pub fn to_broadcast_topic(self, compression: Compression) -> broadcast::Topic {
+        broadcast::Topic::new(self.topic(compression).as_bytes())
     }
 
-    pub fn to_broadcast_topic(self) -> broadcast::Topic {
-        broadcast::Topic::new(self.as_str().as_bytes())
+    fn topic(self, compression: Compression) -> String {
+        format!("{}{}", self.as_str(), compression.topic_suffix())
     }
 
     pub fn as_str(&self) -> &'static str {
@@ -36,34 +44,39 @@ impl Channel {
         }
     }
 
-    pub fn has_gossipsub_topic(topic_hash: &gossipsub::TopicHash) -> bool {
-        Self::all()
-            .iter()
-            .any(|channel| &channel.to_gossipsub_topic().hash() == topic_hash)
+    pub fn has_gossipsub_topic(
+        topic_hash: &gossipsub::TopicHash,
+        compression: &CompressionConfig,
+    ) -> bool {
+        Self::from_gossipsub_topic_hash(topic_hash, compression).is_some()
     }
 
-    pub fn has_broadcast_topic(topic: &broadcast::Topic) -> bool {
-        Self::all()
-            .iter()
-            .any(|channel| &channel.to_broadcast_topic() == topic)
+    pub fn has_broadcast_topic(topic: &broadcast::Topic, compression: &CompressionConfig) -> bool {
+        Self::from_broadcast_topic(topic, compression).is_some()
     }
 
-    pub fn from_gossipsub_topic_hash(topic: &gossipsub::TopicHash) -> Option<Self> {
-        match topic.as_str() {
-            "/consensus" => Some(Channel::Consensus),
-            "/proposal_parts" => Some(Channel::ProposalParts),
-            "/sync" => Some(Channel::Sync),
-            _ => None,
-        }
+    /// Channel of the topic, if that topic is the one used for the channel with the
+    /// given compression
+    pub fn from_gossipsub_topic_hash(
+        topic: &gossipsub::TopicHash,
+        compression: &CompressionConfig,
+    ) -> Option<Self> {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|channel| &channel.to_gossipsub_topic(compression.get(*channel)).hash() == topic)
     }
 
-    pub fn from_broadcast_topic(topic: &broadcast::Topic) -> Option<Self> {
-        match topic.as_ref() {
-            b"/consensus" => Some(Channel::Consensus),
-            b"/proposal_parts" => Some(Channel::ProposalParts),
-            b"/sync" => Some(Channel::Sync),
-            _ => None,
-        }
+    /// Channel of the topic, if that topic is the one used for the channel with the
+    /// given compression
+    pub fn from_broadcast_topic(
+        topic: &broadcast::Topic,
+        compression: &CompressionConfig,
+    ) -> Option<Self> {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|channel| &channel.to_broadcast_topic(compression.get(*channel)) == topic)
     }
 }
 
@@ -0,0 +1,350 @@
+use std::fmt;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use malachitebft_metrics::prometheus::metrics::family::Family;
+use malachitebft_metrics::prometheus::metrics::histogram::{linear_buckets, Histogram};
+use malachitebft_metrics::Registry;
+
+use crate::Channel;
+
+/// Label of the compression metrics for the sync responses
+const RPC_LABEL: &str = "rpc";
+
+type CompressionRatio = Family<Vec<(String, String)>, Histogram, fn() -> Histogram>;
+
+/// Compression algorithm of a message.
+///
+/// Channels with compression are published on their own topics, on which every message starts
+/// with the tag of the algorithm it is compressed with, so that they are never mistaken for the
+/// messages of peers without compression, which are sent as is on the plain topics.
+/// Messages sent over direct streams and sync responses sent over the compressed sync protocol
+/// always start with that tag too.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Compression {
+    #[default]
+    None,
+    Lz4,
+}
+
+impl Compression {
+    const TAG_NONE: u8 = 0x00;
+    const TAG_LZ4: u8 = 0x01;
+
+    fn tag(&self) -> u8 {
+        match self {
+            Self::None => Self::TAG_NONE,
+            Self::Lz4 => Self::TAG_LZ4,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            Self::TAG_NONE => Some(Self::None),
+            Self::TAG_LZ4 => Some(Self::Lz4),
+            _ => None,
+        }
+    }
+
+    /// Suffix of the topics of the channels compressed with this algorithm
+    pub(crate) fn topic_suffix(&self) -> &'static str {
+        match self {
+            Self::None => "",
+            Self::Lz4 => "+lz4",
+        }
+    }
+}
+
+/// Compression of the messages sent on each channel and of the sync responses.
+///
+/// Sync responses are only compressed for peers which also have compression of the
+/// sync responses enabled, as they are sent over a distinct protocol.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompressionConfig {
+    pub consensus: Compression,
+    pub proposal_parts: Compression,
+    pub sync: Compression,
+    pub rpc: Compression,
+}
+
+impl CompressionConfig {
+    pub fn get(&self, channel: Channel) -> Compression {
+        match channel {
+            Channel::Consensus => self.consensus,
+            Channel::ProposalParts => self.proposal_parts,
+            Channel::Sync => self.sync,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DecompressionError {
+    MissingAlgorithm,
+    UnknownAlgorithm(u8),
+    TooLarge { size: usize, max_size: usize },
+    Lz4(lz4_flex::block::DecompressError),
+}
+
+impl fmt::Display for DecompressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingAlgorithm => write!(f, "missing compression algorithm"),
+            Self::UnknownAlgorithm(tag) => write!(f, "unknown compression algorithm: {tag}"),
+            Self::TooLarge { size, max_size } => {
+                write!(f, "decompressed size {size} exceeds maximum of {max_size}")
+            }
+            Self::Lz4(e) => write!(f, "invalid LZ4 data: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for DecompressionError {}
+
+/// Compresses the outgoing messages and decompresses the incoming ones,
+/// recording the compression ratio achieved on each channel.
+#[derive(Clone, Debug)]
+pub struct Compressor {
+    config: CompressionConfig,
+    pubsub_max_size: usize,
+    rpc_max_size: usize,
+
+    /// Ratio of the uncompressed size to the compressed size of the messages sent
+    compression_ratio: CompressionRatio,
+}
+
+impl Compressor {
+    pub fn new(
+        config: CompressionConfig,
+        pubsub_max_size: usize,
+        rpc_max_size: usize,
+        registry: &mut Registry,
+    ) -> Self {
+        let compression_ratio =
+            CompressionRatio::new_with_constructor(|| Histogram::new(linear_buckets(1.0, 0.5, 20)));
+
+        registry.register(
+            "compression_ratio",
+            "Ratio of the uncompressed size to the compressed size of the messages sent",
+            compression_ratio.clone(),
+        );
+
+        Self {
+            config,
+            pubsub_max_size,
+            rpc_max_size,
+            compression_ratio,
+        }
+    }
+
+    /// Compress a message to be published on the topic of the given channel, if that makes it
+    /// smaller, and tag it with its algorithm unless the channel is not compressed
+    pub fn compress(&self, channel: Channel, data: Bytes) -> Bytes {
+        match self.config.get(channel) {
+            Compression::None => data,
+            compression => self.compress_tagged(channel_label(channel), compression, data),
+        }
+    }
+
+    /// Compress a message to be sent over a direct stream, if that makes it smaller,
+    /// and tag it with its algorithm
+    pub fn compress_direct(&self, channel: Channel, data: Bytes) -> Bytes {
+        let compression = self.config.get(channel);
+        self.compress_tagged(channel_label(channel), compression, data)
+    }
+
+    /// Compress a sync response sent over the compressed sync protocol, if that makes it
+    /// smaller, and tag it with its algorithm
+    pub fn compress_response(&self, data: Bytes) -> Bytes {
+        self.compress_tagged(RPC_LABEL, self.config.rpc, data)
+    }
+
+    /// Decompress a message received on the topic of the given channel,
+    /// messages of channels without compression are left as is
+    pub fn decompress(&self, channel: Channel, data: Bytes) -> Result<Bytes, DecompressionError> {
+        match self.config.get(channel) {
+            Compression::None => Ok(data),
+            _ => decompress_tagged(data, self.pubsub_max_size).map(|(_, data)| data),
+        }
+    }
+
+    /// Decompress a message received over a direct stream
+    pub fn decompress_direct(&self, data: Bytes) -> Result<Bytes, DecompressionError> {
+        decompress_tagged(data, self.pubsub_max_size).map(|(_, data)| data)
+    }
+
+    /// Decompress a sync response received over the compressed sync protocol
+    pub fn decompress_response(&self, data: Bytes) -> Result<Bytes, DecompressionError> {
+        decompress_tagged(data, self.rpc_max_size).map(|(_, data)| data)
+    }
+
+    fn compress_tagged(&self, label: &str, compression: Compression, data: Bytes) -> Bytes {
+        let compressed = compress(compression, &data);
+
+        let (compression, payload) = if compressed.len() < data.len() {
+            (compression, compressed)
+        } else {
+            (Compression::None, data.clone())
+        };
+
+        if compression != Compression::None {
+            self.compression_ratio
+                .get_or_create(&vec![("channel".to_string(), label.to_string())])
+                .observe(data.len() as f64 / payload.len().max(1) as f64);
+        }
+
+        let mut buf = BytesMut::with_capacity(payload.len() + 1);
+        buf.put_u8(compression.tag());
+        buf.put(payload);
+        buf.freeze()
+    }
+}
+
+fn channel_label(channel: Channel) -> &'static str {
+    channel.as_str().trim_start_matches('/')
+}
+
+fn compress(compression: Compression, data: &Bytes) -> Bytes {
+    match compression {
+        Compression::None => data.clone(),
+        Compression::Lz4 => Bytes::from(lz4_flex::compress_prepend_size(data)),
+    }
+}
+
+fn decompress_tagged(
+    data: Bytes,
+    max_size: usize,
+) -> Result<(Compression, Bytes), DecompressionError> {
+    let tag = *data.first().ok_or(DecompressionError::MissingAlgorithm)?;
+    let payload = data.slice(1..);
+
+    let compression =
+        Compression::from_tag(tag).ok_or(DecompressionError::UnknownAlgorithm(tag))?;
+
+    match compression {
+        Compression::None => Ok((compression, payload)),
+        Compression::Lz4 => {
+            // Check the size prepended to the data before allocating for it
+            let size = payload
+                .get(..4)
+                .map(|size| u32::from_le_bytes(size.try_into().unwrap()) as usize)
+                .ok_or(DecompressionError::Lz4(
+                    lz4_flex::block::DecompressError::ExpectedAnotherByte,
+                ))?;
+
+            if size > max_size {
+                return Err(DecompressionError::TooLarge { size, max_size });
+            }
+
+            let data =
+                lz4_flex::decompress_size_prepended(&payload).map_err(DecompressionError::Lz4)?;
+
+            Ok((compression, Bytes::from(data)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compressor(config: CompressionConfig) -> Compressor {
+        Compressor::new(config, 1024 * 1024, 1024 * 1024, &mut Registry::default())
+    }
+
+    #[test]
+    fn compress_roundtrip() {
+        let compressor = compressor(CompressionConfig {
+            proposal_parts: Compression::Lz4,
+            rpc: Compression::Lz4,
+            ..Default::default()
+        });
+
+        let data = Bytes::from(vec![42; 10_000]);
+
+        let compressed = compressor.compress(Channel::ProposalParts, data.clone());
+        assert!(compressed.len() < data.len());
+        assert_eq!(
+            compressor
+                .decompress(Channel::ProposalParts, compressed)
+                .unwrap(),
+            data
+        );
+
+        // Small messages are tagged as uncompressed on compressed channels
+        let small = Bytes::from_static(b"small");
+        let tagged = compressor.compress(Channel::ProposalParts, small.clone());
+        assert_eq!(tagged.len(), small.len() + 1);
+        assert_eq!(
+            compressor
+                .decompress(Channel::ProposalParts, tagged)
+                .unwrap(),
+            small
+        );
+
+        // Messages over direct streams are tagged, whatever the compression of their channel
+        for channel in [Channel::Consensus, Channel::ProposalParts] {
+            let tagged = compressor.compress_direct(channel, data.clone());
+            assert_eq!(compressor.decompress_direct(tagged).unwrap(), data);
+        }
+
+        let response = compressor.compress_response(data.clone());
+        assert!(response.len() < data.len());
+        assert_eq!(compressor.decompress_response(response).unwrap(), data);
+    }
+
+    #[test]
+    fn send_uncompressed_channels_as_is() {
+        let compressor = compressor(CompressionConfig::default());
+
+        // Messages on channels without compression are never inspected, whatever their first byte
+        for message in [
+            &b""[..],
+            b"\x00",
+            b"\x01\x02",
+            b"\x07\x42",
+            b"{\"height\":1}",
+        ] {
+            let message = Bytes::from_static(message);
+
+            assert_eq!(
+                compressor.compress(Channel::Consensus, message.clone()),
+                message
+            );
+            assert_eq!(
+                compressor
+                    .decompress(Channel::Consensus, message.clone())
+                    .unwrap(),
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_oversized_messages() {
+        let compressor = Compressor::new(
+            CompressionConfig {
+                sync: Compression::Lz4,
+                ..Default::default()
+            },
+            100,
+            100,
+            &mut Registry::default(),
+        );
+
+        let compressed = compressor.compress(Channel::Sync, Bytes::from(vec![0; 1000]));
+
+        assert!(matches!(
+            compressor.decompress(Channel::Sync, compressed),
+            Err(DecompressionError::TooLarge { size: 1000, .. })
+        ));
+
+        assert!(matches!(
+            compressor.decompress(Channel::Sync, Bytes::from_static(&[0x42, 0])),
+            Err(DecompressionError::UnknownAlgorithm(0x42))
+        ));
+
+        assert!(matches!(
+            compressor.decompress_direct(Bytes::new()),
+            Err(DecompressionError::MissingAlgorithm)
+        ));
+    }
+}
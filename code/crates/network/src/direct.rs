@@ -0,0 +1,766 @@
+//! Direct streams between peers, bypassing gossip.
+//!
+//! A single long-lived outbound stream is opened to every connected peer supporting the protocol,
+//! on which messages are sent one after the other, so that they are delivered in order.
+//! Messages are only published to the validators, ie. the persistent peers, and are not forwarded.
+//! This is therefore only suitable for small networks where every validator is directly connected
+//! to every other validator, while the other nodes catch up through sync.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use std::{fmt, io, mem};
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, FutureExt};
+use libp2p::core::transport::PortUse;
+use libp2p::core::upgrade::ReadyUpgrade;
+use libp2p::core::{ConnectedPoint, Endpoint};
+use libp2p::swarm::handler::{
+    ConnectionEvent, DialUpgradeError, FullyNegotiatedInbound, FullyNegotiatedOutbound,
+};
+use libp2p::swarm::{
+    ConnectionDenied, ConnectionHandler, ConnectionHandlerEvent, ConnectionId, FromSwarm,
+    NetworkBehaviour, NotifyHandler, Stream, StreamUpgradeError, SubstreamProtocol, THandler,
+    THandlerInEvent, THandlerOutEvent, ToSwarm,
+};
+use libp2p::{Multiaddr, PeerId, StreamProtocol};
+use tracing::{debug, trace};
+
+use malachitebft_discovery as discovery;
+
+use crate::Channel;
+
+const PROTOCOL: StreamProtocol = StreamProtocol::new("/malachitebft-direct/v1beta1");
+
+/// Maximum number of messages queued for a peer, beyond which messages to that peer are dropped
+const MAX_QUEUED_MESSAGES: usize = 64;
+
+/// Maximum time to send a message to a peer before giving up on its stream
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Maximum size of a message
+    pub max_message_size: usize,
+
+    /// Addresses of the validators, which messages are published to,
+    /// matched against the connected peers as described in [`discovery::PeerLists`]
+    pub validators: Vec<Multiaddr>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    Received(PeerId, Channel, Bytes),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SendError {
+    /// The peer is not connected, or does not support the protocol
+    NotConnected,
+    /// Too many messages are already waiting to be sent to the peer
+    Congested,
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotConnected => write!(f, "peer is not connected"),
+            Self::Congested => write!(f, "peer is not keeping up"),
+        }
+    }
+}
+
+/// Sends messages to, and receives messages from, every connected peer over direct streams.
+pub struct Behaviour {
+    config: Config,
+
+    /// Connections to each peer supporting the protocol, messages are sent over the first one
+    connections: HashMap<PeerId, Vec<ConnectionId>>,
+
+    /// Connected peers which match the address of a validator
+    validators: HashSet<PeerId>,
+
+    /// Number of messages queued on each connection which have not been sent yet
+    queued: HashMap<ConnectionId, usize>,
+
+    events: VecDeque<ToSwarm<Event, Frame>>,
+}
+
+impl fmt::Debug for Behaviour {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Behaviour")
+            .field("config", &self.config)
+            .field("connections", &self.connections)
+            .field("validators", &self.validators)
+            .field("queued", &self.queued)
+            .finish()
+    }
+}
+
+impl Behaviour {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            connections: HashMap::new(),
+            validators: HashSet::new(),
+            queued: HashMap::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Send a message to every connected validator.
+    ///
+    /// Validators which are not keeping up do not get the message,
+    /// rather than delaying the messages to the other validators.
+    pub fn publish(&mut self, channel: Channel, data: Bytes) {
+        let validators: Vec<PeerId> = self.validators.iter().copied().collect();
+
+        for peer_id in validators {
+            if let Err(e) = self.send(peer_id, channel, data.clone()) {
+                debug!(%peer_id, %channel, "Not sending message to validator: {e}");
+            }
+        }
+    }
+
+    /// Send a message to the given peer
+    pub fn send(
+        &mut self,
+        peer_id: PeerId,
+        channel: Channel,
+        data: Bytes,
+    ) -> Result<(), SendError> {
+        let Some(connection_id) = self
+            .connections
+            .get(&peer_id)
+            .and_then(|connections| connections.first())
+        else {
+            return Err(SendError::NotConnected);
+        };
+
+        let queued = self.queued.entry(*connection_id).or_default();
+        if *queued >= MAX_QUEUED_MESSAGES {
+            return Err(SendError::Congested);
+        }
+
+        *queued += 1;
+
+        self.events.push_back(ToSwarm::NotifyHandler {
+            peer_id,
+            handler: NotifyHandler::One(*connection_id),
+            event: Frame { channel, data },
+        });
+
+        Ok(())
+    }
+
+    fn add_connection(&mut self, peer_id: PeerId, connection_id: ConnectionId, addr: &Multiaddr) {
+        self.connections
+            .entry(peer_id)
+            .or_default()
+            .push(connection_id);
+
+        if self.is_validator(&peer_id, addr) {
+            self.validators.insert(peer_id);
+        }
+    }
+
+    /// Record the addresses a connected peer listens on, as reported by Identify.
+    ///
+    /// The address of an inbound connection uses an ephemeral port,
+    /// so validators dialing us can only be recognized by their listen addresses.
+    pub fn add_listen_addrs(&mut self, peer_id: PeerId, addrs: &[Multiaddr]) {
+        if !self.connections.contains_key(&peer_id) {
+            return;
+        }
+
+        if addrs.iter().any(|addr| self.is_validator(&peer_id, addr)) {
+            self.validators.insert(peer_id);
+        }
+    }
+
+    fn is_validator(&self, peer_id: &PeerId, addr: &Multiaddr) -> bool {
+        self.config
+            .validators
+            .iter()
+            .any(|pattern| discovery::matches(pattern, Some(peer_id), Some(addr)))
+    }
+
+    fn dequeue(&mut self, connection_id: ConnectionId, count: usize) {
+        if let Some(queued) = self.queued.get_mut(&connection_id) {
+            *queued = queued.saturating_sub(count);
+        }
+    }
+
+    fn remove_connection(&mut self, peer_id: PeerId, connection_id: ConnectionId) {
+        if let Some(connections) = self.connections.get_mut(&peer_id) {
+            connections.retain(|id| *id != connection_id);
+
+            if connections.is_empty() {
+                self.connections.remove(&peer_id);
+                self.validators.remove(&peer_id);
+            }
+        }
+
+        self.queued.remove(&connection_id);
+    }
+}
+
+impl NetworkBehaviour for Behaviour {
+    type ConnectionHandler = Handler;
+    type ToSwarm = Event;
+
+    fn handle_established_inbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        _peer: PeerId,
+        _local_addr: &Multiaddr,
+        _remote_addr: &Multiaddr,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        Ok(Handler::new(self.config.max_message_size))
+    }
+
+    fn handle_established_outbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        _peer: PeerId,
+        _addr: &Multiaddr,
+        _role_override: Endpoint,
+        _port_use: PortUse,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        Ok(Handler::new(self.config.max_message_size))
+    }
+
+    fn on_swarm_event(&mut self, event: FromSwarm) {
+        match event {
+            FromSwarm::ConnectionEstablished(c) => {
+                let addr = match c.endpoint {
+                    ConnectedPoint::Dialer { address, .. } => address,
+                    ConnectedPoint::Listener { send_back_addr, .. } => send_back_addr,
+                };
+
+                self.add_connection(c.peer_id, c.connection_id, addr);
+            }
+            FromSwarm::ConnectionClosed(c) => {
+                self.remove_connection(c.peer_id, c.connection_id);
+            }
+            _ => {}
+        }
+    }
+
+    fn on_connection_handler_event(
+        &mut self,
+        peer_id: PeerId,
+        connection_id: ConnectionId,
+        event: THandlerOutEvent<Self>,
+    ) {
+        match event {
+            HandlerEvent::Received(channel, data) => {
+                self.events
+                    .push_back(ToSwarm::GenerateEvent(Event::Received(
+                        peer_id, channel, data,
+                    )));
+            }
+            HandlerEvent::Sent => {
+                self.dequeue(connection_id, 1);
+            }
+            HandlerEvent::Dropped(count) => {
+                debug!(%peer_id, "Dropped {count} messages which could not be sent");
+                self.dequeue(connection_id, count);
+            }
+            HandlerEvent::Unsupported => {
+                trace!(%peer_id, "Peer does not support direct streams");
+                self.remove_connection(peer_id, connection_id);
+            }
+        }
+    }
+
+    fn poll(
+        &mut self,
+        _cx: &mut Context<'_>,
+    ) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
+        match self.events.pop_front() {
+            Some(event) => Poll::Ready(event),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// A message sent over a direct stream
+#[derive(Clone, Debug)]
+pub struct Frame {
+    channel: Channel,
+    data: Bytes,
+}
+
+/// Transmission between the [`Handler`] and the [`Behaviour`]
+#[derive(Debug)]
+pub enum HandlerEvent {
+    /// We received a message from the remote
+    Received(Channel, Bytes),
+    /// We sent a message to the remote
+    Sent,
+    /// We could not send the given number of messages to the remote
+    Dropped(usize),
+    /// The remote does not support the protocol
+    Unsupported,
+}
+
+enum Outbound {
+    /// No stream has been opened yet, or the previous one failed
+    Idle,
+    /// Waiting for the stream to be negotiated
+    Opening,
+    /// Ready to send the next message
+    Ready(Stream),
+    /// Sending a message
+    Sending(BoxFuture<'static, io::Result<Stream>>),
+    /// The remote does not support the protocol
+    Unsupported,
+}
+
+type Inbound = BoxFuture<'static, (Stream, io::Result<Frame>)>;
+
+pub struct Handler {
+    max_message_size: usize,
+    outbound: Outbound,
+    inbound: Option<Inbound>,
+    queue: VecDeque<Frame>,
+    events: VecDeque<HandlerEvent>,
+}
+
+impl Handler {
+    fn new(max_message_size: usize) -> Self {
+        Self {
+            max_message_size,
+            outbound: Outbound::Idle,
+            inbound: None,
+            queue: VecDeque::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    fn drop_queue(&mut self) {
+        if !self.queue.is_empty() {
+            self.events
+                .push_back(HandlerEvent::Dropped(self.queue.len()));
+            self.queue.clear();
+        }
+    }
+
+    fn on_dial_upgrade_error(&mut self, error: StreamUpgradeError<impl fmt::Debug>) {
+        if let StreamUpgradeError::NegotiationFailed = error {
+            self.outbound = Outbound::Unsupported;
+            self.events.push_back(HandlerEvent::Unsupported);
+        } else {
+            debug!("Failed to open direct stream: {error:?}");
+            self.outbound = Outbound::Idle;
+        }
+
+        self.drop_queue();
+    }
+}
+
+impl ConnectionHandler for Handler {
+    type FromBehaviour = Frame;
+    type ToBehaviour = HandlerEvent;
+    type InboundProtocol = ReadyUpgrade<StreamProtocol>;
+    type OutboundProtocol = ReadyUpgrade<StreamProtocol>;
+    type InboundOpenInfo = ();
+    type OutboundOpenInfo = ();
+
+    fn listen_protocol(&self) -> SubstreamProtocol<Self::InboundProtocol, Self::InboundOpenInfo> {
+        SubstreamProtocol::new(ReadyUpgrade::new(PROTOCOL), ())
+    }
+
+    fn poll(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<ConnectionHandlerEvent<Self::OutboundProtocol, (), Self::ToBehaviour>> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(event));
+            }
+
+            if let Some(Poll::Ready((stream, result))) =
+                self.inbound.as_mut().map(|inbound| inbound.poll_unpin(cx))
+            {
+                match result {
+                    Ok(frame) => {
+                        self.inbound = Some(recv(stream, self.max_message_size).boxed());
+                        self.events
+                            .push_back(HandlerEvent::Received(frame.channel, frame.data));
+                    }
+                    Err(e) => {
+                        if e.kind() != io::ErrorKind::UnexpectedEof {
+                            debug!("Failed to receive message over direct stream: {e}");
+                        }
+
+                        self.inbound = None;
+                    }
+                }
+
+                continue;
+            }
+
+            match mem::replace(&mut self.outbound, Outbound::Idle) {
+                Outbound::Idle if !self.queue.is_empty() => {
+                    self.outbound = Outbound::Opening;
+
+                    return Poll::Ready(ConnectionHandlerEvent::OutboundSubstreamRequest {
+                        protocol: SubstreamProtocol::new(ReadyUpgrade::new(PROTOCOL), ()),
+                    });
+                }
+
+                Outbound::Ready(stream) => match self.queue.pop_front() {
+                    Some(frame) => {
+                        self.outbound = Outbound::Sending(send(stream, frame).boxed());
+                        continue;
+                    }
+                    None => {
+                        self.outbound = Outbound::Ready(stream);
+                    }
+                },
+
+                Outbound::Sending(mut sending) => match sending.poll_unpin(cx) {
+                    Poll::Ready(Ok(stream)) => {
+                        self.outbound = Outbound::Ready(stream);
+                        self.events.push_back(HandlerEvent::Sent);
+                        continue;
+                    }
+                    Poll::Ready(Err(e)) => {
+                        // Give up on the stream and the messages queued behind,
+                        // a new stream will be opened for the next messages.
+                        debug!("Failed to send message over direct stream: {e}");
+                        self.events.push_back(HandlerEvent::Dropped(1));
+                        self.drop_queue();
+                        continue;
+                    }
+                    Poll::Pending => {
+                        self.outbound = Outbound::Sending(sending);
+                    }
+                },
+
+                outbound => {
+                    self.outbound = outbound;
+                }
+            }
+
+            return Poll::Pending;
+        }
+    }
+
+    fn on_behaviour_event(&mut self, frame: Frame) {
+        if let Outbound::Unsupported = self.outbound {
+            self.events.push_back(HandlerEvent::Dropped(1));
+        } else {
+            self.queue.push_back(frame);
+        }
+    }
+
+    fn on_connection_event(
+        &mut self,
+        event: ConnectionEvent<Self::InboundProtocol, Self::OutboundProtocol, (), ()>,
+    ) {
+        match event {
+            ConnectionEvent::FullyNegotiatedInbound(FullyNegotiatedInbound {
+                protocol: stream,
+                ..
+            }) => {
+                // The remote opened a new stream, the previous one is not used anymore
+                self.inbound = Some(recv(stream, self.max_message_size).boxed());
+            }
+            ConnectionEvent::FullyNegotiatedOutbound(FullyNegotiatedOutbound {
+                protocol: stream,
+                ..
+            }) => {
+                self.outbound = Outbound::Ready(stream);
+            }
+            ConnectionEvent::DialUpgradeError(DialUpgradeError { error, .. }) => {
+                self.on_dial_upgrade_error(error);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn channel_to_tag(channel: Channel) -> u8 {
+    match channel {
+        Channel::Consensus => 0,
+        Channel::ProposalParts => 1,
+        Channel::Sync => 2,
+    }
+}
+
+fn channel_from_tag(tag: u8) -> Option<Channel> {
+    match tag {
+        0 => Some(Channel::Consensus),
+        1 => Some(Channel::ProposalParts),
+        2 => Some(Channel::Sync),
+        _ => None,
+    }
+}
+
+/// Write a frame as its length, its channel tag and its data
+async fn write_frame(stream: &mut (impl AsyncWrite + Unpin), frame: &Frame) -> io::Result<()> {
+    let len = u32::try_from(frame.data.len() + 1)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "message too large"))?;
+
+    stream.write_all(&len.to_be_bytes()).await?;
+    stream.write_all(&[channel_to_tag(frame.channel)]).await?;
+    stream.write_all(&frame.data).await?;
+    stream.flush().await
+}
+
+async fn read_frame(
+    stream: &mut (impl AsyncRead + Unpin),
+    max_message_size: usize,
+) -> io::Result<Frame> {
+    let mut len = [0; 4];
+    stream.read_exact(&mut len).await?;
+
+    let len = u32::from_be_bytes(len) as usize;
+    if len == 0 || len > max_message_size + 1 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid message length: {len}"),
+        ));
+    }
+
+    let mut buf = vec![0; len];
+    stream.read_exact(&mut buf).await?;
+
+    let channel = channel_from_tag(buf[0]).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unknown channel: {}", buf[0]),
+        )
+    })?;
+
+    let mut data = Bytes::from(buf);
+    Ok(Frame {
+        channel,
+        data: data.split_off(1),
+    })
+}
+
+async fn send(mut stream: Stream, frame: Frame) -> io::Result<Stream> {
+    match tokio::time::timeout(SEND_TIMEOUT, write_frame(&mut stream, &frame)).await {
+        Ok(result) => result.map(|()| stream),
+        Err(_) => Err(io::Error::new(io::ErrorKind::TimedOut, "send timed out")),
+    }
+}
+
+async fn recv(mut stream: Stream, max_message_size: usize) -> (Stream, io::Result<Frame>) {
+    let result = read_frame(&mut stream, max_message_size).await;
+    (stream, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::io::Cursor;
+
+    use super::*;
+
+    fn frame(channel: Channel, data: &'static [u8]) -> Frame {
+        Frame {
+            channel,
+            data: Bytes::from_static(data),
+        }
+    }
+
+    fn addr(s: &str) -> Multiaddr {
+        s.parse().unwrap()
+    }
+
+    /// Peers notified of a message, in order
+    fn notified(behaviour: &mut Behaviour) -> Vec<PeerId> {
+        behaviour
+            .events
+            .drain(..)
+            .filter_map(|event| match event {
+                ToSwarm::NotifyHandler { peer_id, .. } => Some(peer_id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn frames_roundtrip() {
+        let mut stream = Cursor::new(Vec::new());
+        write_frame(&mut stream, &frame(Channel::Consensus, b"vote"))
+            .await
+            .unwrap();
+        write_frame(&mut stream, &frame(Channel::ProposalParts, b""))
+            .await
+            .unwrap();
+
+        stream.set_position(0);
+
+        let received = read_frame(&mut stream, 16).await.unwrap();
+        assert_eq!(received.channel, Channel::Consensus);
+        assert_eq!(received.data, Bytes::from_static(b"vote"));
+
+        let received = read_frame(&mut stream, 16).await.unwrap();
+        assert_eq!(received.channel, Channel::ProposalParts);
+        assert!(received.data.is_empty());
+
+        let error = read_frame(&mut stream, 16).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[tokio::test]
+    async fn invalid_frames_are_rejected() {
+        let mut stream = Cursor::new(Vec::new());
+        write_frame(&mut stream, &frame(Channel::Consensus, &[0; 17]))
+            .await
+            .unwrap();
+        stream.set_position(0);
+
+        let error = read_frame(&mut stream, 16).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let mut empty = Cursor::new(vec![0, 0, 0, 0]);
+        let error = read_frame(&mut empty, 16).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let mut unknown_channel = Cursor::new(vec![0, 0, 0, 2, 9, 0]);
+        let error = read_frame(&mut unknown_channel, 16).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn publish_to_validators_only() {
+        let validator = PeerId::random();
+        let other_validator = PeerId::random();
+        let full_node = PeerId::random();
+
+        let mut behaviour = Behaviour::new(Config {
+            max_message_size: 16,
+            validators: vec![addr(&format!("/p2p/{validator}")), addr("/ip4/10.0.0.2")],
+        });
+
+        behaviour.add_connection(
+            validator,
+            ConnectionId::new_unchecked(0),
+            &addr("/ip4/10.0.0.1/tcp/27000"),
+        );
+        behaviour.add_connection(
+            other_validator,
+            ConnectionId::new_unchecked(1),
+            &addr("/ip4/10.0.0.2/tcp/27000"),
+        );
+        behaviour.add_connection(
+            full_node,
+            ConnectionId::new_unchecked(2),
+            &addr("/ip4/10.0.0.3/tcp/27000"),
+        );
+
+        behaviour.publish(Channel::Consensus, Bytes::from_static(b"vote"));
+
+        let mut peers = notified(&mut behaviour);
+        peers.sort();
+        let mut validators = vec![validator, other_validator];
+        validators.sort();
+        assert_eq!(peers, validators);
+
+        // Validators dialing us are recognized by their listen addresses
+        let inbound_validator = PeerId::random();
+        behaviour.add_connection(
+            inbound_validator,
+            ConnectionId::new_unchecked(3),
+            &addr("/ip4/10.0.0.4/tcp/51234"),
+        );
+        behaviour.add_listen_addrs(full_node, &[addr("/ip4/10.0.0.5/tcp/27000")]);
+        behaviour.add_listen_addrs(inbound_validator, &[addr("/ip4/10.0.0.2/tcp/27000")]);
+
+        behaviour.publish(Channel::Consensus, Bytes::from_static(b"vote"));
+        assert_eq!(notified(&mut behaviour).len(), 3);
+
+        // Messages can still be sent to a single peer which is not a validator
+        assert_eq!(
+            behaviour.send(full_node, Channel::ProposalParts, Bytes::new()),
+            Ok(())
+        );
+        assert_eq!(notified(&mut behaviour), vec![full_node]);
+    }
+
+    #[test]
+    fn drop_messages_to_congested_peers_only() {
+        let slow_peer = PeerId::random();
+        let peer = PeerId::random();
+        let connection_id = ConnectionId::new_unchecked(0);
+
+        let mut behaviour = Behaviour::new(Config {
+            max_message_size: 16,
+            validators: Vec::new(),
+        });
+
+        behaviour.add_connection(slow_peer, connection_id, &addr("/ip4/10.0.0.1"));
+        behaviour.add_connection(peer, ConnectionId::new_unchecked(1), &addr("/ip4/10.0.0.2"));
+
+        for _ in 0..MAX_QUEUED_MESSAGES {
+            assert_eq!(
+                behaviour.send(slow_peer, Channel::Consensus, Bytes::new()),
+                Ok(())
+            );
+        }
+
+        assert_eq!(
+            behaviour.send(slow_peer, Channel::Consensus, Bytes::new()),
+            Err(SendError::Congested)
+        );
+        assert_eq!(
+            behaviour.send(peer, Channel::Consensus, Bytes::new()),
+            Ok(())
+        );
+
+        // Once a message is sent, there is room for another one
+        behaviour.on_connection_handler_event(slow_peer, connection_id, HandlerEvent::Sent);
+        assert_eq!(
+            behaviour.send(slow_peer, Channel::Consensus, Bytes::new()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn stop_sending_to_peers_not_supporting_the_protocol() {
+        let mut handler = Handler::new(16);
+
+        handler.on_behaviour_event(frame(Channel::Consensus, b"vote"));
+        handler.on_dial_upgrade_error(StreamUpgradeError::<io::Error>::NegotiationFailed);
+
+        assert!(matches!(
+            handler.events.pop_front(),
+            Some(HandlerEvent::Unsupported)
+        ));
+        assert!(matches!(
+            handler.events.pop_front(),
+            Some(HandlerEvent::Dropped(1))
+        ));
+
+        // Messages sent afterwards are dropped right away
+        handler.on_behaviour_event(frame(Channel::Consensus, b"vote"));
+        assert!(matches!(
+            handler.events.pop_front(),
+            Some(HandlerEvent::Dropped(1))
+        ));
+
+        let peer = PeerId::random();
+        let connection_id = ConnectionId::new_unchecked(0);
+
+        let mut behaviour = Behaviour::new(Config {
+            max_message_size: 16,
+            validators: Vec::new(),
+        });
+
+        behaviour.add_connection(peer, connection_id, &addr("/ip4/10.0.0.1"));
+        behaviour.on_connection_handler_event(peer, connection_id, HandlerEvent::Unsupported);
+
+        assert_eq!(
+            behaviour.send(peer, Channel::Consensus, Bytes::new()),
+            Err(SendError::NotConnected)
+        );
+    }
+}
@@ -5,7 +5,7 @@ use tokio::task;
 
 use malachitebft_peer::PeerId;
 
-use crate::{Channel, CtrlMsg, Event};
+use crate::{Channel, CtrlMsg, Event, MessageAcceptance, MessageId};
 
 pub struct RecvHandle {
     peer_id: PeerId,
@@ -43,6 +43,32 @@ impl CtrlHandle {
         Ok(())
     }
 
+    /// Send a message to a single peer, over a direct stream
+    pub async fn send(
+        &self,
+        peer_id: PeerId,
+        channel: Channel,
+        data: Bytes,
+    ) -> Result<(), eyre::Report> {
+        self.tx_ctrl
+            .send(CtrlMsg::Send(peer_id, channel, data))
+            .await?;
+        Ok(())
+    }
+
+    /// Report whether a message received over GossipSub is valid, which decides whether
+    /// GossipSub forwards it to other peers and how it scores the peer which sent it.
+    pub async fn report_validation(
+        &self,
+        message_id: MessageId,
+        acceptance: MessageAcceptance,
+    ) -> Result<(), eyre::Report> {
+        self.tx_ctrl
+            .send(CtrlMsg::ReportValidation(message_id, acceptance))
+            .await?;
+        Ok(())
+    }
+
     pub async fn sync_request(
         &self,
         peer_id: PeerId,
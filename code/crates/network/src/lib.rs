@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use std::error::Error;
 use std::ops::ControlFlow;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use futures::StreamExt;
 use libp2p::metrics::{Metrics, Recorder};
@@ -12,6 +12,7 @@ use libp2p_broadcast as broadcast;
 use tokio::sync::{mpsc, oneshot};
 use tracing::{debug, error, error_span, trace, warn, Instrument};
 
+use malachitebft_config::RateLimitsConfig;
 use malachitebft_discovery::{self as discovery};
 use malachitebft_metrics::SharedRegistry;
 use malachitebft_sync::{self as sync};
@@ -19,17 +20,32 @@ use malachitebft_sync::{self as sync};
 pub use malachitebft_peer::PeerId;
 
 pub use bytes::Bytes;
-pub use libp2p::gossipsub::MessageId;
+pub use libp2p::gossipsub::{MessageAcceptance, MessageId};
 pub use libp2p::identity::Keypair;
 pub use libp2p::Multiaddr;
 
 pub mod behaviour;
+pub mod direct;
 pub mod handle;
 pub mod pubsub;
 
 mod channel;
 pub use channel::Channel;
 
+mod rate_limit;
+use rate_limit::RateLimiter;
+
+mod validation;
+use validation::{PendingValidations, VALIDATION_TIMEOUT};
+
+mod compression;
+use compression::Compressor;
+pub use compression::{Compression, CompressionConfig};
+
+mod nat;
+pub use nat::NatConfig;
+use nat::RelayListeners;
+
 use behaviour::{Behaviour, NetworkEvent};
 use handle::Handle;
 
@@ -37,6 +53,9 @@ const PROTOCOL: &str = "/malachitebft-core-consensus/v1beta1";
 const METRICS_PREFIX: &str = "malachitebft_network";
 const DISCOVERY_METRICS_PREFIX: &str = "malachitebft_discovery";
 
+/// Interval at which the penalties of the peers having exceeded their rate limits decay
+const PENALTY_DECAY_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(Copy, Clone, Debug, Default)]
 pub enum PubSubProtocol {
     /// GossipSub: a pubsub protocol based on epidemic broadcast trees
@@ -45,6 +64,10 @@ pub enum PubSubProtocol {
 
     /// Broadcast: a simple broadcast protocol
     Broadcast,
+
+    /// Direct: a dedicated stream to every connected peer, with ordered delivery and backpressure.
+    /// Messages are not forwarded, every node must thus be directly connected to every validator.
+    Direct,
 }
 
 impl PubSubProtocol {
@@ -55,6 +78,10 @@ impl PubSubProtocol {
     pub fn is_broadcast(&self) -> bool {
         matches!(self, Self::Broadcast)
     }
+
+    pub fn is_direct(&self) -> bool {
+        matches!(self, Self::Direct)
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -82,21 +109,38 @@ pub type BoxError = Box<dyn Error + Send + Sync + 'static>;
 pub type DiscoveryConfig = discovery::Config;
 pub type BootstrapProtocol = discovery::config::BootstrapProtocol;
 pub type Selector = discovery::config::Selector;
+pub type PeerLists = discovery::PeerLists;
+pub type AddressBookConfig = discovery::AddressBookConfig;
 
 #[derive(Clone, Debug)]
 pub struct Config {
-    pub listen_addr: Multiaddr,
+    /// Addresses to listen for incoming connections on, with any of the enabled transports
+    pub listen_addrs: Vec<Multiaddr>,
     pub persistent_peers: Vec<Multiaddr>,
+    pub peer_lists: PeerLists,
     pub discovery: DiscoveryConfig,
+    /// Where to persist the peers this node connects to, to seed discovery after a restart
+    pub address_book: Option<AddressBookConfig>,
     pub idle_connection_timeout: Duration,
     pub transport: TransportProtocol,
     pub gossipsub: GossipSubConfig,
     pub pubsub_protocol: PubSubProtocol,
     pub rpc_max_size: usize,
     pub pubsub_max_size: usize,
+    pub rate_limits: RateLimitsConfig,
+    pub compression: CompressionConfig,
+    pub nat: NatConfig,
+    /// Whether to open direct streams to peers, to send messages to a single peer,
+    /// eg. the chunks of erasure-coded proposal parts. Always enabled with [`PubSubProtocol::Direct`].
+    pub direct_streams: bool,
 }
 
 impl Config {
+    /// Whether direct streams are opened to peers, see [`Config::direct_streams`]
+    pub fn has_direct_streams(&self) -> bool {
+        self.direct_streams || self.pubsub_protocol.is_direct()
+    }
+
     fn apply_to_swarm(&self, cfg: swarm::Config) -> swarm::Config {
         cfg.with_idle_connection_timeout(self.idle_connection_timeout)
     }
@@ -115,6 +159,8 @@ impl Config {
 pub enum TransportProtocol {
     Tcp,
     Quic,
+    /// Both TCP and QUIC, dialing peers over QUIC first and falling back to TCP
+    Both,
 }
 
 /// sync event details:
@@ -133,7 +179,7 @@ pub enum Event {
     Listening(Multiaddr),
     PeerConnected(PeerId),
     PeerDisconnected(PeerId),
-    Message(Channel, PeerId, Bytes),
+    Message(Channel, PeerId, MessageId, Bytes),
     Sync(sync::RawMessage),
 }
 
@@ -141,6 +187,8 @@ pub enum Event {
 pub enum CtrlMsg {
     Publish(Channel, Bytes),
     Broadcast(Channel, Bytes),
+    Send(PeerId, Channel, Bytes),
+    ReportValidation(MessageId, MessageAcceptance),
     SyncRequest(PeerId, Bytes, oneshot::Sender<OutboundRequestId>),
     SyncReply(InboundRequestId, Bytes),
     Shutdown,
@@ -148,15 +196,31 @@ pub enum CtrlMsg {
 
 #[derive(Debug)]
 pub struct State {
-    pub sync_channels: HashMap<InboundRequestId, sync::ResponseChannel>,
+    /// Channels to respond to the Sync requests, along with whether the response must be compressed
+    pub sync_channels: HashMap<InboundRequestId, (sync::ResponseChannel, bool)>,
     pub discovery: discovery::Discovery<Behaviour>,
+    pub rate_limiter: RateLimiter,
+    pub compressor: Compressor,
+    relay_listeners: RelayListeners,
+
+    /// Peers which forwarded the GossipSub messages awaiting validation
+    pub pending_validations: PendingValidations,
 }
 
 impl State {
-    fn new(discovery: discovery::Discovery<Behaviour>) -> Self {
+    fn new(
+        discovery: discovery::Discovery<Behaviour>,
+        rate_limits: RateLimitsConfig,
+        compressor: Compressor,
+        relays: Vec<Multiaddr>,
+    ) -> Self {
         Self {
             sync_channels: Default::default(),
             discovery,
+            rate_limiter: RateLimiter::new(rate_limits),
+            compressor,
+            relay_listeners: RelayListeners::new(relays),
+            pending_validations: Default::default(),
         }
     }
 }
@@ -176,15 +240,36 @@ pub async fn spawn(
                     libp2p::yamux::Config::default,
                 )?
                 .with_dns()?
+                .with_relay_client(libp2p::noise::Config::new, libp2p::yamux::Config::default)?
                 .with_bandwidth_metrics(registry)
-                .with_behaviour(|kp| Behaviour::new_with_metrics(&config, kp, registry))?
+                .with_behaviour(|kp, relay_client| {
+                    Behaviour::new_with_metrics(&config, kp, relay_client, registry)
+                })?
                 .with_swarm_config(|cfg| config.apply_to_swarm(cfg))
                 .build()),
             TransportProtocol::Quic => Ok(builder
                 .with_quic_config(|cfg| config.apply_to_quic(cfg))
                 .with_dns()?
+                .with_relay_client(libp2p::noise::Config::new, libp2p::yamux::Config::default)?
                 .with_bandwidth_metrics(registry)
-                .with_behaviour(|kp| Behaviour::new_with_metrics(&config, kp, registry))?
+                .with_behaviour(|kp, relay_client| {
+                    Behaviour::new_with_metrics(&config, kp, relay_client, registry)
+                })?
+                .with_swarm_config(|cfg| config.apply_to_swarm(cfg))
+                .build()),
+            TransportProtocol::Both => Ok(builder
+                .with_tcp(
+                    libp2p::tcp::Config::new().nodelay(true), // Disable Nagle's algorithm
+                    libp2p::noise::Config::new,
+                    libp2p::yamux::Config::default,
+                )?
+                .with_quic_config(|cfg| config.apply_to_quic(cfg))
+                .with_dns()?
+                .with_relay_client(libp2p::noise::Config::new, libp2p::yamux::Config::default)?
+                .with_bandwidth_metrics(registry)
+                .with_behaviour(|kp, relay_client| {
+                    Behaviour::new_with_metrics(&config, kp, relay_client, registry)
+                })?
                 .with_swarm_config(|cfg| config.apply_to_swarm(cfg))
                 .build()),
         }
@@ -195,11 +280,42 @@ pub async fn spawn(
     let (tx_event, rx_event) = mpsc::channel(32);
     let (tx_ctrl, rx_ctrl) = mpsc::channel(32);
 
+    // Stay connected to the relays, the reservations are lost with the connections
+    let mut peer_lists = config.peer_lists.clone();
+    for relay in &config.nat.relays {
+        if !peer_lists.unconditional.contains(relay) {
+            peer_lists.unconditional.push(relay.clone());
+        }
+    }
+
     let discovery = registry.with_prefix(DISCOVERY_METRICS_PREFIX, |reg| {
-        discovery::Discovery::new(config.discovery, config.persistent_peers.clone(), reg)
+        discovery::Discovery::new(
+            discovery::Config {
+                tcp_fallback: config.transport == TransportProtocol::Both,
+                ..config.discovery
+            },
+            config.persistent_peers.clone(),
+            peer_lists,
+            discovery::AddressBook::load(config.address_book.clone()),
+            reg,
+        )
+    });
+
+    let compressor = registry.with_prefix(METRICS_PREFIX, |registry| {
+        Compressor::new(
+            config.compression,
+            config.pubsub_max_size,
+            config.rpc_max_size,
+            registry,
+        )
     });
 
-    let state = State::new(discovery);
+    let state = State::new(
+        discovery,
+        config.rate_limits,
+        compressor,
+        config.nat.relays.clone(),
+    );
 
     let peer_id = PeerId::from_libp2p(swarm.local_peer_id());
     let span = error_span!("network", peer = %peer_id);
@@ -217,23 +333,41 @@ async fn run(
     mut rx_ctrl: mpsc::Receiver<CtrlMsg>,
     tx_event: mpsc::Sender<Event>,
 ) {
-    if let Err(e) = swarm.listen_on(config.listen_addr.clone()) {
-        error!("Error listening on {}: {e}", config.listen_addr);
-        return;
+    for listen_addr in &config.listen_addrs {
+        if let Err(e) = swarm.listen_on(listen_addr.clone()) {
+            error!("Error listening on {listen_addr}: {e}");
+            return;
+        }
     }
 
     state.discovery.dial_bootstrap_nodes(&swarm);
-
-    if let Err(e) = pubsub::subscribe(&mut swarm, config.pubsub_protocol, Channel::consensus()) {
+    state.discovery.dial_unconditional_peers(&swarm);
+
+    if let Err(e) = pubsub::subscribe(
+        &mut swarm,
+        config.pubsub_protocol,
+        &config.compression,
+        Channel::consensus(),
+    ) {
         error!("Error subscribing to consensus channels: {e}");
         return;
     };
 
-    if let Err(e) = pubsub::subscribe(&mut swarm, PubSubProtocol::Broadcast, &[Channel::Sync]) {
+    if let Err(e) = pubsub::subscribe(
+        &mut swarm,
+        PubSubProtocol::Broadcast,
+        &config.compression,
+        &[Channel::Sync],
+    ) {
         error!("Error subscribing to Sync channel: {e}");
         return;
     };
 
+    let mut penalty_decay = tokio::time::interval(PENALTY_DECAY_INTERVAL);
+    let mut validation_expiry = tokio::time::interval(VALIDATION_TIMEOUT / 5);
+    let mut relay_listen = tokio::time::interval(nat::RELAY_LISTEN_INTERVAL);
+    let mut address_book_save = tokio::time::interval(discovery::AddressBook::SAVE_INTERVAL);
+
     loop {
         let result = tokio::select! {
             event = swarm.select_next_some() => {
@@ -263,6 +397,40 @@ async fn run(
             Some(ctrl) = rx_ctrl.recv() => {
                 handle_ctrl_msg(&mut swarm, &mut state, &config, ctrl).await
             }
+
+            _ = penalty_decay.tick() => {
+                for (peer_id, score) in state.rate_limiter.decay_penalties() {
+                    swarm.behaviour_mut().gossipsub.set_application_score(&peer_id, score);
+                }
+
+                ControlFlow::Continue(())
+            }
+
+            // Ignore the messages which were never validated, eg. votes for a height
+            // consensus is not at, or parts of a proposal which could not be rebuilt
+            _ = validation_expiry.tick() => {
+                for (message_id, peer_id) in state.pending_validations.expire(Instant::now()) {
+                    trace!(%message_id, %peer_id, "Ignoring message not validated in time");
+
+                    let _ = swarm.behaviour_mut().gossipsub.report_message_validation_result(
+                        &message_id,
+                        &peer_id,
+                        MessageAcceptance::Ignore,
+                    );
+                }
+
+                ControlFlow::Continue(())
+            }
+
+            _ = relay_listen.tick(), if !config.nat.relays.is_empty() => {
+                state.relay_listeners.listen(&mut swarm);
+                ControlFlow::Continue(())
+            }
+
+            _ = address_book_save.tick() => {
+                state.discovery.flush_address_book();
+                ControlFlow::Continue(())
+            }
         };
 
         match result {
@@ -270,6 +438,8 @@ async fn run(
             ControlFlow::Break(()) => break,
         }
     }
+
+    state.discovery.flush_address_book();
 }
 
 async fn handle_ctrl_msg(
@@ -281,7 +451,19 @@ async fn handle_ctrl_msg(
     match msg {
         CtrlMsg::Publish(channel, data) => {
             let msg_size = data.len();
-            let result = pubsub::publish(swarm, config.pubsub_protocol, channel, data);
+            let data = if config.pubsub_protocol.is_direct() {
+                state.compressor.compress_direct(channel, data)
+            } else {
+                state.compressor.compress(channel, data)
+            };
+
+            let result = pubsub::publish(
+                swarm,
+                config.pubsub_protocol,
+                &config.compression,
+                channel,
+                data,
+            );
 
             match result {
                 Ok(()) => debug!(%channel, size = %msg_size, "Published message"),
@@ -293,7 +475,14 @@ async fn handle_ctrl_msg(
 
         CtrlMsg::Broadcast(channel, data) => {
             let msg_size = data.len();
-            let result = pubsub::publish(swarm, PubSubProtocol::Broadcast, channel, data);
+            let data = state.compressor.compress(channel, data);
+            let result = pubsub::publish(
+                swarm,
+                PubSubProtocol::Broadcast,
+                &config.compression,
+                channel,
+                data,
+            );
 
             match result {
                 Ok(()) => debug!(%channel, size = %msg_size, "Broadcasted message"),
@@ -303,6 +492,40 @@ async fn handle_ctrl_msg(
             ControlFlow::Continue(())
         }
 
+        CtrlMsg::Send(peer_id, channel, data) => {
+            let msg_size = data.len();
+            let data = state.compressor.compress_direct(channel, data);
+
+            let Some(direct) = swarm.behaviour_mut().direct.as_mut() else {
+                error!(%peer_id, %channel, "Error sending message: direct streams are disabled");
+                return ControlFlow::Continue(());
+            };
+
+            match direct.send(peer_id.to_libp2p(), channel, data) {
+                Ok(()) => debug!(%peer_id, %channel, size = %msg_size, "Sent message"),
+                Err(e) => error!(%peer_id, %channel, "Error sending message: {e}"),
+            }
+
+            ControlFlow::Continue(())
+        }
+
+        CtrlMsg::ReportValidation(message_id, acceptance) => {
+            // Only GossipSub messages await validation
+            if let Some(peer_id) = state.pending_validations.remove(&message_id) {
+                trace!(%message_id, %peer_id, ?acceptance, "Reporting message validation result");
+
+                if let Err(e) = swarm
+                    .behaviour_mut()
+                    .gossipsub
+                    .report_message_validation_result(&message_id, &peer_id, acceptance)
+                {
+                    error!(%message_id, "Error reporting message validation result: {e}");
+                }
+            }
+
+            ControlFlow::Continue(())
+        }
+
         CtrlMsg::SyncRequest(peer_id, request, reply_to) => {
             let request_id = swarm
                 .behaviour_mut()
@@ -317,11 +540,17 @@ async fn handle_ctrl_msg(
         }
 
         CtrlMsg::SyncReply(request_id, data) => {
-            let Some(channel) = state.sync_channels.remove(&request_id) else {
+            let Some((channel, compressed)) = state.sync_channels.remove(&request_id) else {
                 error!(%request_id, "Received Sync reply for unknown request ID");
                 return ControlFlow::Continue(());
             };
 
+            let data = if compressed {
+                state.compressor.compress_response(data)
+            } else {
+                data
+            };
+
             let result = swarm.behaviour_mut().sync.send_response(channel, data);
 
             match result {
@@ -338,7 +567,7 @@ async fn handle_ctrl_msg(
 
 async fn handle_swarm_event(
     event: SwarmEvent<NetworkEvent>,
-    _config: &Config,
+    config: &Config,
     metrics: &Metrics,
     swarm: &mut swarm::Swarm<Behaviour>,
     state: &mut State,
@@ -354,12 +583,27 @@ async fn handle_swarm_event(
         SwarmEvent::NewListenAddr { address, .. } => {
             debug!(%address, "Node is listening");
 
+            if config.nat.relay_server {
+                nat::add_relay_server_address(swarm, &address);
+            }
+
             if let Err(e) = tx_event.send(Event::Listening(address)).await {
                 error!("Error sending listening event to handle: {e}");
                 return ControlFlow::Break(());
             }
         }
 
+        SwarmEvent::ListenerClosed {
+            listener_id,
+            ref addresses,
+            ref reason,
+        } => {
+            debug!(?addresses, ?reason, "Listener closed");
+
+            state.relay_listeners.on_listener_closed(listener_id);
+            metrics.record(&event);
+        }
+
         SwarmEvent::ConnectionEstablished {
             peer_id,
             connection_id,
@@ -371,6 +615,8 @@ async fn handle_swarm_event(
             state
                 .discovery
                 .handle_connection(swarm, peer_id, connection_id, endpoint);
+
+            state.relay_listeners.listen(swarm);
         }
 
         SwarmEvent::OutgoingConnectionError {
@@ -388,6 +634,7 @@ async fn handle_swarm_event(
         SwarmEvent::ConnectionClosed {
             peer_id,
             connection_id,
+            num_established,
             cause,
             ..
         } => {
@@ -401,6 +648,10 @@ async fn handle_swarm_event(
                 .discovery
                 .handle_closed_connection(swarm, peer_id, connection_id);
 
+            if num_established == 0 {
+                state.rate_limiter.remove_peer(&peer_id);
+            }
+
             if let Err(e) = tx_event
                 .send(Event::PeerDisconnected(PeerId::from_libp2p(&peer_id)))
                 .await
@@ -432,6 +683,10 @@ async fn handle_swarm_event(
                     info.protocol_version
                 );
 
+                if let Some(direct) = swarm.behaviour_mut().direct.as_mut() {
+                    direct.add_listen_addrs(peer_id, &info.listen_addrs);
+                }
+
                 state
                     .discovery
                     .handle_new_peer(swarm, connection_id, peer_id, info);
@@ -466,11 +721,15 @@ async fn handle_swarm_event(
         }
 
         SwarmEvent::Behaviour(NetworkEvent::GossipSub(event)) => {
-            return handle_gossipsub_event(event, metrics, swarm, state, tx_event).await;
+            return handle_gossipsub_event(event, config, metrics, swarm, state, tx_event).await;
         }
 
         SwarmEvent::Behaviour(NetworkEvent::Broadcast(event)) => {
-            return handle_broadcast_event(event, metrics, swarm, state, tx_event).await;
+            return handle_broadcast_event(event, config, metrics, swarm, state, tx_event).await;
+        }
+
+        SwarmEvent::Behaviour(NetworkEvent::Direct(event)) => {
+            return handle_direct_event(event, config, metrics, swarm, state, tx_event).await;
         }
 
         SwarmEvent::Behaviour(NetworkEvent::Sync(event)) => {
@@ -481,6 +740,15 @@ async fn handle_swarm_event(
             state.discovery.on_network_event(swarm, network_event);
         }
 
+        SwarmEvent::Behaviour(
+            event @ (NetworkEvent::AutoNat(_)
+            | NetworkEvent::RelayServer(_)
+            | NetworkEvent::RelayClient(_)
+            | NetworkEvent::Dcutr(_)),
+        ) => {
+            nat::handle_event(event);
+        }
+
         swarm_event => {
             metrics.record(&swarm_event);
         }
@@ -491,14 +759,15 @@ async fn handle_swarm_event(
 
 async fn handle_gossipsub_event(
     event: gossipsub::Event,
+    config: &Config,
     _metrics: &Metrics,
-    _swarm: &mut swarm::Swarm<Behaviour>,
-    _state: &mut State,
+    swarm: &mut swarm::Swarm<Behaviour>,
+    state: &mut State,
     tx_event: &mpsc::Sender<Event>,
 ) -> ControlFlow<()> {
     match event {
         gossipsub::Event::Subscribed { peer_id, topic } => {
-            if !Channel::has_gossipsub_topic(&topic) {
+            if !Channel::has_gossipsub_topic(&topic, &config.compression) {
                 trace!("Peer {peer_id} tried to subscribe to unknown topic: {topic}");
                 return ControlFlow::Continue(());
             }
@@ -507,7 +776,7 @@ async fn handle_gossipsub_event(
         }
 
         gossipsub::Event::Unsubscribed { peer_id, topic } => {
-            if !Channel::has_gossipsub_topic(&topic) {
+            if !Channel::has_gossipsub_topic(&topic, &config.compression) {
                 trace!("Peer {peer_id} tried to unsubscribe from unknown topic: {topic}");
                 return ControlFlow::Continue(());
             }
@@ -516,34 +785,94 @@ async fn handle_gossipsub_event(
         }
 
         gossipsub::Event::Message {
+            propagation_source,
             message_id,
             message,
-            ..
         } => {
+            let gossipsub = &mut swarm.behaviour_mut().gossipsub;
+
             let Some(peer_id) = message.source else {
+                let _ = gossipsub.report_message_validation_result(
+                    &message_id,
+                    &propagation_source,
+                    MessageAcceptance::Reject,
+                );
+
                 return ControlFlow::Continue(());
             };
 
-            let Some(channel) = Channel::from_gossipsub_topic_hash(&message.topic) else {
+            let Some(channel) =
+                Channel::from_gossipsub_topic_hash(&message.topic, &config.compression)
+            else {
                 trace!(
                     "Received message {message_id} from {peer_id} on different channel: {}",
                     message.topic
                 );
 
+                let _ = gossipsub.report_message_validation_result(
+                    &message_id,
+                    &propagation_source,
+                    MessageAcceptance::Ignore,
+                );
+
                 return ControlFlow::Continue(());
             };
 
+            // Rate limit the peer which sent us the message, as it is the one costing us
+            // resources, without rejecting the message to not penalize it a second time
+            if !state
+                .rate_limiter
+                .check(propagation_source, channel, Instant::now())
+            {
+                debug!(
+                    "Dropping message {message_id} from {propagation_source} on channel {channel}: rate limit exceeded"
+                );
+
+                let _ = gossipsub.report_message_validation_result(
+                    &message_id,
+                    &propagation_source,
+                    MessageAcceptance::Ignore,
+                );
+
+                let score = state.rate_limiter.penalize(propagation_source);
+                gossipsub.set_application_score(&propagation_source, score);
+
+                return ControlFlow::Continue(());
+            }
+
             trace!(
                 "Received message {message_id} from {peer_id} on channel {channel} of {} bytes",
                 message.data.len()
             );
 
-            let event = Event::Message(
-                channel,
-                PeerId::from_libp2p(&peer_id),
-                Bytes::from(message.data),
+            let data = match state
+                .compressor
+                .decompress(channel, Bytes::from(message.data))
+            {
+                Ok(data) => data,
+                Err(e) => {
+                    debug!(
+                        "Rejecting message {message_id} from {peer_id} on channel {channel}: {e}"
+                    );
+
+                    let _ = gossipsub.report_message_validation_result(
+                        &message_id,
+                        &propagation_source,
+                        MessageAcceptance::Reject,
+                    );
+
+                    return ControlFlow::Continue(());
+                }
+            };
+
+            state.pending_validations.insert(
+                message_id.clone(),
+                propagation_source,
+                Instant::now(),
             );
 
+            let event = Event::Message(channel, PeerId::from_libp2p(&peer_id), message_id, data);
+
             if let Err(e) = tx_event.send(event).await {
                 error!("Error sending message to handle: {e}");
                 return ControlFlow::Break(());
@@ -559,14 +888,15 @@ async fn handle_gossipsub_event(
 
 async fn handle_broadcast_event(
     event: broadcast::Event,
+    config: &Config,
     _metrics: &Metrics,
     _swarm: &mut swarm::Swarm<Behaviour>,
-    _state: &mut State,
+    state: &mut State,
     tx_event: &mpsc::Sender<Event>,
 ) -> ControlFlow<()> {
     match event {
         broadcast::Event::Subscribed(peer_id, topic) => {
-            if !Channel::has_broadcast_topic(&topic) {
+            if !Channel::has_broadcast_topic(&topic, &config.compression) {
                 trace!("Peer {peer_id} tried to subscribe to unknown topic: {topic:?}");
                 return ControlFlow::Continue(());
             }
@@ -575,7 +905,7 @@ async fn handle_broadcast_event(
         }
 
         broadcast::Event::Unsubscribed(peer_id, topic) => {
-            if !Channel::has_broadcast_topic(&topic) {
+            if !Channel::has_broadcast_topic(&topic, &config.compression) {
                 trace!("Peer {peer_id} tried to unsubscribe from unknown topic: {topic:?}");
                 return ControlFlow::Continue(());
             }
@@ -584,20 +914,89 @@ async fn handle_broadcast_event(
         }
 
         broadcast::Event::Received(peer_id, topic, message) => {
-            let Some(channel) = Channel::from_broadcast_topic(&topic) else {
+            let Some(channel) = Channel::from_broadcast_topic(&topic, &config.compression) else {
                 trace!("Received message from {peer_id} on different channel: {topic:?}");
                 return ControlFlow::Continue(());
             };
 
+            if !state.rate_limiter.check(peer_id, channel, Instant::now()) {
+                debug!("Dropping message from {peer_id} on channel {channel}: rate limit exceeded");
+                return ControlFlow::Continue(());
+            }
+
             trace!(
                 "Received message from {peer_id} on channel {channel} of {} bytes",
                 message.len()
             );
 
+            let data = match state
+                .compressor
+                .decompress(channel, Bytes::copy_from_slice(message.as_ref()))
+            {
+                Ok(data) => data,
+                Err(e) => {
+                    debug!("Dropping message from {peer_id} on channel {channel}: {e}");
+                    return ControlFlow::Continue(());
+                }
+            };
+
+            let event = Event::Message(
+                channel,
+                PeerId::from_libp2p(&peer_id),
+                behaviour::broadcast_message_id(data.as_ref()),
+                data,
+            );
+
+            if let Err(e) = tx_event.send(event).await {
+                error!("Error sending message to handle: {e}");
+                return ControlFlow::Break(());
+            }
+        }
+    }
+
+    ControlFlow::Continue(())
+}
+
+async fn handle_direct_event(
+    event: direct::Event,
+    config: &Config,
+    _metrics: &Metrics,
+    _swarm: &mut swarm::Swarm<Behaviour>,
+    state: &mut State,
+    tx_event: &mpsc::Sender<Event>,
+) -> ControlFlow<()> {
+    match event {
+        direct::Event::Received(peer_id, channel, message) => {
+            // Unless direct streams are the pubsub protocol, they only carry the chunks
+            // of erasure-coded proposal parts, which are sent to a single peer
+            if !config.pubsub_protocol.is_direct() && channel != Channel::ProposalParts {
+                debug!("Dropping message from {peer_id} on channel {channel}: not published over direct streams");
+                return ControlFlow::Continue(());
+            }
+
+            if !state.rate_limiter.check(peer_id, channel, Instant::now()) {
+                debug!("Dropping message from {peer_id} on channel {channel}: rate limit exceeded");
+                return ControlFlow::Continue(());
+            }
+
+            trace!(
+                "Received message from {peer_id} on channel {channel} of {} bytes",
+                message.len()
+            );
+
+            let data = match state.compressor.decompress_direct(message) {
+                Ok(data) => data,
+                Err(e) => {
+                    debug!("Dropping message from {peer_id} on channel {channel}: {e}");
+                    return ControlFlow::Continue(());
+                }
+            };
+
             let event = Event::Message(
                 channel,
                 PeerId::from_libp2p(&peer_id),
-                Bytes::copy_from_slice(message.as_ref()),
+                behaviour::broadcast_message_id(data.as_ref()),
+                data,
             );
 
             if let Err(e) = tx_event.send(event).await {
@@ -625,13 +1024,15 @@ async fn handle_sync_event(
                     request,
                     channel,
                 } => {
-                    state.sync_channels.insert(request_id, channel);
+                    state
+                        .sync_channels
+                        .insert(request_id, (channel, request.compressed));
 
                     let _ = tx_event
                         .send(Event::Sync(sync::RawMessage::Request {
                             request_id,
                             peer: PeerId::from_libp2p(&peer),
-                            body: request.0,
+                            body: request.data,
                         }))
                         .await
                         .map_err(|e| {
@@ -643,11 +1044,23 @@ async fn handle_sync_event(
                     request_id,
                     response,
                 } => {
+                    let body = if response.compressed {
+                        match state.compressor.decompress_response(response.data) {
+                            Ok(body) => body,
+                            Err(e) => {
+                                error!(%peer, "Error decompressing Sync response: {e}");
+                                return ControlFlow::Continue(());
+                            }
+                        }
+                    } else {
+                        response.data
+                    };
+
                     let _ = tx_event
                         .send(Event::Sync(sync::RawMessage::Response {
                             request_id,
                             peer: PeerId::from_libp2p(&peer),
-                            body: response.0,
+                            body,
                         }))
                         .await
                         .map_err(|e| {
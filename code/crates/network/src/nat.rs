@@ -0,0 +1,200 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use libp2p::core::transport::ListenerId;
+use libp2p::multiaddr::Protocol;
+use libp2p::swarm;
+use libp2p::{autonat, dcutr, relay, Multiaddr, PeerId};
+use tracing::{debug, error, info, warn};
+
+use crate::behaviour::{Behaviour, NetworkEvent};
+
+/// Maximum duration of a connection relayed by this node, after which the peers must reconnect
+const MAX_CIRCUIT_DURATION: Duration = Duration::from_secs(60 * 60);
+
+/// Maximum amount of data relayed over a single connection, after which the peers must reconnect
+const MAX_CIRCUIT_BYTES: u64 = 1024 * 1024 * 1024; // 1 GiB
+
+/// Interval at which to listen again on the relays whose listeners were closed
+pub(crate) const RELAY_LISTEN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Traversal of the NATs and firewalls preventing peers from connecting to each other
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NatConfig {
+    /// Probe whether this node is publicly reachable with AutoNAT,
+    /// and help other nodes find out whether they are
+    pub autonat: bool,
+
+    /// Relay the connections to the nodes behind a NAT with circuit relay v2
+    pub relay_server: bool,
+
+    /// Peers allowed to reserve a slot on this relay and to connect through it.
+    /// No peer may use the relay if empty.
+    pub relay_allowed_peers: Vec<PeerId>,
+
+    /// Connect to the peers behind a NAT through the relays they listen on
+    pub relay_client: bool,
+
+    /// Relays to listen on, to be reachable from behind a NAT. Implies `relay_client`.
+    /// Each address must end with the peer id of the relay.
+    pub relays: Vec<Multiaddr>,
+
+    /// Upgrade relayed connections to direct connections with DCUtR hole punching.
+    /// Implies `relay_client`.
+    pub hole_punching: bool,
+}
+
+impl NatConfig {
+    pub fn is_relay_client_enabled(&self) -> bool {
+        self.relay_client || self.hole_punching || !self.relays.is_empty()
+    }
+}
+
+/// Relays the connections of the allowed peers only, within the default limits
+/// on the number of reservations and circuits, and on the rate at which they are made.
+pub(crate) fn relay_server_config(allowed_peers: &[PeerId]) -> relay::Config {
+    if allowed_peers.is_empty() {
+        warn!("No peer is allowed to use this relay, see `relay_allowed_peers`");
+    }
+
+    let mut config = relay::Config {
+        max_circuit_duration: MAX_CIRCUIT_DURATION,
+        max_circuit_bytes: MAX_CIRCUIT_BYTES,
+        ..Default::default()
+    };
+
+    config
+        .reservation_rate_limiters
+        .push(allow_list(allowed_peers));
+
+    config
+        .circuit_src_rate_limiters
+        .push(allow_list(allowed_peers));
+
+    config
+}
+
+/// Rejects the reservations and circuits of the peers which are not allowed
+fn allow_list(allowed_peers: &[PeerId]) -> Box<dyn relay::RateLimiter> {
+    let allowed_peers: HashSet<PeerId> = allowed_peers.iter().copied().collect();
+
+    Box::new(move |peer_id, _: &Multiaddr, _| allowed_peers.contains(&peer_id))
+}
+
+/// A relay server must be publicly reachable at its listen addresses,
+/// which it hands out to the peers making a reservation.
+pub(crate) fn add_relay_server_address(swarm: &mut swarm::Swarm<Behaviour>, address: &Multiaddr) {
+    let is_local = address.iter().any(|protocol| match protocol {
+        Protocol::Ip4(ip) => ip.is_loopback() || ip.is_unspecified(),
+        Protocol::Ip6(ip) => ip.is_loopback() || ip.is_unspecified(),
+        _ => false,
+    });
+
+    if !is_local {
+        swarm.add_external_address(address.clone());
+    }
+}
+
+/// Listeners on the relays this node is reachable through.
+///
+/// The connections to the relays are maintained by the discovery, which treats them as unconditional peers.
+/// A listener is only opened once connected to its relay, as it would otherwise race with the discovery
+/// to dial the relay. It is closed when the connection to the relay drops, and reopened on reconnection.
+#[derive(Debug, Default)]
+pub(crate) struct RelayListeners {
+    relays: Vec<(PeerId, Multiaddr)>,
+    listeners: HashMap<ListenerId, Multiaddr>,
+}
+
+impl RelayListeners {
+    pub(crate) fn new(relays: Vec<Multiaddr>) -> Self {
+        let relays = relays
+            .into_iter()
+            .filter_map(|relay| match relay.iter().last() {
+                Some(Protocol::P2p(peer_id)) => Some((peer_id, relay)),
+                _ => {
+                    error!(%relay, "Relay address must end with the peer id of the relay");
+                    None
+                }
+            })
+            .collect();
+
+        Self {
+            relays,
+            listeners: HashMap::new(),
+        }
+    }
+
+    /// Listen on the connected relays which are not listened on yet
+    pub(crate) fn listen(&mut self, swarm: &mut swarm::Swarm<Behaviour>) {
+        for (peer_id, relay) in &self.relays {
+            if !swarm.is_connected(peer_id) || self.listeners.values().any(|addr| addr == relay) {
+                continue;
+            }
+
+            match swarm.listen_on(relay.clone().with(Protocol::P2pCircuit)) {
+                Ok(listener_id) => {
+                    debug!(%relay, "Listening through relay");
+                    self.listeners.insert(listener_id, relay.clone());
+                }
+                Err(e) => error!(%relay, "Error listening through relay: {e}"),
+            }
+        }
+    }
+
+    pub(crate) fn on_listener_closed(&mut self, listener_id: ListenerId) {
+        if let Some(relay) = self.listeners.remove(&listener_id) {
+            warn!(%relay, "Stopped listening through relay");
+        }
+    }
+}
+
+pub(crate) fn handle_event(event: NetworkEvent) {
+    match event {
+        NetworkEvent::AutoNat(autonat::Event::StatusChanged { old, new }) => match new {
+            autonat::NatStatus::Public(addr) => {
+                info!(%addr, "Node is publicly reachable");
+            }
+            autonat::NatStatus::Private => {
+                warn!("Node is not publicly reachable, peers must connect to it through a relay");
+            }
+            autonat::NatStatus::Unknown => {
+                debug!(?old, "Reachability of the node is unknown");
+            }
+        },
+
+        NetworkEvent::AutoNat(event) => {
+            debug!("AutoNAT event: {event:?}");
+        }
+
+        NetworkEvent::RelayClient(relay::client::Event::ReservationReqAccepted {
+            relay_peer_id,
+            renewal: false,
+            ..
+        }) => {
+            info!(relay = %relay_peer_id, "Reserved a slot on relay");
+        }
+
+        NetworkEvent::RelayClient(event) => {
+            debug!("Relay client event: {event:?}");
+        }
+
+        NetworkEvent::RelayServer(event) => {
+            debug!("Relay server event: {event:?}");
+        }
+
+        NetworkEvent::Dcutr(dcutr::Event {
+            remote_peer_id,
+            result,
+        }) => match result {
+            Ok(connection_id) => {
+                info!(peer = %remote_peer_id, %connection_id, "Upgraded relayed connection to a direct connection");
+            }
+            Err(e) => {
+                debug!(peer = %remote_peer_id, "Hole punching failed, staying on relayed connection: {e}");
+            }
+        },
+
+        _ => {}
+    }
+}
@@ -2,11 +2,12 @@ use bytes::Bytes;
 use libp2p::swarm;
 
 use crate::behaviour::Behaviour;
-use crate::{Channel, PubSubProtocol};
+use crate::{Channel, CompressionConfig, PubSubProtocol};
 
 pub fn subscribe(
     swarm: &mut swarm::Swarm<Behaviour>,
     protocol: PubSubProtocol,
+    compression: &CompressionConfig,
     channels: &[Channel],
 ) -> Result<(), eyre::Report> {
     match protocol {
@@ -15,7 +16,7 @@ pub fn subscribe(
                 swarm
                     .behaviour_mut()
                     .gossipsub
-                    .subscribe(&channel.to_gossipsub_topic())?;
+                    .subscribe(&channel.to_gossipsub_topic(compression.get(*channel)))?;
             }
         }
         PubSubProtocol::Broadcast => {
@@ -23,9 +24,12 @@ pub fn subscribe(
                 swarm
                     .behaviour_mut()
                     .broadcast
-                    .subscribe(channel.to_broadcast_topic());
+                    .subscribe(channel.to_broadcast_topic(compression.get(*channel)));
             }
         }
+        PubSubProtocol::Direct => {
+            // Direct streams are opened to every connected validator, regardless of the channel
+        }
     }
 
     Ok(())
@@ -34,6 +38,7 @@ pub fn subscribe(
 pub fn publish(
     swarm: &mut swarm::Swarm<Behaviour>,
     protocol: PubSubProtocol,
+    compression: &CompressionConfig,
     channel: Channel,
     data: Bytes,
 ) -> Result<(), eyre::Report> {
@@ -42,13 +47,21 @@ pub fn publish(
             swarm
                 .behaviour_mut()
                 .gossipsub
-                .publish(channel.to_gossipsub_topic(), data)?;
+                .publish(channel.to_gossipsub_topic(compression.get(channel)), data)?;
         }
         PubSubProtocol::Broadcast => {
             swarm
                 .behaviour_mut()
                 .broadcast
-                .broadcast(&channel.to_broadcast_topic(), data);
+                .broadcast(&channel.to_broadcast_topic(compression.get(channel)), data);
+        }
+        PubSubProtocol::Direct => {
+            swarm
+                .behaviour_mut()
+                .direct
+                .as_mut()
+                .ok_or_else(|| eyre::eyre!("Direct streams are disabled"))?
+                .publish(channel, data);
         }
     }
 
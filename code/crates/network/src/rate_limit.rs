@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use libp2p::PeerId;
+
+use malachitebft_config::{RateLimitConfig, RateLimitsConfig};
+
+use crate::Channel;
+
+/// Penalty added to the application-specific score of a peer every time it exceeds a rate limit
+const RATE_LIMIT_PENALTY: f64 = 1.0;
+
+/// Factor by which penalties decay at every call to [`RateLimiter::decay_penalties`]
+const PENALTY_DECAY: f64 = 0.9;
+
+/// Penalties below this value are forgotten
+const PENALTY_DECAY_TO_ZERO: f64 = 0.01;
+
+/// The rate limit of the messages on the given channel, if any
+fn rate_limit(limits: &RateLimitsConfig, channel: Channel) -> Option<RateLimitConfig> {
+    match channel {
+        Channel::Consensus => limits.consensus,
+        Channel::ProposalParts => limits.proposal_parts,
+        Channel::Sync => limits.sync,
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn full(limit: RateLimitConfig, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(limit.burst),
+            last_refill: now,
+        }
+    }
+
+    fn try_take(&mut self, limit: RateLimitConfig, now: Instant) -> bool {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+
+        self.tokens =
+            (self.tokens + elapsed * f64::from(limit.messages_per_sec)).min(f64::from(limit.burst));
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Token-bucket rate limiting of the messages received from each peer on each channel,
+/// and penalties for the peers exceeding their rate limits.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limits: RateLimitsConfig,
+    buckets: HashMap<(PeerId, Channel), TokenBucket>,
+    penalties: HashMap<PeerId, f64>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimitsConfig) -> Self {
+        Self {
+            limits,
+            buckets: HashMap::new(),
+            penalties: HashMap::new(),
+        }
+    }
+
+    /// Whether a message just received from the given peer on the given channel
+    /// is within the rate limit for that channel.
+    pub fn check(&mut self, peer_id: PeerId, channel: Channel, now: Instant) -> bool {
+        let Some(limit) = rate_limit(&self.limits, channel) else {
+            return true;
+        };
+
+        self.buckets
+            .entry((peer_id, channel))
+            .or_insert_with(|| TokenBucket::full(limit, now))
+            .try_take(limit, now)
+    }
+
+    /// Penalize a peer for exceeding its rate limit, returning its new application-specific score
+    pub fn penalize(&mut self, peer_id: PeerId) -> f64 {
+        let penalty = self.penalties.entry(peer_id).or_default();
+        *penalty += RATE_LIMIT_PENALTY;
+        -*penalty
+    }
+
+    /// Decay the penalties of all penalized peers, returning their new application-specific scores
+    pub fn decay_penalties(&mut self) -> Vec<(PeerId, f64)> {
+        let mut scores = Vec::with_capacity(self.penalties.len());
+
+        self.penalties.retain(|peer_id, penalty| {
+            *penalty *= PENALTY_DECAY;
+
+            if *penalty < PENALTY_DECAY_TO_ZERO {
+                scores.push((*peer_id, 0.0));
+                false
+            } else {
+                scores.push((*peer_id, -*penalty));
+                true
+            }
+        });
+
+        scores
+    }
+
+    /// Forget the rate limits state of a disconnected peer, but not its penalties
+    pub fn remove_peer(&mut self, peer_id: &PeerId) {
+        self.buckets.retain(|(id, _), _| id != peer_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn limits_rate_per_peer_and_channel() {
+        let mut limiter = RateLimiter::new(RateLimitsConfig {
+            consensus: Some(RateLimitConfig {
+                messages_per_sec: 10,
+                burst: 2,
+            }),
+            ..Default::default()
+        });
+
+        let peer = PeerId::random();
+        let other_peer = PeerId::random();
+        let now = Instant::now();
+
+        assert!(limiter.check(peer, Channel::Consensus, now));
+        assert!(limiter.check(peer, Channel::Consensus, now));
+        assert!(!limiter.check(peer, Channel::Consensus, now));
+
+        // Other peers and channels are not affected
+        assert!(limiter.check(other_peer, Channel::Consensus, now));
+        assert!(limiter.check(peer, Channel::ProposalParts, now));
+
+        // One token is refilled every 100ms
+        let later = now + Duration::from_millis(100);
+        assert!(limiter.check(peer, Channel::Consensus, later));
+        assert!(!limiter.check(peer, Channel::Consensus, later));
+    }
+
+    #[test]
+    fn penalties_decay() {
+        let mut limiter = RateLimiter::new(RateLimitsConfig::default());
+        let peer = PeerId::random();
+
+        assert_eq!(limiter.penalize(peer), -RATE_LIMIT_PENALTY);
+        assert_eq!(limiter.penalize(peer), -2.0 * RATE_LIMIT_PENALTY);
+
+        let mut score = -2.0 * RATE_LIMIT_PENALTY;
+        while score < 0.0 {
+            let scores = limiter.decay_penalties();
+            assert_eq!(scores.len(), 1);
+            assert!(scores[0].1 > score);
+            score = scores[0].1;
+        }
+
+        assert!(limiter.decay_penalties().is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use libp2p::gossipsub::MessageId;
+use libp2p::PeerId;
+
+/// Time after which a GossipSub message still awaiting validation is ignored.
+///
+/// GossipSub only keeps the messages awaiting validation for `history_length` heartbeats,
+/// ie. 5 seconds, after which reporting their validation result has no effect.
+pub const VALIDATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The GossipSub messages awaiting validation by the application,
+/// along with the peer which forwarded each of them.
+#[derive(Debug, Default)]
+pub struct PendingValidations {
+    pending: HashMap<MessageId, (PeerId, Instant)>,
+}
+
+impl PendingValidations {
+    pub fn insert(&mut self, message_id: MessageId, peer_id: PeerId, now: Instant) {
+        self.pending.insert(message_id, (peer_id, now));
+    }
+
+    /// Stop awaiting the validation of a message,
+    /// returning the peer which forwarded it if it was still pending.
+    pub fn remove(&mut self, message_id: &MessageId) -> Option<PeerId> {
+        self.pending.remove(message_id).map(|(peer_id, _)| peer_id)
+    }
+
+    /// Stop awaiting the validation of the messages received more than [`VALIDATION_TIMEOUT`] ago,
+    /// returning them along with the peers which forwarded them.
+    pub fn expire(&mut self, now: Instant) -> Vec<(MessageId, PeerId)> {
+        let mut expired = Vec::new();
+
+        self.pending.retain(|message_id, (peer_id, received)| {
+            if now.saturating_duration_since(*received) < VALIDATION_TIMEOUT {
+                true
+            } else {
+                expired.push((message_id.clone(), *peer_id));
+                false
+            }
+        });
+
+        expired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expire_after_timeout() {
+        let mut pending = PendingValidations::default();
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        pending.insert(MessageId::from("validated"), peer, now);
+        pending.insert(MessageId::from("forgotten"), peer, now);
+        pending.insert(MessageId::from("recent"), peer, now + VALIDATION_TIMEOUT);
+
+        assert_eq!(pending.remove(&MessageId::from("validated")), Some(peer));
+        assert_eq!(pending.remove(&MessageId::from("validated")), None);
+
+        assert!(pending.expire(now).is_empty());
+
+        let expired = pending.expire(now + VALIDATION_TIMEOUT);
+        assert_eq!(expired, vec![(MessageId::from("forgotten"), peer)]);
+
+        let expired = pending.expire(now + 2 * VALIDATION_TIMEOUT);
+        assert_eq!(expired, vec![(MessageId::from("recent"), peer)]);
+    }
+}
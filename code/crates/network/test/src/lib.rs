@@ -5,13 +5,16 @@ use libp2p_identity::{ecdsa, PeerId};
 use malachitebft_config::TransportProtocol;
 use malachitebft_metrics::SharedRegistry;
 use malachitebft_network::{
-    spawn, BootstrapProtocol, Config, DiscoveryConfig, Keypair, PeerIdExt, Selector,
+    spawn, BootstrapProtocol, Config, DiscoveryConfig, Keypair, Multiaddr, PeerIdExt, PeerLists,
+    Selector,
 };
 use malachitebft_starknet_host::types::PrivateKey;
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use tokio::time::sleep;
 use tracing::{debug, info};
 
+pub mod netns;
+
 //---------------------------------------------------------------------
 // Expected primitives
 //---------------------------------------------------------------------
@@ -147,31 +150,60 @@ impl<const N: usize> Test<N> {
     }
 
     fn generate_default_configs(&self) -> [Config; N] {
-        std::array::from_fn(|i| Config {
-            listen_addr: TransportProtocol::Quic
-                .multiaddr("127.0.0.1", self.consensus_base_port + i),
-            persistent_peers: self.nodes[i]
-                .bootstrap_nodes
-                .iter()
-                .map(|j| {
-                    TransportProtocol::Quic.multiaddr("127.0.0.1", self.consensus_base_port + *j)
-                })
-                .collect(),
-            discovery: DiscoveryConfig {
-                enabled: true,
-                bootstrap_protocol: BootstrapProtocol::Full,
-                selector: Selector::Random,
-                ..Default::default()
-            },
-            idle_connection_timeout: Duration::from_secs(60),
-            transport: malachitebft_network::TransportProtocol::Quic,
-            gossipsub: malachitebft_network::GossipSubConfig::default(),
-            pubsub_protocol: malachitebft_network::PubSubProtocol::default(),
-            rpc_max_size: 10 * 1024 * 1024,   // 10 MiB
-            pubsub_max_size: 4 * 1024 * 1024, // 4 MiB
+        std::array::from_fn(|i| {
+            let transport = self.nodes[i].transport;
+            let port = self.consensus_base_port + i;
+
+            Config {
+                listen_addrs: std::iter::once(transport.multiaddr("127.0.0.1", port))
+                    .chain(transport.fallback_multiaddrs("127.0.0.1", port))
+                    .collect(),
+                persistent_peers: self.nodes[i]
+                    .bootstrap_nodes
+                    .iter()
+                    .map(|j| transport.multiaddr("127.0.0.1", self.consensus_base_port + *j))
+                    .collect(),
+                peer_lists: PeerLists {
+                    allowed: self.peer_addrs(&self.nodes[i].allowed_peers),
+                    private: self.peer_addrs(&self.nodes[i].private_peers),
+                    ..Default::default()
+                },
+                discovery: DiscoveryConfig {
+                    enabled: !self.nodes[i].hidden,
+                    hidden: self.nodes[i].hidden,
+                    bootstrap_protocol: BootstrapProtocol::Full,
+                    selector: Selector::Random,
+                    ..Default::default()
+                },
+                address_book: None,
+                idle_connection_timeout: Duration::from_secs(60),
+                transport: match transport {
+                    TransportProtocol::Tcp => malachitebft_network::TransportProtocol::Tcp,
+                    TransportProtocol::Quic => malachitebft_network::TransportProtocol::Quic,
+                    TransportProtocol::Both => malachitebft_network::TransportProtocol::Both,
+                },
+                gossipsub: malachitebft_network::GossipSubConfig::default(),
+                pubsub_protocol: malachitebft_network::PubSubProtocol::default(),
+                rpc_max_size: 10 * 1024 * 1024,   // 10 MiB
+                pubsub_max_size: 4 * 1024 * 1024, // 4 MiB
+                rate_limits: Default::default(),
+                compression: Default::default(),
+                nat: Default::default(),
+                direct_streams: false,
+            }
         })
     }
 
+    fn peer_addrs(&self, nodes: &[usize]) -> Vec<Multiaddr> {
+        nodes
+            .iter()
+            .map(|&j| {
+                let peer_id = PeerId::from_public_key(&self.keypairs[j].public());
+                format!("/p2p/{peer_id}").parse().unwrap()
+            })
+            .collect()
+    }
+
     pub async fn run(self) {
         init_logging();
         info!("Starting test with {} nodes", N);
@@ -277,6 +309,10 @@ pub enum Fault {
 pub struct TestNode {
     _id: usize,
     bootstrap_nodes: Vec<usize>,
+    allowed_peers: Vec<usize>,
+    private_peers: Vec<usize>,
+    hidden: bool,
+    transport: TransportProtocol,
     faults: Vec<Fault>,
 }
 
@@ -285,6 +321,10 @@ impl TestNode {
         Self {
             _id: id,
             bootstrap_nodes,
+            allowed_peers: Vec::new(),
+            private_peers: Vec::new(),
+            hidden: false,
+            transport: TransportProtocol::Quic,
             faults: Vec::new(),
         }
     }
@@ -293,10 +333,39 @@ impl TestNode {
         Self {
             _id: id,
             bootstrap_nodes,
+            allowed_peers: Vec::new(),
+            private_peers: Vec::new(),
+            hidden: false,
+            transport: TransportProtocol::Quic,
             faults,
         }
     }
 
+    /// Only connect to the given nodes
+    pub fn with_allowed_peers(mut self, allowed_peers: Vec<usize>) -> Self {
+        self.allowed_peers = allowed_peers;
+        self
+    }
+
+    /// Never share the addresses of the given nodes
+    pub fn with_private_peers(mut self, private_peers: Vec<usize>) -> Self {
+        self.private_peers = private_peers;
+        self
+    }
+
+    /// Only connect to the bootstrap nodes, which never share the address of this node,
+    /// as a validator behind sentry nodes
+    pub fn hidden(mut self) -> Self {
+        self.hidden = true;
+        self
+    }
+
+    /// Listen and dial with the given transport, QUIC by default
+    pub fn with_transport(mut self, transport: TransportProtocol) -> Self {
+        self.transport = transport;
+        self
+    }
+
     pub fn bootstrap_nodes(&self) -> &[usize] {
         &self.bootstrap_nodes
     }
@@ -310,7 +379,7 @@ impl TestNode {
 // Helpers
 //---------------------------------------------------------------------
 
-fn init_logging() {
+pub fn init_logging() {
     use tracing_subscriber::util::SubscriberInitExt;
     use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
@@ -0,0 +1,154 @@
+//! Network namespaces to test nodes which cannot reach each other directly.
+//!
+//! Requires root privileges and the `ip` command.
+
+use std::fs::File;
+use std::io;
+use std::process::Command;
+use std::thread;
+
+use malachitebft_metrics::SharedRegistry;
+use malachitebft_network::handle::Handle;
+use malachitebft_network::{spawn, Config, Keypair};
+use nix::sched::{setns, CloneFlags};
+use tokio::runtime::Runtime;
+
+/// A relay namespace connected to each of the other namespaces through its own subnet.
+///
+/// The relay does not forward packets, so the other namespaces can only reach the relay,
+/// at any of its addresses.
+/// The namespace at index `i` is reachable at `10.0.{i + 1}.2`,
+/// and reaches the relay at `10.0.{i + 1}.1`.
+pub struct RelayTopology {
+    relay: String,
+    peers: Vec<String>,
+}
+
+impl RelayTopology {
+    pub fn new(prefix: &str, peers: usize) -> io::Result<Self> {
+        let topology = Self {
+            relay: format!("{prefix}-r"),
+            peers: (0..peers).map(|i| format!("{prefix}-{i}")).collect(),
+        };
+
+        ip(&["netns", "add", &topology.relay])?;
+        ip(&["-n", &topology.relay, "link", "set", "lo", "up"])?;
+
+        for (i, peer) in topology.peers.iter().enumerate() {
+            let subnet = i + 1;
+            let (relay_if, peer_if) = (format!("{peer}r"), format!("{peer}p"));
+
+            ip(&["netns", "add", peer])?;
+            ip(&["-n", peer, "link", "set", "lo", "up"])?;
+            ip(&[
+                "link",
+                "add",
+                &relay_if,
+                "netns",
+                &topology.relay,
+                "type",
+                "veth",
+                "peer",
+                "name",
+                &peer_if,
+                "netns",
+                peer,
+            ])?;
+
+            let relay_ip = format!("10.0.{subnet}.1");
+            let relay_addr = format!("{relay_ip}/24");
+            let peer_addr = format!("10.0.{subnet}.2/24");
+            ip(&[
+                "-n",
+                &topology.relay,
+                "addr",
+                "add",
+                &relay_addr,
+                "dev",
+                &relay_if,
+            ])?;
+            ip(&["-n", peer, "addr", "add", &peer_addr, "dev", &peer_if])?;
+            ip(&["-n", &topology.relay, "link", "set", &relay_if, "up"])?;
+            ip(&["-n", peer, "link", "set", &peer_if, "up"])?;
+            ip(&["-n", peer, "route", "add", "default", "via", &relay_ip])?;
+        }
+
+        Ok(topology)
+    }
+
+    /// Address of the relay, as seen from the namespace at the given index
+    pub fn relay_ip(&self, peer: usize) -> String {
+        format!("10.0.{}.1", peer + 1)
+    }
+
+    /// Spawn a node in the relay namespace
+    pub fn spawn_relay(
+        &self,
+        keypair: Keypair,
+        config: Config,
+        registry: SharedRegistry,
+    ) -> io::Result<(Handle, Runtime)> {
+        spawn_in(&self.relay, keypair, config, registry)
+    }
+
+    /// Spawn a node in the namespace at the given index
+    pub fn spawn_peer(
+        &self,
+        peer: usize,
+        keypair: Keypair,
+        config: Config,
+        registry: SharedRegistry,
+    ) -> io::Result<(Handle, Runtime)> {
+        spawn_in(&self.peers[peer], keypair, config, registry)
+    }
+}
+
+impl Drop for RelayTopology {
+    fn drop(&mut self) {
+        // Deleting the namespaces deletes the interfaces within them
+        for ns in self.peers.iter().chain(std::iter::once(&self.relay)) {
+            let _ = ip(&["netns", "delete", ns]);
+        }
+    }
+}
+
+/// Spawn a node on a runtime whose threads all live in the given namespace
+fn spawn_in(
+    ns: &str,
+    keypair: Keypair,
+    config: Config,
+    registry: SharedRegistry,
+) -> io::Result<(Handle, Runtime)> {
+    let ns_path = format!("/var/run/netns/{ns}");
+
+    thread::spawn(move || {
+        // The worker threads of the runtime inherit the namespace of the thread creating them
+        setns(File::open(ns_path)?, CloneFlags::CLONE_NEWNET)?;
+
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+
+        let handle = rt
+            .block_on(spawn(keypair, config, registry))
+            .map_err(|e| io::Error::other(e.to_string()))?;
+
+        Ok((handle, rt))
+    })
+    .join()
+    .expect("spawning thread panicked")
+}
+
+fn ip(args: &[&str]) -> io::Result<()> {
+    let output = Command::new("ip").args(args).output()?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "`ip {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
@@ -1,6 +1,7 @@
 use std::{time::Duration, vec};
 
 use informalsystems_malachitebft_discovery_test::{Expected, Test, TestNode};
+use malachitebft_config::TransportProtocol;
 
 // Testing the following circular bootstrap sets graph:
 //     0 <--- 1 <--- 2 <--- 3 <--- 4
@@ -54,3 +55,97 @@ pub async fn circular_graph_n() {
 
     test.run().await
 }
+
+// Testing a validator (0) behind a sentry node (1):
+//     0 ---> 1 <--- 2 <--- 3
+// The sentry never shares the address of the validator, which only connects to the sentry.
+#[tokio::test]
+pub async fn sentry_node() {
+    let test = Test::new(
+        [
+            TestNode::correct(0, vec![1]).with_allowed_peers(vec![1]),
+            TestNode::correct(1, vec![]).with_private_peers(vec![0]),
+            TestNode::correct(2, vec![1]),
+            TestNode::correct(3, vec![2]),
+        ],
+        [
+            Expected::Exactly(vec![1]),
+            Expected::Exactly(vec![0, 2, 3]),
+            Expected::Exactly(vec![1, 3]),
+            Expected::Exactly(vec![1, 2]),
+        ],
+        Duration::from_secs(0),
+        Duration::from_secs(10),
+    );
+
+    test.run().await
+}
+
+// Testing a hidden validator (1) behind a sentry node (0):
+//     1 ---> 0 <--- 2 <--- 3
+// The sentry never shares the address of the validator, without having to list it as private.
+#[tokio::test]
+pub async fn hidden_node() {
+    let test = Test::new(
+        [
+            TestNode::correct(0, vec![]),
+            TestNode::correct(1, vec![0]).hidden(),
+            TestNode::correct(2, vec![0]),
+            TestNode::correct(3, vec![2]),
+        ],
+        [
+            Expected::Exactly(vec![1, 2, 3]),
+            Expected::Exactly(vec![0]),
+            Expected::Exactly(vec![0, 3]),
+            Expected::Exactly(vec![0, 2]),
+        ],
+        // Let the validator connect to the sentry before the other nodes request its peers
+        Duration::from_secs(1),
+        Duration::from_secs(10),
+    );
+
+    test.run().await
+}
+
+// Testing nodes with different transports, where 0 and 3 listen on both TCP and QUIC:
+//     1 (TCP) ---> 0 <--- 2 (QUIC) <--- 3
+// Every node connects to the others it shares a transport with.
+#[tokio::test]
+pub async fn mixed_transports() {
+    let test = Test::new(
+        [
+            TestNode::correct(0, vec![]).with_transport(TransportProtocol::Both),
+            TestNode::correct(1, vec![0]).with_transport(TransportProtocol::Tcp),
+            TestNode::correct(2, vec![0]).with_transport(TransportProtocol::Quic),
+            TestNode::correct(3, vec![2]).with_transport(TransportProtocol::Both),
+        ],
+        [
+            Expected::Exactly(vec![1, 2, 3]),
+            Expected::Exactly(vec![0, 3]),
+            Expected::Exactly(vec![0, 3]),
+            Expected::Exactly(vec![0, 1, 2]),
+        ],
+        Duration::from_secs(0),
+        Duration::from_secs(10),
+    );
+
+    test.run().await
+}
+
+// Testing a node listening on both TCP and QUIC bootstrapping from a node listening on TCP only:
+//     0 (TCP) <--- 1
+// The persistent peer of 1 is given by its QUIC address, which 1 falls back from to TCP.
+#[tokio::test]
+pub async fn fall_back_to_tcp() {
+    let test = Test::new(
+        [
+            TestNode::correct(0, vec![]).with_transport(TransportProtocol::Tcp),
+            TestNode::correct(1, vec![0]).with_transport(TransportProtocol::Both),
+        ],
+        [Expected::Exactly(vec![1]), Expected::Exactly(vec![0])],
+        Duration::from_secs(0),
+        Duration::from_secs(10),
+    );
+
+    test.run().await
+}
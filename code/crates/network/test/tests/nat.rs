@@ -0,0 +1,182 @@
+use std::time::Duration;
+
+use informalsystems_malachitebft_discovery_test::init_logging;
+use informalsystems_malachitebft_discovery_test::netns::RelayTopology;
+use libp2p_identity::PeerId;
+use malachitebft_config::TransportProtocol;
+use malachitebft_metrics::SharedRegistry;
+use malachitebft_network::handle::Handle;
+use malachitebft_network::{
+    BootstrapProtocol, Config, DiscoveryConfig, Event, Keypair, Multiaddr, NatConfig, PeerIdExt,
+    Selector,
+};
+use tokio::time::timeout;
+
+/// Maximum time to wait for the nodes to reach the expected state
+const WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn config(listen_addr: Multiaddr, persistent_peers: Vec<Multiaddr>, nat: NatConfig) -> Config {
+    Config {
+        listen_addrs: vec![listen_addr],
+        persistent_peers,
+        peer_lists: Default::default(),
+        discovery: DiscoveryConfig {
+            enabled: true,
+            bootstrap_protocol: BootstrapProtocol::Full,
+            selector: Selector::Random,
+            ..Default::default()
+        },
+        address_book: None,
+        idle_connection_timeout: Duration::from_secs(60),
+        transport: malachitebft_network::TransportProtocol::Quic,
+        gossipsub: Default::default(),
+        pubsub_protocol: Default::default(),
+        rpc_max_size: 10 * 1024 * 1024,   // 10 MiB
+        pubsub_max_size: 4 * 1024 * 1024, // 4 MiB
+        rate_limits: Default::default(),
+        compression: Default::default(),
+        nat,
+        direct_streams: false,
+    }
+}
+
+/// Wait until the node receives an event for which `done` holds, keeping track of the peers it is connected to
+async fn wait_until(
+    handle: &mut Handle,
+    peers: &mut Vec<PeerId>,
+    done: impl Fn(&Event, &[PeerId]) -> bool,
+) {
+    while let Some(event) = handle.recv().await {
+        match &event {
+            Event::PeerConnected(peer_id) if !peers.contains(&peer_id.to_libp2p()) => {
+                peers.push(peer_id.to_libp2p());
+            }
+            Event::PeerDisconnected(peer_id) => {
+                peers.retain(|p| p != &peer_id.to_libp2p());
+            }
+            _ => {}
+        }
+
+        if done(&event, peers) {
+            return;
+        }
+    }
+
+    panic!("Network stopped, connected to {peers:?}");
+}
+
+/// Wait until the node is connected to exactly the given peers
+async fn connected_to(handle: &mut Handle, peers: &mut Vec<PeerId>, mut expected: Vec<PeerId>) {
+    expected.sort();
+
+    if sorted(peers.clone()) == expected {
+        return;
+    }
+
+    wait_until(handle, peers, |_, peers| sorted(peers.to_vec()) == expected).await
+}
+
+fn sorted(mut peers: Vec<PeerId>) -> Vec<PeerId> {
+    peers.sort();
+    peers
+}
+
+// Nodes A and B can only reach the relay R, not each other:
+//     A <---> R <---> B
+// A listens through R, B discovers the relayed address of A through R and connects to it.
+#[tokio::test]
+#[ignore = "requires the permission to create network namespaces, eg. run as root"]
+pub async fn relayed_connection() {
+    init_logging();
+
+    let topology = RelayTopology::new(&format!("mnat{}", std::process::id() % 10000), 2)
+        .expect("cannot create network namespaces");
+
+    let [relay, a, b] = std::array::from_fn(|_| Keypair::generate_ecdsa());
+    let relay_id = PeerId::from_public_key(&relay.public());
+    let (a_id, b_id) = (
+        PeerId::from_public_key(&a.public()),
+        PeerId::from_public_key(&b.public()),
+    );
+
+    let port = 27000;
+    let relay_addr = |peer: usize| -> Multiaddr {
+        TransportProtocol::Quic.multiaddr(&topology.relay_ip(peer), port)
+    };
+
+    let relay_config = config(
+        TransportProtocol::Quic.multiaddr("0.0.0.0", port),
+        Vec::new(),
+        NatConfig {
+            autonat: true,
+            relay_server: true,
+            relay_allowed_peers: vec![a_id, b_id],
+            ..Default::default()
+        },
+    );
+
+    let a_config = config(
+        TransportProtocol::Quic.multiaddr("0.0.0.0", port),
+        vec![relay_addr(0)],
+        NatConfig {
+            autonat: true,
+            relays: vec![relay_addr(0).with_p2p(relay_id).unwrap()],
+            hole_punching: true,
+            ..Default::default()
+        },
+    );
+
+    let b_config = config(
+        TransportProtocol::Quic.multiaddr("0.0.0.0", port),
+        vec![relay_addr(1)],
+        NatConfig {
+            autonat: true,
+            relay_client: true,
+            hole_punching: true,
+            ..Default::default()
+        },
+    );
+
+    let registry = |moniker: &str| SharedRegistry::global().with_moniker(moniker);
+
+    let (mut relay_handle, relay_rt) = topology
+        .spawn_relay(relay, relay_config, registry("relay"))
+        .unwrap();
+    let (mut a_handle, a_rt) = topology.spawn_peer(0, a, a_config, registry("a")).unwrap();
+
+    let [mut relay_peers, mut a_peers, mut b_peers] = std::array::from_fn(|_| Vec::new());
+
+    let listening_through_relay = |event: &Event, _: &[PeerId]| match event {
+        Event::Listening(addr) => addr.iter().any(|protocol| protocol.tag() == "p2p-circuit"),
+        _ => false,
+    };
+
+    // A must have made its reservation on the relay before B asks the relay for peers
+    timeout(
+        WAIT_TIMEOUT,
+        wait_until(&mut a_handle, &mut a_peers, listening_through_relay),
+    )
+    .await
+    .expect("A should listen through the relay");
+
+    let (mut b_handle, b_rt) = topology.spawn_peer(1, b, b_config, registry("b")).unwrap();
+
+    let connected = timeout(WAIT_TIMEOUT, async {
+        tokio::join!(
+            connected_to(&mut relay_handle, &mut relay_peers, vec![a_id, b_id]),
+            connected_to(&mut a_handle, &mut a_peers, vec![relay_id, b_id]),
+            connected_to(&mut b_handle, &mut b_peers, vec![relay_id, a_id]),
+        )
+    })
+    .await;
+
+    for handle in [relay_handle, a_handle, b_handle] {
+        handle.shutdown().await.unwrap();
+    }
+
+    for rt in [relay_rt, a_rt, b_rt] {
+        rt.shutdown_background();
+    }
+
+    connected.expect("all nodes should be connected to each other");
+}
@@ -0,0 +1,446 @@
+//! RPC server exposing the status of a running node as JSON over HTTP.
+//!
+//! # Endpoints
+//!
+//! - `GET /consensus`: current height, round and step, proposer, and votes received in the current round
+//! - `GET /network`: addresses the node listens on and connected peers
+//! - `GET /sync`: sync status, or `404 Not Found` if sync is disabled
+//! - `GET /certificate`: certificate for the last decided value, or `404 Not Found` if none yet.
+//!   After a restart, the certificate of the last value decided before is fetched from the application
+//! - `GET /events`: WebSocket stream of consensus events, one text message per event.
+//!   A client which cannot keep up with the events is disconnected
+//! - `POST /tx`: submit a transaction to the application, the request body being the raw transaction bytes.
+//!   Returns the hex-encoded hash of the transaction if accepted, `400 Bad Request` with the reason
+//!   why the application rejected it, or `503 Service Unavailable` if too many transactions are pending
+//! - `GET /tx/{hash}`: inclusion status of a transaction submitted to this node, or `404 Not Found` if unknown
+//!
+//! # Features
+//!
+//! - `websocket` (default): Serve the `/events` WebSocket stream.
+
+use std::io;
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use derive_where::derive_where;
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, mpsc, oneshot, RwLock};
+use tracing::{info, warn};
+
+use malachitebft_config::RpcConfig;
+use malachitebft_core_types::{CommitCertificate, Context, Height};
+use malachitebft_engine::consensus::{ConsensusMsg, ConsensusRef};
+use malachitebft_engine::host::{HostMsg, HostRef};
+use malachitebft_engine::network::{NetworkMsg, NetworkRef};
+use malachitebft_engine::sync::{Msg as SyncMsg, SyncRef};
+use malachitebft_engine::util::events::{Event, TxEvent};
+
+pub mod tx;
+use tx::{SubmittedTx, TxIngest, SUBMIT_TIMEOUT};
+
+pub mod types;
+use types::{Certificate, ConsensusStatus, NetworkStatus, SyncStatus, TxStatus, TxSubmitted};
+
+/// The actors of a node queried by the RPC server
+#[derive_where(Clone)]
+pub struct Handles<Ctx: Context> {
+    pub consensus: ConsensusRef<Ctx>,
+    pub host: HostRef<Ctx>,
+    pub network: NetworkRef<Ctx>,
+    pub sync: Option<SyncRef<Ctx>>,
+    pub events: TxEvent<Ctx>,
+    pub transactions: Option<TxIngest>,
+}
+
+type RpcResult<T> = Result<Json<T>, (StatusCode, String)>;
+
+#[derive_where(Clone)]
+struct Rpc<Ctx: Context> {
+    handles: Handles<Ctx>,
+    last_certificate: Arc<RwLock<Option<CommitCertificate<Ctx>>>>,
+}
+
+/// Bind the RPC server to the configured address, to then [`serve`] the RPC endpoints
+pub async fn bind(config: &RpcConfig) -> io::Result<TcpListener> {
+    let listener = TcpListener::bind(config.listen_addr).await?;
+    info!(address = %config.listen_addr, "Serving RPC");
+
+    Ok(listener)
+}
+
+/// Serve the RPC endpoints with the given listener, see [`bind`], until the node shuts down.
+#[tracing::instrument(name = "rpc", skip_all)]
+pub async fn serve<Ctx: Context>(listener: TcpListener, handles: Handles<Ctx>) -> io::Result<()> {
+    let rpc = Rpc {
+        last_certificate: Arc::default(),
+        handles,
+    };
+
+    // Subscribe before serving any request, so that no decision is missed
+    tokio::spawn(track_certificates(
+        rpc.handles.events.subscribe(),
+        rpc.last_certificate.clone(),
+    ));
+
+    let app = Router::new()
+        .route("/consensus", get(get_consensus::<Ctx>))
+        .route("/network", get(get_network::<Ctx>))
+        .route("/sync", get(get_sync::<Ctx>))
+        .route("/certificate", get(get_certificate::<Ctx>))
+        .route("/tx", post(post_tx::<Ctx>))
+        .route("/tx/:hash", get(get_tx::<Ctx>));
+
+    #[cfg(feature = "websocket")]
+    let app = app.route("/events", get(ws::get_events::<Ctx>));
+
+    axum::serve(listener, app.with_state(rpc)).await
+}
+
+async fn track_certificates<Ctx: Context>(
+    mut rx: broadcast::Receiver<Event<Ctx>>,
+    last_certificate: Arc<RwLock<Option<CommitCertificate<Ctx>>>>,
+) {
+    loop {
+        match rx.recv().await {
+            Ok(Event::Decided(certificate)) => {
+                *last_certificate.write().await = Some(certificate);
+            }
+            Ok(_) => (),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!("Skipped {skipped} events while tracking decisions");
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+fn internal_error(e: impl ToString) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+async fn get_consensus<Ctx: Context>(State(rpc): State<Rpc<Ctx>>) -> RpcResult<ConsensusStatus> {
+    let info =
+        ractor::call!(rpc.handles.consensus, ConsensusMsg::GetInfo).map_err(internal_error)?;
+    Ok(Json(info.into()))
+}
+
+async fn get_network<Ctx: Context>(State(rpc): State<Rpc<Ctx>>) -> RpcResult<NetworkStatus> {
+    let info = ractor::call!(rpc.handles.network, |reply| NetworkMsg::GetInfo { reply })
+        .map_err(internal_error)?;
+
+    Ok(Json(info.into()))
+}
+
+async fn get_sync<Ctx: Context>(State(rpc): State<Rpc<Ctx>>) -> RpcResult<SyncStatus> {
+    let Some(sync) = &rpc.handles.sync else {
+        return Err((StatusCode::NOT_FOUND, "Sync is disabled".to_string()));
+    };
+
+    let info = ractor::call!(sync, SyncMsg::GetInfo).map_err(internal_error)?;
+    Ok(Json(info.into()))
+}
+
+async fn get_certificate<Ctx: Context>(State(rpc): State<Rpc<Ctx>>) -> RpcResult<Certificate> {
+    if let Some(certificate) = rpc.last_certificate.read().await.as_ref() {
+        return Ok(Json(certificate.into()));
+    }
+
+    // Nothing was decided since the node started, eg. after a restart,
+    // so we look for the last value decided before in the application's store
+    let Some(stored) = stored_certificate(&rpc.handles).await? else {
+        return Err((StatusCode::NOT_FOUND, "No value decided yet".to_string()));
+    };
+
+    // Unless a value has been decided in the meantime
+    let mut last_certificate = rpc.last_certificate.write().await;
+    let certificate = last_certificate.get_or_insert(stored);
+
+    Ok(Json((&*certificate).into()))
+}
+
+/// The certificate of the value decided at the height preceding the current one, if stored
+async fn stored_certificate<Ctx: Context>(
+    handles: &Handles<Ctx>,
+) -> Result<Option<CommitCertificate<Ctx>>, (StatusCode, String)> {
+    let info = ractor::call!(handles.consensus, ConsensusMsg::GetInfo).map_err(internal_error)?;
+
+    let Some(height) = info.height.decrement() else {
+        return Ok(None);
+    };
+
+    let decided = ractor::call!(handles.host, |reply_to| HostMsg::GetDecidedValue {
+        height,
+        reply_to
+    })
+    .map_err(internal_error)?;
+
+    Ok(decided.map(|decided| decided.certificate))
+}
+
+fn tx_ingest<Ctx: Context>(rpc: &Rpc<Ctx>) -> Result<&TxIngest, (StatusCode, String)> {
+    rpc.handles.transactions.as_ref().ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            "Transaction submission is disabled".to_string(),
+        )
+    })
+}
+
+async fn post_tx<Ctx: Context>(State(rpc): State<Rpc<Ctx>>, tx: Bytes) -> RpcResult<TxSubmitted> {
+    let ingest = tx_ingest(&rpc)?;
+
+    let unavailable = || {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Application is not accepting transactions".to_string(),
+        )
+    };
+
+    // Turn clients away rather than queue up transactions in front of the consensus messages
+    let (reply, rx) = oneshot::channel();
+    ingest
+        .submit
+        .try_send(SubmittedTx { tx, reply })
+        .map_err(|e| match e {
+            mpsc::error::TrySendError::Full(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Too many transactions are pending".to_string(),
+            ),
+            mpsc::error::TrySendError::Closed(_) => unavailable(),
+        })?;
+
+    let result = tokio::time::timeout(SUBMIT_TIMEOUT, rx)
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::GATEWAY_TIMEOUT,
+                "Application did not accept the transaction in time".to_string(),
+            )
+        })?
+        .map_err(|_| unavailable())?;
+
+    match result {
+        Ok(hash) => {
+            ingest.receipts.pending(hash.clone());
+            Ok(Json(TxSubmitted {
+                hash: hex::encode(hash),
+            }))
+        }
+        Err(reason) => Err((StatusCode::BAD_REQUEST, reason)),
+    }
+}
+
+async fn get_tx<Ctx: Context>(
+    State(rpc): State<Rpc<Ctx>>,
+    Path(hash): Path<String>,
+) -> RpcResult<TxStatus> {
+    let ingest = tx_ingest(&rpc)?;
+
+    let hash = hex::decode(&hash).map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            "Invalid transaction hash".to_string(),
+        )
+    })?;
+
+    match ingest.receipts.get(&hash) {
+        Some(receipt) => Ok(Json(TxStatus {
+            hash: hex::encode(hash),
+            receipt,
+        })),
+        None => Err((StatusCode::NOT_FOUND, "Unknown transaction".to_string())),
+    }
+}
+
+#[cfg(feature = "websocket")]
+mod ws {
+    use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade};
+    use axum::extract::State;
+    use axum::response::Response;
+    use tokio::sync::broadcast;
+    use tracing::{debug, warn};
+
+    use malachitebft_core_types::Context;
+    use malachitebft_engine::util::events::Event;
+
+    use crate::Rpc;
+
+    pub async fn get_events<Ctx: Context>(
+        ws: WebSocketUpgrade,
+        State(rpc): State<Rpc<Ctx>>,
+    ) -> Response {
+        let rx = rpc.handles.events.subscribe();
+        ws.on_upgrade(move |socket| stream_events(socket, rx))
+    }
+
+    async fn stream_events<Ctx: Context>(
+        mut socket: WebSocket,
+        mut rx: broadcast::Receiver<Event<Ctx>>,
+    ) {
+        loop {
+            tokio::select! {
+                event = rx.recv() => match event {
+                    Ok(event) => {
+                        if socket.send(Message::Text(event.to_string())).await.is_err() {
+                            break;
+                        }
+                    }
+                    // Rather than silently skipping events, disconnect the client,
+                    // which may then reconnect and resynchronize with the other endpoints
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Skipped {skipped} events, disconnecting slow client");
+
+                        let close = CloseFrame {
+                            code: close_code::AGAIN,
+                            reason: format!("Client is too slow, skipped {skipped} events").into(),
+                        };
+
+                        let _ = socket.send(Message::Close(Some(close))).await;
+                        break;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+
+                msg = socket.recv() => match msg {
+                    // Ignore messages from the client, only stop when it disconnects
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => (),
+                },
+            }
+        }
+
+        debug!("Client disconnected from event stream");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use ractor::{Actor, ActorProcessingErr, ActorRef};
+
+    use malachitebft_core_state_machine::state::Step;
+    use malachitebft_core_types::Round;
+    use malachitebft_engine::consensus::ConsensusInfo;
+    use malachitebft_sync::DecidedValue;
+    use malachitebft_test::{Height, TestContext, ValueId};
+
+    use super::*;
+
+    /// Stand-in for an actor of the node, answering its messages with the given function
+    struct Stub<M>(fn(M));
+
+    #[async_trait]
+    impl<M: ractor::Message> Actor for Stub<M> {
+        type Msg = M;
+        type State = ();
+        type Arguments = ();
+
+        async fn pre_start(
+            &self,
+            _myself: ActorRef<M>,
+            _args: (),
+        ) -> Result<(), ActorProcessingErr> {
+            Ok(())
+        }
+
+        async fn handle(
+            &self,
+            _myself: ActorRef<M>,
+            msg: M,
+            _state: &mut (),
+        ) -> Result<(), ActorProcessingErr> {
+            (self.0)(msg);
+            Ok(())
+        }
+    }
+
+    fn certificate(height: u64) -> CommitCertificate<TestContext> {
+        CommitCertificate::new(
+            Height::new(height),
+            Round::new(0),
+            ValueId::new(height),
+            Vec::new(),
+        )
+    }
+
+    /// Consensus having just restarted at height 3
+    fn restarted(msg: ConsensusMsg<TestContext>) {
+        if let ConsensusMsg::GetInfo(reply) = msg {
+            let _ = reply.send(ConsensusInfo {
+                height: Height::new(3),
+                round: Round::Nil,
+                step: Step::Unstarted,
+                proposer: None,
+                votes: None,
+            });
+        }
+    }
+
+    async fn rpc(host: fn(HostMsg<TestContext>)) -> Rpc<TestContext> {
+        let (consensus, _) = Actor::spawn(None, Stub(restarted), ()).await.unwrap();
+        let (host, _) = Actor::spawn(None, Stub(host), ()).await.unwrap();
+        let (network, _) = Actor::spawn(None, Stub(|_| ()), ()).await.unwrap();
+
+        Rpc {
+            handles: Handles {
+                consensus,
+                host,
+                network,
+                sync: None,
+                events: TxEvent::new(),
+                transactions: None,
+            },
+            last_certificate: Arc::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn certificate_is_read_from_store_after_restart() {
+        let rpc = rpc(|msg| {
+            if let HostMsg::GetDecidedValue { height, reply_to } = msg {
+                let decided = DecidedValue::new(Bytes::new(), certificate(height.as_u64()));
+                let _ = reply_to.send(Some(decided));
+            }
+        })
+        .await;
+
+        let Json(certificate) = get_certificate(State(rpc.clone())).await.unwrap();
+        assert_eq!(certificate.height, 2);
+
+        // The stored certificate is kept, until the next decision
+        assert!(rpc.last_certificate.read().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn certificate_follows_decisions() {
+        let rpc = rpc(|msg| {
+            if let HostMsg::GetDecidedValue { reply_to, .. } = msg {
+                let _ = reply_to.send(None);
+            }
+        })
+        .await;
+
+        let (status, _) = get_certificate(State(rpc.clone())).await.unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let events = TxEvent::new();
+        let tracker = tokio::spawn(track_certificates(
+            events.subscribe(),
+            rpc.last_certificate.clone(),
+        ));
+
+        events.send(|| Event::Decided(certificate(3)));
+        events.send(|| Event::Decided(certificate(4)));
+
+        // Stop tracking once the events are handled
+        drop(events);
+        tracker.await.unwrap();
+
+        let Json(certificate) = get_certificate(State(rpc)).await.unwrap();
+        assert_eq!(certificate.height, 4);
+    }
+}
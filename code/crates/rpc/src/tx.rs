@@ -0,0 +1,279 @@
+//! Submission of transactions to the application, and tracking of their inclusion in decided values.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use serde::Serialize;
+use tokio::sync::{mpsc, oneshot};
+
+use malachitebft_core_types::{CommitCertificate, Context, Height};
+
+/// Maximum number of receipts kept in memory, older receipts are forgotten first
+pub const MAX_RECEIPTS: usize = 10_000;
+
+/// Maximum time given to the application to accept or reject a submitted transaction
+pub const SUBMIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum number of proposed values whose transactions are tracked until a decision,
+/// values at the lowest heights are forgotten first
+pub const MAX_PROPOSED_VALUES: usize = 1_000;
+
+/// A transaction submitted through the `POST /tx` endpoint, to be handed over to the application
+#[derive(Debug)]
+pub struct SubmittedTx {
+    /// The raw transaction bytes, as sent by the client
+    pub tx: Bytes,
+
+    /// Channel for the application to reply with the hash of the transaction if it was accepted,
+    /// or with the reason why it was rejected
+    pub reply: oneshot::Sender<Result<Bytes, String>>,
+}
+
+/// Inclusion status of a transaction accepted by the application
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TxReceipt {
+    /// The transaction has not been included in a decided value yet
+    Pending,
+
+    /// The transaction has been included in the value decided at the given height and round
+    Included {
+        height: u64,
+        round: i64,
+        value_id: String,
+    },
+}
+
+/// Receipts of the transactions submitted to this node, indexed by transaction hash.
+///
+/// Transactions are marked as pending once accepted by the application.
+/// The application then tells which transactions each value it proposes or receives contains
+/// with [`Receipts::proposed`], and these transactions are reported as included as soon as
+/// the value is decided. Alternatively, the application may report them itself with
+/// [`Receipts::included`] when it commits the decided value.
+#[derive(Clone, Debug, Default)]
+pub struct Receipts {
+    inner: Arc<Mutex<ReceiptsInner>>,
+}
+
+#[derive(Debug, Default)]
+struct ReceiptsInner {
+    receipts: HashMap<Bytes, TxReceipt>,
+    order: VecDeque<Bytes>,
+
+    /// Transactions contained in the values proposed at undecided heights,
+    /// indexed by height and value id
+    proposed: BTreeMap<(u64, String), Vec<Bytes>>,
+}
+
+impl ReceiptsInner {
+    fn insert(&mut self, hash: Bytes, receipt: TxReceipt) {
+        if self.receipts.insert(hash.clone(), receipt).is_some() {
+            return;
+        }
+
+        self.order.push_back(hash);
+
+        if self.order.len() > MAX_RECEIPTS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.receipts.remove(&oldest);
+            }
+        }
+    }
+}
+
+impl Receipts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark a transaction accepted by the application as pending, unless it is already included.
+    pub fn pending(&self, hash: Bytes) {
+        let mut inner = self.inner.lock().unwrap();
+
+        if !inner.receipts.contains_key(&hash) {
+            inner.insert(hash, TxReceipt::Pending);
+        }
+    }
+
+    /// Record the transactions contained in a value proposed at the given height,
+    /// for them to be reported as included by [`Receipts::decided`] if that value is decided.
+    pub fn proposed(
+        &self,
+        height: impl Height,
+        value_id: impl Display,
+        tx_hashes: impl IntoIterator<Item = Bytes>,
+    ) {
+        let tx_hashes = tx_hashes.into_iter().collect::<Vec<_>>();
+        if tx_hashes.is_empty() {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+
+        inner
+            .proposed
+            .insert((height.as_u64(), value_id.to_string()), tx_hashes);
+
+        if inner.proposed.len() > MAX_PROPOSED_VALUES {
+            inner.proposed.pop_first();
+        }
+    }
+
+    /// Report the transactions of the value decided with the given certificate as included,
+    /// if that value was recorded with [`Receipts::proposed`].
+    pub fn decided<Ctx: Context>(&self, certificate: &CommitCertificate<Ctx>) {
+        let height = certificate.height.as_u64();
+        let value_id = certificate.value_id.to_string();
+
+        let tx_hashes = {
+            let mut inner = self.inner.lock().unwrap();
+            let tx_hashes = inner.proposed.remove(&(height, value_id));
+
+            // Values proposed up to the decided height will never be decided
+            inner.proposed.retain(|(h, _), _| *h > height);
+
+            tx_hashes
+        };
+
+        if let Some(tx_hashes) = tx_hashes {
+            self.included(certificate, tx_hashes);
+        }
+    }
+
+    /// Report the given transactions as included in the value decided with the given certificate.
+    pub fn included<Ctx: Context>(
+        &self,
+        certificate: &CommitCertificate<Ctx>,
+        tx_hashes: impl IntoIterator<Item = Bytes>,
+    ) {
+        let receipt = TxReceipt::Included {
+            height: certificate.height.as_u64(),
+            round: certificate.round.as_i64(),
+            value_id: certificate.value_id.to_string(),
+        };
+
+        let mut inner = self.inner.lock().unwrap();
+
+        for hash in tx_hashes {
+            inner.insert(hash, receipt.clone());
+        }
+    }
+
+    /// The receipt of the transaction with the given hash, if known
+    pub fn get(&self, hash: &[u8]) -> Option<TxReceipt> {
+        self.inner.lock().unwrap().receipts.get(hash).cloned()
+    }
+}
+
+/// Entry point for transactions submitted through the RPC server
+#[derive(Clone, Debug)]
+pub struct TxIngest {
+    /// Channel over which submitted transactions are sent to the application
+    pub submit: mpsc::Sender<SubmittedTx>,
+
+    /// Receipts of the submitted transactions
+    pub receipts: Receipts,
+}
+
+#[cfg(test)]
+mod tests {
+    use malachitebft_core_types::Round;
+    use malachitebft_test::{Height, TestContext, ValueId};
+
+    use super::*;
+
+    fn certificate(height: u64, value_id: u64) -> CommitCertificate<TestContext> {
+        CommitCertificate::new(
+            Height::new(height),
+            Round::new(0),
+            ValueId::new(value_id),
+            Vec::new(),
+        )
+    }
+
+    fn hash(n: u64) -> Bytes {
+        Bytes::copy_from_slice(&n.to_be_bytes())
+    }
+
+    fn included(height: u64, value_id: u64) -> Option<TxReceipt> {
+        Some(TxReceipt::Included {
+            height,
+            round: 0,
+            value_id: ValueId::new(value_id).to_string(),
+        })
+    }
+
+    #[test]
+    fn pending_then_included() {
+        let receipts = Receipts::new();
+        assert_eq!(receipts.get(&hash(1)), None);
+
+        receipts.pending(hash(1));
+        assert_eq!(receipts.get(&hash(1)), Some(TxReceipt::Pending));
+
+        receipts.included(&certificate(1, 10), [hash(1)]);
+        assert_eq!(receipts.get(&hash(1)), included(1, 10));
+
+        // Submitting an included transaction again does not make it pending
+        receipts.pending(hash(1));
+        assert_eq!(receipts.get(&hash(1)), included(1, 10));
+    }
+
+    #[test]
+    fn decided_value_includes_its_transactions() {
+        let receipts = Receipts::new();
+        receipts.pending(hash(1));
+        receipts.pending(hash(2));
+        receipts.pending(hash(3));
+
+        receipts.proposed(Height::new(1), ValueId::new(10), [hash(1)]);
+        receipts.proposed(Height::new(1), ValueId::new(11), [hash(2)]);
+        receipts.proposed(Height::new(2), ValueId::new(12), [hash(3)]);
+
+        receipts.decided(&certificate(1, 10));
+        assert_eq!(receipts.get(&hash(1)), included(1, 10));
+        assert_eq!(receipts.get(&hash(2)), Some(TxReceipt::Pending));
+
+        // The other value proposed at the decided height is forgotten
+        receipts.decided(&certificate(1, 11));
+        assert_eq!(receipts.get(&hash(2)), Some(TxReceipt::Pending));
+
+        receipts.decided(&certificate(2, 12));
+        assert_eq!(receipts.get(&hash(3)), included(2, 12));
+    }
+
+    #[test]
+    fn forgets_oldest_receipts() {
+        let receipts = Receipts::new();
+
+        for n in 0..=MAX_RECEIPTS as u64 {
+            receipts.pending(hash(n));
+        }
+
+        assert_eq!(receipts.get(&hash(0)), None);
+        assert_eq!(receipts.get(&hash(1)), Some(TxReceipt::Pending));
+        assert_eq!(
+            receipts.get(&hash(MAX_RECEIPTS as u64)),
+            Some(TxReceipt::Pending)
+        );
+    }
+
+    #[test]
+    fn forgets_values_at_lowest_heights() {
+        let receipts = Receipts::new();
+
+        for n in 0..=MAX_PROPOSED_VALUES as u64 {
+            receipts.proposed(Height::new(n + 1), ValueId::new(n), [hash(n)]);
+        }
+
+        receipts.decided(&certificate(1, 0));
+        assert_eq!(receipts.get(&hash(0)), None);
+
+        receipts.decided(&certificate(2, 1));
+        assert_eq!(receipts.get(&hash(1)), included(2, 1));
+    }
+}
@@ -0,0 +1,232 @@
+//! Responses returned by the RPC endpoints, serialized as JSON.
+//!
+//! Consensus types are generic over the [`Context`], so addresses, peer ids and value ids
+//! are rendered with their [`Display`](core::fmt::Display) implementation.
+
+use serde::Serialize;
+
+use malachitebft_core_consensus::VoteTally;
+use malachitebft_core_types::{CommitCertificate, Context, Height, VotingPower};
+use malachitebft_engine::consensus::ConsensusInfo;
+use malachitebft_engine::network::NetworkInfo;
+use malachitebft_engine::sync::SyncInfo;
+
+use crate::tx::TxReceipt;
+
+/// Response of the `/consensus` endpoint
+#[derive(Clone, Debug, Serialize)]
+pub struct ConsensusStatus {
+    /// The current height
+    pub height: u64,
+
+    /// The current round, `-1` if the round has not started yet
+    pub round: i64,
+
+    /// The current step within the round
+    pub step: String,
+
+    /// The proposer for the current round, if the round has started
+    pub proposer: Option<String>,
+
+    /// The votes received so far in the current round
+    pub votes: Option<Votes>,
+}
+
+impl<Ctx: Context> From<ConsensusInfo<Ctx>> for ConsensusStatus {
+    fn from(info: ConsensusInfo<Ctx>) -> Self {
+        Self {
+            height: info.height.as_u64(),
+            round: info.round.as_i64(),
+            step: format!("{:?}", info.step),
+            proposer: info.proposer.map(|address| address.to_string()),
+            votes: info.votes.map(Votes::from),
+        }
+    }
+}
+
+/// Tally of the votes received in a round
+#[derive(Clone, Debug, Serialize)]
+pub struct Votes {
+    /// Number of validators in the validator set
+    pub validators: usize,
+
+    /// Number of validators having voted
+    pub validators_voted: usize,
+
+    /// Total voting power of the validator set
+    pub total_voting_power: VotingPower,
+
+    /// Total voting power of the validators having voted
+    pub voted_power: VotingPower,
+
+    /// Total weight of prevotes
+    pub prevotes: VotingPower,
+
+    /// Total weight of prevotes for nil
+    pub prevotes_nil: VotingPower,
+
+    /// Total weight of precommits
+    pub precommits: VotingPower,
+
+    /// Total weight of precommits for nil
+    pub precommits_nil: VotingPower,
+}
+
+impl From<VoteTally> for Votes {
+    fn from(tally: VoteTally) -> Self {
+        Self {
+            validators: tally.validators,
+            validators_voted: tally.validators_voted,
+            total_voting_power: tally.total_voting_power,
+            voted_power: tally.voted_power,
+            prevotes: tally.prevotes,
+            prevotes_nil: tally.prevotes_nil,
+            precommits: tally.precommits,
+            precommits_nil: tally.precommits_nil,
+        }
+    }
+}
+
+/// Response of the `/network` endpoint
+#[derive(Clone, Debug, Serialize)]
+pub struct NetworkStatus {
+    /// The addresses the node listens on
+    pub listen_addrs: Vec<String>,
+
+    /// The peers the node is connected to
+    pub peers: Vec<String>,
+}
+
+impl From<NetworkInfo> for NetworkStatus {
+    fn from(info: NetworkInfo) -> Self {
+        Self {
+            listen_addrs: info.listen_addrs.iter().map(|a| a.to_string()).collect(),
+            peers: info.peers.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+}
+
+/// Response of the `/sync` endpoint
+#[derive(Clone, Debug, Serialize)]
+pub struct SyncStatus {
+    /// Height of last decided value
+    pub tip_height: u64,
+
+    /// Height currently syncing
+    pub sync_height: u64,
+
+    /// Latest status received from each peer
+    pub peers: Vec<PeerStatus>,
+
+    /// Pending requests for decided values
+    pub pending_value_requests: Vec<PendingRequest>,
+
+    /// Pending requests for vote sets
+    pub pending_vote_set_requests: Vec<PendingRequest>,
+}
+
+impl<Ctx: Context> From<SyncInfo<Ctx>> for SyncStatus {
+    fn from(info: SyncInfo<Ctx>) -> Self {
+        Self {
+            tip_height: info.tip_height.as_u64(),
+            sync_height: info.sync_height.as_u64(),
+            peers: info
+                .peers
+                .iter()
+                .map(|status| PeerStatus {
+                    peer_id: status.peer_id.to_string(),
+                    height: status.height.as_u64(),
+                    history_min_height: status.history_min_height.as_u64(),
+                })
+                .collect(),
+            pending_value_requests: info
+                .pending_value_requests
+                .iter()
+                .map(|(height, peer_id)| PendingRequest {
+                    height: height.as_u64(),
+                    round: None,
+                    peer_id: peer_id.to_string(),
+                })
+                .collect(),
+            pending_vote_set_requests: info
+                .pending_vote_set_requests
+                .iter()
+                .map(|(height, round, peer_id)| PendingRequest {
+                    height: height.as_u64(),
+                    round: Some(round.as_i64()),
+                    peer_id: peer_id.to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Status last advertised by a peer
+#[derive(Clone, Debug, Serialize)]
+pub struct PeerStatus {
+    pub peer_id: String,
+    pub height: u64,
+    pub history_min_height: u64,
+}
+
+/// A sync request awaiting a response from a peer
+#[derive(Clone, Debug, Serialize)]
+pub struct PendingRequest {
+    pub height: u64,
+
+    /// Only set for vote set requests
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub round: Option<i64>,
+
+    pub peer_id: String,
+}
+
+/// Response of the `/certificate` endpoint
+#[derive(Clone, Debug, Serialize)]
+pub struct Certificate {
+    /// The decided height
+    pub height: u64,
+
+    /// The round in which the value was decided
+    pub round: i64,
+
+    /// The id of the decided value
+    pub value_id: String,
+
+    /// The addresses of the validators whose precommits make up the certificate
+    pub signers: Vec<String>,
+}
+
+impl<Ctx: Context> From<&CommitCertificate<Ctx>> for Certificate {
+    fn from(certificate: &CommitCertificate<Ctx>) -> Self {
+        Self {
+            height: certificate.height.as_u64(),
+            round: certificate.round.as_i64(),
+            value_id: certificate.value_id.to_string(),
+            signers: certificate
+                .aggregated_signature
+                .signatures
+                .iter()
+                .map(|signature| signature.address.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Response of the `POST /tx` endpoint
+#[derive(Clone, Debug, Serialize)]
+pub struct TxSubmitted {
+    /// The hash of the transaction, hex-encoded, to query its receipt with `GET /tx/{hash}`
+    pub hash: String,
+}
+
+/// Response of the `GET /tx/{hash}` endpoint
+#[derive(Clone, Debug, Serialize)]
+pub struct TxStatus {
+    /// The hash of the transaction, hex-encoded
+    pub hash: String,
+
+    /// The inclusion status of the transaction
+    #[serde(flatten)]
+    pub receipt: TxReceipt,
+}
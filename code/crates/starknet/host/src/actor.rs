@@ -10,9 +10,10 @@ use rand::SeedableRng;
 use tokio::time::Instant;
 use tracing::{debug, error, info, trace, warn};
 
-use malachitebft_core_consensus::PeerId;
+use malachitebft_app::streaming::StreamingMetrics;
+use malachitebft_core_consensus::{PeerId, ValueValidity};
 use malachitebft_core_types::{CommitCertificate, Round, Validity, ValueOrigin};
-use malachitebft_engine::consensus::{ConsensusMsg, ConsensusRef};
+use malachitebft_engine::consensus::{ConsensusMsg, ConsensusRef, ParamsUpdate};
 use malachitebft_engine::host::{LocallyProposedValue, ProposedValue};
 use malachitebft_engine::network::{NetworkMsg, NetworkRef};
 use malachitebft_engine::util::streaming::{StreamContent, StreamMessage};
@@ -33,6 +34,9 @@ pub struct Host {
     span: tracing::Span,
 }
 
+/// Time given to a Byzantine node to build a value it proposes out of turn
+const OUT_OF_TURN_PROPOSAL_TIMEOUT: Duration = Duration::from_secs(1);
+
 pub type HostRef = malachitebft_engine::host::HostRef<MockContext>;
 pub type HostMsg = malachitebft_engine::host::HostMsg<MockContext>;
 
@@ -43,6 +47,7 @@ impl Host {
         mempool: MempoolRef,
         network: NetworkRef<MockContext>,
         metrics: Metrics,
+        streaming_metrics: StreamingMetrics,
         span: tracing::Span,
     ) -> Result<HostRef, SpawnErr> {
         let db_dir = home_dir.join("db");
@@ -52,7 +57,12 @@ impl Host {
         let (actor_ref, _) = Actor::spawn(
             None,
             Self::new(mempool, network, metrics, span),
-            HostState::new(host, db_path, &mut StdRng::from_entropy()),
+            HostState::new(
+                host,
+                db_path,
+                streaming_metrics,
+                &mut StdRng::from_entropy(),
+            ),
         )
         .await?;
 
@@ -124,7 +134,7 @@ impl Host {
                 height,
                 round,
                 proposer,
-            } => on_started_round(state, height, round, proposer).await,
+            } => on_started_round(state, &self.network, height, round, proposer).await,
 
             HostMsg::GetHistoryMinHeight { reply_to } => on_get_history_min_height(state, reply_to),
 
@@ -132,6 +142,7 @@ impl Host {
                 height,
                 round,
                 timeout,
+                vote_extensions: _,
                 reply_to,
             } => on_get_value(state, &self.network, height, round, timeout, reply_to).await,
 
@@ -160,6 +171,37 @@ impl Host {
                 reply_to,
             } => on_received_proposal_part(state, part, from, reply_to).await,
 
+            HostMsg::ValidateProposal { reply_to, .. } => {
+                // Values are block hashes, whose blocks are only known when streamed as proposal parts,
+                // so there is nothing to validate when receiving them in proposal messages only.
+                reply_to.send(ValueValidity::Valid)?;
+                Ok(())
+            }
+
+            HostMsg::ExtendVote {
+                height,
+                round,
+                value_id: _,
+                reply_to,
+            } => {
+                reply_to.send(state.host.generate_vote_extension(height, round))?;
+                Ok(())
+            }
+
+            HostMsg::VerifyVoteExtension {
+                address,
+                extension,
+                reply_to,
+                ..
+            } => {
+                reply_to.send(
+                    state
+                        .host
+                        .verify_vote_extension(&address, extension.as_ref()),
+                )?;
+                Ok(())
+            }
+
             HostMsg::GetValidatorSet { height, reply_to } => {
                 on_get_validator_set(state, height, reply_to).await
             }
@@ -196,6 +238,7 @@ fn on_consensus_ready(
     consensus.cast(ConsensusMsg::StartHeight(
         start_height,
         state.host.validator_set.clone(),
+        ParamsUpdate::default(),
     ))?;
 
     Ok(())
@@ -227,6 +270,7 @@ async fn replay_undecided_values(
 
 async fn on_started_round(
     state: &mut HostState,
+    network: &NetworkRef<MockContext>,
     height: Height,
     round: Round,
     proposer: Address,
@@ -239,6 +283,15 @@ async fn on_started_round(
     // feed them back to consensus. This may happen when we are restarting after a crash.
     replay_undecided_values(state, height, round).await?;
 
+    if state.host.params.propose_out_of_turn && proposer != state.host.address {
+        warn!(%height, %round, %proposer, "Proposing a value out of turn");
+
+        let deadline = Instant::now() + OUT_OF_TURN_PROPOSAL_TIMEOUT;
+        let block_hash = build_and_stream_value(state, network, height, round, deadline).await?;
+
+        debug!(%block_hash, "Streamed value proposed out of turn");
+    }
+
     Ok(())
 }
 
@@ -280,14 +333,43 @@ async fn on_get_value(
             value.height,
             value.round,
             value.value,
-            value.extension,
         ))?;
 
         return Ok(());
     }
 
     let deadline = Instant::now() + timeout;
+    let block_hash = build_and_stream_value(state, network, height, round, deadline).await?;
+
+    let parts = state.host.part_store.all_parts(height, round);
+
+    let Some(value) = state.build_value_from_parts(&parts, height, round).await else {
+        error!(%height, %round, "Failed to build block from parts");
+        return Ok(());
+    };
+
+    debug!(%height, %round, %block_hash, "Storing proposed value from assembled block");
+    if let Err(e) = state.block_store.store_undecided_value(value.clone()).await {
+        error!(%e, %height, %round, "Failed to store the proposed value");
+    }
 
+    reply_to.send(LocallyProposedValue::new(
+        value.height,
+        value.round,
+        value.value,
+    ))?;
+
+    Ok(())
+}
+
+/// Build a new value for the given height and round, and stream its parts to our peers.
+async fn build_and_stream_value(
+    state: &mut HostState,
+    network: &NetworkRef<MockContext>,
+    height: Height,
+    round: Round,
+    deadline: Instant,
+) -> Result<Hash, ActorProcessingErr> {
     debug!(%height, %round, "Building new proposal...");
 
     let (mut rx_part, rx_hash) = state.host.build_new_proposal(height, round, deadline).await;
@@ -322,26 +404,7 @@ async fn on_get_value(
         .part_store
         .store_value_id(height, round, block_hash);
 
-    let parts = state.host.part_store.all_parts(height, round);
-
-    let Some(value) = state.build_value_from_parts(&parts, height, round).await else {
-        error!(%height, %round, "Failed to build block from parts");
-        return Ok(());
-    };
-
-    debug!(%height, %round, %block_hash, "Storing proposed value from assembled block");
-    if let Err(e) = state.block_store.store_undecided_value(value.clone()).await {
-        error!(%e, %height, %round, "Failed to store the proposed value");
-    }
-
-    reply_to.send(LocallyProposedValue::new(
-        value.height,
-        value.round,
-        value.value,
-        value.extension,
-    ))?;
-
-    Ok(())
+    Ok(block_hash)
 }
 
 /// If we have already built a block for this height and round, return it to consensus
@@ -433,7 +496,6 @@ fn on_process_synced_value(
             proposer,
             value: block.block_hash,
             validity: Validity::Valid,
-            extension: None,
         };
 
         reply_to.send(proposed_value)?;
@@ -607,6 +669,7 @@ async fn on_decided(
     consensus.cast(ConsensusMsg::StartHeight(
         state.height.increment(),
         state.host.validator_set.clone(),
+        ParamsUpdate::default(),
     ))?;
 
     Ok(())
@@ -0,0 +1,202 @@
+//! Byzantine behaviors for test nodes.
+//!
+//! The [`ByzantineNetwork`] actor sits between the actors of a node and its network actor,
+//! and tampers with the messages they publish according to a [`ByzantineConfig`].
+
+use async_trait::async_trait;
+use ractor::{Actor, ActorProcessingErr, ActorRef, SpawnErr};
+use tracing::{debug, warn};
+
+use malachitebft_config::ByzantineConfig;
+use malachitebft_core_consensus::SignedConsensusMsg;
+use malachitebft_core_types::{Context, NilOrVal, Round, SigningProvider};
+use malachitebft_engine::network::{NetworkMsg, NetworkRef};
+
+use crate::types::{BlockHash, Height, MockContext};
+
+/// A value which is never proposed by correct nodes
+const INVALID_VALUE: BlockHash = BlockHash::new([0; 32]);
+
+pub type ByzantineNetworkRef = NetworkRef<MockContext>;
+
+/// Proxy for the network actor which deviates from the protocol
+/// when publishing consensus messages and proposal parts.
+///
+/// All other messages are forwarded to the network actor untouched.
+pub struct ByzantineNetwork {
+    ctx: MockContext,
+    network: NetworkRef<MockContext>,
+    config: ByzantineConfig,
+    span: tracing::Span,
+}
+
+#[derive(Default)]
+pub struct State {
+    /// Height and round of the last published message
+    latest: Option<(Height, Round)>,
+
+    /// Messages published at the current and previous heights,
+    /// to be replayed when moving to a new round
+    published: Vec<SignedConsensusMsg<MockContext>>,
+}
+
+impl ByzantineNetwork {
+    pub async fn spawn(
+        ctx: MockContext,
+        network: NetworkRef<MockContext>,
+        config: ByzantineConfig,
+        span: tracing::Span,
+    ) -> Result<ByzantineNetworkRef, SpawnErr> {
+        let actor = Self {
+            ctx,
+            network,
+            config,
+            span,
+        };
+
+        let (actor_ref, _) = Actor::spawn(None, actor, ()).await?;
+        Ok(actor_ref)
+    }
+
+    fn publish(&self, msg: SignedConsensusMsg<MockContext>) -> Result<(), ActorProcessingErr> {
+        self.network.cast(NetworkMsg::Publish(msg))?;
+        Ok(())
+    }
+
+    fn on_publish(
+        &self,
+        state: &mut State,
+        msg: SignedConsensusMsg<MockContext>,
+    ) -> Result<(), ActorProcessingErr> {
+        let msg = match msg {
+            SignedConsensusMsg::Vote(vote) if self.config.vote_invalid_values => {
+                warn!(height = %vote.height, round = %vote.round, "Voting for an invalid value");
+
+                let mut vote = vote.message;
+                vote.block_hash = NilOrVal::Val(INVALID_VALUE);
+
+                SignedConsensusMsg::Vote(self.ctx.signing_provider().sign_vote(vote))
+            }
+            msg => msg,
+        };
+
+        let position = (msg.height(), round(&msg));
+
+        if self.config.replay_old_rounds && state.latest.is_some_and(|latest| latest < position) {
+            warn!(
+                height = %position.0, round = %position.1,
+                "Replaying {} messages from previous rounds",
+                state.published.len()
+            );
+
+            for old in &state.published {
+                self.publish(old.clone())?;
+            }
+        }
+
+        self.publish(msg.clone())?;
+
+        match &msg {
+            SignedConsensusMsg::Vote(vote) if self.config.equivocate_votes => {
+                warn!(height = %vote.height, round = %vote.round, "Publishing a conflicting vote");
+
+                let mut conflicting = vote.message.clone();
+                conflicting.block_hash = match conflicting.block_hash {
+                    NilOrVal::Nil => NilOrVal::Val(INVALID_VALUE),
+                    NilOrVal::Val(_) => NilOrVal::Nil,
+                };
+
+                let conflicting = self.ctx.signing_provider().sign_vote(conflicting);
+                self.publish(SignedConsensusMsg::Vote(conflicting))?;
+            }
+
+            SignedConsensusMsg::Proposal(proposal) if self.config.equivocate_proposals => {
+                warn!(
+                    height = %proposal.height, round = %proposal.round,
+                    "Publishing a conflicting proposal"
+                );
+
+                let mut conflicting = proposal.message.clone();
+                conflicting.block_hash = INVALID_VALUE;
+
+                let conflicting = self.ctx.signing_provider().sign_proposal(conflicting);
+                self.publish(SignedConsensusMsg::Proposal(conflicting))?;
+            }
+
+            _ => (),
+        }
+
+        if self.config.replay_old_rounds {
+            // Only keep the messages of the current and previous heights
+            let height = position.0;
+            state
+                .published
+                .retain(|old| old.height().as_u64() + 1 >= height.as_u64());
+
+            state.published.push(msg);
+        }
+
+        if state.latest.is_none_or(|latest| latest < position) {
+            state.latest = Some(position);
+        }
+
+        Ok(())
+    }
+}
+
+fn round(msg: &SignedConsensusMsg<MockContext>) -> Round {
+    match msg {
+        SignedConsensusMsg::Vote(vote) => vote.round,
+        SignedConsensusMsg::Proposal(proposal) => proposal.round,
+    }
+}
+
+#[async_trait]
+impl Actor for ByzantineNetwork {
+    type Msg = NetworkMsg<MockContext>;
+    type State = State;
+    type Arguments = ();
+
+    async fn pre_start(
+        &self,
+        _myself: ActorRef<Self::Msg>,
+        _args: (),
+    ) -> Result<State, ActorProcessingErr> {
+        Ok(State::default())
+    }
+
+    #[tracing::instrument(name = "byzantine", parent = &self.span, skip_all)]
+    async fn handle(
+        &self,
+        _myself: ActorRef<Self::Msg>,
+        msg: Self::Msg,
+        state: &mut State,
+    ) -> Result<(), ActorProcessingErr> {
+        match msg {
+            NetworkMsg::Publish(msg) => self.on_publish(state, msg)?,
+
+            NetworkMsg::PublishProposalPart(part) => {
+                if self.config.withhold_proposal_parts {
+                    debug!(
+                        stream_id = %part.stream_id, sequence = %part.sequence,
+                        "Withholding proposal part"
+                    );
+                } else if let Some(delay) = self.config.delay_proposal_parts {
+                    debug!(
+                        stream_id = %part.stream_id, sequence = %part.sequence, ?delay,
+                        "Delaying proposal part"
+                    );
+
+                    self.network
+                        .send_after(delay, move || NetworkMsg::PublishProposalPart(part));
+                } else {
+                    self.network.cast(NetworkMsg::PublishProposalPart(part))?;
+                }
+            }
+
+            msg => self.network.cast(msg)?,
+        }
+
+        Ok(())
+    }
+}
@@ -9,7 +9,9 @@ use tracing::{debug, Instrument};
 
 use malachitebft_config::VoteExtensionsConfig;
 use malachitebft_core_consensus::ValuePayload;
-use malachitebft_core_types::{CommitCertificate, Extension, Round, SignedExtension, SignedVote};
+use malachitebft_core_types::{
+    CommitCertificate, Extension, Round, SignedExtension, SignedVote, Validity,
+};
 
 use crate::host::Host;
 use crate::mempool::MempoolRef;
@@ -28,6 +30,7 @@ pub struct StarknetParams {
     pub exec_time_per_tx: Duration,
     pub max_retain_blocks: usize,
     pub vote_extensions: VoteExtensionsConfig,
+    pub propose_out_of_turn: bool,
 }
 
 pub struct StarknetHost {
@@ -81,6 +84,31 @@ impl StarknetHost {
 
         Some(SignedExtension::new(extension, signature))
     }
+
+    /// Verify that a vote extension was signed by the validator who sent it.
+    pub fn verify_vote_extension(
+        &self,
+        address: &Address,
+        extension: Option<&SignedExtension<MockContext>>,
+    ) -> Validity {
+        use sha3::Digest;
+
+        // Every precommit for a value must be extended when vote extensions are enabled
+        let Some(extension) = extension else {
+            return Validity::from_bool(!self.params.vote_extensions.enabled);
+        };
+
+        let Some(validator) = self.validator_set.get_by_address(address) else {
+            return Validity::Invalid;
+        };
+
+        let hash = Hash::new(sha3::Keccak256::digest(&extension.message.data).into());
+        let valid = validator
+            .public_key
+            .verify(&hash.as_felt(), &extension.signature);
+
+        Validity::from_bool(valid)
+    }
 }
 
 #[async_trait]
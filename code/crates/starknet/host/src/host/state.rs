@@ -5,7 +5,8 @@ use rand::RngCore;
 use sha3::Digest;
 use tracing::{debug, error, trace};
 
-use malachitebft_core_types::{Round, SignedExtension, Validity};
+use malachitebft_app::streaming::StreamingMetrics;
+use malachitebft_core_types::{Round, Validity};
 use malachitebft_engine::consensus::ConsensusRef;
 use malachitebft_engine::host::ProposedValue;
 use malachitebft_engine::util::streaming::StreamId;
@@ -28,7 +29,12 @@ pub struct HostState {
 }
 
 impl HostState {
-    pub fn new<R>(host: StarknetHost, db_path: impl AsRef<Path>, rng: &mut R) -> Self
+    pub fn new<R>(
+        host: StarknetHost,
+        db_path: impl AsRef<Path>,
+        streaming_metrics: StreamingMetrics,
+        rng: &mut R,
+    ) -> Self
     where
         R: RngCore,
     {
@@ -39,7 +45,7 @@ impl HostState {
             host,
             consensus: None,
             block_store: BlockStore::new(db_path).unwrap(),
-            part_streams_map: PartStreamsMap::default(),
+            part_streams_map: PartStreamsMap::new(streaming_metrics),
             next_stream_id: rng.next_u64(),
         }
     }
@@ -59,7 +65,7 @@ impl HostState {
         height: Height,
         round: Round,
     ) -> Option<ProposedValue<MockContext>> {
-        let (valid_round, value, proposer, validity, extension) = self
+        let (valid_round, value, proposer, validity) = self
             .build_proposal_content_from_parts(parts, height, round)
             .await?;
 
@@ -70,24 +76,16 @@ impl HostState {
             valid_round,
             value,
             validity,
-            extension,
         })
     }
 
-    #[allow(clippy::type_complexity)]
     #[tracing::instrument(skip_all, fields(%height, %round))]
     pub async fn build_proposal_content_from_parts(
         &self,
         parts: &[Arc<ProposalPart>],
         height: Height,
         round: Round,
-    ) -> Option<(
-        Round,
-        BlockHash,
-        Address,
-        Validity,
-        Option<SignedExtension<MockContext>>,
-    )> {
+    ) -> Option<(Round, BlockHash, Address, Validity)> {
         if parts.is_empty() {
             return None;
         }
@@ -109,8 +107,6 @@ impl HostState {
 
         trace!(parts.len = %parts.len(), "Building proposal content from parts");
 
-        let extension = self.host.generate_vote_extension(height, round);
-
         let block_hash = {
             let mut block_hasher = sha3::Keccak256::new();
             for part in parts {
@@ -135,7 +131,7 @@ impl HostState {
             .verify_proposal_validity(init, &proposal_hash, &fin.signature)
             .await?;
 
-        Some((valid_round, block_hash, init.proposer, validity, extension))
+        Some((valid_round, block_hash, init.proposer, validity))
     }
 
     async fn verify_proposal_validity(
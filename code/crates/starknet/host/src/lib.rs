@@ -1,5 +1,6 @@
 pub mod actor;
 pub mod block_store;
+pub mod byzantine;
 pub mod codec;
 pub mod host;
 pub mod mempool;
@@ -7,8 +7,8 @@ use rand::RngCore;
 use tracing::{debug, info, trace};
 
 use malachitebft_config::{MempoolConfig, TestConfig};
-use malachitebft_test_mempool::types::MempoolTransactionBatch;
-use malachitebft_test_mempool::{Event as NetworkEvent, NetworkMsg, PeerId};
+use malachitebft_mempool_network::types::MempoolTransactionBatch;
+use malachitebft_mempool_network::{Event as NetworkEvent, NetworkMsg, PeerId};
 
 use crate::proto::Protobuf;
 use crate::types::{Hash, Transaction, Transactions};
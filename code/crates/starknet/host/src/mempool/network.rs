@@ -11,11 +11,11 @@ use ractor::{Actor, RpcReplyPort};
 use tokio::task::JoinHandle;
 use tracing::error;
 
+use malachitebft_mempool_network::handle::CtrlHandle;
+use malachitebft_mempool_network::types::MempoolTransactionBatch;
+use malachitebft_mempool_network::Channel::Mempool;
+use malachitebft_mempool_network::{Config, Event, NetworkMsg, PeerId};
 use malachitebft_metrics::SharedRegistry;
-use malachitebft_test_mempool::handle::CtrlHandle;
-use malachitebft_test_mempool::types::MempoolTransactionBatch;
-use malachitebft_test_mempool::Channel::Mempool;
-use malachitebft_test_mempool::{Config, Event, NetworkMsg, PeerId};
 
 pub type MempoolNetworkMsg = Msg;
 pub type MempoolNetworkRef = ActorRef<Msg>;
@@ -85,7 +85,7 @@ impl Actor for MempoolNetwork {
         args: Args,
     ) -> Result<State, ActorProcessingErr> {
         let handle =
-            malachitebft_test_mempool::spawn(args.keypair, args.config, args.metrics).await?;
+            malachitebft_mempool_network::spawn(args.keypair, args.config, args.metrics).await?;
         let (mut recv_handle, ctrl_handle) = handle.split();
 
         let recv_task = tokio::spawn(async move {
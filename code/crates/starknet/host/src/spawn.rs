@@ -2,6 +2,7 @@ use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use libp2p_identity::ecdsa;
+use malachitebft_app::streaming::StreamingMetrics;
 use malachitebft_engine::util::events::TxEvent;
 use malachitebft_engine::wal::{Wal, WalRef};
 use tokio::task::JoinHandle;
@@ -10,18 +11,20 @@ use malachitebft_config::{
     self as config, Config as NodeConfig, MempoolConfig, SyncConfig, TestConfig, TransportProtocol,
 };
 use malachitebft_core_consensus::ValuePayload;
+use malachitebft_core_types::SynchronyParams;
 use malachitebft_engine::consensus::{Consensus, ConsensusParams, ConsensusRef};
 use malachitebft_engine::host::HostRef;
 use malachitebft_engine::network::{Network, NetworkRef};
 use malachitebft_engine::node::{Node, NodeRef};
 use malachitebft_engine::sync::{Params as SyncParams, Sync, SyncRef};
+use malachitebft_mempool_network::Config as MempoolNetworkConfig;
 use malachitebft_metrics::Metrics;
 use malachitebft_metrics::SharedRegistry;
 use malachitebft_network::Keypair;
 use malachitebft_sync as sync;
-use malachitebft_test_mempool::Config as MempoolNetworkConfig;
 
 use crate::actor::Host;
+use crate::byzantine::ByzantineNetwork;
 use crate::codec::ProtobufCodec;
 use crate::host::{StarknetHost, StarknetParams};
 use crate::mempool::network::{MempoolNetwork, MempoolNetworkRef};
@@ -52,7 +55,12 @@ pub async fn spawn_node_actor(
         spawn_mempool_actor(mempool_network.clone(), &cfg.mempool, &cfg.test, &span).await;
 
     // Spawn consensus gossip
-    let network = spawn_network_actor(&cfg, &private_key, &registry, &span).await;
+    let network = spawn_network_actor(&cfg, &home_dir, &private_key, &registry, &span).await;
+
+    // If the node is configured to misbehave, route all the messages it publishes
+    // through a proxy which tampers with them before handing them to the network actor
+    let byzantine = spawn_byzantine_actor(&ctx, network.clone(), &cfg.test, &span).await;
+    let outbound = byzantine.clone().unwrap_or_else(|| network.clone());
 
     // Spawn the host actor
     let host = spawn_host_actor(
@@ -62,15 +70,16 @@ pub async fn spawn_node_actor(
         &private_key,
         &initial_validator_set,
         mempool.clone(),
-        network.clone(),
+        outbound.clone(),
         metrics.clone(),
+        StreamingMetrics::register(&registry),
         &span,
     )
     .await;
 
     let sync = spawn_sync_actor(
         ctx.clone(),
-        network.clone(),
+        outbound.clone(),
         host.clone(),
         &cfg.sync,
         &registry,
@@ -87,7 +96,7 @@ pub async fn spawn_node_actor(
         address,
         ctx.clone(),
         cfg,
-        network.clone(),
+        outbound,
         host.clone(),
         wal.clone(),
         sync.clone(),
@@ -112,9 +121,31 @@ pub async fn spawn_node_actor(
 
     let (actor_ref, handle) = node.spawn().await.unwrap();
 
+    if let Some(byzantine) = byzantine {
+        byzantine.link(actor_ref.get_cell());
+    }
+
     (actor_ref, handle)
 }
 
+async fn spawn_byzantine_actor(
+    ctx: &MockContext,
+    network: NetworkRef<MockContext>,
+    test_config: &TestConfig,
+    span: &tracing::Span,
+) -> Option<NetworkRef<MockContext>> {
+    if !test_config.byzantine.is_enabled() {
+        return None;
+    }
+
+    let actor_ref =
+        ByzantineNetwork::spawn(ctx.clone(), network, test_config.byzantine, span.clone())
+            .await
+            .unwrap();
+
+    Some(actor_ref)
+}
+
 async fn spawn_wal_actor(
     ctx: &MockContext,
     codec: ProtobufCodec,
@@ -177,12 +208,18 @@ async fn spawn_consensus_actor(
         malachitebft_config::ValuePayload::ProposalAndParts => ValuePayload::ProposalAndParts,
     };
 
+    let pbts = cfg.consensus.pbts;
+    let synchrony_params = pbts
+        .enabled
+        .then(|| SynchronyParams::new(pbts.precision, pbts.message_delay));
+
     let consensus_params = ConsensusParams {
         initial_height,
         initial_validator_set,
         address,
         threshold_params: Default::default(),
         value_payload,
+        synchrony_params,
     };
 
     Consensus::spawn(
@@ -203,6 +240,7 @@ async fn spawn_consensus_actor(
 
 async fn spawn_network_actor(
     cfg: &NodeConfig,
+    home_dir: &Path,
     private_key: &PrivateKey,
     registry: &SharedRegistry,
     span: &tracing::Span,
@@ -220,10 +258,17 @@ async fn spawn_network_actor(
     };
 
     let config_gossip = gossip::Config {
-        listen_addr: cfg.consensus.p2p.listen_addr.clone(),
+        listen_addrs: cfg.consensus.p2p.listen_addrs(),
         persistent_peers: cfg.consensus.p2p.persistent_peers.clone(),
+        peer_lists: gossip::PeerLists {
+            allowed: cfg.consensus.p2p.peer_lists.allowed.clone(),
+            denied: cfg.consensus.p2p.peer_lists.denied.clone(),
+            private: cfg.consensus.p2p.peer_lists.private.clone(),
+            unconditional: cfg.consensus.p2p.peer_lists.unconditional.clone(),
+        },
         discovery: gossip::DiscoveryConfig {
             enabled: cfg.consensus.p2p.discovery.enabled,
+            hidden: cfg.consensus.p2p.discovery.hidden,
             bootstrap_protocol,
             selector,
             num_outbound_peers: cfg.consensus.p2p.discovery.num_outbound_peers,
@@ -231,14 +276,22 @@ async fn spawn_network_actor(
             ephemeral_connection_timeout: cfg.consensus.p2p.discovery.ephemeral_connection_timeout,
             ..Default::default()
         },
+        address_book: cfg.consensus.p2p.discovery.address_book.enabled.then(|| {
+            gossip::AddressBookConfig {
+                path: home_dir.join("address_book.json"),
+                max_age: cfg.consensus.p2p.discovery.address_book.max_age,
+            }
+        }),
         idle_connection_timeout: Duration::from_secs(15 * 60),
         transport: match cfg.consensus.p2p.transport {
             TransportProtocol::Tcp => gossip::TransportProtocol::Tcp,
             TransportProtocol::Quic => gossip::TransportProtocol::Quic,
+            TransportProtocol::Both => gossip::TransportProtocol::Both,
         },
         pubsub_protocol: match cfg.consensus.p2p.protocol {
             config::PubSubProtocol::GossipSub(_) => gossip::PubSubProtocol::GossipSub,
             config::PubSubProtocol::Broadcast => gossip::PubSubProtocol::Broadcast,
+            config::PubSubProtocol::Direct => gossip::PubSubProtocol::Direct,
         },
         gossipsub: match cfg.consensus.p2p.protocol {
             config::PubSubProtocol::GossipSub(config) => gossip::GossipSubConfig {
@@ -247,10 +300,16 @@ async fn spawn_network_actor(
                 mesh_n_low: config.mesh_n_low(),
                 mesh_outbound_min: config.mesh_outbound_min(),
             },
-            config::PubSubProtocol::Broadcast => gossip::GossipSubConfig::default(),
+            config::PubSubProtocol::Broadcast | config::PubSubProtocol::Direct => {
+                gossip::GossipSubConfig::default()
+            }
         },
         rpc_max_size: cfg.consensus.p2p.rpc_max_size.as_u64() as usize,
         pubsub_max_size: cfg.consensus.p2p.pubsub_max_size.as_u64() as usize,
+        rate_limits: cfg.consensus.p2p.rate_limits,
+        compression: make_compression_config(cfg.consensus.p2p.compression),
+        nat: make_nat_config(&cfg.consensus.p2p.nat),
+        direct_streams: cfg.consensus.erasure_coding.enabled,
     };
 
     let keypair = make_keypair(private_key);
@@ -259,6 +318,7 @@ async fn spawn_network_actor(
     Network::spawn(
         keypair,
         config_gossip,
+        cfg.consensus.erasure_coding,
         registry.clone(),
         codec,
         span.clone(),
@@ -267,6 +327,35 @@ async fn spawn_network_actor(
     .unwrap()
 }
 
+fn make_nat_config(config: &config::NatConfig) -> malachitebft_network::NatConfig {
+    malachitebft_network::NatConfig {
+        autonat: config.autonat,
+        relay_server: config.relay_server,
+        relay_allowed_peers: config.relay_allowed_peers.clone(),
+        relay_client: config.relay_client,
+        relays: config.relays.clone(),
+        hole_punching: config.hole_punching,
+    }
+}
+
+fn make_compression_config(
+    config: config::CompressionConfig,
+) -> malachitebft_network::CompressionConfig {
+    malachitebft_network::CompressionConfig {
+        consensus: make_compression(config.consensus),
+        proposal_parts: make_compression(config.proposal_parts),
+        sync: make_compression(config.sync),
+        rpc: make_compression(config.rpc),
+    }
+}
+
+fn make_compression(compression: config::Compression) -> malachitebft_network::Compression {
+    match compression {
+        config::Compression::None => malachitebft_network::Compression::None,
+        config::Compression::Lz4 => malachitebft_network::Compression::Lz4,
+    }
+}
+
 fn make_keypair(private_key: &PrivateKey) -> Keypair {
     let pk_bytes = private_key.inner().to_bytes_be();
     let secret_key = ecdsa::SecretKey::try_from_bytes(pk_bytes).unwrap();
@@ -303,8 +392,11 @@ async fn spawn_mempool_network_actor(
         persistent_peers: cfg.mempool.p2p.persistent_peers.clone(),
         idle_connection_timeout: Duration::from_secs(15 * 60),
         transport: match cfg.mempool.p2p.transport {
-            TransportProtocol::Tcp => malachitebft_test_mempool::TransportProtocol::Tcp,
-            TransportProtocol::Quic => malachitebft_test_mempool::TransportProtocol::Quic,
+            TransportProtocol::Tcp => malachitebft_mempool_network::TransportProtocol::Tcp,
+            // The mempool network only supports a single transport, use the preferred one
+            TransportProtocol::Quic | TransportProtocol::Both => {
+                malachitebft_mempool_network::TransportProtocol::Quic
+            }
         },
     };
 
@@ -323,6 +415,7 @@ async fn spawn_host_actor(
     mempool: MempoolRef,
     network: NetworkRef<MockContext>,
     metrics: Metrics,
+    streaming_metrics: StreamingMetrics,
     span: &tracing::Span,
 ) -> HostRef<MockContext> {
     let value_payload = match cfg.consensus.value_payload {
@@ -340,6 +433,7 @@ async fn spawn_host_actor(
         exec_time_per_tx: cfg.test.exec_time_per_tx,
         max_retain_blocks: cfg.test.max_retain_blocks,
         vote_extensions: cfg.test.vote_extensions,
+        propose_out_of_turn: cfg.test.byzantine.propose_out_of_turn,
     };
 
     let mock_host = StarknetHost::new(
@@ -356,6 +450,7 @@ async fn spawn_host_actor(
         mempool,
         network,
         metrics,
+        streaming_metrics,
         span.clone(),
     )
     .await
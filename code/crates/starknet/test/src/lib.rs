@@ -14,8 +14,8 @@ use tokio::time::{sleep, Duration};
 use tracing::{debug, error, error_span, info, Instrument, Span};
 
 use malachitebft_config::{
-    Config as NodeConfig, Config, DiscoveryConfig, LoggingConfig, PubSubProtocol, SyncConfig,
-    TestConfig, TransportProtocol,
+    ByzantineConfig, CompressionConfig, Config as NodeConfig, Config, DiscoveryConfig,
+    ErasureCodingConfig, LoggingConfig, PubSubProtocol, SyncConfig, TestConfig, TransportProtocol,
 };
 use malachitebft_core_consensus::{SignedConsensusMsg, ValueToPropose};
 use malachitebft_core_types::{SignedVote, VotingPower};
@@ -67,6 +67,8 @@ pub struct TestParams {
     pub value_payload: ValuePayload,
     pub max_retain_blocks: usize,
     pub timeout_step: Duration,
+    pub erasure_coding: ErasureCodingConfig,
+    pub compression: CompressionConfig,
 }
 
 impl Default for TestParams {
@@ -81,6 +83,8 @@ impl Default for TestParams {
             value_payload: ValuePayload::default(),
             max_retain_blocks: 50,
             timeout_step: Duration::from_secs(30),
+            erasure_coding: ErasureCodingConfig::default(),
+            compression: CompressionConfig::default(),
         }
     }
 }
@@ -97,6 +101,8 @@ impl TestParams {
         config.test.vote_extensions.size = self.vote_extensions.unwrap_or_default();
         config.test.max_retain_blocks = self.max_retain_blocks;
         config.consensus.timeouts.timeout_step = self.timeout_step;
+        config.consensus.erasure_coding = self.erasure_coding;
+        config.consensus.p2p.compression = self.compression;
     }
 }
 
@@ -127,6 +133,7 @@ pub struct TestNode<State = ()> {
     pub voting_power: VotingPower,
     pub start_height: Height,
     pub start_delay: Duration,
+    pub byzantine: ByzantineConfig,
     pub steps: Vec<Step<State>>,
     pub state: State,
 }
@@ -145,6 +152,7 @@ impl<State> TestNode<State> {
             voting_power: 1,
             start_height: Height::new(1, 1),
             start_delay: Duration::from_secs(0),
+            byzantine: ByzantineConfig::default(),
             steps: vec![],
             state,
         }
@@ -174,6 +182,41 @@ impl<State> TestNode<State> {
         self
     }
 
+    pub fn equivocate_votes(&mut self) -> &mut Self {
+        self.byzantine.equivocate_votes = true;
+        self
+    }
+
+    pub fn equivocate_proposals(&mut self) -> &mut Self {
+        self.byzantine.equivocate_proposals = true;
+        self
+    }
+
+    pub fn vote_invalid_values(&mut self) -> &mut Self {
+        self.byzantine.vote_invalid_values = true;
+        self
+    }
+
+    pub fn withhold_proposal_parts(&mut self) -> &mut Self {
+        self.byzantine.withhold_proposal_parts = true;
+        self
+    }
+
+    pub fn delay_proposal_parts(&mut self, delay: Duration) -> &mut Self {
+        self.byzantine.delay_proposal_parts = Some(delay);
+        self
+    }
+
+    pub fn propose_out_of_turn(&mut self) -> &mut Self {
+        self.byzantine.propose_out_of_turn = true;
+        self
+    }
+
+    pub fn replay_old_rounds(&mut self) -> &mut Self {
+        self.byzantine.replay_old_rounds = true;
+        self
+    }
+
     pub fn crash(&mut self) -> &mut Self {
         self.steps.push(Step::Crash(Duration::from_secs(0)));
         self
@@ -242,6 +285,44 @@ impl<State> TestNode<State> {
         })
     }
 
+    pub fn expect_vote_equivocation(&mut self) -> &mut Self {
+        self.on_event(|event, _| {
+            let Event::VoteEquivocation(existing, conflicting) = event else {
+                return Ok(HandlerResult::WaitForNextEvent);
+            };
+
+            info!(
+                "Detected equivocating votes from {} at height {} and round {}",
+                existing.voter, existing.height, existing.round
+            );
+
+            if existing.message == conflicting.message {
+                bail!("Evidence of equivocation contains the same vote twice")
+            }
+
+            Ok(HandlerResult::ContinueTest)
+        })
+    }
+
+    pub fn expect_proposal_equivocation(&mut self) -> &mut Self {
+        self.on_event(|event, _| {
+            let Event::ProposalEquivocation(existing, conflicting) = event else {
+                return Ok(HandlerResult::WaitForNextEvent);
+            };
+
+            info!(
+                "Detected equivocating proposals from {} at height {} and round {}",
+                existing.proposer, existing.height, existing.round
+            );
+
+            if existing.message == conflicting.message {
+                bail!("Evidence of equivocation contains the same proposal twice")
+            }
+
+            Ok(HandlerResult::ContinueTest)
+        })
+    }
+
     pub fn on_proposed_value<F>(&mut self, f: F) -> &mut Self
     where
         F: Fn(ValueToPropose<MockContext>, &mut State) -> Result<HandlerResult, eyre::Report>
@@ -671,8 +752,8 @@ pub fn init_logging(test_module: &str) {
 use bytesize::ByteSize;
 
 use malachitebft_config::{
-    ConsensusConfig, MempoolConfig, MetricsConfig, P2pConfig, RuntimeConfig, TimeoutConfig,
-    ValuePayload,
+    ConsensusConfig, HostConfig, MempoolConfig, MetricsConfig, P2pConfig, PbtsConfig, RpcConfig,
+    RuntimeConfig, TimeoutConfig, ValuePayload,
 };
 
 fn transport_from_env(default: TransportProtocol) -> TransportProtocol {
@@ -694,11 +775,15 @@ pub fn make_node_config<S>(test: &Test<S>, i: usize) -> NodeConfig {
             max_block_size: ByteSize::mib(1),
             value_payload: ValuePayload::default(),
             timeouts: TimeoutConfig::default(),
+            pbts: PbtsConfig::default(),
+            erasure_coding: ErasureCodingConfig::default(),
             p2p: P2pConfig {
                 transport,
                 protocol,
                 discovery: DiscoveryConfig::default(),
                 listen_addr: transport.multiaddr("127.0.0.1", test.consensus_base_port + i),
+                additional_listen_addrs: transport
+                    .fallback_multiaddrs("127.0.0.1", test.consensus_base_port + i),
                 persistent_peers: (0..test.nodes.len())
                     .filter(|j| i != *j)
                     .map(|j| transport.multiaddr("127.0.0.1", test.consensus_base_port + j))
@@ -731,8 +816,13 @@ pub fn make_node_config<S>(test: &Test<S>, i: usize) -> NodeConfig {
                 .parse()
                 .unwrap(),
         },
+        rpc: RpcConfig::default(),
+        host: HostConfig::default(),
         runtime: RuntimeConfig::single_threaded(),
-        test: TestConfig::default(),
+        test: TestConfig {
+            byzantine: test.nodes[i].byzantine,
+            ..TestConfig::default()
+        },
     }
 }
 
@@ -0,0 +1,363 @@
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use eyre::bail;
+use tracing::info;
+
+use malachitebft_config::ValuePayload;
+use malachitebft_core_consensus::SignedConsensusMsg;
+use malachitebft_core_types::{Context, NilOrVal, Round, ValueOrigin};
+use malachitebft_engine::util::events::Event;
+use malachitebft_starknet_host::types::{Address, BlockHash, Height, MockContext, ValidatorSet};
+
+use informalsystems_malachitebft_starknet_test::{
+    init_logging, make_validators, HandlerResult, TestBuilder, TestParams,
+};
+
+/// Address of the Byzantine node, which is always the first node added to the test
+fn byzantine_address() -> Address {
+    make_validators(vec![1; 4])[0].0.address
+}
+
+/// Address of the proposer of the given height and round
+fn proposer(height: Height, round: Round) -> Address {
+    let (validators, keys): (Vec<_>, Vec<_>) = make_validators(vec![1; 4]).into_iter().unzip();
+    let ctx = MockContext::new(keys[0]);
+
+    ctx.select_proposer(&ValidatorSet::new(validators), height, round)
+        .address
+}
+
+#[tokio::test]
+pub async fn equivocating_votes() {
+    init_logging(module_path!());
+
+    const HEIGHT: u64 = 3;
+
+    let mut test = TestBuilder::<()>::new();
+
+    test.add_node()
+        .equivocate_votes()
+        .start()
+        .wait_until(HEIGHT)
+        .success();
+
+    for _ in 0..3 {
+        test.add_node()
+            .start()
+            .expect_vote_equivocation()
+            .wait_until(HEIGHT)
+            .success();
+    }
+
+    test.build().run(Duration::from_secs(30)).await
+}
+
+#[tokio::test]
+pub async fn equivocating_proposals() {
+    init_logging(module_path!());
+
+    let mut test = TestBuilder::<()>::new();
+
+    test.add_node().equivocate_proposals().start().success();
+
+    // Proposers take turns, so the Byzantine node proposes within the first 4 heights.
+    // Without parts, the host cannot tell which of the conflicting values is invalid
+    // and honest nodes may well decide on it, so we stop as soon as the fault is detected.
+    for _ in 0..3 {
+        test.add_node()
+            .start()
+            .expect_proposal_equivocation()
+            .success();
+    }
+
+    // Conflicting proposals are only detected when proposals are sent over the network
+    let params = TestParams {
+        value_payload: ValuePayload::ProposalOnly,
+        ..Default::default()
+    };
+
+    test.build()
+        .run_with_custom_config(Duration::from_secs(60), params)
+        .await
+}
+
+#[tokio::test]
+pub async fn votes_for_invalid_values() {
+    init_logging(module_path!());
+
+    const HEIGHT: u64 = 5;
+
+    let mut test = TestBuilder::<()>::new();
+
+    test.add_node()
+        .vote_invalid_values()
+        .start()
+        .wait_until(HEIGHT)
+        .success();
+
+    let byzantine = byzantine_address();
+
+    // The precommits of the Byzantine node are for a value which was never proposed,
+    // so they must never count towards a decision
+    for _ in 0..3 {
+        test.add_node()
+            .start()
+            .on_event(move |event, _| {
+                let Event::Decided(certificate) = event else {
+                    return Ok(HandlerResult::WaitForNextEvent);
+                };
+
+                let signers = &certificate.aggregated_signature.signatures;
+                if signers.iter().any(|s| s.address == byzantine) {
+                    bail!(
+                        "Decided at height {} with a precommit from the Byzantine node",
+                        certificate.height
+                    )
+                }
+
+                if certificate.height.as_u64() < HEIGHT {
+                    Ok(HandlerResult::WaitForNextEvent)
+                } else {
+                    Ok(HandlerResult::ContinueTest)
+                }
+            })
+            .success();
+    }
+
+    test.build().run(Duration::from_secs(60)).await
+}
+
+#[tokio::test]
+pub async fn withheld_proposal_parts() {
+    init_logging(module_path!());
+
+    #[derive(Clone, Debug, Default)]
+    struct State {
+        later_round_decisions: usize,
+    }
+
+    const HEIGHT: u64 = 5;
+
+    let mut test = TestBuilder::<State>::new();
+
+    test.add_node()
+        .withhold_proposal_parts()
+        .start()
+        .wait_until(HEIGHT)
+        .success();
+
+    let byzantine = byzantine_address();
+
+    // Proposers take turns, so the Byzantine node proposes within the first 4 heights.
+    // Its value never reaches the other nodes, which must move on to the next round instead.
+    for _ in 0..3 {
+        test.add_node()
+            .start()
+            .on_event(move |event, state| match event {
+                Event::ReceivedProposedValue(value, ValueOrigin::Consensus)
+                    if value.proposer == byzantine =>
+                {
+                    bail!(
+                        "Received a value at height {} and round {} whose parts were withheld",
+                        value.height,
+                        value.round
+                    )
+                }
+
+                Event::Decided(certificate) => {
+                    if certificate.round > Round::new(0) {
+                        state.later_round_decisions += 1;
+                    }
+
+                    if certificate.height.as_u64() < HEIGHT {
+                        return Ok(HandlerResult::WaitForNextEvent);
+                    }
+
+                    if state.later_round_decisions == 0 {
+                        bail!("Never skipped the round of the Byzantine proposer")
+                    }
+
+                    Ok(HandlerResult::ContinueTest)
+                }
+
+                _ => Ok(HandlerResult::WaitForNextEvent),
+            })
+            .success();
+    }
+
+    test.build().run(Duration::from_secs(60)).await
+}
+
+#[tokio::test]
+pub async fn delayed_proposal_parts() {
+    init_logging(module_path!());
+
+    #[derive(Clone, Debug, Default)]
+    struct State {
+        later_round_decisions: usize,
+    }
+
+    const HEIGHT: u64 = 5;
+
+    let mut test = TestBuilder::<State>::new();
+
+    // Parts arrive after the propose timeout has elapsed on the other nodes
+    test.add_node()
+        .delay_proposal_parts(Duration::from_secs(5))
+        .start()
+        .wait_until(HEIGHT)
+        .success();
+
+    // The other nodes must not wait for the late value, and move on to the next round instead
+    for _ in 0..3 {
+        test.add_node()
+            .start()
+            .on_event(move |event, state| {
+                let Event::Decided(certificate) = event else {
+                    return Ok(HandlerResult::WaitForNextEvent);
+                };
+
+                if certificate.round > Round::new(0) {
+                    state.later_round_decisions += 1;
+                }
+
+                if certificate.height.as_u64() < HEIGHT {
+                    return Ok(HandlerResult::WaitForNextEvent);
+                }
+
+                if state.later_round_decisions == 0 {
+                    bail!("Never skipped the round of the Byzantine proposer")
+                }
+
+                Ok(HandlerResult::ContinueTest)
+            })
+            .success();
+    }
+
+    test.build().run(Duration::from_secs(60)).await
+}
+
+#[tokio::test]
+pub async fn proposing_out_of_turn() {
+    init_logging(module_path!());
+
+    #[derive(Clone, Debug, Default)]
+    struct State {
+        /// Values received from the Byzantine node in rounds where it was not the proposer
+        out_of_turn: BTreeSet<(Height, Round, BlockHash)>,
+        /// Values we voted for, per height and round
+        voted: BTreeSet<(Height, Round, BlockHash)>,
+    }
+
+    const HEIGHT: u64 = 5;
+
+    let mut test = TestBuilder::<State>::new();
+
+    test.add_node()
+        .propose_out_of_turn()
+        .start()
+        .wait_until(HEIGHT)
+        .success();
+
+    let byzantine = byzantine_address();
+
+    // Values proposed out of turn must never be voted for
+    for _ in 0..3 {
+        test.add_node()
+            .start()
+            .on_event(move |event, state| match event {
+                Event::ReceivedProposedValue(value, ValueOrigin::Consensus)
+                    if value.proposer == byzantine
+                        && proposer(value.height, value.round) != byzantine =>
+                {
+                    state
+                        .out_of_turn
+                        .insert((value.height, value.round, value.value));
+
+                    Ok(HandlerResult::WaitForNextEvent)
+                }
+
+                Event::Published(SignedConsensusMsg::Vote(vote)) => {
+                    if let NilOrVal::Val(value) = vote.block_hash {
+                        state.voted.insert((vote.height, vote.round, value));
+                    }
+
+                    Ok(HandlerResult::WaitForNextEvent)
+                }
+
+                Event::Decided(certificate) if certificate.height.as_u64() >= HEIGHT => {
+                    if state.out_of_turn.is_empty() {
+                        bail!("Never received a value proposed out of turn")
+                    }
+
+                    if let Some((height, round, value)) =
+                        state.out_of_turn.intersection(&state.voted).next()
+                    {
+                        bail!(
+                            "Voted for value {value} proposed out of turn at height {height} and round {round}"
+                        )
+                    }
+
+                    info!(
+                        "Ignored {} values proposed out of turn",
+                        state.out_of_turn.len()
+                    );
+
+                    Ok(HandlerResult::ContinueTest)
+                }
+
+                _ => Ok(HandlerResult::WaitForNextEvent),
+            })
+            .success();
+    }
+
+    // Rounds of honest proposers are disrupted by the conflicting values, so heights take longer to decide
+    test.build().run(Duration::from_secs(120)).await
+}
+
+#[tokio::test]
+pub async fn replaying_old_rounds() {
+    init_logging(module_path!());
+
+    const HEIGHT: u64 = 5;
+
+    let mut test = TestBuilder::<()>::new();
+
+    test.add_node()
+        .replay_old_rounds()
+        .start()
+        .wait_until(HEIGHT)
+        .success();
+
+    // Replayed messages are duplicates of messages already received,
+    // so they must not be mistaken for evidence of equivocation
+    for _ in 0..3 {
+        test.add_node()
+            .start()
+            .on_event(|event, _| match event {
+                Event::VoteEquivocation(existing, _) => {
+                    bail!(
+                        "Replayed votes from {} taken as equivocation",
+                        existing.voter
+                    )
+                }
+
+                Event::ProposalEquivocation(existing, _) => {
+                    bail!(
+                        "Replayed proposals from {} taken as equivocation",
+                        existing.proposer
+                    )
+                }
+
+                Event::Decided(certificate) if certificate.height.as_u64() >= HEIGHT => {
+                    Ok(HandlerResult::ContinueTest)
+                }
+
+                _ => Ok(HandlerResult::WaitForNextEvent),
+            })
+            .success();
+    }
+
+    test.build().run(Duration::from_secs(60)).await
+}
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use bytesize::ByteSize;
+use informalsystems_malachitebft_starknet_test::{init_logging, TestBuilder, TestParams};
+use malachitebft_config::{ErasureCodingConfig, GossipSubConfig, PubSubProtocol};
+
+async fn run_test(params: TestParams) {
+    init_logging(module_path!());
+
+    const HEIGHT: u64 = 5;
+
+    let mut test = TestBuilder::<()>::new();
+
+    test.add_node().start().wait_until(HEIGHT).success();
+    test.add_node().start().wait_until(HEIGHT).success();
+    test.add_node().start().wait_until(HEIGHT).success();
+
+    test.build()
+        .run_with_custom_config(Duration::from_secs(30), params)
+        .await
+}
+
+fn erasure_coding() -> ErasureCodingConfig {
+    ErasureCodingConfig {
+        enabled: true,
+        data_shards: 4,
+        parity_shards: 2,
+    }
+}
+
+#[tokio::test]
+pub async fn gossip_erasure_coding() {
+    let params = TestParams {
+        protocol: PubSubProtocol::GossipSub(GossipSubConfig::default()),
+        block_size: ByteSize::kib(64),
+        tx_size: ByteSize::kib(1),
+        txs_per_part: 16,
+        erasure_coding: erasure_coding(),
+        ..Default::default()
+    };
+
+    run_test(params).await
+}
+
+#[tokio::test]
+pub async fn broadcast_erasure_coding() {
+    let params = TestParams {
+        protocol: PubSubProtocol::Broadcast,
+        block_size: ByteSize::kib(64),
+        tx_size: ByteSize::kib(1),
+        txs_per_part: 16,
+        erasure_coding: erasure_coding(),
+        ..Default::default()
+    };
+
+    run_test(params).await
+}
@@ -75,3 +75,31 @@ pub async fn gossip_custom_config_2ktx() {
 
     run_test(params).await
 }
+
+#[tokio::test]
+pub async fn direct_custom_config_1ktx() {
+    let params = TestParams {
+        enable_sync: false,
+        protocol: PubSubProtocol::Direct,
+        block_size: ByteSize::kib(1),
+        tx_size: ByteSize::kib(1),
+        txs_per_part: 1,
+        ..Default::default()
+    };
+
+    run_test(params).await
+}
+
+#[tokio::test]
+pub async fn direct_custom_config_2ktx() {
+    let params = TestParams {
+        enable_sync: false,
+        protocol: PubSubProtocol::Direct,
+        block_size: ByteSize::kib(2),
+        tx_size: ByteSize::kib(2),
+        txs_per_part: 1,
+        ..Default::default()
+    };
+
+    run_test(params).await
+}
@@ -1,7 +1,7 @@
 use std::time::Duration;
 
 use informalsystems_malachitebft_starknet_test::{init_logging, TestBuilder, TestParams};
-use malachitebft_config::ValuePayload;
+use malachitebft_config::{Compression, CompressionConfig, ValuePayload};
 
 pub async fn crash_restart_from_start(params: TestParams) {
     init_logging(module_path!());
@@ -84,6 +84,21 @@ pub async fn crash_restart_from_start_proposal_and_parts() {
     crash_restart_from_start(params).await
 }
 
+#[tokio::test]
+pub async fn crash_restart_from_start_compressed() {
+    let params = TestParams {
+        compression: CompressionConfig {
+            consensus: Compression::Lz4,
+            proposal_parts: Compression::Lz4,
+            sync: Compression::Lz4,
+            rpc: Compression::Lz4,
+        },
+        ..Default::default()
+    };
+
+    crash_restart_from_start(params).await
+}
+
 #[tokio::test]
 pub async fn crash_restart_from_latest() {
     init_logging(module_path!());
@@ -25,6 +25,9 @@ pub struct Config {
     pub request_timeout: Duration,
     pub max_request_size: usize,
     pub max_response_size: usize,
+    /// Whether to request compressed responses, and to compress the responses to the
+    /// peers requesting them
+    pub compression: bool,
 }
 
 impl Config {
@@ -42,6 +45,11 @@ impl Config {
         self.max_response_size = max_response_size;
         self
     }
+
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
 }
 
 impl Default for Config {
@@ -50,21 +58,28 @@ impl Default for Config {
             request_timeout: Duration::from_secs(30),
             max_request_size: 1024 * 1024,        // 1 MiB
             max_response_size: 512 * 1024 * 1024, // 512 MiB
+            compression: false,
         }
     }
 }
 
 impl Behaviour {
-    pub const PROTOCOL: [(StreamProtocol, ProtocolSupport); 1] = [(
-        StreamProtocol::new("/malachitebft-sync/v1beta1"),
-        ProtocolSupport::Full,
-    )];
+    pub const PROTOCOL: StreamProtocol = StreamProtocol::new("/malachitebft-sync/v1beta1");
+
+    /// Protocol over which the responses are compressed, preferred over [`Self::PROTOCOL`]
+    /// when both peers have compression enabled
+    pub const COMPRESSED_PROTOCOL: StreamProtocol =
+        StreamProtocol::new("/malachitebft-sync/v1beta1+compressed");
 
     pub fn new(config: Config) -> Self {
         let rpc_config = rpc::Config::default().with_request_timeout(config.request_timeout);
 
         Self {
-            rpc: rpc::Behaviour::with_codec(Codec::new(config), Self::PROTOCOL, rpc_config),
+            rpc: rpc::Behaviour::with_codec(
+                Codec::new(config),
+                Self::protocols(config),
+                rpc_config,
+            ),
             // metrics: None,
         }
     }
@@ -73,19 +88,36 @@ impl Behaviour {
         let rpc_config = rpc::Config::default().with_request_timeout(config.request_timeout);
 
         Self {
-            rpc: rpc::Behaviour::with_codec(Codec::new(config), Self::PROTOCOL, rpc_config),
+            rpc: rpc::Behaviour::with_codec(
+                Codec::new(config),
+                Self::protocols(config),
+                rpc_config,
+            ),
             // metrics: Some(Metrics::new(registry)),
         }
     }
 
+    fn protocols(config: Config) -> Vec<(StreamProtocol, ProtocolSupport)> {
+        let mut protocols = Vec::with_capacity(2);
+
+        if config.compression {
+            protocols.push((Self::COMPRESSED_PROTOCOL, ProtocolSupport::Full));
+        }
+
+        protocols.push((Self::PROTOCOL, ProtocolSupport::Full));
+        protocols
+    }
+
+    /// Send the response to a request, which must be compressed if the request was received
+    /// over the compressed protocol
     pub fn send_response(&mut self, channel: ResponseChannel, data: Bytes) -> Result<(), Error> {
         self.rpc
-            .send_response(channel, RawResponse(data))
+            .send_response(channel, RawResponse::new(data))
             .map_err(|_| Error::SendResponse)
     }
 
     pub fn send_request(&mut self, peer: PeerId, data: Bytes) -> OutboundRequestId {
-        self.rpc.send_request(&peer, RawRequest(data))
+        self.rpc.send_request(&peer, RawRequest::new(data))
     }
 }
 
@@ -3,7 +3,7 @@ use bytes::Bytes;
 use libp2p::futures::{io, AsyncRead, AsyncWrite};
 use libp2p::StreamProtocol;
 
-use crate::behaviour::Config;
+use crate::behaviour::{Behaviour, Config};
 use crate::types::{RawRequest, RawResponse};
 
 #[derive(Copy, Clone)]
@@ -24,26 +24,36 @@ impl libp2p::request_response::Codec for Codec {
     type Request = RawRequest;
     type Response = RawResponse;
 
-    async fn read_request<T>(&mut self, _: &Self::Protocol, io: &mut T) -> io::Result<Self::Request>
+    async fn read_request<T>(
+        &mut self,
+        protocol: &Self::Protocol,
+        io: &mut T,
+    ) -> io::Result<Self::Request>
     where
         T: AsyncRead + Unpin + Send,
     {
-        read_length_prefixed(io, self.config.max_request_size)
-            .await
-            .map(RawRequest)
+        let data = read_length_prefixed(io, self.config.max_request_size).await?;
+
+        Ok(RawRequest {
+            data,
+            compressed: is_compressed(protocol),
+        })
     }
 
     async fn read_response<T>(
         &mut self,
-        _: &Self::Protocol,
+        protocol: &Self::Protocol,
         io: &mut T,
     ) -> io::Result<Self::Response>
     where
         T: AsyncRead + Unpin + Send,
     {
-        read_length_prefixed(io, self.config.max_response_size)
-            .await
-            .map(RawResponse)
+        let data = read_length_prefixed(io, self.config.max_response_size).await?;
+
+        Ok(RawResponse {
+            data,
+            compressed: is_compressed(protocol),
+        })
     }
 
     async fn write_request<T>(
@@ -55,7 +65,7 @@ impl libp2p::request_response::Codec for Codec {
     where
         T: AsyncWrite + Unpin + Send,
     {
-        write_length_prefixed(io, req.0, self.config.max_request_size).await
+        write_length_prefixed(io, req.data, self.config.max_request_size).await
     }
 
     async fn write_response<T>(
@@ -67,10 +77,14 @@ impl libp2p::request_response::Codec for Codec {
     where
         T: AsyncWrite + Unpin + Send,
     {
-        write_length_prefixed(io, res.0, self.config.max_response_size).await
+        write_length_prefixed(io, res.data, self.config.max_response_size).await
     }
 }
 
+fn is_compressed(protocol: &StreamProtocol) -> bool {
+    *protocol == Behaviour::COMPRESSED_PROTOCOL
+}
+
 const U32_LENGTH: usize = size_of::<u32>();
 
 async fn write_length_prefixed<T>(dst: &mut T, data: Bytes, max_len: usize) -> io::Result<()>
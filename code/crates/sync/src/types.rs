@@ -103,10 +103,37 @@ pub enum RawMessage {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
-pub struct RawRequest(pub Bytes);
+pub struct RawRequest {
+    pub data: Bytes,
+    /// Whether the request was received over the compressed protocol,
+    /// in which case its response must be compressed
+    pub compressed: bool,
+}
+
+impl RawRequest {
+    pub fn new(data: Bytes) -> Self {
+        Self {
+            data,
+            compressed: false,
+        }
+    }
+}
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
-pub struct RawResponse(pub Bytes);
+pub struct RawResponse {
+    pub data: Bytes,
+    /// Whether the response was received over the compressed protocol
+    pub compressed: bool,
+}
+
+impl RawResponse {
+    pub fn new(data: Bytes) -> Self {
+        Self {
+            data,
+            compressed: false,
+        }
+    }
+}
 
 #[derive_where(Clone, Debug, PartialEq, Eq)]
 pub struct VoteSetRequest<Ctx: Context> {
@@ -80,6 +80,7 @@ pub struct DistributedTestnetCmd {
     /// The transport protocol to use for P2P communication
     /// Possible values:
     /// - "quic": QUIC (default)
+    /// - "both": TCP and QUIC, dialing over QUIC first
     /// - "tcp": TCP + Noise
     #[clap(short, long, default_value = "quic", verbatim_doc_comment)]
     pub transport: TransportProtocol,
@@ -201,6 +202,7 @@ where
 const CONSENSUS_BASE_PORT: usize = 27000;
 const MEMPOOL_BASE_PORT: usize = 28000;
 const METRICS_BASE_PORT: usize = 29000;
+const RPC_BASE_PORT: usize = 30000;
 
 /// Generate configuration for node "index" out of "total" number of nodes.
 #[allow(clippy::too_many_arguments)]
@@ -223,6 +225,7 @@ fn generate_distributed_config(
     let consensus_port = CONSENSUS_BASE_PORT + (index / machines.len());
     let mempool_port = MEMPOOL_BASE_PORT + (index / machines.len());
     let metrics_port = METRICS_BASE_PORT + (index / machines.len());
+    let rpc_port = RPC_BASE_PORT + (index / machines.len());
 
     Config {
         moniker: format!("test-{}", index),
@@ -230,9 +233,12 @@ fn generate_distributed_config(
             max_block_size: ByteSize::mib(1),
             value_payload: ValuePayload::default(),
             timeouts: TimeoutConfig::default(),
+            pbts: PbtsConfig::default(),
+            erasure_coding: ErasureCodingConfig::default(),
             p2p: P2pConfig {
                 protocol: PubSubProtocol::default(),
                 listen_addr: transport.multiaddr(&machine, consensus_port),
+                additional_listen_addrs: transport.fallback_multiaddrs(&machine, consensus_port),
                 persistent_peers: if enable_discovery {
                     let peers =
                         ((index.saturating_sub(bootstrap_set_size))..index).collect::<Vec<_>>();
@@ -262,6 +268,7 @@ fn generate_distributed_config(
                 },
                 discovery: DiscoveryConfig {
                     enabled: enable_discovery,
+                    hidden: false,
                     bootstrap_protocol,
                     selector,
                     num_outbound_peers,
@@ -269,6 +276,7 @@ fn generate_distributed_config(
                     ephemeral_connection_timeout: Duration::from_millis(
                         ephemeral_connection_timeout_ms,
                     ),
+                    address_book: Default::default(),
                 },
                 transport,
                 ..Default::default()
@@ -281,11 +289,13 @@ fn generate_distributed_config(
                 persistent_peers: vec![],
                 discovery: DiscoveryConfig {
                     enabled: false,
+                    hidden: false,
                     bootstrap_protocol,
                     selector,
                     num_outbound_peers: 0,
                     num_inbound_peers: 0,
                     ephemeral_connection_timeout: Duration::from_secs(0),
+                    address_book: Default::default(),
                 },
                 transport,
                 ..Default::default()
@@ -302,6 +312,11 @@ fn generate_distributed_config(
             enabled: true,
             listen_addr: format!("{machine}:{metrics_port}").parse().unwrap(),
         },
+        rpc: RpcConfig {
+            enabled: true,
+            listen_addr: format!("{machine}:{rpc_port}").parse().unwrap(),
+        },
+        host: HostConfig::default(),
         logging,
         runtime,
         test: TestConfig::default(),
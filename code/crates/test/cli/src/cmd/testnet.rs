@@ -99,6 +99,7 @@ pub struct TestnetCmd {
     /// Possible values:
     /// - "tcp": TCP + Noise (default)
     /// - "quic": QUIC
+    /// - "both": TCP and QUIC, dialing over QUIC first
     #[clap(short, long, default_value = "tcp", verbatim_doc_comment)]
     pub transport: TransportProtocol,
 }
@@ -18,6 +18,7 @@ const MAX_VOTING_POWER: u64 = 1;
 const CONSENSUS_BASE_PORT: usize = 27000;
 const MEMPOOL_BASE_PORT: usize = 28000;
 const METRICS_BASE_PORT: usize = 29000;
+const RPC_BASE_PORT: usize = 30000;
 
 /// Generate private keys. Random or deterministic for different use-cases.
 pub fn generate_private_keys<N>(
@@ -79,6 +80,7 @@ pub fn generate_config(
     let consensus_port = CONSENSUS_BASE_PORT + index;
     let mempool_port = MEMPOOL_BASE_PORT + index;
     let metrics_port = METRICS_BASE_PORT + index;
+    let rpc_port = RPC_BASE_PORT + index;
 
     Config {
         moniker: format!("test-{}", index),
@@ -86,9 +88,12 @@ pub fn generate_config(
             max_block_size: ByteSize::mib(1),
             value_payload: ValuePayload::default(),
             timeouts: TimeoutConfig::default(),
+            pbts: PbtsConfig::default(),
+            erasure_coding: ErasureCodingConfig::default(),
             p2p: P2pConfig {
                 protocol: PubSubProtocol::default(),
                 listen_addr: transport.multiaddr("127.0.0.1", consensus_port),
+                additional_listen_addrs: transport.fallback_multiaddrs("127.0.0.1", consensus_port),
                 persistent_peers: if enable_discovery {
                     let mut rng = rand::thread_rng();
                     let count = if total > 1 {
@@ -113,6 +118,7 @@ pub fn generate_config(
                 },
                 discovery: DiscoveryConfig {
                     enabled: enable_discovery,
+                    hidden: false,
                     bootstrap_protocol,
                     selector,
                     num_outbound_peers,
@@ -120,6 +126,7 @@ pub fn generate_config(
                     ephemeral_connection_timeout: Duration::from_millis(
                         ephemeral_connection_timeout_ms,
                     ),
+                    address_book: Default::default(),
                 },
                 transport,
                 ..Default::default()
@@ -135,6 +142,7 @@ pub fn generate_config(
                     .collect(),
                 discovery: DiscoveryConfig {
                     enabled: false,
+                    hidden: false,
                     bootstrap_protocol,
                     selector,
                     num_outbound_peers,
@@ -142,6 +150,7 @@ pub fn generate_config(
                     ephemeral_connection_timeout: Duration::from_millis(
                         ephemeral_connection_timeout_ms,
                     ),
+                    address_book: Default::default(),
                 },
                 transport,
                 ..Default::default()
@@ -154,6 +163,11 @@ pub fn generate_config(
             enabled: true,
             listen_addr: format!("127.0.0.1:{metrics_port}").parse().unwrap(),
         },
+        rpc: RpcConfig {
+            enabled: true,
+            listen_addr: format!("127.0.0.1:{rpc_port}").parse().unwrap(),
+        },
+        host: HostConfig::default(),
         logging,
         runtime,
         test: TestConfig::default(),
@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+/// How a Byzantine node deviates from the protocol.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Behavior {
+    /// Follows the protocol locally but never sends any message.
+    Silent,
+
+    /// Follows the protocol until the given virtual time, then stops
+    /// processing and sending messages altogether.
+    Crash { at: Duration },
+
+    /// Sends conflicting proposals and votes to different halves of its peers:
+    /// peers in even positions receive the message produced by consensus,
+    /// while peers in odd positions receive a conflicting, validly signed, message.
+    /// All equivocating nodes collude, and conflict on the same value at each round.
+    Equivocate,
+}
+
+impl Behavior {
+    /// Whether a node with this behavior has crashed at the given time
+    pub fn has_crashed(&self, now: Duration) -> bool {
+        matches!(self, Self::Crash { at } if now >= *at)
+    }
+
+    /// Whether a node with this behavior sends messages to its peers
+    pub fn sends_messages(&self) -> bool {
+        !matches!(self, Self::Silent)
+    }
+}
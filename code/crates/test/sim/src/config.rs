@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use malachitebft_config::TimeoutConfig;
+use malachitebft_core_types::VotingPower;
+
+use crate::byzantine::Behavior;
+
+/// Index of a node in the simulation, ie. its position in [`SimConfig::voting_powers`].
+pub type NodeId = usize;
+
+/// Configuration of a simulation run.
+#[derive(Clone, Debug)]
+pub struct SimConfig {
+    /// Seed from which all the randomness in the simulation is derived.
+    ///
+    /// Two runs with the same configuration and seed go through the exact same steps.
+    pub seed: u64,
+
+    /// Voting power of each node, one node is created per entry
+    pub voting_powers: Vec<VotingPower>,
+
+    /// The simulation completes once every correct node has decided at this height
+    pub target_height: u64,
+
+    /// Virtual time after which the simulation is stopped,
+    /// whether or not the target height has been reached
+    pub max_time: Duration,
+
+    /// Consensus timeouts, expressed in virtual time
+    pub timeouts: TimeoutConfig,
+
+    /// Interval at which nodes ask a random peer for decided values they are missing
+    pub sync_interval: Duration,
+
+    /// Behavior of the simulated network
+    pub network: NetworkConfig,
+
+    /// Nodes which do not follow the protocol, all other nodes are correct
+    pub byzantine: BTreeMap<NodeId, Behavior>,
+}
+
+impl SimConfig {
+    /// Create a configuration for `num_nodes` nodes with equal voting power,
+    /// over a reliable network.
+    pub fn new(seed: u64, num_nodes: usize) -> Self {
+        Self {
+            seed,
+            voting_powers: vec![1; num_nodes],
+            target_height: 3,
+            max_time: Duration::from_secs(600),
+            timeouts: TimeoutConfig {
+                timeout_step: Duration::from_secs(5),
+                ..TimeoutConfig::default()
+            },
+            sync_interval: Duration::from_secs(1),
+            network: NetworkConfig::default(),
+            byzantine: BTreeMap::new(),
+        }
+    }
+
+    /// Number of nodes in the simulation
+    pub fn num_nodes(&self) -> usize {
+        self.voting_powers.len()
+    }
+
+    /// Whether the given node follows the protocol
+    pub fn is_correct(&self, node: NodeId) -> bool {
+        !self.byzantine.contains_key(&node)
+    }
+}
+
+/// Behavior of the simulated network.
+#[derive(Clone, Debug)]
+pub struct NetworkConfig {
+    /// Minimum latency of a message
+    pub min_latency: Duration,
+
+    /// Maximum latency of a message, actual latencies are uniformly distributed
+    /// between [`min_latency`](Self::min_latency) and this value
+    pub max_latency: Duration,
+
+    /// Probability for any given message to be dropped, between 0.0 and 1.0.
+    ///
+    /// Dropped messages are never retransmitted, so consensus is not guaranteed
+    /// to make progress when this is non-zero.
+    pub drop_probability: f64,
+
+    /// Network partitions, messages sent across partitions while they are active are dropped
+    pub partitions: Vec<Partition>,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            min_latency: Duration::from_millis(10),
+            max_latency: Duration::from_millis(100),
+            drop_probability: 0.0,
+            partitions: Vec::new(),
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// Whether a message sent at `time` from `from` to `to` is blocked by an active partition
+    pub fn is_partitioned(&self, time: Duration, from: NodeId, to: NodeId) -> bool {
+        self.partitions
+            .iter()
+            .any(|p| p.is_active(time) && p.separates(from, to))
+    }
+}
+
+/// A network partition, active between [`start`](Self::start) (inclusive)
+/// and [`end`](Self::end) (exclusive).
+///
+/// Nodes can only communicate with nodes in the same group.
+/// Nodes which are not in any group are isolated from every other node.
+#[derive(Clone, Debug)]
+pub struct Partition {
+    pub start: Duration,
+    pub end: Duration,
+    pub groups: Vec<Vec<NodeId>>,
+}
+
+impl Partition {
+    pub fn new(start: Duration, end: Duration, groups: Vec<Vec<NodeId>>) -> Self {
+        Self { start, end, groups }
+    }
+
+    pub fn is_active(&self, time: Duration) -> bool {
+        self.start <= time && time < self.end
+    }
+
+    pub fn separates(&self, from: NodeId, to: NodeId) -> bool {
+        !self
+            .groups
+            .iter()
+            .any(|group| group.contains(&from) && group.contains(&to))
+    }
+}
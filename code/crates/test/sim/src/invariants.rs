@@ -0,0 +1,135 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::time::Duration;
+
+use malachitebft_core_types::Round;
+use malachitebft_test::{Height, Value, ValueId};
+
+use crate::config::{NodeId, SimConfig};
+
+/// A value decided by a node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Decision {
+    pub node: NodeId,
+    pub height: Height,
+    pub round: Round,
+    pub value_id: ValueId,
+
+    /// Virtual time at which the decision was made
+    pub time: Duration,
+}
+
+/// A safety property which was violated during a simulation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// Two correct nodes decided different values at the same height
+    Agreement {
+        height: Height,
+        first: (NodeId, ValueId),
+        second: (NodeId, ValueId),
+    },
+
+    /// A correct node decided a value which was never proposed at that height
+    Validity {
+        node: NodeId,
+        height: Height,
+        value_id: ValueId,
+    },
+
+    /// A correct node decided more than once at the same height
+    Integrity { node: NodeId, height: Height },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Agreement {
+                height,
+                first,
+                second,
+            } => write!(
+                f,
+                "agreement violated at height {height}: node {} decided {} but node {} decided {}",
+                first.0, first.1, second.0, second.1
+            ),
+            Self::Validity {
+                node,
+                height,
+                value_id,
+            } => write!(
+                f,
+                "validity violated at height {height}: node {node} decided {value_id} which was never proposed"
+            ),
+            Self::Integrity { node, height } => {
+                write!(f, "integrity violated: node {node} decided twice at height {height}")
+            }
+        }
+    }
+}
+
+/// Everything that was proposed and decided during a simulation,
+/// against which the safety invariants are checked.
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    proposed: BTreeMap<(Height, ValueId), Value>,
+    decisions: Vec<Decision>,
+}
+
+impl History {
+    pub fn record_proposal(&mut self, height: Height, value: Value) {
+        self.proposed.insert((height, value.id()), value);
+    }
+
+    pub fn record_decision(&mut self, decision: Decision) {
+        self.decisions.push(decision);
+    }
+
+    /// The value with the given id proposed at the given height, if any
+    pub fn proposed_value(&self, height: Height, value_id: ValueId) -> Option<Value> {
+        self.proposed.get(&(height, value_id)).copied()
+    }
+
+    pub fn decisions(&self) -> &[Decision] {
+        &self.decisions
+    }
+
+    pub fn has_decided(&self, node: NodeId, height: Height) -> bool {
+        self.decisions
+            .iter()
+            .any(|d| d.node == node && d.height == height)
+    }
+
+    /// Check agreement, validity and integrity for the decisions of all correct nodes
+    pub fn check(&self, config: &SimConfig) -> Result<(), Violation> {
+        let mut decided: BTreeMap<Height, (NodeId, ValueId)> = BTreeMap::new();
+        let mut seen: BTreeSet<(NodeId, Height)> = BTreeSet::new();
+
+        for d in self.decisions.iter().filter(|d| config.is_correct(d.node)) {
+            if !seen.insert((d.node, d.height)) {
+                return Err(Violation::Integrity {
+                    node: d.node,
+                    height: d.height,
+                });
+            }
+
+            if !self.proposed.contains_key(&(d.height, d.value_id)) {
+                return Err(Violation::Validity {
+                    node: d.node,
+                    height: d.height,
+                    value_id: d.value_id,
+                });
+            }
+
+            let first = *decided.entry(d.height).or_insert((d.node, d.value_id));
+            if first.1 != d.value_id {
+                return Err(Violation::Agreement {
+                    height: d.height,
+                    first,
+                    second: (d.node, d.value_id),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+//! Deterministic in-process simulator for the Malachite consensus core.
+//!
+//! The simulator runs several instances of the consensus [`State`][state] in a single thread,
+//! driving each of them with the [`process!`][process] macro. Messages, timeouts and locally
+//! produced inputs are all events scheduled in virtual time by a seeded scheduler,
+//! so that a run is entirely determined by its [`SimConfig`], including its seed.
+//!
+//! The simulated network supports configurable latency, message drops and partitions,
+//! and some nodes can be configured to exhibit [Byzantine behaviors](Behavior).
+//! After every event, the simulator checks that correct nodes never decide different
+//! values at the same height (agreement), never decide a value that was not proposed
+//! (validity), and never decide twice at the same height (integrity).
+//!
+//! Any [`Failure`] reports the seed needed to reproduce it.
+//!
+//! # Example
+//!
+//! ```rust
+//! use informalsystems_malachitebft_test_sim::{SimConfig, Simulator};
+//!
+//! let outcome = Simulator::new(SimConfig::new(42, 4)).run().unwrap();
+//! assert!(outcome.completed);
+//! ```
+//!
+//! [state]: malachitebft_core_consensus::State
+//! [process]: malachitebft_core_consensus::process
+
+mod byzantine;
+mod config;
+mod invariants;
+mod node;
+mod scheduler;
+mod sim;
+
+pub use byzantine::Behavior;
+pub use config::{NetworkConfig, NodeId, Partition, SimConfig};
+pub use invariants::{Decision, Violation};
+pub use scheduler::NetworkStats;
+pub use sim::{Failure, Outcome, Simulator};
@@ -0,0 +1,369 @@
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::time::Duration;
+
+use rand::Rng;
+
+use malachitebft_config::TimeoutConfig;
+use malachitebft_core_consensus::{
+    process, ConsensusMsg, Effect, Error, Input, Metrics, ParamsUpdate, Resumable, Resume,
+    SignedConsensusMsg, State, ValueToPropose, ValueValidity,
+};
+use malachitebft_core_types::{
+    CommitCertificate, Context, NilOrVal, Round, SignedMessage, SigningProvider,
+    SigningProviderExt, Timeout, Timestamp, Validity,
+};
+use malachitebft_test::{Height, Proposal, TestContext, ValidatorSet, Value, Vote};
+
+use crate::byzantine::Behavior;
+use crate::config::NodeId;
+use crate::invariants::{Decision, History};
+use crate::scheduler::{Event, Message, Scheduler};
+
+/// Everything shared by the nodes of a simulation.
+pub struct World {
+    pub scheduler: Scheduler,
+    pub history: History,
+
+    /// Values used by equivocating nodes in their conflicting messages, per height and round
+    pub equivocations: BTreeMap<(Height, Round), Value>,
+}
+
+/// A simulated node, running its own instance of the consensus state machine.
+pub struct Node {
+    pub state: State<TestContext>,
+    pub metrics: Metrics,
+    pub io: NodeIo,
+}
+
+impl Node {
+    /// Process an input, handling all the effects it produces against the simulated world.
+    ///
+    /// Consensus errors, eg. for inputs referring to a past height, are returned
+    /// to the caller but do not by themselves indicate a safety issue.
+    pub fn process(
+        &mut self,
+        world: &mut World,
+        input: Input<TestContext>,
+    ) -> Result<(), Box<Error<TestContext>>> {
+        let Self { state, metrics, io } = self;
+
+        process!(
+            input: input,
+            state: state,
+            metrics: metrics,
+            with: effect => io.handle_effect(world, effect)
+        )
+    }
+}
+
+/// The part of a node which interacts with the rest of the simulation.
+pub struct NodeIo {
+    pub id: NodeId,
+    pub ctx: TestContext,
+    pub behavior: Option<Behavior>,
+    pub validator_set: ValidatorSet,
+    pub peers: Vec<NodeId>,
+    pub timeouts: TimeoutConfig,
+    pub target_height: Height,
+
+    /// Active timeouts, along with the id of the timer which will fire them
+    timers: Vec<(Timeout, u64)>,
+    next_timer: u64,
+
+    /// Commit certificates for the heights decided by this node, served to lagging peers
+    pub certificates: BTreeMap<Height, CommitCertificate<TestContext>>,
+}
+
+impl NodeIo {
+    pub fn new(
+        id: NodeId,
+        ctx: TestContext,
+        behavior: Option<Behavior>,
+        validator_set: ValidatorSet,
+        peers: Vec<NodeId>,
+        timeouts: TimeoutConfig,
+        target_height: Height,
+    ) -> Self {
+        Self {
+            id,
+            ctx,
+            behavior,
+            validator_set,
+            peers,
+            timeouts,
+            target_height,
+            timers: Vec::new(),
+            next_timer: 0,
+            certificates: BTreeMap::new(),
+        }
+    }
+
+    /// Whether this node has crashed at the given time
+    pub fn has_crashed(&self, now: Duration) -> bool {
+        self.behavior.is_some_and(|b| b.has_crashed(now))
+    }
+
+    /// Whether the given timer is still active, in which case it is removed from the active timers
+    pub fn fire_timer(&mut self, timeout: Timeout, timer: u64) -> bool {
+        let before = self.timers.len();
+        self.timers.retain(|&(t, id)| (t, id) != (timeout, timer));
+        self.timers.len() != before
+    }
+
+    /// Send a message to a peer, unless this node does not send messages at all
+    pub fn send(&self, scheduler: &mut Scheduler, to: NodeId, message: Message) {
+        if self.behavior.is_none_or(|b| b.sends_messages()) {
+            scheduler.send(self.id, to, message);
+        }
+    }
+
+    fn broadcast(&self, scheduler: &mut Scheduler, message: Message) {
+        for &peer in &self.peers {
+            self.send(scheduler, peer, message.clone());
+        }
+    }
+
+    /// Timeouts grow linearly with the round, as in the Tendermint paper
+    fn timeout_duration(&self, timeout: Timeout) -> Duration {
+        let base = self.timeouts.timeout_duration(timeout.kind);
+        let delta = self
+            .timeouts
+            .delta_duration(timeout.kind)
+            .unwrap_or_default();
+        let round = timeout.round.as_i64().max(0) as u32;
+
+        base + delta * round
+    }
+
+    fn publish(&self, world: &mut World, msg: SignedConsensusMsg<TestContext>) {
+        if let SignedConsensusMsg::Proposal(proposal) = &msg {
+            world
+                .history
+                .record_proposal(proposal.height, proposal.value);
+        }
+
+        if self.behavior != Some(Behavior::Equivocate) {
+            self.broadcast(&mut world.scheduler, Message::Consensus(msg));
+            return;
+        }
+
+        let conflicting = self.conflicting(world, &msg);
+
+        for (i, &peer) in self.peers.iter().enumerate() {
+            let msg = if i % 2 == 0 { &msg } else { &conflicting };
+            self.send(&mut world.scheduler, peer, Message::Consensus(msg.clone()));
+        }
+    }
+
+    /// Build and sign a message which conflicts with the given one.
+    ///
+    /// Equivocating nodes collude: all the conflicting messages they send
+    /// for a given height and round refer to the same value.
+    fn conflicting(
+        &self,
+        world: &mut World,
+        msg: &SignedConsensusMsg<TestContext>,
+    ) -> SignedConsensusMsg<TestContext> {
+        let signer = self.ctx.signing_provider();
+
+        let (height, round) = match msg {
+            SignedConsensusMsg::Vote(vote) => (vote.height, vote.round),
+            SignedConsensusMsg::Proposal(proposal) => (proposal.height, proposal.round),
+        };
+
+        let other = *world
+            .equivocations
+            .entry((height, round))
+            .or_insert_with(|| Value::new(world.scheduler.rng.gen()));
+
+        match msg {
+            SignedConsensusMsg::Vote(vote) => {
+                let value = if vote.value == NilOrVal::Val(other.id()) {
+                    NilOrVal::Nil
+                } else {
+                    NilOrVal::Val(other.id())
+                };
+
+                let vote = Vote {
+                    value,
+                    ..vote.message.clone()
+                };
+
+                SignedConsensusMsg::Vote(signer.sign_vote(vote))
+            }
+
+            SignedConsensusMsg::Proposal(proposal) => {
+                world.history.record_proposal(height, other);
+
+                let proposal = Proposal {
+                    value: other,
+                    ..proposal.message.clone()
+                };
+
+                SignedConsensusMsg::Proposal(signer.sign_proposal(proposal))
+            }
+        }
+    }
+
+    fn handle_effect(
+        &mut self,
+        world: &mut World,
+        effect: Effect<TestContext>,
+    ) -> Result<Resume<TestContext>, Infallible> {
+        match effect {
+            // Timeout durations only depend on the round, so there is nothing to reset
+            Effect::ResetTimeouts(r) => Ok(r.resume_with(())),
+
+            Effect::CancelAllTimeouts(r) => {
+                self.timers.clear();
+                Ok(r.resume_with(()))
+            }
+
+            Effect::CancelTimeout(timeout, r) => {
+                self.timers.retain(|(t, _)| *t != timeout);
+                Ok(r.resume_with(()))
+            }
+
+            Effect::ScheduleTimeout(timeout, r) => {
+                let timer = self.next_timer;
+                self.next_timer += 1;
+
+                self.timers.retain(|(t, _)| *t != timeout);
+                self.timers.push((timeout, timer));
+
+                let event = Event::Timeout {
+                    node: self.id,
+                    timeout,
+                    timer,
+                };
+
+                world
+                    .scheduler
+                    .schedule(self.timeout_duration(timeout), event);
+
+                Ok(r.resume_with(()))
+            }
+
+            Effect::StartRound(_, _, _, r) => Ok(r.resume_with(())),
+
+            Effect::Publish(msg, r) => {
+                self.publish(world, msg);
+                Ok(r.resume_with(()))
+            }
+
+            Effect::GetValue(height, round, _timeout, _vote_extensions, r) => {
+                let value = Value::new(world.scheduler.rng.gen());
+
+                let input = Input::Propose(ValueToPropose {
+                    height,
+                    round,
+                    valid_round: Round::Nil,
+                    value,
+                });
+
+                world
+                    .scheduler
+                    .schedule(Duration::ZERO, Event::Input(self.id, input));
+
+                Ok(r.resume_with(()))
+            }
+
+            // Proposals carry their value, so there are no proposal parts to restream
+            Effect::RestreamValue(_, _, _, _, _, r) => Ok(r.resume_with(())),
+
+            // Proposed values are simple numbers, which are always valid
+            Effect::ValidateValue(_, _, _, _, r) => Ok(r.resume_with(ValueValidity::Valid)),
+
+            // Votes are never extended in simulations
+            Effect::ExtendVote(_, _, _, r) => Ok(r.resume_with(None)),
+            Effect::VerifyVoteExtension(_, _, _, _, _, r) => Ok(r.resume_with(Validity::Valid)),
+
+            Effect::GetValidatorSet(_, r) => Ok(r.resume_with(Some(self.validator_set.clone()))),
+
+            Effect::Decide(certificate, r) => {
+                let height = certificate.height;
+
+                world.history.record_decision(Decision {
+                    node: self.id,
+                    height,
+                    round: certificate.round,
+                    value_id: certificate.value_id,
+                    time: world.scheduler.now(),
+                });
+
+                self.certificates.insert(height, certificate);
+
+                if height < self.target_height {
+                    let input = Input::StartHeight(
+                        height.increment(),
+                        self.validator_set.clone(),
+                        ParamsUpdate::default(),
+                    );
+
+                    world
+                        .scheduler
+                        .schedule(Duration::ZERO, Event::Input(self.id, input));
+                }
+
+                Ok(r.resume_with(()))
+            }
+
+            Effect::GetVoteSet(height, round, r) => {
+                let request_id = self.id.to_string();
+                let message = Message::VoteSetRequest(request_id, height, round);
+                self.broadcast(&mut world.scheduler, message);
+
+                Ok(r.resume_with(()))
+            }
+
+            Effect::SendVoteSetResponse(request_id, _, _, vote_set, r) => {
+                if let Ok(to) = request_id.parse() {
+                    let message = Message::VoteSetResponse(vote_set);
+                    self.send(&mut world.scheduler, to, message);
+                }
+
+                Ok(r.resume_with(()))
+            }
+
+            // Nodes never restart during a simulation, so there is nothing to persist
+            Effect::PersistMessage(_, r)
+            | Effect::PersistTimeout(_, r)
+            | Effect::PersistProposalReceiveTime(_, _, r) => Ok(r.resume_with(())),
+
+            Effect::SignVote(vote, r) => {
+                Ok(r.resume_with(self.ctx.signing_provider().sign_vote(vote)))
+            }
+
+            Effect::SignProposal(proposal, r) => {
+                Ok(r.resume_with(self.ctx.signing_provider().sign_proposal(proposal)))
+            }
+
+            Effect::VerifySignature(msg, pk, r) => {
+                let SignedMessage { message, signature } = msg;
+                let signer = self.ctx.signing_provider();
+
+                let valid = match message {
+                    ConsensusMsg::Vote(v) => signer.verify_signed_vote(&v, &signature, &pk),
+                    ConsensusMsg::Proposal(p) => signer.verify_signed_proposal(&p, &signature, &pk),
+                };
+
+                Ok(r.resume_with(valid))
+            }
+
+            Effect::VerifyCertificate(certificate, validator_set, thresholds, r) => {
+                let valid = self.ctx.signing_provider().verify_certificate(
+                    &certificate,
+                    &validator_set,
+                    thresholds,
+                );
+
+                Ok(r.resume_with(valid))
+            }
+
+            Effect::GetLocalTime(r) => {
+                Ok(r.resume_with(Timestamp::from_unix_duration(world.scheduler.now())))
+            }
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use malachitebft_core_consensus::{Input, SignedConsensusMsg};
+use malachitebft_core_types::{CommitCertificate, Round, Timeout, VoteSet};
+use malachitebft_test::{Height, TestContext, Value};
+
+use crate::config::{NetworkConfig, NodeId};
+
+/// A message sent from one node to another over the simulated network.
+#[derive(Clone, Debug)]
+pub enum Message {
+    /// A signed proposal or vote
+    Consensus(SignedConsensusMsg<TestContext>),
+
+    /// Request for the votes of a given height and round
+    VoteSetRequest(String, Height, Round),
+
+    /// Response to a [`Message::VoteSetRequest`]
+    VoteSetResponse(VoteSet<TestContext>),
+
+    /// Request for the value decided at the given height, if any
+    SyncRequest(Height),
+
+    /// Response to a [`Message::SyncRequest`]
+    SyncResponse(CommitCertificate<TestContext>, Value),
+}
+
+/// An event scheduled to happen at some point in virtual time.
+#[derive(Clone, Debug)]
+pub enum Event {
+    /// An input produced locally by a node, eg. a value to propose or the start of a new height
+    Input(NodeId, Input<TestContext>),
+
+    /// A message reaches its destination
+    Deliver {
+        from: NodeId,
+        to: NodeId,
+        message: Message,
+    },
+
+    /// A timeout elapses, unless it was cancelled or re-scheduled in the meantime
+    Timeout {
+        node: NodeId,
+        timeout: Timeout,
+        timer: u64,
+    },
+
+    /// A node asks a random peer for a decided value it is missing
+    SyncTick(NodeId),
+}
+
+/// Statistics about the messages sent over the simulated network.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct NetworkStats {
+    pub sent: u64,
+    pub dropped: u64,
+}
+
+/// Seeded discrete-event scheduler.
+///
+/// Events are processed in order of virtual time, and events scheduled
+/// for the same instant are processed in the order they were scheduled in.
+/// All randomness in a simulation is drawn from the scheduler's RNG,
+/// which makes every run fully determined by its seed.
+pub struct Scheduler {
+    now: Duration,
+    next_seq: u64,
+    queue: BTreeMap<(Duration, u64), Event>,
+    network: NetworkConfig,
+    stats: NetworkStats,
+    pub rng: ChaCha8Rng,
+}
+
+impl Scheduler {
+    pub fn new(seed: u64, network: NetworkConfig) -> Self {
+        Self {
+            now: Duration::ZERO,
+            next_seq: 0,
+            queue: BTreeMap::new(),
+            network,
+            stats: NetworkStats::default(),
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    /// Current virtual time
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+
+    pub fn stats(&self) -> NetworkStats {
+        self.stats
+    }
+
+    /// Schedule an event to happen after the given delay
+    pub fn schedule(&mut self, delay: Duration, event: Event) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.queue.insert((self.now + delay, seq), event);
+    }
+
+    /// Send a message over the network, subject to partitions, drops and latency
+    pub fn send(&mut self, from: NodeId, to: NodeId, message: Message) {
+        self.stats.sent += 1;
+
+        if self.network.is_partitioned(self.now, from, to)
+            || self.rng.gen_bool(self.network.drop_probability)
+        {
+            self.stats.dropped += 1;
+            return;
+        }
+
+        let latency = self
+            .rng
+            .gen_range(self.network.min_latency..=self.network.max_latency);
+
+        self.schedule(latency, Event::Deliver { from, to, message });
+    }
+
+    /// Advance virtual time to the next event and return it
+    pub fn next_event(&mut self) -> Option<Event> {
+        let ((time, _), event) = self.queue.pop_first()?;
+        self.now = time;
+        Some(event)
+    }
+
+    /// Time at which the next event is scheduled
+    pub fn peek_time(&self) -> Option<Duration> {
+        self.queue.keys().next().map(|(time, _)| *time)
+    }
+}
@@ -0,0 +1,329 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use malachitebft_core_consensus::{
+    Input, Metrics, Params, ParamsUpdate, ProposedValue, SignedConsensusMsg, State, ValuePayload,
+};
+use malachitebft_core_types::{Round, Validity, ValueOrigin};
+use malachitebft_test::{Height, PrivateKey, TestContext, Validator, ValidatorSet};
+
+use crate::config::{NodeId, SimConfig};
+use crate::invariants::{Decision, History, Violation};
+use crate::node::{Node, NodeIo, World};
+use crate::scheduler::{Event, Message, NetworkStats, Scheduler};
+
+/// A safety invariant was violated during a simulation.
+#[derive(Clone, Debug)]
+pub struct Failure {
+    /// Seed to pass in [`SimConfig::seed`] to reproduce the failure
+    pub seed: u64,
+
+    /// Number of events processed when the violation was detected
+    pub step: u64,
+
+    /// Virtual time at which the violation was detected
+    pub time: Duration,
+
+    pub violation: Violation,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (step {}, time {:?}), reproduce with seed {}",
+            self.violation, self.step, self.time, self.seed
+        )
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// Result of a simulation in which no invariant was violated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Outcome {
+    pub seed: u64,
+
+    /// Whether every correct node reached the target height before the time limit
+    pub completed: bool,
+
+    /// Number of events processed
+    pub steps: u64,
+
+    /// Virtual time at which the simulation stopped
+    pub time: Duration,
+
+    /// Decisions made by all nodes, in the order they were made
+    pub decisions: Vec<Decision>,
+
+    pub network: NetworkStats,
+}
+
+impl Outcome {
+    /// The decision made by the given node at the given height, if any
+    pub fn decision(&self, node: NodeId, height: Height) -> Option<&Decision> {
+        self.decisions
+            .iter()
+            .find(|d| d.node == node && d.height == height)
+    }
+}
+
+/// Deterministic discrete-event simulator running several consensus nodes in-process.
+///
+/// Nodes exchange messages over a simulated network with configurable latency,
+/// drops and partitions, and all timeouts elapse in virtual time.
+/// The agreement, validity and integrity invariants are checked after every event.
+pub struct Simulator {
+    config: SimConfig,
+    nodes: Vec<Node>,
+    world: World,
+    steps: u64,
+}
+
+impl Simulator {
+    pub fn new(config: SimConfig) -> Self {
+        // Keys do not depend on the seed, so that the same validators are used in every run
+        let mut rng = ChaCha8Rng::seed_from_u64(0x42);
+
+        let keys: Vec<PrivateKey> = config
+            .voting_powers
+            .iter()
+            .map(|_| PrivateKey::generate(&mut rng))
+            .collect();
+
+        let validator_set = ValidatorSet::new(
+            keys.iter()
+                .zip(&config.voting_powers)
+                .map(|(key, &power)| Validator::new(key.public_key(), power)),
+        );
+
+        let num_nodes = config.num_nodes();
+
+        let nodes = keys
+            .into_iter()
+            .enumerate()
+            .map(|(id, key)| {
+                let address = Validator::new(key.public_key(), 0).address;
+                let ctx = TestContext::new(key);
+
+                let params = Params {
+                    initial_height: Height::new(1),
+                    initial_validator_set: validator_set.clone(),
+                    address,
+                    threshold_params: Default::default(),
+                    value_payload: ValuePayload::ProposalOnly,
+                    synchrony_params: None,
+                };
+
+                let peers = (0..num_nodes).filter(|&peer| peer != id).collect();
+
+                let io = NodeIo::new(
+                    id,
+                    ctx.clone(),
+                    config.byzantine.get(&id).copied(),
+                    validator_set.clone(),
+                    peers,
+                    config.timeouts,
+                    Height::new(config.target_height),
+                );
+
+                Node {
+                    state: State::new(ctx, params),
+                    metrics: Metrics::new(),
+                    io,
+                }
+            })
+            .collect();
+
+        let world = World {
+            scheduler: Scheduler::new(config.seed, config.network.clone()),
+            history: History::default(),
+            equivocations: BTreeMap::new(),
+        };
+
+        Self {
+            config,
+            nodes,
+            world,
+            steps: 0,
+        }
+    }
+
+    /// Run the simulation until every correct node has decided at the target height,
+    /// or until the time limit is reached.
+    pub fn run(mut self) -> Result<Outcome, Failure> {
+        for id in 0..self.nodes.len() {
+            let input = Input::StartHeight(
+                Height::new(1),
+                self.nodes[id].io.validator_set.clone(),
+                ParamsUpdate::default(),
+            );
+
+            let scheduler = &mut self.world.scheduler;
+            scheduler.schedule(Duration::ZERO, Event::Input(id, input));
+            scheduler.schedule(self.config.sync_interval, Event::SyncTick(id));
+        }
+
+        let mut completed = false;
+
+        while !completed {
+            match self.world.scheduler.peek_time() {
+                Some(time) if time <= self.config.max_time => (),
+                _ => break,
+            }
+
+            let event = self.world.scheduler.next_event().expect("peeked event");
+            self.steps += 1;
+            self.handle_event(event);
+
+            if let Err(violation) = self.world.history.check(&self.config) {
+                return Err(Failure {
+                    seed: self.config.seed,
+                    step: self.steps,
+                    time: self.world.scheduler.now(),
+                    violation,
+                });
+            }
+
+            completed = self.is_complete();
+        }
+
+        Ok(Outcome {
+            seed: self.config.seed,
+            completed,
+            steps: self.steps,
+            time: self.world.scheduler.now(),
+            decisions: self.world.history.decisions().to_vec(),
+            network: self.world.scheduler.stats(),
+        })
+    }
+
+    fn is_complete(&self) -> bool {
+        let target = Height::new(self.config.target_height);
+
+        (0..self.nodes.len())
+            .filter(|&id| self.config.is_correct(id))
+            .all(|id| self.world.history.has_decided(id, target))
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        let now = self.world.scheduler.now();
+
+        match event {
+            Event::Input(id, input) => self.process(id, input),
+
+            Event::Deliver { from, to, message } => {
+                if !self.nodes[to].io.has_crashed(now) {
+                    self.deliver(from, to, message);
+                }
+            }
+
+            Event::Timeout {
+                node,
+                timeout,
+                timer,
+            } => {
+                if self.nodes[node].io.fire_timer(timeout, timer) {
+                    self.process(node, Input::TimeoutElapsed(timeout));
+                }
+            }
+
+            Event::SyncTick(id) => self.sync_tick(id),
+        }
+    }
+
+    fn process(&mut self, id: NodeId, input: Input<TestContext>) {
+        if self.nodes[id].io.has_crashed(self.world.scheduler.now()) {
+            return;
+        }
+
+        // Errors are expected for some inputs, eg. messages from Byzantine nodes
+        // or certificates for a height that has already been decided, and are
+        // not safety issues in themselves. Safety is checked by the invariants instead.
+        let _ = self.nodes[id].process(&mut self.world, input);
+    }
+
+    fn deliver(&mut self, from: NodeId, to: NodeId, message: Message) {
+        match message {
+            Message::Consensus(SignedConsensusMsg::Vote(vote)) => {
+                self.process(to, Input::Vote(vote));
+            }
+
+            Message::Consensus(SignedConsensusMsg::Proposal(proposal)) => {
+                self.process(to, Input::Proposal(proposal));
+            }
+
+            Message::VoteSetRequest(request_id, height, round) => {
+                self.process(to, Input::VoteSetRequest(request_id, height, round));
+            }
+
+            Message::VoteSetResponse(vote_set) => {
+                self.process(to, Input::VoteSetResponse(vote_set));
+            }
+
+            Message::SyncRequest(height) => {
+                let node = &self.nodes[to];
+
+                let Some(certificate) = node.io.certificates.get(&height) else {
+                    return;
+                };
+
+                let Some(value) = self
+                    .world
+                    .history
+                    .proposed_value(height, certificate.value_id)
+                else {
+                    return;
+                };
+
+                let response = Message::SyncResponse(certificate.clone(), value);
+                node.io.send(&mut self.world.scheduler, from, response);
+            }
+
+            Message::SyncResponse(certificate, value) => {
+                let node = &self.nodes[to];
+                let height = certificate.height;
+
+                if node.state.height() != height || node.io.certificates.contains_key(&height) {
+                    return;
+                }
+
+                let proposed_value = ProposedValue {
+                    height,
+                    round: certificate.round,
+                    valid_round: Round::Nil,
+                    proposer: *node.state.get_proposer(height, certificate.round),
+                    value,
+                    validity: Validity::Valid,
+                };
+
+                self.process(to, Input::ProposedValue(proposed_value, ValueOrigin::Sync));
+                self.process(to, Input::CommitCertificate(certificate));
+            }
+        }
+    }
+
+    /// Ask a random peer for the value decided at the current height,
+    /// so that nodes which missed messages can eventually catch up.
+    fn sync_tick(&mut self, id: NodeId) {
+        let scheduler = &mut self.world.scheduler;
+        let node = &self.nodes[id];
+
+        if node.io.has_crashed(scheduler.now()) {
+            return;
+        }
+
+        let height = node.state.height();
+
+        if !node.io.certificates.contains_key(&height) && !node.io.peers.is_empty() {
+            let peer = node.io.peers[scheduler.rng.gen_range(0..node.io.peers.len())];
+            node.io.send(scheduler, peer, Message::SyncRequest(height));
+        }
+
+        scheduler.schedule(self.config.sync_interval, Event::SyncTick(id));
+    }
+}
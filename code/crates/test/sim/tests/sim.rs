@@ -0,0 +1,216 @@
+use std::time::Duration;
+
+use informalsystems_malachitebft_test_sim::{Behavior, Partition, SimConfig, Simulator};
+use malachitebft_test::Height;
+
+/// Seeds used by the tests which explore many schedules.
+/// Can be overridden with the `MALACHITE_SIM_SEED` environment variable to reproduce a failure.
+fn seeds() -> Vec<u64> {
+    match std::env::var("MALACHITE_SIM_SEED") {
+        Ok(seed) => vec![seed.parse().expect("invalid seed")],
+        Err(_) => (0..20).collect(),
+    }
+}
+
+fn run(config: SimConfig) -> informalsystems_malachitebft_test_sim::Outcome {
+    Simulator::new(config)
+        .run()
+        .unwrap_or_else(|e| panic!("{e}"))
+}
+
+#[test]
+fn happy_path() {
+    for seed in seeds() {
+        let outcome = run(SimConfig::new(seed, 4));
+        assert!(
+            outcome.completed,
+            "seed {seed}: did not reach target height"
+        );
+
+        for node in 0..4 {
+            let decision = outcome.decision(node, Height::new(1)).unwrap();
+            assert_eq!(decision.round.as_i64(), 0, "seed {seed}");
+        }
+    }
+}
+
+#[test]
+fn same_seed_same_outcome() {
+    let mut config = SimConfig::new(7, 4);
+    config.network.drop_probability = 0.2;
+
+    let first = run(config.clone());
+    let second = run(config);
+
+    assert_eq!(first, second);
+}
+
+#[test]
+fn message_drops() {
+    // Dropped messages are never retransmitted, so consensus may not make progress,
+    // eg. if a polka is lost for good. Only safety is checked here.
+    for seed in seeds() {
+        let mut config = SimConfig::new(seed, 4);
+        config.max_time = Duration::from_secs(120);
+        config.network.drop_probability = 0.3;
+
+        let outcome = run(config);
+        assert!(outcome.network.dropped > 0);
+    }
+}
+
+#[test]
+fn partition_heals() {
+    for seed in seeds() {
+        let mut config = SimConfig::new(seed, 4);
+        config.network.partitions = vec![Partition::new(
+            Duration::ZERO,
+            Duration::from_secs(20),
+            vec![vec![0, 1], vec![2, 3]],
+        )];
+
+        let outcome = run(config);
+        assert!(
+            outcome.completed,
+            "seed {seed}: did not reach target height"
+        );
+
+        // Neither side has a quorum, so nothing can be decided before the partition heals
+        let first = outcome.decisions.first().unwrap();
+        assert!(first.time >= Duration::from_secs(20), "seed {seed}");
+    }
+}
+
+#[test]
+fn minority_partition_catches_up() {
+    for seed in seeds() {
+        let mut config = SimConfig::new(seed, 4);
+        config.network.partitions = vec![Partition::new(
+            Duration::ZERO,
+            Duration::from_secs(30),
+            vec![vec![0, 1, 2]],
+        )];
+
+        let outcome = run(config);
+        assert!(
+            outcome.completed,
+            "seed {seed}: did not reach target height"
+        );
+
+        // The majority side keeps deciding while node 3 is isolated
+        let isolated = outcome.decision(3, Height::new(1)).unwrap();
+        let majority = outcome.decision(0, Height::new(1)).unwrap();
+        assert!(majority.time < Duration::from_secs(30), "seed {seed}");
+        assert!(isolated.time >= Duration::from_secs(30), "seed {seed}");
+    }
+}
+
+#[test]
+fn no_quorum_no_progress() {
+    let mut config = SimConfig::new(0, 4);
+    config.max_time = Duration::from_secs(60);
+    config.network.partitions = vec![Partition::new(
+        Duration::ZERO,
+        Duration::MAX,
+        vec![vec![0, 1], vec![2, 3]],
+    )];
+
+    let outcome = run(config);
+    assert!(!outcome.completed);
+    assert!(outcome.decisions.is_empty());
+}
+
+#[test]
+fn byzantine_silent() {
+    for seed in seeds() {
+        let mut config = SimConfig::new(seed, 4);
+        config.byzantine.insert(1, Behavior::Silent);
+
+        let outcome = run(config);
+        assert!(
+            outcome.completed,
+            "seed {seed}: did not reach target height"
+        );
+    }
+}
+
+#[test]
+fn byzantine_crash() {
+    for seed in seeds() {
+        let mut config = SimConfig::new(seed, 4);
+        config.byzantine.insert(
+            2,
+            Behavior::Crash {
+                at: Duration::from_millis(500),
+            },
+        );
+
+        let outcome = run(config);
+        assert!(
+            outcome.completed,
+            "seed {seed}: did not reach target height"
+        );
+    }
+}
+
+#[test]
+fn byzantine_equivocate() {
+    for seed in seeds() {
+        let mut config = SimConfig::new(seed, 4);
+        config.byzantine.insert(0, Behavior::Equivocate);
+
+        let outcome = run(config);
+        assert!(
+            outcome.completed,
+            "seed {seed}: did not reach target height"
+        );
+    }
+}
+
+#[test]
+fn byzantine_equivocate_with_drops() {
+    for seed in seeds() {
+        let mut config = SimConfig::new(seed, 4);
+        config.max_time = Duration::from_secs(120);
+        config.network.drop_probability = 0.1;
+        config.byzantine.insert(0, Behavior::Equivocate);
+
+        run(config);
+    }
+}
+
+#[test]
+fn too_many_byzantine_nodes_break_agreement() {
+    // With 2 equivocating nodes out of 4, f >= n/3 and safety is not guaranteed.
+    // Look for a seed under which agreement is violated, to make sure the checks work.
+    let failure = (0..200)
+        .find_map(|seed| {
+            let mut config = SimConfig::new(seed, 4);
+            config.target_height = 1;
+            config.byzantine.insert(0, Behavior::Equivocate);
+            config.byzantine.insert(1, Behavior::Equivocate);
+            config.network.partitions = vec![Partition::new(
+                Duration::ZERO,
+                Duration::MAX,
+                vec![vec![0, 1, 2], vec![0, 1, 3]],
+            )];
+
+            Simulator::new(config).run().err()
+        })
+        .expect("agreement should be violated for some seed");
+
+    // The failure can be reproduced from its seed
+    let mut config = SimConfig::new(failure.seed, 4);
+    config.target_height = 1;
+    config.byzantine.insert(0, Behavior::Equivocate);
+    config.byzantine.insert(1, Behavior::Equivocate);
+    config.network.partitions = vec![Partition::new(
+        Duration::ZERO,
+        Duration::MAX,
+        vec![vec![0, 1, 2], vec![0, 1, 3]],
+    )];
+
+    let reproduced = Simulator::new(config).run().unwrap_err();
+    assert_eq!(reproduced.violation, failure.violation);
+    assert_eq!(reproduced.step, failure.step);
+}
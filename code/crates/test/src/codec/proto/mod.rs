@@ -3,10 +3,10 @@ use prost::Message;
 
 use malachitebft_app::streaming::{StreamContent, StreamMessage};
 use malachitebft_codec::Codec;
-use malachitebft_core_consensus::SignedConsensusMsg;
+use malachitebft_core_consensus::{ProposedValue, SignedConsensusMsg};
 use malachitebft_core_types::{
     AggregatedSignature, CommitCertificate, CommitSignature, Extension, Round, SignedExtension,
-    SignedProposal, SignedVote, VoteSet,
+    SignedProposal, SignedVote, Validity, VoteSet,
 };
 use malachitebft_proto::{Error as ProtoError, Protobuf};
 use malachitebft_signing_ed25519::Signature;
@@ -219,6 +219,64 @@ impl Codec<sync::Response<TestContext>> for ProtobufCodec {
     }
 }
 
+impl Codec<ProposedValue<TestContext>> for ProtobufCodec {
+    type Error = ProtoError;
+
+    fn decode(&self, bytes: Bytes) -> Result<ProposedValue<TestContext>, Self::Error> {
+        decode_proposed_value(proto::ProposedValue::decode(bytes)?)
+    }
+
+    fn encode(&self, msg: &ProposedValue<TestContext>) -> Result<Bytes, Self::Error> {
+        encode_proposed_value(msg).map(|proto| proto.encode_to_vec().into())
+    }
+}
+
+impl Codec<CommitCertificate<TestContext>> for ProtobufCodec {
+    type Error = ProtoError;
+
+    fn decode(&self, bytes: Bytes) -> Result<CommitCertificate<TestContext>, Self::Error> {
+        decode_certificate(proto::CommitCertificate::decode(bytes)?)
+    }
+
+    fn encode(&self, msg: &CommitCertificate<TestContext>) -> Result<Bytes, Self::Error> {
+        encode_certificate(msg).map(|proto| proto.encode_to_vec().into())
+    }
+}
+
+fn decode_proposed_value(
+    proto: proto::ProposedValue,
+) -> Result<ProposedValue<TestContext>, ProtoError> {
+    let proposer = proto
+        .proposer
+        .ok_or_else(|| ProtoError::missing_field::<proto::ProposedValue>("proposer"))?;
+
+    let value = proto
+        .value
+        .ok_or_else(|| ProtoError::missing_field::<proto::ProposedValue>("value"))?;
+
+    Ok(ProposedValue {
+        height: Height::new(proto.height),
+        round: Round::new(proto.round),
+        valid_round: proto.valid_round.map(Round::new).unwrap_or(Round::Nil),
+        proposer: Address::from_proto(proposer)?,
+        value: Value::from_proto(value)?,
+        validity: Validity::from_bool(proto.validity),
+    })
+}
+
+fn encode_proposed_value(
+    msg: &ProposedValue<TestContext>,
+) -> Result<proto::ProposedValue, ProtoError> {
+    Ok(proto::ProposedValue {
+        height: msg.height.as_u64(),
+        round: msg.round.as_u32().expect("round should not be nil"),
+        valid_round: msg.valid_round.as_u32(),
+        proposer: Some(msg.proposer.to_proto()?),
+        value: Some(msg.value.to_proto()?),
+        validity: msg.validity.is_valid(),
+    })
+}
+
 fn decode_sync_response(
     proto_response: proto::SyncResponse,
 ) -> Result<sync::Response<TestContext>, ProtoError> {
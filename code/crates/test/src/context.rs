@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use malachitebft_core_types::{Context, NilOrVal, Round, ValidatorSet as _};
+use malachitebft_core_types::{Context, NilOrVal, Round, Timestamp, ValidatorSet as _};
 
 use crate::address::*;
 use crate::height::*;
@@ -71,6 +71,17 @@ impl Context for TestContext {
         Proposal::new(height, round, value, pol_round, address)
     }
 
+    fn new_timed_proposal(
+        height: Height,
+        round: Round,
+        value: Value,
+        pol_round: Round,
+        address: Address,
+        timestamp: Timestamp,
+    ) -> Proposal {
+        Proposal::new(height, round, value, pol_round, address).with_timestamp(timestamp)
+    }
+
     fn new_prevote(
         height: Height,
         round: Round,
@@ -1,5 +1,5 @@
 use bytes::Bytes;
-use malachitebft_core_types::Round;
+use malachitebft_core_types::{Round, Timestamp};
 use malachitebft_proto::{Error as ProtoError, Protobuf};
 
 use crate::{Address, Height, TestContext, Value};
@@ -12,6 +12,7 @@ pub struct Proposal {
     pub value: Value,
     pub pol_round: Round,
     pub validator_address: Address,
+    pub timestamp: Option<Timestamp>,
 }
 
 impl Proposal {
@@ -28,6 +29,14 @@ impl Proposal {
             value,
             pol_round,
             validator_address,
+            timestamp: None,
+        }
+    }
+
+    pub fn with_timestamp(self, timestamp: Timestamp) -> Self {
+        Self {
+            timestamp: Some(timestamp),
+            ..self
         }
     }
 
@@ -60,6 +69,10 @@ impl malachitebft_core_types::Proposal<TestContext> for Proposal {
     fn validator_address(&self) -> &Address {
         &self.validator_address
     }
+
+    fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
 }
 
 impl Protobuf for Proposal {
@@ -73,6 +86,7 @@ impl Protobuf for Proposal {
             value: Some(self.value.to_proto()?),
             pol_round: self.pol_round.as_u32(),
             validator_address: Some(self.validator_address.to_proto()?),
+            timestamp: self.timestamp.map(|t| t.as_unix_nanos()),
         })
     }
 
@@ -92,6 +106,7 @@ impl Protobuf for Proposal {
                     .validator_address
                     .ok_or_else(|| ProtoError::missing_field::<Self::Proto>("validator_address"))?,
             )?,
+            timestamp: proto.timestamp.map(Timestamp::from_unix_nanos),
         })
     }
 }
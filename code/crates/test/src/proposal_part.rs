@@ -2,6 +2,7 @@ use bytes::Bytes;
 use malachitebft_signing_ed25519::Signature;
 use serde::{Deserialize, Serialize};
 
+use malachitebft_app::streaming::StreamedProposalPart;
 use malachitebft_core_types::Round;
 use malachitebft_proto::{self as proto, Error as ProtoError, Protobuf};
 
@@ -10,16 +11,16 @@ use crate::{Address, Height, TestContext};
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ProposalData {
-    pub factor: u64,
+    pub bytes: Bytes,
 }
 
 impl ProposalData {
-    pub fn new(factor: u64) -> Self {
-        Self { factor }
+    pub fn new(bytes: Bytes) -> Self {
+        Self { bytes }
     }
 
     pub fn size_bytes(&self) -> usize {
-        std::mem::size_of::<u64>()
+        self.bytes.len()
     }
 }
 
@@ -60,6 +61,20 @@ impl ProposalPart {
         }
     }
 
+    pub fn size_bytes(&self) -> usize {
+        match self {
+            Self::Data(data) => data.size_bytes(),
+            Self::Init(_) | Self::Fin(_) => std::mem::size_of_val(self),
+        }
+    }
+
+    pub fn as_fin(&self) -> Option<&ProposalFin> {
+        match self {
+            Self::Fin(fin) => Some(fin),
+            _ => None,
+        }
+    }
+
     pub fn to_sign_bytes(&self) -> Bytes {
         proto::Protobuf::to_bytes(self).unwrap() // FIXME: unwrap
     }
@@ -84,14 +99,16 @@ impl ProposalInit {
     }
 }
 
+/// The last part of a proposal, with the hash of the proposal signed by its proposer
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ProposalFin {
+    pub hash: Bytes,
     pub signature: Signature,
 }
 
 impl ProposalFin {
-    pub fn new(signature: Signature) -> Self {
-        Self { signature }
+    pub fn new(hash: Bytes, signature: Signature) -> Self {
+        Self { hash, signature }
     }
 }
 
@@ -105,6 +122,20 @@ impl malachitebft_core_types::ProposalPart<TestContext> for ProposalPart {
     }
 }
 
+impl StreamedProposalPart<TestContext> for ProposalPart {
+    fn init(height: Height, round: Round, proposer: Address) -> Self {
+        Self::Init(ProposalInit::new(height, round, proposer))
+    }
+
+    fn data(chunk: Bytes) -> Self {
+        Self::Data(ProposalData::new(chunk))
+    }
+
+    fn fin(hash: Bytes, signature: Signature) -> Self {
+        Self::Fin(ProposalFin::new(hash, signature))
+    }
+}
+
 impl Protobuf for ProposalPart {
     type Proto = crate::proto::ProposalPart;
 
@@ -125,8 +156,9 @@ impl Protobuf for ProposalPart {
                     .ok_or_else(|| ProtoError::missing_field::<Self::Proto>("proposer"))
                     .and_then(Address::from_proto)?,
             })),
-            Part::Data(data) => Ok(Self::Data(ProposalData::new(data.factor))),
+            Part::Data(data) => Ok(Self::Data(ProposalData::new(data.bytes))),
             Part::Fin(fin) => Ok(Self::Fin(ProposalFin {
+                hash: fin.hash,
                 signature: fin
                     .signature
                     .ok_or_else(|| ProtoError::missing_field::<Self::Proto>("signature"))
@@ -150,14 +182,103 @@ impl Protobuf for ProposalPart {
             }),
             Self::Data(data) => Ok(Self::Proto {
                 part: Some(Part::Data(proto::ProposalData {
-                    factor: data.factor,
+                    bytes: data.bytes.clone(),
                 })),
             }),
             Self::Fin(fin) => Ok(Self::Proto {
                 part: Some(Part::Fin(proto::ProposalFin {
+                    hash: fin.hash.clone(),
                     signature: Some(encode_signature(&fin.signature)),
                 })),
             }),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use malachitebft_app::streaming::{ProposalHasher, ProposalStreamer, StreamContent};
+
+    use super::*;
+
+    use crate::PrivateKey;
+
+    #[test]
+    fn stream_signed_proposal() {
+        let mut rng = StdRng::seed_from_u64(0x42);
+
+        let sk = PrivateKey::generate(&mut rng);
+        let ctx = TestContext::new(sk.clone());
+        let proposer = Address::from_public_key(&sk.public_key());
+
+        let mut streamer = ProposalStreamer::new(4);
+        let value = Bytes::from_static(b"0123456789");
+
+        let msgs = streamer.stream::<TestContext>(
+            Height::new(1),
+            Round::new(0),
+            proposer,
+            value,
+            |hash| ctx.signing_provider.sign(hash),
+        );
+
+        assert_eq!(msgs.len(), 6);
+        assert!(msgs.iter().all(|msg| msg.stream_id == 0));
+        assert!(msgs
+            .iter()
+            .enumerate()
+            .all(|(i, msg)| msg.sequence == i as u64));
+        assert_eq!(msgs[5].content, StreamContent::Fin(true));
+
+        let parts: Vec<_> = msgs
+            .into_iter()
+            .filter_map(|msg| msg.content.into_data())
+            .collect();
+
+        // Parts survive a roundtrip through their protobuf encoding
+        for part in &parts {
+            assert_eq!(
+                &ProposalPart::from_bytes(&part.to_bytes().unwrap()).unwrap(),
+                part
+            );
+        }
+
+        let init = parts[0].as_init().unwrap();
+
+        let chunks: Vec<_> = parts
+            .iter()
+            .filter_map(|part| part.as_data())
+            .map(|data| data.bytes.as_ref())
+            .collect();
+
+        assert_eq!(chunks, [&b"0123"[..], b"4567", b"89"]);
+
+        // The signed hash covers the metadata of the proposal as well as its chunks
+        let mut hasher =
+            ProposalHasher::new::<TestContext>(init.height, init.round, &init.proposer);
+        chunks.iter().for_each(|chunk| hasher.update(chunk));
+
+        let fin = parts[4].as_fin().unwrap();
+        assert_eq!(fin.hash, hasher.finalize());
+        assert!(sk.public_key().verify(&fin.hash, &fin.signature).is_ok());
+
+        let mut hasher =
+            ProposalHasher::new::<TestContext>(init.height, Round::new(1), &init.proposer);
+        chunks.iter().for_each(|chunk| hasher.update(chunk));
+        assert_ne!(fin.hash, hasher.finalize());
+
+        // The next value is streamed on a new stream
+        let msgs = streamer.stream::<TestContext>(
+            Height::new(1),
+            Round::new(1),
+            proposer,
+            Bytes::new(),
+            |hash| ctx.signing_provider.sign(hash),
+        );
+        assert_eq!(msgs.len(), 3);
+        assert!(msgs.iter().all(|msg| msg.stream_id == 1));
+    }
+}
@@ -3,7 +3,7 @@ use tracing::{error, info};
 
 use malachitebft_app_channel::app::streaming::StreamContent;
 use malachitebft_app_channel::app::types::core::{Round, Validity};
-use malachitebft_app_channel::app::types::ProposedValue;
+use malachitebft_app_channel::app::types::{ParamsUpdate, ProposedValue, ValueValidity};
 use malachitebft_app_channel::{AppMsg, Channels, ConsensusMsg, NetworkMsg};
 use malachitebft_test::{Genesis, TestContext};
 
@@ -27,6 +27,7 @@ pub async fn run(
                     .send(ConsensusMsg::StartHeight(
                         state.current_height,
                         genesis.validator_set.clone(),
+                        ParamsUpdate::default(),
                     ))
                     .is_err()
                 {
@@ -55,21 +56,30 @@ pub async fn run(
                 height,
                 round,
                 timeout: _,
+                vote_extensions: _,
                 reply,
             } => {
                 // NOTE: We can ignore the timeout as we are building the value right away.
                 // If we were let's say reaping as many txes from a mempool and executing them,
                 // then we would need to respect the timeout and stop at a certain point.
+                //
+                // We can also ignore the vote extensions of the previous height, since our votes
+                // are never extended. An application extending its votes could include them
+                // in the value it builds here.
 
                 info!(%height, %round, "Consensus is requesting a value to propose");
 
                 // Here it is important that, if we have previously built a value for this height and round,
                 // we send back the very same value. We will not go into details here but this has to do
-                // with crash recovery and is not strictly necessary in this example app since all our state
-                // is kept in-memory and therefore is not crash tolerant at all.
-                if let Some(proposal) = state.get_previously_built_value(height, round) {
+                // with crash recovery, which is why our proposals are kept in the store.
+                if let Some(proposal) = state.get_previously_built_value(height, round).await? {
                     info!(value = %proposal.value.id(), "Re-using previously built value");
 
+                    let tx_hashes = state.submitted_tx_hash(&proposal.value);
+                    channels
+                        .receipts
+                        .proposed(height, proposal.value.id(), tx_hashes);
+
                     if reply.send(proposal).is_err() {
                         error!("Failed to send GetValue reply");
                     }
@@ -79,7 +89,14 @@ pub async fn run(
 
                 // If we have not previously built a value for that very same height and round,
                 // we need to create a new value to propose and send it back to consensus.
-                let proposal = state.propose_value(height, round);
+                let proposal = state.propose_value(height, round).await?;
+
+                // If the value was submitted as a transaction through the RPC server, we tell which one,
+                // for it to be reported as included if the value gets decided
+                let tx_hashes = state.submitted_tx_hash(&proposal.value);
+                channels
+                    .receipts
+                    .proposed(height, proposal.value.id(), tx_hashes);
 
                 // Send it to consensus
                 if reply.send(proposal.clone()).is_err() {
@@ -116,13 +133,55 @@ pub async fn run(
 
                 info!(%from, %part.sequence, part.type = %part_type, "Received proposal part");
 
-                let proposed_value = state.received_proposal_part(from, part);
+                let proposed_value = state
+                    .received_proposal_part(from, part, &genesis.validator_set)
+                    .await?;
+
+                // The value may have been submitted to us as a transaction too
+                if let Some(proposed) = &proposed_value {
+                    let tx_hashes = state.submitted_tx_hash(&proposed.value);
+                    channels
+                        .receipts
+                        .proposed(proposed.height, proposed.value.id(), tx_hashes);
+                }
 
                 if reply.send(proposed_value).is_err() {
                     error!("Failed to send ReceivedProposalPart reply");
                 }
             }
 
+            // When values are carried by proposal messages rather than streamed as proposal parts,
+            // consensus asks the application whether a value received in a proposal is valid.
+            // If not, this node will prevote nil for that proposal.
+            //
+            // In our case, we do not impose any constraint on values, so they are always valid.
+            AppMsg::ValidateProposal { reply, .. } => {
+                if reply.send(ValueValidity::Valid).is_err() {
+                    error!("Failed to send ValidateProposal reply");
+                }
+            }
+
+            // When about to precommit for a value, consensus asks the application for
+            // an extension to attach to the precommit, eg. some data to be included in
+            // the value built at the next height.
+            //
+            // In our case, we do not extend our votes.
+            AppMsg::ExtendVote { reply, .. } => {
+                if reply.send(None).is_err() {
+                    error!("Failed to send ExtendVote reply");
+                }
+            }
+
+            // When receiving a precommit with an extension from a peer, consensus asks
+            // the application to verify it. Precommits with invalid extensions are not counted.
+            //
+            // In our case, since votes are never extended, we accept any extension.
+            AppMsg::VerifyVoteExtension { reply, .. } => {
+                if reply.send(Validity::Valid).is_err() {
+                    error!("Failed to send VerifyVoteExtension reply");
+                }
+            }
+
             // In some cases, e.g. to verify the signature of a vote received at a higher height
             // than the one we are at (e.g. because we are lagging behind a little bit),
             // the engine may ask us for the validator set at that height.
@@ -147,14 +206,17 @@ pub async fn run(
                     "Consensus has decided on value"
                 );
 
-                // When that happens, we store the decided value in our store
-                state.commit(certificate);
+                // When that happens, we store the decided value in our store.
+                // If the value was submitted as a transaction through the RPC server,
+                // it has already been reported as included by consensus.
+                state.commit(certificate).await?;
 
                 // And then we instruct consensus to start the next height
                 if reply
                     .send(ConsensusMsg::StartHeight(
                         state.current_height,
                         genesis.validator_set.clone(),
+                        ParamsUpdate::default(),
                     ))
                     .is_err()
                 {
@@ -177,20 +239,19 @@ pub async fn run(
             } => {
                 info!(%height, %round, "Processing synced value");
 
-                let value = decode_value(value_bytes);
+                let value = ProposedValue {
+                    height,
+                    round,
+                    valid_round: Round::Nil,
+                    proposer,
+                    value: decode_value(value_bytes),
+                    validity: Validity::Valid,
+                };
 
-                if reply
-                    .send(ProposedValue {
-                        height,
-                        round,
-                        valid_round: Round::Nil,
-                        proposer,
-                        value,
-                        validity: Validity::Valid,
-                        extension: None,
-                    })
-                    .is_err()
-                {
+                // We store the synced value so that we can commit it once consensus decides on it
+                state.received_synced_value(value.clone()).await?;
+
+                if reply.send(value).is_err() {
                     error!("Failed to send ProcessSyncedValue reply");
                 }
             }
@@ -201,7 +262,7 @@ pub async fn run(
             // that was decided at some lower height. In that case, we fetch it from our store
             // and send it to consensus.
             AppMsg::GetDecidedValue { height, reply } => {
-                let decided_value = state.get_decided_value(&height).cloned();
+                let decided_value = state.get_decided_value(height).await?;
 
                 if reply.send(decided_value).is_err() {
                     error!("Failed to send GetDecidedValue reply");
@@ -211,7 +272,7 @@ pub async fn run(
             // In order to figure out if we can help a peer that is lagging behind,
             // the engine may ask us for the height of the earliest available value in our store.
             AppMsg::GetHistoryMinHeight { reply } => {
-                if reply.send(state.get_earliest_height()).is_err() {
+                if reply.send(state.get_earliest_height().await?).is_err() {
                     error!("Failed to send GetHistoryMinHeight reply");
                 }
             }
@@ -219,6 +280,20 @@ pub async fn run(
             AppMsg::RestreamProposal { .. } => {
                 error!("RestreamProposal not implemented");
             }
+
+            // Users may submit transactions to the RPC server, which are forwarded to the application.
+            // We check them and queue them to be proposed, replying with their hash if accepted.
+            AppMsg::ReceivedTransaction { tx, reply } => {
+                let result = state.received_transaction(tx);
+
+                if let Err(reason) = &result {
+                    info!("Rejected transaction: {reason}");
+                }
+
+                if reply.send(result).is_err() {
+                    error!("Failed to send ReceivedTransaction reply");
+                }
+            }
         }
     }
 
@@ -6,8 +6,11 @@ use std::path::{Path, PathBuf};
 use async_trait::async_trait;
 use rand::{CryptoRng, RngCore};
 
+use malachitebft_app_channel::app::store::{DecidedValueStore, RedbStore};
+use malachitebft_app_channel::app::streaming::StreamingMetrics;
 use malachitebft_app_channel::app::types::config::Config;
 use malachitebft_app_channel::app::types::core::VotingPower;
+use malachitebft_app_channel::app::types::metrics::SharedRegistry;
 use malachitebft_app_channel::app::types::Keypair;
 use malachitebft_app_channel::app::Node;
 
@@ -105,19 +108,44 @@ impl Node for App {
 
         let codec = ProtobufCodec;
 
-        let mut channels = malachitebft_app_channel::run(
+        let db_dir = self.get_home_dir().join("db");
+        std::fs::create_dir_all(&db_dir)?;
+        let store = RedbStore::open(db_dir.join("store.db"), codec)?;
+
+        // Resume from the height following the last decided value in the store, if any
+        let start_height = match self.start_height {
+            Some(height) => height,
+            None => store
+                .max_decided_height()
+                .await?
+                .map(|height| height.increment())
+                .unwrap_or_default(),
+        };
+
+        let (mut channels, handle) = malachitebft_app_channel::run(
             ctx.clone(),
             codec,
             self.clone(),
             self.config.clone(),
             self.private_key_file.clone(),
-            self.start_height,
+            Some(start_height),
             initial_validator_set,
         )
         .await?;
 
-        let mut state = State::new(ctx, address, self.start_height.unwrap_or_default());
+        let registry = SharedRegistry::global().with_moniker(self.config.moniker.as_str());
+        let streaming_metrics = StreamingMetrics::register(&registry);
 
-        crate::app::run(genesis, &mut state, &mut channels).await
+        let mut state = State::new(ctx, address, start_height, store, streaming_metrics);
+
+        tokio::select! {
+            result = crate::app::run(genesis, &mut state, &mut channels) => result,
+
+            // Stop the node gracefully on Ctrl-C, so that the WAL is flushed to disk
+            _ = tokio::signal::ctrl_c() => {
+                tracing::info!("Received Ctrl-C, shutting down");
+                handle.stop().await
+            }
+        }
     }
 }
@@ -1,28 +1,39 @@
 //! Internal state of the application. This is a simplified abstract to keep it simple.
-//! A regular application would have mempool implemented, a proper database and input methods like RPC.
+//! A regular application would have mempool implemented and input methods like RPC.
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::VecDeque;
 
 use bytes::Bytes;
+use eyre::eyre;
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
-use sha3::Digest;
 use tracing::debug;
 
 use malachitebft_app_channel::app::consensus::ProposedValue;
 use malachitebft_app_channel::app::host::LocallyProposedValue;
-use malachitebft_app_channel::app::streaming::{StreamContent, StreamMessage};
+use malachitebft_app_channel::app::store::{DecidedValueStore, RedbStore};
+use malachitebft_app_channel::app::streaming::{
+    ProposalHasher, ProposalStreamer, StreamMessage, StreamingMetrics,
+};
 use malachitebft_app_channel::app::types::codec::Codec;
 use malachitebft_app_channel::app::types::core::{CommitCertificate, Round, Validity};
 use malachitebft_app_channel::app::types::sync::DecidedValue;
 use malachitebft_app_channel::app::types::PeerId;
 use malachitebft_test::codec::proto::ProtobufCodec;
-use malachitebft_test::{
-    Address, Height, ProposalData, ProposalFin, ProposalInit, ProposalPart, TestContext, Value,
-};
+use malachitebft_test::{Address, Height, ProposalPart, TestContext, ValidatorSet, Value};
 
 use crate::streaming::{PartStreamsMap, ProposalParts};
 
+/// Maximum size of the chunks of a value sent in each proposal part.
+///
+/// Values in this example are tiny and fit in a single part, but a real application
+/// would typically split blocks of transactions into many parts in order to
+/// reduce bandwidth requirements due to duplication of gossip messages.
+const PART_SIZE: usize = 1024;
+
+/// Maximum number of submitted transactions waiting to be proposed
+const MAX_PENDING_TXS: usize = 1000;
+
 /// Represents the internal state of the application node
 /// Contains information about current height, round, proposals and blocks
 pub struct State {
@@ -33,13 +44,14 @@ pub struct State {
     pub current_round: Round,
     pub current_proposer: Option<Address>,
 
-    undecided_proposals: HashMap<(Height, Round), ProposedValue<TestContext>>,
-    decided_proposals: HashMap<Height, ProposedValue<TestContext>>,
-    decided_values: BTreeMap<Height, DecidedValue<TestContext>>,
+    store: RedbStore<TestContext, ProtobufCodec>,
 
-    stream_id: u64,
+    streamer: ProposalStreamer,
     streams_map: PartStreamsMap,
 
+    /// Transactions submitted through the RPC server, not decided yet
+    pending_txs: VecDeque<Value>,
+
     rng: StdRng,
 }
 
@@ -57,42 +69,46 @@ fn seed_from_address(address: &Address) -> u64 {
 
 impl State {
     /// Creates a new State instance with the given validator address and starting height
-    pub fn new(ctx: TestContext, address: Address, height: Height) -> Self {
+    pub fn new(
+        ctx: TestContext,
+        address: Address,
+        height: Height,
+        store: RedbStore<TestContext, ProtobufCodec>,
+        streaming_metrics: StreamingMetrics,
+    ) -> Self {
         Self {
             ctx,
             current_height: height,
             current_round: Round::new(0),
             current_proposer: None,
             address,
-            stream_id: 0,
-            undecided_proposals: HashMap::new(),
-            decided_proposals: HashMap::new(),
-            decided_values: BTreeMap::new(),
-            streams_map: PartStreamsMap::new(),
+            streamer: ProposalStreamer::new(PART_SIZE),
+            store,
+            streams_map: PartStreamsMap::new(streaming_metrics),
+            pending_txs: VecDeque::new(),
             rng: StdRng::seed_from_u64(seed_from_address(&address)),
         }
     }
 
-    /// Returns the earliest height available in the state
-    pub fn get_earliest_height(&self) -> Height {
-        self.decided_values
-            .keys()
-            .next()
-            .copied()
-            .unwrap_or_default()
+    /// Returns the earliest height available in the store
+    pub async fn get_earliest_height(&self) -> eyre::Result<Height> {
+        Ok(self.store.history_min_height().await?)
     }
 
     /// Processes and adds a new proposal to the state if it's valid
     /// Returns Some(ProposedValue) if the proposal was accepted, None otherwise
-    pub fn received_proposal_part(
+    pub async fn received_proposal_part(
         &mut self,
         from: PeerId,
         part: StreamMessage<ProposalPart>,
-    ) -> Option<ProposedValue<TestContext>> {
+        validator_set: &ValidatorSet,
+    ) -> eyre::Result<Option<ProposedValue<TestContext>>> {
         let sequence = part.sequence;
 
         // Check if we have a full proposal
-        let parts = self.streams_map.insert(from, part)?;
+        let Some(parts) = self.streams_map.insert(from, part) else {
+            return Ok(None);
+        };
 
         // Check if the proposal is outdated
         if parts.height < self.current_height {
@@ -105,71 +121,124 @@ impl State {
                 "Received outdated proposal part, ignoring"
             );
 
-            return None;
+            return Ok(None);
         }
 
         // Re-assemble the proposal from its parts
-        let value = assemble_value_from_parts(parts);
+        let Some(value) = assemble_value_from_parts(parts, validator_set) else {
+            debug!(%from, "Received a proposal with an invalid value, ignoring");
+            return Ok(None);
+        };
 
-        self.undecided_proposals
-            .insert((value.height, value.round), value.clone());
+        self.store.store_undecided_proposal(value.clone()).await?;
 
-        Some(value)
+        Ok(Some(value))
+    }
+
+    /// Stores a value synced from a peer, for it to be committed once consensus decides on it
+    pub async fn received_synced_value(
+        &mut self,
+        value: ProposedValue<TestContext>,
+    ) -> eyre::Result<()> {
+        Ok(self.store.store_undecided_proposal(value).await?)
     }
 
     /// Retrieves a decided block at the given height
-    pub fn get_decided_value(&self, height: &Height) -> Option<&DecidedValue<TestContext>> {
-        self.decided_values.get(height)
+    pub async fn get_decided_value(
+        &self,
+        height: Height,
+    ) -> eyre::Result<Option<DecidedValue<TestContext>>> {
+        Ok(self.store.get_decided_value(height).await?)
+    }
+
+    /// Checks a transaction submitted through the RPC server and queues it to be proposed.
+    ///
+    /// In this example, a transaction is simply a value to propose, encoded as 8 big-endian bytes,
+    /// and its hash is the encoded value itself.
+    pub fn received_transaction(&mut self, tx: Bytes) -> Result<Bytes, String> {
+        let bytes = <[u8; 8]>::try_from(tx.as_ref()).map_err(|_| {
+            format!(
+                "Invalid transaction length, got {} bytes expected 8",
+                tx.len()
+            )
+        })?;
+
+        if self.pending_txs.len() >= MAX_PENDING_TXS {
+            return Err("Too many pending transactions".to_string());
+        }
+
+        let value = Value::new(u64::from_be_bytes(bytes));
+
+        if !self.pending_txs.contains(&value) {
+            self.pending_txs.push_back(value);
+        }
+
+        Ok(tx)
+    }
+
+    /// Returns the hash of the transaction submitted through the RPC server
+    /// which the given value is made of, if any
+    pub fn submitted_tx_hash(&self, value: &Value) -> Option<Bytes> {
+        self.pending_txs
+            .contains(value)
+            .then(|| Bytes::copy_from_slice(&value.as_u64().to_be_bytes()))
     }
 
     /// Commits a value with the given certificate, updating internal state
     /// and moving to the next height
-    pub fn commit(&mut self, certificate: CommitCertificate<TestContext>) {
-        // Sort out proposals
-        for ((height, round), value) in self.undecided_proposals.clone() {
-            if height > self.current_height {
-                continue;
-            }
-
-            if height == certificate.height {
-                self.decided_proposals.insert(height, value);
-            }
-
-            self.undecided_proposals.remove(&(height, round));
-        }
+    pub async fn commit(
+        &mut self,
+        certificate: CommitCertificate<TestContext>,
+    ) -> eyre::Result<()> {
+        let Some(proposal) = self
+            .store
+            .get_undecided_proposal(certificate.height, certificate.round)
+            .await?
+        else {
+            return Err(eyre!(
+                "Trying to commit a value at height {} and round {} that is not stored",
+                certificate.height,
+                certificate.round
+            ));
+        };
 
-        let value = self.decided_proposals.get(&certificate.height).unwrap();
-        let value_bytes = encode_value(&value.value);
+        let value_bytes = encode_value(&proposal.value);
 
-        self.decided_values.insert(
-            self.current_height,
-            DecidedValue::new(value_bytes, certificate),
-        );
+        // Storing the decided value also drops the undecided proposals up to its height
+        self.store
+            .store_decided_value(DecidedValue::new(value_bytes, certificate))
+            .await?;
 
         // Move to next height
         self.current_height = self.current_height.increment();
         self.current_round = Round::new(0);
+
+        // The decided value does not need to be proposed anymore
+        self.pending_txs.retain(|value| *value != proposal.value);
+
+        Ok(())
     }
 
     /// Retrieves a previously built proposal value for the given height
-    pub fn get_previously_built_value(
+    pub async fn get_previously_built_value(
         &self,
         height: Height,
         round: Round,
-    ) -> Option<LocallyProposedValue<TestContext>> {
-        let proposal = self.undecided_proposals.get(&(height, round))?;
+    ) -> eyre::Result<Option<LocallyProposedValue<TestContext>>> {
+        let proposal = self.store.get_undecided_proposal(height, round).await?;
 
-        Some(LocallyProposedValue::new(
-            proposal.height,
-            proposal.round,
-            proposal.value,
-            proposal.extension.clone(),
-        ))
+        Ok(proposal.map(|proposal| {
+            LocallyProposedValue::new(proposal.height, proposal.round, proposal.value)
+        }))
     }
 
     /// Creates a new proposal value for the given height
     /// Returns either a previously built proposal or creates a new one
-    fn create_proposal(&mut self, height: Height, round: Round) -> ProposedValue<TestContext> {
+    async fn create_proposal(
+        &mut self,
+        height: Height,
+        round: Round,
+    ) -> eyre::Result<ProposedValue<TestContext>> {
         assert_eq!(height, self.current_height);
         assert_eq!(round, self.current_round);
 
@@ -183,14 +252,14 @@ impl State {
             proposer: self.address, // We are the proposer
             value,
             validity: Validity::Valid, // Our proposals are de facto valid
-            extension: None,           // Vote extension can be added here
         };
 
         // Insert the new proposal into the undecided proposals.
-        self.undecided_proposals
-            .insert((height, round), proposal.clone());
+        self.store
+            .store_undecided_proposal(proposal.clone())
+            .await?;
 
-        proposal
+        Ok(proposal)
     }
 
     /// Make up a new value to propose
@@ -198,121 +267,108 @@ impl State {
     /// typically reaping transactions from a mempool and executing them against its state,
     /// before computing the merkle root of the new app state.
     fn make_value(&mut self) -> Value {
+        // Propose the oldest submitted transaction first, if any
+        if let Some(value) = self.pending_txs.front() {
+            return *value;
+        }
+
         let value = self.rng.gen_range(100..=100000);
         Value::new(value)
     }
 
     /// Creates a new proposal value for the given height
     /// Returns either a previously built proposal or creates a new one
-    pub fn propose_value(
+    pub async fn propose_value(
         &mut self,
         height: Height,
         round: Round,
-    ) -> LocallyProposedValue<TestContext> {
+    ) -> eyre::Result<LocallyProposedValue<TestContext>> {
         assert_eq!(height, self.current_height);
         assert_eq!(round, self.current_round);
 
-        let proposal = self.create_proposal(height, round);
+        let proposal = self.create_proposal(height, round).await?;
 
-        LocallyProposedValue::new(
+        Ok(LocallyProposedValue::new(
             proposal.height,
             proposal.round,
             proposal.value,
-            proposal.extension,
-        )
+        ))
     }
 
-    /// Creates a stream message containing a proposal part.
-    /// Updates internal sequence number and current proposal.
+    /// Splits the proposed value into parts on a new stream, signing the hash of the proposal.
     pub fn stream_proposal(
         &mut self,
         value: LocallyProposedValue<TestContext>,
     ) -> impl Iterator<Item = StreamMessage<ProposalPart>> {
-        let parts = self.value_to_parts(value);
-
-        let stream_id = self.stream_id;
-        self.stream_id += 1;
-
-        let mut msgs = Vec::with_capacity(parts.len() + 1);
-        let mut sequence = 0;
-
-        for part in parts {
-            let msg = StreamMessage::new(stream_id, sequence, StreamContent::Data(part));
-            sequence += 1;
-            msgs.push(msg);
-        }
-
-        msgs.push(StreamMessage::new(
-            stream_id,
-            sequence,
-            StreamContent::Fin(true),
-        ));
-
-        msgs.into_iter()
-    }
+        let signing_provider = &self.ctx.signing_provider;
 
-    fn value_to_parts(&self, value: LocallyProposedValue<TestContext>) -> Vec<ProposalPart> {
-        let mut hasher = sha3::Keccak256::new();
-        let mut parts = Vec::new();
-
-        // Init
-        // Include metadata about the proposal
-        {
-            parts.push(ProposalPart::Init(ProposalInit::new(
+        self.streamer
+            .stream::<TestContext>(
                 value.height,
                 value.round,
                 self.address,
-            )));
-
-            hasher.update(value.height.as_u64().to_be_bytes().as_slice());
-            hasher.update(value.round.as_i64().to_be_bytes().as_slice());
-
-            if let Some(ext) = &value.extension {
-                hasher.update(ext.data.as_ref());
-            }
-        }
-
-        // Data
-        // Include each prime factor of the value as a separate proposal part
-        {
-            for factor in factor_value(value.value) {
-                parts.push(ProposalPart::Data(ProposalData::new(factor)));
-
-                hasher.update(factor.to_be_bytes().as_slice());
-            }
-        }
-
-        // Fin
-        // Sign the hash of the proposal parts
-        {
-            let hash = hasher.finalize().to_vec();
-            let signature = self.ctx.signing_provider.sign(&hash);
-            parts.push(ProposalPart::Fin(ProposalFin::new(signature)));
-        }
-
-        parts
+                encode_value(&value.value),
+                |hash| signing_provider.sign(hash),
+            )
+            .into_iter()
     }
 }
 
 /// Re-assemble a [`ProposedValue`] from its [`ProposalParts`].
 ///
-/// This is done by multiplying all the factors in the parts.
-fn assemble_value_from_parts(parts: ProposalParts) -> ProposedValue<TestContext> {
-    let value = parts
+/// This is done by decoding the value from the concatenation of the data parts.
+/// The value is only valid if the `Fin` part is signed by the proposer.
+fn assemble_value_from_parts(
+    parts: ProposalParts,
+    validator_set: &ValidatorSet,
+) -> Option<ProposedValue<TestContext>> {
+    let validity = Validity::from_bool(verify_proposal_signature(&parts, validator_set));
+
+    let bytes: Vec<u8> = parts
         .parts
         .iter()
         .filter_map(|part| part.as_data())
-        .fold(1, |acc, data| acc * data.factor);
+        .flat_map(|data| data.bytes.iter().copied())
+        .collect();
+
+    let value = ProtobufCodec.decode(Bytes::from(bytes)).ok()?;
 
-    ProposedValue {
+    Some(ProposedValue {
         height: parts.height,
         round: parts.round,
         valid_round: Round::Nil,
         proposer: parts.proposer,
-        value: Value::new(value),
-        validity: Validity::Valid, // TODO: Check signature in Fin part
-        extension: None,
+        value,
+        validity,
+    })
+}
+
+/// Checks that the `Fin` part of a proposal carries the hash of the proposal,
+/// signed by its proposer.
+fn verify_proposal_signature(parts: &ProposalParts, validator_set: &ValidatorSet) -> bool {
+    let Some(init) = parts.parts.first().and_then(|part| part.as_init()) else {
+        return false;
+    };
+
+    let Some(fin) = parts.parts.last().and_then(|part| part.as_fin()) else {
+        return false;
+    };
+
+    let Some(proposer) = validator_set.get_by_address(&init.proposer) else {
+        return false;
+    };
+
+    let mut hasher = ProposalHasher::new::<TestContext>(init.height, init.round, &init.proposer);
+
+    for data in parts.parts.iter().filter_map(|part| part.as_data()) {
+        hasher.update(&data.bytes);
     }
+
+    fin.hash == hasher.finalize()
+        && proposer
+            .public_key
+            .verify(&fin.hash, &fin.signature)
+            .is_ok()
 }
 
 /// Decodes a Value from its byte representation using ProtobufCodec
@@ -324,29 +380,3 @@ pub fn decode_value(bytes: Bytes) -> Value {
 pub fn encode_value(value: &Value) -> Bytes {
     ProtobufCodec.encode(value).unwrap()
 }
-
-/// Returns the list of prime factors of the given value
-///
-/// In a real application, this would typically split transactions
-/// into chunks ino order to reduce bandwidth requirements due
-/// to duplication of gossip messages.
-fn factor_value(value: Value) -> Vec<u64> {
-    let mut factors = Vec::new();
-    let mut n = value.as_u64();
-
-    let mut i = 2;
-    while i * i <= n {
-        if n % i == 0 {
-            factors.push(i);
-            n /= i;
-        } else {
-            i += 1;
-        }
-    }
-
-    if n > 1 {
-        factors.push(n);
-    }
-
-    factors
-}
@@ -1,98 +1,11 @@
-use std::cmp::Ordering;
-use std::collections::{BTreeMap, BinaryHeap, HashSet};
+use tracing::debug;
 
 use malachitebft_app_channel::app::consensus::PeerId;
-use malachitebft_app_channel::app::streaming::{Sequence, StreamId, StreamMessage};
+use malachitebft_app_channel::app::streaming::{
+    ReassemblerConfig, StreamMessage, StreamReassembler, StreamingMetrics,
+};
 use malachitebft_app_channel::app::types::core::Round;
-use malachitebft_test::{Address, Height, ProposalInit, ProposalPart};
-
-struct MinSeq<T>(StreamMessage<T>);
-
-impl<T> PartialEq for MinSeq<T> {
-    fn eq(&self, other: &Self) -> bool {
-        self.0.sequence == other.0.sequence
-    }
-}
-
-impl<T> Eq for MinSeq<T> {}
-
-impl<T> Ord for MinSeq<T> {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other.0.sequence.cmp(&self.0.sequence)
-    }
-}
-
-impl<T> PartialOrd for MinSeq<T> {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-struct MinHeap<T>(BinaryHeap<MinSeq<T>>);
-
-impl<T> Default for MinHeap<T> {
-    fn default() -> Self {
-        Self(BinaryHeap::new())
-    }
-}
-
-impl<T> MinHeap<T> {
-    fn push(&mut self, msg: StreamMessage<T>) {
-        self.0.push(MinSeq(msg));
-    }
-
-    fn len(&self) -> usize {
-        self.0.len()
-    }
-
-    fn drain(&mut self) -> Vec<T> {
-        self.0
-            .drain()
-            .filter_map(|msg| msg.0.content.into_data())
-            .collect()
-    }
-}
-
-#[derive(Default)]
-struct StreamState {
-    buffer: MinHeap<ProposalPart>,
-    init_info: Option<ProposalInit>,
-    seen_sequences: HashSet<Sequence>,
-    total_messages: usize,
-    fin_received: bool,
-}
-
-impl StreamState {
-    fn is_done(&self) -> bool {
-        self.init_info.is_some() && self.fin_received && self.buffer.len() == self.total_messages
-    }
-
-    fn insert(&mut self, msg: StreamMessage<ProposalPart>) -> Option<ProposalParts> {
-        if msg.is_first() {
-            self.init_info = msg.content.as_data().and_then(|p| p.as_init()).cloned();
-        }
-
-        if msg.is_fin() {
-            self.fin_received = true;
-            self.total_messages = msg.sequence as usize + 1;
-        }
-
-        self.buffer.push(msg);
-
-        if self.is_done() {
-            let init_info = self.init_info.take()?;
-
-            Some(ProposalParts {
-                height: init_info.height,
-                round: init_info.round,
-                proposer: init_info.proposer,
-                parts: self.buffer.drain(),
-            })
-        } else {
-            None
-        }
-    }
-}
+use malachitebft_test::{Address, Height, ProposalPart};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ProposalParts {
@@ -102,14 +15,33 @@ pub struct ProposalParts {
     pub parts: Vec<ProposalPart>,
 }
 
-#[derive(Default)]
+impl ProposalParts {
+    /// The first part of a proposal must be its `Init` part
+    fn from_parts(parts: Vec<ProposalPart>) -> Option<Self> {
+        let init = parts.first()?.as_init()?.clone();
+
+        Some(Self {
+            height: init.height,
+            round: init.round,
+            proposer: init.proposer,
+            parts,
+        })
+    }
+}
+
 pub struct PartStreamsMap {
-    streams: BTreeMap<(PeerId, StreamId), StreamState>,
+    reassembler: StreamReassembler<ProposalPart>,
 }
 
 impl PartStreamsMap {
-    pub fn new() -> Self {
-        Self::default()
+    pub fn new(metrics: StreamingMetrics) -> Self {
+        Self {
+            reassembler: StreamReassembler::new(
+                ReassemblerConfig::default(),
+                ProposalPart::size_bytes,
+                metrics,
+            ),
+        }
     }
 
     pub fn insert(
@@ -117,20 +49,20 @@ impl PartStreamsMap {
         peer_id: PeerId,
         msg: StreamMessage<ProposalPart>,
     ) -> Option<ProposalParts> {
-        let stream_id = msg.stream_id;
-        let state = self.streams.entry((peer_id, stream_id)).or_default();
-
-        if !state.seen_sequences.insert(msg.sequence) {
-            // We have already seen a message with this sequence number.
-            return None;
-        }
-
-        let result = state.insert(msg);
-
-        if state.is_done() {
-            self.streams.remove(&(peer_id, stream_id));
+        let parts = match self.reassembler.insert(peer_id, msg) {
+            Ok(parts) => parts?,
+            Err(e) => {
+                debug!(%peer_id, "Ignoring proposal part: {e}");
+                return None;
+            }
+        };
+
+        let parts = ProposalParts::from_parts(parts);
+
+        if parts.is_none() {
+            debug!(%peer_id, "Ignoring proposal not starting with an init part");
         }
 
-        result
+        parts
     }
 }